@@ -0,0 +1,9 @@
+local punctuation = require("modules.punctuation")
+
+local greeting = {}
+
+function greeting.greet(name)
+    return "Hello, " .. name .. punctuation.exclamation
+end
+
+return greeting
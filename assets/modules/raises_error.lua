@@ -0,0 +1,5 @@
+local M = {}
+function M.raise()
+    error("raised in module")
+end
+return M
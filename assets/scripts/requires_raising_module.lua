@@ -0,0 +1,7 @@
+-- the comment lines are stripped by the preprocessor in `tests/event_handler_tests.rs`,
+-- so errors raised in this script are reported on lines which need to be mapped back,
+-- while errors raised in the module refer to the module's own lines
+function on_test()
+    local module = require("modules.raises_error")
+    module.raise()
+end
@@ -0,0 +1,3 @@
+local TestComponent = world.get_type_by_name("TestComponent")
+local observer = world.add_observer(ScriptObserverTrigger.on_remove(TestComponent), "on_component_removed", script_id)
+assert(world.has_entity(observer), "Expected the observer entity to exist")
@@ -0,0 +1,27 @@
+runs = {}
+local TestComponent = world.get_type_by_name("TestComponent")
+
+function on_test()
+    world.add_observer(ScriptObserverTrigger.on_remove(TestComponent), "on_component_removed", script_id)
+
+    local existing_component = world.get_component(world._get_entity_with_test_component("TestComponent"), TestComponent)
+    local new_entity = world.spawn()
+    world.insert_component(new_entity, TestComponent, existing_component)
+    world.remove_component(new_entity, TestComponent)
+    return true
+end
+
+function on_component_removed(observed_entity, removed)
+    runs[#runs + 1] = "on_component_removed"
+    assert(not world.has_component(observed_entity, TestComponent), "Expected the component to be removed by the time the callback runs")
+    assert(removed.strings[1] == "Initial", "Expected the removed value, got: " .. tostring(removed.strings[1]))
+end
+
+function on_test_post_update()
+    return true
+end
+
+function on_test_last()
+    assert(#runs == 1, "Expected 1 run, got: " .. #runs)
+    return true
+end
@@ -0,0 +1,26 @@
+runs = {}
+local TestComponent = world.get_type_by_name("TestComponent")
+
+function on_test()
+    world.add_observer(ScriptObserverTrigger.on_add(TestComponent), "on_component_added", script_id)
+
+    local existing_component = world.get_component(world._get_entity_with_test_component("TestComponent"), TestComponent)
+    expected_entity = world.spawn()
+    world.insert_component(expected_entity, TestComponent, existing_component)
+    return true
+end
+
+function on_component_added(observed_entity)
+    runs[#runs + 1] = observed_entity:to_bits()
+    assert(entity:to_bits() == observed_entity:to_bits(), "Expected the callback to run for the observed entity")
+end
+
+function on_test_post_update()
+    return true
+end
+
+function on_test_last()
+    assert(#runs == 1, "Expected 1 run, got: " .. #runs)
+    assert(runs[1] == expected_entity:to_bits(), "Expected the observer to run for the spawned entity")
+    return true
+end
@@ -0,0 +1,32 @@
+local TestComponent = world.get_type_by_name("TestComponent")
+
+function on_test()
+    removed_from = world._get_entity_with_test_component("TestComponent")
+    despawned = world.spawn()
+
+    -- commands can be queued while iterating a query
+    local next = world.query():component(TestComponent):iter()
+    local result = next()
+    while result do
+        spawned = commands.spawn()
+        commands.insert(spawned, TestComponent, result:components()[1])
+        result = next()
+    end
+    commands.remove(removed_from, TestComponent)
+    commands.despawn(despawned)
+
+    assert(world.has_component(removed_from, TestComponent), "Expected the removal to be deferred")
+    assert(world.has_entity(despawned), "Expected the despawn to be deferred")
+    return true
+end
+
+function on_test_post_update()
+    assert(world.has_component(spawned, TestComponent), "Expected the spawned entity to have the component")
+    assert(not world.has_component(removed_from, TestComponent), "Expected the component to be removed")
+    assert(not world.has_entity(despawned), "Expected the entity to be despawned")
+    return true
+end
+
+function on_test_last()
+    return true
+end
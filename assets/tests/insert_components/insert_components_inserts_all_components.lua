@@ -0,0 +1,11 @@
+local SimpleStruct = world.get_type_by_name("SimpleStruct")
+local SimpleEnum = world.get_type_by_name("SimpleEnum")
+local entity = world.spawn()
+
+world.insert_components(entity, {
+    SimpleStruct = { foo = 1 },
+    SimpleEnum = { variant = "Struct", foo = 2 }
+})
+
+assert(world.get_component(entity, SimpleStruct).foo == 1, "Expected SimpleStruct to be constructed from the given values")
+assert(world.get_component(entity, SimpleEnum):variant_name() == "Struct", "Expected SimpleEnum to be constructed from the given values")
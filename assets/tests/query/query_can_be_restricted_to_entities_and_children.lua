@@ -0,0 +1,21 @@
+local Marker = world.register_new_component("RestrictedMarker")
+
+local parent = world.spawn()
+local child_a = world.spawn()
+local child_b = world.spawn()
+local other = world.spawn()
+world.add_default_component(child_a, Marker)
+world.add_default_component(child_b, Marker)
+world.add_default_component(other, Marker)
+world.push_children(parent, {child_a, child_b})
+
+local children = world.query():component(Marker):children_of(parent):build()
+assert(#children == 2, "Expected 2 children, got " .. #children)
+for _, result in pairs(children) do
+    assert(result:entity():index() ~= other:index(), "Did not expect a non child entity")
+end
+
+local restricted = world.query():component(Marker):entities({other, child_b, parent}):build()
+assert(#restricted == 2, "Expected 2 results, got " .. #restricted)
+assert(restricted[1]:entity():index() == other:index(), "Expected results in the order of the list")
+assert(restricted[2]:entity():index() == child_b:index(), "Expected results in the order of the list")
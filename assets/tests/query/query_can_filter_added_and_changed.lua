@@ -0,0 +1,15 @@
+local entity_a = world.spawn()
+local NewComponent = world.register_new_component("FreshComponent")
+
+world.add_default_component(entity_a, NewComponent)
+
+local added = world.query():component(NewComponent):added(NewComponent):build()
+assert(#added == 1, "Expected 1 added entity, got " .. #added)
+assert(added[1]:entity():index() == entity_a:index(), "Expected the spawned entity")
+
+local changed = world.query():changed(NewComponent):build()
+assert(#changed == 1, "Expected 1 changed entity, got " .. #changed)
+
+local component = added[1]:components()[1]
+assert(component:is_added(), "Expected the component to be added")
+assert(component:is_changed(), "Expected the component to be changed")
@@ -0,0 +1,32 @@
+local Marker = world.register_new_component("OptionalMarker")
+local Tag = world.register_new_component("OptionalTag")
+local Other = world.register_new_component("OptionalOther")
+
+local entity_a = world.spawn()
+world.add_default_component(entity_a, Marker)
+local entity_b = world.spawn()
+world.add_default_component(entity_b, Marker)
+world.add_default_component(entity_b, Tag)
+local entity_c = world.spawn()
+world.add_default_component(entity_c, Other)
+
+local results = world.query():component(Marker):optional_component(Tag):build()
+assert(#results == 2, "Expected 2 results, got " .. #results)
+for _, result in pairs(results) do
+    local components = result:components()
+    assert(components[1] ~= nil, "Expected the required component to be present")
+    if result:entity():index() == entity_a:index() then
+        assert(components[2] == nil, "Expected the optional component to be missing")
+    else
+        assert(components[2] ~= nil, "Expected the optional component to be present")
+    end
+end
+
+local any = world.query():any_of({
+    ScriptQueryFilter.with_component(Tag),
+    ScriptQueryFilter.with_component(Other)
+}):build()
+assert(#any == 2, "Expected 2 results, got " .. #any)
+for _, result in pairs(any) do
+    assert(result:entity():index() ~= entity_a:index(), "Did not expect entity_a to match")
+end
@@ -0,0 +1,23 @@
+local Marker = world.register_new_component("MovingMarker")
+local Extra = world.register_new_component("MovingExtra")
+
+for i = 1, 3 do
+    world.add_default_component(world.spawn(), Marker)
+end
+-- make sure the archetype the entities move to exists, and is visited after theirs
+local with_extra = world.spawn()
+world.add_default_component(with_extra, Marker)
+world.add_default_component(with_extra, Extra)
+
+local visited = {}
+local found = 0
+for result in world.query():component(Marker):iter() do
+    local index = result:entity():index()
+    assert(visited[index] == nil, "Entity " .. index .. " was visited twice")
+    visited[index] = true
+    found = found + 1
+    if not world.has_component(result:entity(), Extra) then
+        world.insert_component(result:entity(), Extra, construct(Extra, {}))
+    end
+end
+assert(found == 4, "Expected 4 results, got " .. found)
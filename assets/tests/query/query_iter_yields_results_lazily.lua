@@ -0,0 +1,26 @@
+local Marker = world.register_new_component("IterMarker")
+
+local entity_a = world.spawn()
+local entity_b = world.spawn()
+world.add_default_component(entity_a, Marker)
+world.add_default_component(entity_b, Marker)
+
+local query = world.query():component(Marker)
+
+local found = 0
+for result in query:iter() do
+    local index = result:entity():index()
+    assert(index == entity_a:index() or index == entity_b:index(), "Unexpected entity " .. index)
+    assert(result:components()[1] ~= nil, "Expected the component to be retrieved")
+    found = found + 1
+end
+assert(found == 2, "Expected 2 results, got " .. found)
+
+-- despawning entities while iterating does not skip the others
+local next = query:iter()
+local first = next()
+world.despawn(first:entity())
+local second = next()
+assert(second ~= nil, "Expected the remaining entity to be visited")
+assert(second:entity():index() ~= first:entity():index(), "Expected a different entity")
+assert(next() == nil, "Expected the iteration to end")
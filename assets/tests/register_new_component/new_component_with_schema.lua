@@ -0,0 +1,25 @@
+local NewComponent = world.register_new_component("ScriptComponentWithSchema", {
+    { name = "speed", type = "f32", default = 2.5 },
+    { name = "label", type = "String" }
+})
+
+local new_entity = world.spawn()
+world.add_default_component(new_entity, NewComponent)
+
+local component_instance = world.get_component(new_entity, NewComponent)
+assert(component_instance.fields.speed == 2.5, "unexpected default speed: " .. tostring(component_instance.fields.speed))
+assert(component_instance.fields.label == "", "unexpected default label: " .. tostring(component_instance.fields.label))
+
+component_instance.fields.speed = 5
+assert(component_instance.fields.speed == 5, "unexpected speed: " .. tostring(component_instance.fields.speed))
+
+local other_entity = world.spawn()
+world.insert_component(other_entity, NewComponent, construct(NewComponent, {
+    label = "fast"
+}))
+local other_instance = world.get_component(other_entity, NewComponent)
+assert(other_instance.fields.speed == 2.5, "unexpected speed: " .. tostring(other_instance.fields.speed))
+assert(other_instance.fields.label == "fast", "unexpected label: " .. tostring(other_instance.fields.label))
+
+assert(not pcall(construct, NewComponent, { speed = "fast" }), "expected a type mismatch to fail")
+assert(not pcall(construct, NewComponent, { velocity = 1 }), "expected an unknown field to fail")
@@ -0,0 +1,16 @@
+local NewResource = world.register_new_resource("ScriptResourceA")
+assert(NewResource ~= nil, "Failed to register new resource")
+assert(NewResource:short_name() == "DynamicResource", "Unexpected resource type")
+
+assert(world.has_resource(NewResource), "Resource was not inserted on registration")
+local retrieved = world.get_type_by_name("ScriptResourceA")
+assert(retrieved ~= nil, "Failed to retrieve resource by name")
+
+local resource_instance = world.get_resource(retrieved)
+resource_instance.data = {
+    foo = "bar"
+}
+assert(world.get_resource(NewResource).data.foo == "bar", "unexpected value: " .. tostring(world.get_resource(NewResource).data.foo))
+
+world.remove_resource(NewResource)
+assert(world.has_resource(NewResource) == false, "Resource was not removed")
@@ -0,0 +1,31 @@
+runs = {}
+local NewResource = world.register_new_resource("ScriptResourceB")
+world.get_resource(NewResource).data = 1
+
+function on_test()
+    local post_update_schedule = world.get_schedule_by_name("PostUpdate")
+
+    world.add_system(
+        post_update_schedule,
+        system_builder("my_resource_system", script_id)
+            :resource(NewResource)
+    )
+
+    return true
+end
+
+function my_resource_system(resource)
+    runs[#runs + 1] = "my_resource_system"
+    assert(resource ~= nil, "Expected to get resource but got nil")
+    resource.data = resource.data + 1
+end
+
+function on_test_post_update()
+    return true
+end
+
+function on_test_last()
+    assert(#runs == 1, "Expected 1 runs, got: " .. #runs)
+    assert(world.get_resource(NewResource).data == 2, "unexpected value: " .. tostring(world.get_resource(NewResource).data))
+    return true
+end
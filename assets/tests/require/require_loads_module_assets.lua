@@ -0,0 +1,5 @@
+local greeting = require("modules.greeting")
+local message = greeting.greet("Bob")
+
+assert(message == "Hello, Bob!", "Unexpected greeting: " .. message)
+assert(require("modules.greeting") == greeting, "Module was loaded twice")
@@ -0,0 +1,42 @@
+runs = {}
+local ScriptEvent = world.register_new_event("ScriptEventA")
+local TestEvent = world.get_event_type("TestEvent")
+
+function on_test()
+    assert(TestEvent ~= nil, "Expected to find TestEvent")
+    assert(TestEvent:event_name() == "TestEvent", "unexpected event name: " .. TestEvent:event_name())
+    local retrieved = world.get_event_type("ScriptEventA")
+    assert(retrieved ~= nil, "Expected to find script registered event")
+    assert(retrieved:event_name() == "ScriptEventA", "unexpected event name: " .. retrieved:event_name())
+
+    local post_update_schedule = world.get_schedule_by_name("PostUpdate")
+    world.add_system(
+        post_update_schedule,
+        system_builder("read_events", script_id)
+            :event_reader(ScriptEvent)
+            :event_reader(TestEvent)
+    )
+
+    world.send_event(ScriptEvent, { foo = "bar" })
+    world.send_event(ScriptEvent, 2)
+    world.send_event(TestEvent, construct(world.get_type_by_name("TestEvent"), { value = "hello" }))
+    return true
+end
+
+function read_events(script_events, test_events)
+    runs[#runs + 1] = "read_events"
+    assert(#script_events == 2, "Expected 2 script events, got: " .. #script_events)
+    assert(script_events[1].foo == "bar", "unexpected payload: " .. tostring(script_events[1].foo))
+    assert(script_events[2] == 2, "unexpected payload: " .. tostring(script_events[2]))
+    assert(#test_events == 1, "Expected 1 test event, got: " .. #test_events)
+    assert(test_events[1].value == "hello", "unexpected value: " .. tostring(test_events[1].value))
+end
+
+function on_test_post_update()
+    return true
+end
+
+function on_test_last()
+    assert(#runs == 1, "Expected 1 runs, got: " .. #runs)
+    return true
+end
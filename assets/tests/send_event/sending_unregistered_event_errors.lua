@@ -0,0 +1,11 @@
+assert(world.get_event_type("NotAnEvent") == nil, "Expected no event type")
+
+local ScriptEvent = world.register_new_event("ScriptEventB")
+assert_throws(function()
+    world.register_new_event("ScriptEventB")
+end, ".*already exists.*")
+
+local TestEvent = world.get_event_type("TestEvent")
+assert_throws(function()
+    world.send_event(TestEvent, 2)
+end, ".*")
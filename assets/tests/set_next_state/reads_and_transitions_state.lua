@@ -0,0 +1,12 @@
+local state = world.get_state("TestState")
+assert(state:variant_name() == "Idle", "Expected the initial state to be Idle, got: " .. state:variant_name())
+
+world.set_next_state("TestState", "Running")
+
+assert_throws(function()
+    world.set_next_state("TestState", "Paused")
+end, "Invalid enum variant: Paused")
+
+assert_throws(function()
+    world.get_state("UnknownState")
+end, "Missing state")
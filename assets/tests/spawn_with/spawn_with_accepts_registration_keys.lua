@@ -0,0 +1,15 @@
+local SimpleStruct = world.get_type_by_name("SimpleStruct")
+local UnitStruct = world.get_type_by_name("UnitStruct")
+
+local entity = world.spawn_with({
+    [SimpleStruct] = { foo = 123 },
+    [UnitStruct] = {}
+})
+
+assert(world.get_component(entity, SimpleStruct).foo == 123, "Expected SimpleStruct to be constructed from the given values")
+assert(world.has_component(entity, UnitStruct), "Expected the entity to have UnitStruct")
+
+world.insert_components(entity, {
+    [SimpleStruct] = { foo = 456 }
+})
+assert(world.get_component(entity, SimpleStruct).foo == 456, "Expected SimpleStruct to be replaced")
@@ -0,0 +1,13 @@
+local SimpleStruct = world.get_type_by_name("SimpleStruct")
+local SimpleTupleStruct = world.get_type_by_name("SimpleTupleStruct")
+local UnitStruct = world.get_type_by_name("UnitStruct")
+
+local entity = world.spawn_with({
+    SimpleStruct = { foo = 123 },
+    SimpleTupleStruct = { _1 = 456 },
+    UnitStruct = {}
+})
+
+assert(world.get_component(entity, SimpleStruct).foo == 123, "Expected SimpleStruct to be constructed from the given values")
+assert(world.get_component(entity, SimpleTupleStruct)._1 == 456, "Expected SimpleTupleStruct to be constructed from the given values")
+assert(world.has_component(entity, UnitStruct), "Expected the entity to have UnitStruct")
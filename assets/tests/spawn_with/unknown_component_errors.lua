@@ -0,0 +1,7 @@
+assert_throws(function()
+    world.spawn_with({ NotAComponent = {} })
+end, "NotAComponent")
+
+assert_throws(function()
+    world.spawn_with({ TestResource = {} })
+end, "TestResource")
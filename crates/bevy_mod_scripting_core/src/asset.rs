@@ -1,24 +1,29 @@
 //! Systems and resources for handling script assets and events
 
 use crate::{
+    bindings::WorldGuard,
     commands::{CreateOrUpdateScript, DeleteScript},
-    error::ScriptError,
+    error::{InteropError, ScriptError},
     script::ScriptId,
     IntoScriptPluginParams, ScriptingSystemSet,
 };
 use bevy::{
     app::{App, PreUpdate},
-    asset::{Asset, AssetEvent, AssetId, AssetLoader, AssetPath, Assets},
+    asset::{
+        Asset, AssetEvent, AssetId, AssetLoadFailedEvent, AssetLoader, AssetPath, AssetServer,
+        Assets, Handle, LoadState,
+    },
     ecs::system::Resource,
     log::{debug, info, trace, warn},
     prelude::{
-        Commands, Event, EventReader, EventWriter, IntoSystemConfigs, IntoSystemSetConfigs, Res,
-        ResMut,
+        Commands, Event, EventReader, EventWriter, IntoSystemConfigs, IntoSystemSetConfigs, Mut,
+        Res, ResMut,
     },
     reflect::TypePath,
-    utils::hashbrown::HashMap,
+    utils::hashbrown::{HashMap, HashSet},
 };
-use std::borrow::Cow;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, sync::Arc};
 
 /// Represents a scripting language. Languages which compile into another language should use the target language as their language.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
@@ -55,6 +60,8 @@ pub struct ScriptAsset {
     pub content: Box<[u8]>,
     /// The virtual filesystem path of the asset, used to map to the script Id for asset backed scripts
     pub asset_path: AssetPath<'static>,
+    /// Maps the lines of the content back to the lines of the original script, if preprocessing moved any lines around
+    pub source_map: Option<ScriptSourceMap>,
 }
 
 #[derive(Event, Debug, Clone)]
@@ -64,27 +71,248 @@ pub(crate) enum ScriptAssetEvent {
     Modified(ScriptMetadata),
 }
 
+/// Maps the lines of a preprocessed script back to the lines of the original script.
+///
+/// Lines are 1-indexed, as they are in error messages.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ScriptSourceMap {
+    /// The original line of each line of the preprocessed script
+    lines: Vec<usize>,
+}
+
+#[profiling::all_functions]
+impl ScriptSourceMap {
+    /// Creates a new source map from the original line of each line of the preprocessed script
+    pub fn new(lines: Vec<usize>) -> Self {
+        Self { lines }
+    }
+
+    /// Returns the line in the original script, of the given line in the preprocessed script
+    pub fn original_line(&self, line: usize) -> Option<usize> {
+        self.lines.get(line.checked_sub(1)?).copied()
+    }
+
+    /// Combines this source map with the source map of a later preprocessing step,
+    /// producing a source map from the output of the later step to the original script
+    pub fn then(&self, next: &ScriptSourceMap) -> ScriptSourceMap {
+        ScriptSourceMap {
+            lines: next
+                .lines
+                .iter()
+                .map(|line| self.original_line(*line).unwrap_or(*line))
+                .collect(),
+        }
+    }
+
+    /// Replaces references to lines of the preprocessed script in the given message, with the lines of the original script.
+    ///
+    /// Only references to the script itself are replaced, references to other scripts such as required modules are left alone:
+    /// - references of the form `chunk:12:` as produced by lua, are replaced if they are qualified by the given chunk name, i.e. `[string "chunk"]:12:`
+    /// - references of the form `line 12` as produced by rhai, which does not name the script, are replaced unless they come from an imported module, i.e. precede an `in module` line
+    pub fn remap_line_references(&self, chunk_name: &str, message: &str) -> String {
+        let bytes = message.as_bytes();
+        // rhai reports errors in modules as the inner error followed by `in module '...'` and the position of the import
+        let own_lines_start = message.rfind("\nin module").unwrap_or(0);
+        let mut out = String::with_capacity(message.len());
+        let mut last = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_digit() {
+                i += 1;
+                continue;
+            }
+            let end = i + bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+            let is_line_reference = (message[end..].starts_with(':')
+                && (message[..i].ends_with(&format!("\"{chunk_name}\"]:"))
+                    || message[..i].ends_with(&format!("{chunk_name}:"))))
+                || (i >= own_lines_start && message[..i].ends_with("line "));
+            let original = message[i..end]
+                .parse()
+                .ok()
+                .and_then(|line| self.original_line(line));
+            if let (true, Some(original)) = (is_line_reference, original) {
+                out.push_str(&message[last..i]);
+                out.push_str(&original.to_string());
+                last = end;
+            }
+            i = end;
+        }
+        out.push_str(&message[last..]);
+        out
+    }
+}
+
+/// The output of a [`ScriptPreprocessor`]
+#[derive(Debug, Clone, Default)]
+pub struct PreprocessedScript {
+    /// The new content of the script
+    pub content: Vec<u8>,
+    /// Maps the lines of the new content to the lines of the preprocessor's input, `None` if no lines were moved
+    pub source_map: Option<ScriptSourceMap>,
+}
+
+impl PreprocessedScript {
+    /// Creates a new preprocessed script which keeps every line in place
+    pub fn new(content: Vec<u8>) -> Self {
+        Self {
+            content,
+            source_map: None,
+        }
+    }
+
+    /// Sets the source map of the preprocessed script
+    pub fn with_source_map(mut self, source_map: ScriptSourceMap) -> Self {
+        self.source_map = Some(source_map);
+        self
+    }
+}
+
+/// The function type of a [`ScriptPreprocessor`], receives the content produced by the previous preprocessor,
+/// the path of the asset being loaded, and the loader settings of the asset.
+pub type ScriptPreprocessorFn = dyn Fn(&[u8], &AssetPath<'_>, &ScriptLoaderSettings) -> Result<PreprocessedScript, ScriptError>
+    + Send
+    + Sync;
+
+/// A named step in the preprocessing pipeline of the [`ScriptAssetLoader`], which transforms the content of a script before it is saved to an asset.
+///
+/// Can be used for templating, macro expansion, defines, stripping debug code and so on.
+#[derive(Clone)]
+pub struct ScriptPreprocessor {
+    /// The name of the preprocessor, used to select it in [`ScriptLoaderSettings`]
+    pub name: Cow<'static, str>,
+    /// The preprocessing function
+    pub process: Arc<ScriptPreprocessorFn>,
+}
+
+impl ScriptPreprocessor {
+    /// Creates a new named preprocessor
+    pub fn new(
+        name: impl Into<Cow<'static, str>>,
+        process: impl Fn(
+                &[u8],
+                &AssetPath<'_>,
+                &ScriptLoaderSettings,
+            ) -> Result<PreprocessedScript, ScriptError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            process: Arc::new(process),
+        }
+    }
+}
+
+impl std::fmt::Debug for ScriptPreprocessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptPreprocessor")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// Per asset settings of the [`ScriptAssetLoader`], configurable via `.meta` files.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ScriptLoaderSettings {
+    /// The names of the preprocessors to run on the script, in order.
+    ///
+    /// If `None`, every registered preprocessor runs in the order it was registered.
+    pub preprocessors: Option<Vec<String>>,
+    /// Definitions made available to the preprocessors, i.e. to toggle sections of code
+    pub defines: HashMap<String, String>,
+}
+
 #[derive(Default)]
 /// A loader for script assets
 pub struct ScriptAssetLoader {
     /// The file extensions this loader should handle
     pub extensions: &'static [&'static str],
-    /// preprocessor to run on the script before saving the content to an asset
-    pub preprocessor: Option<Box<dyn Fn(&mut [u8]) -> Result<(), ScriptError> + Send + Sync>>,
+    /// The preprocessors available to run on scripts before saving their content to an asset, in order
+    pub preprocessors: Vec<ScriptPreprocessor>,
+    /// The file extensions of precompiled scripts, such as bytecode, which are saved to an asset as is without running any preprocessors
+    pub precompiled_extensions: &'static [&'static str],
+}
+
+#[profiling::all_functions]
+impl ScriptAssetLoader {
+    /// Selects the preprocessors to run for an asset with the given settings, in order
+    fn select_preprocessors<'a>(
+        &'a self,
+        settings: &ScriptLoaderSettings,
+    ) -> Result<Vec<&'a ScriptPreprocessor>, ScriptError> {
+        match &settings.preprocessors {
+            None => Ok(self.preprocessors.iter().collect()),
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    self.preprocessors
+                        .iter()
+                        .find(|preprocessor| &preprocessor.name == name)
+                        .ok_or_else(|| {
+                            ScriptError::new_external_boxed(
+                                format!("Unknown script preprocessor: `{name}`").into(),
+                            )
+                        })
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns true if the asset at the given path is a precompiled script
+    fn is_precompiled(&self, asset_path: &AssetPath<'_>) -> bool {
+        asset_path
+            .path()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.precompiled_extensions.contains(&ext))
+    }
+
+    /// Runs the selected preprocessors on the content in order, returning the final content and its source map.
+    ///
+    /// Precompiled scripts are returned as is.
+    fn preprocess(
+        &self,
+        mut content: Vec<u8>,
+        asset_path: &AssetPath<'_>,
+        settings: &ScriptLoaderSettings,
+    ) -> Result<PreprocessedScript, ScriptError> {
+        if self.is_precompiled(asset_path) {
+            return Ok(PreprocessedScript::new(content));
+        }
+        let mut source_map: Option<ScriptSourceMap> = None;
+        for preprocessor in self.select_preprocessors(settings)? {
+            let output = (preprocessor.process)(&content, asset_path, settings).map_err(|e| {
+                e.with_context(format!("running preprocessor `{}`", preprocessor.name))
+            })?;
+            content = output.content;
+            if let Some(step) = output.source_map {
+                source_map = Some(match source_map {
+                    Some(previous) => previous.then(&step),
+                    None => step,
+                });
+            }
+        }
+        Ok(PreprocessedScript {
+            content,
+            source_map,
+        })
+    }
 }
 
 #[profiling::all_functions]
 impl AssetLoader for ScriptAssetLoader {
     type Asset = ScriptAsset;
 
-    type Settings = ();
+    type Settings = ScriptLoaderSettings;
 
     type Error = ScriptError;
 
     async fn load(
         &self,
         reader: &mut dyn bevy::asset::io::Reader,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
         load_context: &mut bevy::asset::LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut content = Vec::new();
@@ -92,12 +320,13 @@ impl AssetLoader for ScriptAssetLoader {
             .read_to_end(&mut content)
             .await
             .map_err(|e| ScriptError::new_external(e).with_context(load_context.asset_path()))?;
-        if let Some(processor) = &self.preprocessor {
-            processor(&mut content)?;
-        }
+        let preprocessed = self
+            .preprocess(content, load_context.asset_path(), settings)
+            .map_err(|e| e.with_context(load_context.asset_path()))?;
         let asset = ScriptAsset {
-            content: content.into_boxed_slice(),
+            content: preprocessed.content.into_boxed_slice(),
             asset_path: load_context.asset_path().to_owned(),
+            source_map: preprocessed.source_map,
         };
         Ok(asset)
     }
@@ -120,6 +349,15 @@ pub struct ScriptAssetSettings {
     ///
     /// Will be used to populate the script asset loader with the supported extensions
     pub supported_extensions: &'static [&'static str],
+
+    /// The preprocessors the script asset loader can run on scripts, in order.
+    ///
+    /// Will be used to populate the script asset loader, per asset [`ScriptLoaderSettings`] select which ones run.
+    pub preprocessors: Vec<ScriptPreprocessor>,
+
+    /// The supported asset extensions of precompiled scripts, which are loaded without running any preprocessors.
+    /// Populated by scripting plugins which opt into loading precompiled scripts.
+    pub precompiled_extensions: &'static [&'static str],
 }
 
 #[profiling::all_functions]
@@ -151,6 +389,8 @@ impl Default for ScriptAssetSettings {
                 ("rn", Language::Rune),
             ]),
             supported_extensions: &["lua", "luau", "rhai", "rn"],
+            preprocessors: Vec::default(),
+            precompiled_extensions: &[],
         }
     }
 }
@@ -202,6 +442,271 @@ impl ScriptMetadataStore {
     pub fn contains(&self, id: AssetId<ScriptAsset>) -> bool {
         self.map.contains_key(&id)
     }
+
+    /// Gets the metadata entry of the script with the given script id
+    pub fn get_by_script_id(&self, script_id: &ScriptId) -> Option<&ScriptMetadata> {
+        self.map.values().find(|meta| &meta.script_id == script_id)
+    }
+}
+
+/// Records which script modules each script imports, so that scripts can be reloaded when the modules they depend on change.
+///
+/// Populated by the [`ScriptModuleResolver`], the dependencies of a script are recorded afresh each time it is loaded.
+#[derive(Default, Debug, Resource)]
+pub struct ScriptDependencyGraph {
+    /// The modules each script imports directly
+    dependencies: HashMap<ScriptId, HashSet<ScriptId>>,
+    /// Handles to every module resolved so far, these keep the modules loaded
+    modules: HashMap<ScriptId, Handle<ScriptAsset>>,
+    /// Modules which were first loaded by the resolver, these are not loaded as scripts on their own
+    libraries: HashSet<ScriptId>,
+    /// Scripts which failed to load as one of their modules was still loading
+    waiting: HashSet<ScriptId>,
+}
+
+#[profiling::all_functions]
+impl ScriptDependencyGraph {
+    /// Records that the given script imports the given module, `is_library` marks modules which are not loaded as scripts on their own
+    pub fn add_dependency(
+        &mut self,
+        dependent: ScriptId,
+        module: ScriptId,
+        handle: Handle<ScriptAsset>,
+        is_library: bool,
+    ) {
+        if !self.modules.contains_key(&module) {
+            if is_library {
+                self.libraries.insert(module.clone());
+            }
+            self.modules.insert(module.clone(), handle);
+        }
+        self.dependencies
+            .entry(dependent)
+            .or_default()
+            .insert(module);
+    }
+
+    /// Marks the script as waiting for one of its modules to load
+    pub fn mark_waiting(&mut self, script_id: ScriptId) {
+        self.waiting.insert(script_id);
+    }
+
+    /// Returns true if the script failed to load because one of its modules was still loading
+    pub fn is_waiting(&self, script_id: &ScriptId) -> bool {
+        self.waiting.contains(script_id)
+    }
+
+    /// Returns true if the script was only ever loaded as a module of another script
+    pub fn is_library(&self, script_id: &ScriptId) -> bool {
+        self.libraries.contains(script_id)
+    }
+
+    /// Iterates over the modules the given script imports directly
+    pub fn dependencies(&self, script_id: &ScriptId) -> impl Iterator<Item = &ScriptId> {
+        self.dependencies.get(script_id).into_iter().flatten()
+    }
+
+    /// Iterates over the scripts which import the given module directly
+    pub fn dependents<'a>(&'a self, module: &'a ScriptId) -> impl Iterator<Item = &'a ScriptId> {
+        self.dependencies
+            .iter()
+            .filter(move |(_, modules)| modules.contains(module))
+            .map(|(dependent, _)| dependent)
+    }
+
+    /// Returns every script which imports the given module, either directly or through other modules
+    pub fn transitive_dependents(&self, module: &ScriptId) -> Vec<ScriptId> {
+        let mut dependents = Vec::new();
+        let mut stack = vec![module.clone()];
+        while let Some(next) = stack.pop() {
+            for dependent in self.dependents(&next) {
+                if dependent != module && !dependents.contains(dependent) {
+                    dependents.push(dependent.clone());
+                    stack.push(dependent.clone());
+                }
+            }
+        }
+        dependents
+    }
+
+    /// Forgets the dependencies of the given script, done before the script is (re)loaded
+    pub fn clear_dependencies(&mut self, script_id: &ScriptId) {
+        self.dependencies.remove(script_id);
+        self.waiting.remove(script_id);
+    }
+}
+
+/// A script module resolved by the [`ScriptModuleResolver`]
+#[derive(Debug, Clone)]
+pub struct ScriptModule {
+    /// The script id of the module
+    pub id: ScriptId,
+    /// The content of the module
+    pub content: Box<[u8]>,
+}
+
+/// Resolves script modules, i.e. scripts imported by other scripts via `require` in Lua or `import` in Rhai, through the [`AssetServer`].
+///
+/// Modules are loaded as [`ScriptAsset`]s and recorded in the [`ScriptDependencyGraph`],
+/// whenever a module changes, every script which imports it is reloaded.
+pub struct ScriptModuleResolver;
+
+#[profiling::all_functions]
+impl ScriptModuleResolver {
+    /// Resolves the module at the given asset path, and records it as a dependency of the given script.
+    ///
+    /// If the module is not loaded yet, it starts loading and an error is returned,
+    /// the dependent script is then reloaded as soon as the module is available.
+    pub fn resolve(
+        world: WorldGuard<'_>,
+        dependent: &ScriptId,
+        module_path: &str,
+    ) -> Result<ScriptModule, InteropError> {
+        let asset_path = AssetPath::try_parse(module_path)
+            .map_err(|e| InteropError::script_module_load_error(module_path, e.to_string()))?
+            .into_owned();
+
+        // each resource is claimed on its own, so resolving a module does not need exclusive access to the world
+        let asset_server =
+            world.with_resource(|asset_server: &AssetServer| asset_server.clone())?;
+        let existing = asset_server.get_handle::<ScriptAsset>(&asset_path);
+        let is_library = existing.is_none();
+        let handle = existing.unwrap_or_else(|| asset_server.load(asset_path.clone()));
+        let script_id = world.with_resource(|settings: &ScriptAssetSettings| {
+            (settings.script_id_mapper.map)(&asset_path)
+        })?;
+        let content = world.with_resource(|assets: &Assets<ScriptAsset>| {
+            assets.get(&handle).map(|asset| asset.content.clone())
+        })?;
+        let load_state = asset_server.get_load_state(handle.id());
+
+        world.with_resource_mut(|mut dependency_graph: Mut<ScriptDependencyGraph>| {
+            dependency_graph.add_dependency(dependent.clone(), script_id.clone(), handle, is_library);
+
+            match (content, load_state) {
+                (Some(content), _) => Ok(ScriptModule {
+                    id: script_id,
+                    content,
+                }),
+                (None, Some(LoadState::Failed(err))) => Err(
+                    InteropError::script_module_load_error(module_path, err.to_string()),
+                ),
+                (None, _) => {
+                    dependency_graph.mark_waiting(dependent.clone());
+                    Err(InteropError::script_module_load_error(
+                        module_path,
+                        "the module is still loading, the script will be reloaded once it is available",
+                    ))
+                }
+            }
+        })?
+    }
+}
+
+/// A hash of the content an artifact was compiled from, used to key the [`ScriptArtifactCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentHash([u8; 32]);
+
+impl ContentHash {
+    /// Hashes the given content
+    pub fn new(content: &[u8]) -> Self {
+        Self(*blake3::hash(content).as_bytes())
+    }
+
+    /// Hashes the given parts as one piece of content, for artifacts which depend on more than the script content, such as the chunk name embedded in Lua bytecode.
+    ///
+    /// Each part is prefixed with its length, so parts cannot run into each other.
+    pub fn from_parts<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        for part in parts {
+            hasher.update(&(part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        Self(*hasher.finalize().as_bytes())
+    }
+}
+
+/// A cache of artifacts compiled from script content, such as bytecode or parsed ASTs.
+///
+/// Used by the language plugins so that content which was already compiled is not compiled again when a script is (re)loaded.
+/// Artifacts are keyed on the [`ContentHash`] of the content they were compiled from, so scripts with identical content share an artifact, and the content itself is not kept around.
+/// An artifact is evicted once no script which was loaded from its content is left, i.e. when every such script was reloaded with new content or had its asset removed.
+/// The cache lives in memory only, removing the resource disables caching.
+#[derive(Resource)]
+pub struct ScriptArtifactCache<T: Send + Sync + 'static> {
+    artifacts: HashMap<ContentHash, T>,
+    scripts: HashMap<ScriptId, ContentHash>,
+}
+
+impl<T: Send + Sync + 'static> Default for ScriptArtifactCache<T> {
+    fn default() -> Self {
+        Self {
+            artifacts: Default::default(),
+            scripts: Default::default(),
+        }
+    }
+}
+
+#[profiling::all_functions]
+impl<T: Send + Sync + 'static> ScriptArtifactCache<T> {
+    /// Gets the artifact compiled from the content with the given hash, if there is one
+    pub fn get(&self, hash: &ContentHash) -> Option<&T> {
+        self.artifacts.get(hash)
+    }
+
+    /// Records that the given script was loaded from the content with the given hash, returning true if an artifact for that content is cached.
+    ///
+    /// Call this on a cache hit, so that the artifact is kept alive for as long as the script uses it.
+    pub fn track(&mut self, script_id: ScriptId, hash: ContentHash) -> bool {
+        if !self.artifacts.contains_key(&hash) {
+            return false;
+        }
+        if let Some(previous) = self.scripts.insert(script_id, hash) {
+            self.evict_if_unused(previous);
+        }
+        true
+    }
+
+    /// Inserts the artifact compiled from the content with the given hash, which the given script was loaded from.
+    ///
+    /// The artifact of the content the script was previously loaded from is evicted, unless another script still uses it.
+    pub fn insert(&mut self, script_id: ScriptId, hash: ContentHash, artifact: T) {
+        self.artifacts.insert(hash, artifact);
+        if let Some(previous) = self.scripts.insert(script_id, hash) {
+            self.evict_if_unused(previous);
+        }
+    }
+
+    /// Forgets the given script, evicting the artifact it was loaded from unless another script still uses it.
+    ///
+    /// Returns the evicted artifact if there was one.
+    pub fn remove(&mut self, script_id: &ScriptId) -> Option<T> {
+        let hash = self.scripts.remove(script_id)?;
+        self.evict_if_unused(hash)
+    }
+
+    fn evict_if_unused(&mut self, hash: ContentHash) -> Option<T> {
+        if self.scripts.values().any(|h| *h == hash) {
+            return None;
+        }
+        self.artifacts.remove(&hash)
+    }
+
+    /// Returns the number of cached artifacts
+    pub fn len(&self) -> usize {
+        self.artifacts.len()
+    }
+
+    /// Returns true if there are no cached artifacts
+    pub fn is_empty(&self) -> bool {
+        self.artifacts.is_empty()
+    }
+
+    /// Removes every cached artifact
+    pub fn clear(&mut self) {
+        self.artifacts.clear();
+        self.scripts.clear();
+    }
 }
 
 /// Converts incoming asset events, into internal script asset events, also loads and inserts metadata for newly added scripts
@@ -275,13 +780,55 @@ pub(crate) fn remove_script_metadata(
     }
 }
 
+/// Queues [`CreateOrUpdateScript`] commands for every script which imports the given module, and passes the filter.
+fn reload_dependents<P: IntoScriptPluginParams>(
+    module: &ScriptId,
+    dependency_graph: &ScriptDependencyGraph,
+    metadata_store: &ScriptMetadataStore,
+    script_assets: &Assets<ScriptAsset>,
+    commands: &mut Commands,
+    filter: impl Fn(&ScriptId) -> bool,
+) {
+    for dependent in dependency_graph.transitive_dependents(module) {
+        // libraries are only ever loaded as part of the scripts importing them
+        if dependency_graph.is_library(&dependent) || !filter(&dependent) {
+            continue;
+        }
+
+        let Some(metadata) = metadata_store
+            .get_by_script_id(&dependent)
+            .filter(|metadata| metadata.language == P::LANGUAGE)
+        else {
+            continue;
+        };
+
+        if let Some(asset) = script_assets.get(metadata.asset_id) {
+            info!(
+                "{}: Reloading Script: {:?}, as its dependency {:?} changed",
+                P::LANGUAGE,
+                dependent,
+                module
+            );
+            commands.queue(CreateOrUpdateScript::<P>::new(
+                dependent,
+                asset.content.clone(),
+                Some(Handle::Weak(metadata.asset_id)),
+            ));
+        }
+    }
+}
+
 /// Listens to [`ScriptAssetEvent`] events and dispatches [`CreateOrUpdateScript`] and [`DeleteScript`] commands accordingly.
 ///
-/// Allows for hot-reloading of scripts.
+/// Allows for hot-reloading of scripts, scripts are also reloaded whenever a module they import changes, or fails to load.
 #[profiling::function]
 pub(crate) fn sync_script_data<P: IntoScriptPluginParams>(
     mut events: EventReader<ScriptAssetEvent>,
+    mut failed_events: EventReader<AssetLoadFailedEvent<ScriptAsset>>,
     script_assets: Res<Assets<ScriptAsset>>,
+    metadata_store: Res<ScriptMetadataStore>,
+    settings: Res<ScriptAssetSettings>,
+    mut dependency_graph: ResMut<ScriptDependencyGraph>,
     mut commands: Commands,
 ) {
     for event in events.read() {
@@ -308,22 +855,53 @@ pub(crate) fn sync_script_data<P: IntoScriptPluginParams>(
                     continue;
                 }
 
-                info!("{}: Loading Script: {:?}", P::LANGUAGE, metadata.script_id,);
-
-                if let Some(asset) = script_assets.get(metadata.asset_id) {
-                    commands.queue(CreateOrUpdateScript::<P>::new(
-                        metadata.script_id.clone(),
-                        asset.content.clone(),
-                        Some(script_assets.reserve_handle().clone_weak()),
-                    ));
+                if dependency_graph.is_library(&metadata.script_id) {
+                    debug!(
+                        "{}: Script asset with id: {} is only imported by other scripts, not loading it on its own.",
+                        P::LANGUAGE,
+                        metadata.script_id
+                    );
+                } else {
+                    info!("{}: Loading Script: {:?}", P::LANGUAGE, metadata.script_id,);
+
+                    if let Some(asset) = script_assets.get(metadata.asset_id) {
+                        commands.queue(CreateOrUpdateScript::<P>::new(
+                            metadata.script_id.clone(),
+                            asset.content.clone(),
+                            Some(Handle::Weak(metadata.asset_id)),
+                        ));
+                    }
                 }
+
+                reload_dependents::<P>(
+                    &metadata.script_id,
+                    &dependency_graph,
+                    &metadata_store,
+                    &script_assets,
+                    &mut commands,
+                    |_| true,
+                );
             }
             ScriptAssetEvent::Removed(_) => {
                 info!("{}: Deleting Script: {:?}", P::LANGUAGE, metadata.script_id,);
+                dependency_graph.clear_dependencies(&metadata.script_id);
                 commands.queue(DeleteScript::<P>::new(metadata.script_id.clone()));
             }
         };
     }
+
+    // scripts waiting on a module which failed to load, are reloaded to report the failure
+    for event in failed_events.read() {
+        let module = (settings.script_id_mapper.map)(&event.path);
+        reload_dependents::<P>(
+            &module,
+            &dependency_graph,
+            &metadata_store,
+            &script_assets,
+            &mut commands,
+            |dependent| dependency_graph.is_waiting(dependent),
+        );
+    }
 }
 
 /// Setup all the asset systems for the scripting plugin and the dependencies
@@ -349,11 +927,37 @@ pub(crate) fn configure_asset_systems(app: &mut App) -> &mut App {
     )
     .init_resource::<ScriptMetadataStore>()
     .init_resource::<ScriptAssetSettings>()
+    .init_resource::<ScriptDependencyGraph>()
     .add_event::<ScriptAssetEvent>();
 
     app
 }
 
+/// Listens to [`ScriptAssetEvent::Removed`] events and evicts the artifacts of the removed scripts.
+#[profiling::function]
+fn evict_script_artifacts<T: Send + Sync + 'static>(
+    mut events: EventReader<ScriptAssetEvent>,
+    cache: Option<ResMut<ScriptArtifactCache<T>>>,
+) {
+    let Some(mut cache) = cache else {
+        return;
+    };
+    for event in events.read() {
+        if let ScriptAssetEvent::Removed(metadata) = event {
+            cache.remove(&metadata.script_id);
+        }
+    }
+}
+
+/// Initializes the [`ScriptArtifactCache`] for artifacts of type `T`, and evicts the artifacts of script assets when they are removed
+#[profiling::function]
+pub fn init_script_artifact_cache<T: Send + Sync + 'static>(app: &mut App) -> &mut App {
+    app.init_resource::<ScriptArtifactCache<T>>().add_systems(
+        PreUpdate,
+        evict_script_artifacts::<T>.in_set(ScriptingSystemSet::ScriptMetadataRemoval),
+    )
+}
+
 /// Setup all the asset systems for the scripting plugin and the dependencies
 #[profiling::function]
 pub(crate) fn configure_asset_systems_for_plugin<P: IntoScriptPluginParams>(
@@ -396,6 +1000,8 @@ mod tests {
                 ("lua", Language::Lua),
                 ("rhai", Language::Rhai),
             ]),
+            preprocessors: Vec::default(),
+            precompiled_extensions: &[],
         }
     }
 
@@ -435,7 +1041,8 @@ mod tests {
     fn test_asset_loader_loads() {
         let loader = ScriptAssetLoader {
             extensions: &["script"],
-            preprocessor: None,
+            preprocessors: Vec::default(),
+            precompiled_extensions: &[],
         };
         let mut app = init_loader_test(loader);
 
@@ -458,14 +1065,44 @@ mod tests {
         );
     }
 
+    /// prepends a header line to the script
+    fn header_preprocessor() -> ScriptPreprocessor {
+        ScriptPreprocessor::new("header", |content, _, _| {
+            let line_count = content.split(|b| *b == b'\n').count();
+            let mut new_content = b"-- header\n".to_vec();
+            new_content.extend_from_slice(content);
+            // the header is attributed to the first line
+            let source_map =
+                ScriptSourceMap::new(std::iter::once(1).chain(1..=line_count).collect());
+            Ok(PreprocessedScript::new(new_content).with_source_map(source_map))
+        })
+    }
+
+    /// replaces the keys of the defines with their values
+    fn defines_preprocessor() -> ScriptPreprocessor {
+        ScriptPreprocessor::new("defines", |content, _, settings| {
+            let mut content =
+                String::from_utf8(content.to_vec()).map_err(ScriptError::new_external)?;
+            for (key, value) in &settings.defines {
+                content = content.replace(key, value);
+            }
+            Ok(PreprocessedScript::new(content.into_bytes()))
+        })
+    }
+
     #[test]
-    fn test_asset_loader_applies_preprocessor() {
+    fn test_asset_loader_applies_preprocessors_in_order() {
         let loader = ScriptAssetLoader {
             extensions: &["script"],
-            preprocessor: Some(Box::new(|content| {
-                content[0] = b'p';
-                Ok(())
-            })),
+            preprocessors: vec![
+                ScriptPreprocessor::new("replace", |content, _, _| {
+                    let mut content = content.to_vec();
+                    content[0] = b'p';
+                    Ok(PreprocessedScript::new(content))
+                }),
+                header_preprocessor(),
+            ],
+            precompiled_extensions: &[],
         };
         let mut app = init_loader_test(loader);
 
@@ -483,7 +1120,103 @@ mod tests {
         );
         assert_eq!(
             String::from_utf8(asset.content.clone().to_vec()).unwrap(),
-            "pest script".to_string()
+            "-- header\npest script".to_string()
+        );
+        assert_eq!(asset.source_map, Some(ScriptSourceMap::new(vec![1, 1])));
+    }
+
+    #[test]
+    fn test_asset_loader_reads_settings_from_meta_files() {
+        let loader = ScriptAssetLoader {
+            extensions: &["script"],
+            preprocessors: vec![header_preprocessor(), defines_preprocessor()],
+            precompiled_extensions: &[],
+        };
+        let mut app = init_loader_test(loader);
+
+        let handle = load_asset(&mut app, "test_assets/test_script_with_meta.script");
+        let asset = app
+            .world()
+            .get_resource::<Assets<ScriptAsset>>()
+            .unwrap()
+            .get(&handle)
+            .unwrap();
+
+        // only the preprocessors selected in the meta file run
+        assert_eq!(
+            String::from_utf8(asset.content.clone().to_vec()).unwrap(),
+            "meta script".to_string()
+        );
+        assert_eq!(asset.source_map, None);
+    }
+
+    #[test]
+    fn test_asset_loader_does_not_preprocess_precompiled_scripts() {
+        let loader = ScriptAssetLoader {
+            extensions: &["script", "precompiled"],
+            preprocessors: vec![header_preprocessor()],
+            precompiled_extensions: &["precompiled"],
+        };
+        let mut app = init_loader_test(loader);
+
+        let handle = load_asset(&mut app, "test_assets/test_script.precompiled");
+        let asset = app
+            .world()
+            .get_resource::<Assets<ScriptAsset>>()
+            .unwrap()
+            .get(&handle)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(asset.content.clone().to_vec()).unwrap(),
+            "test script".to_string()
+        );
+        assert_eq!(asset.source_map, None);
+    }
+
+    #[test]
+    fn test_source_map_composes_and_remaps_lines() {
+        // the first step duplicates the second line, the second step removes the first line
+        let first = ScriptSourceMap::new(vec![1, 2, 2, 3]);
+        let second = ScriptSourceMap::new(vec![2, 3, 4]);
+        let source_map = first.then(&second);
+
+        assert_eq!(source_map, ScriptSourceMap::new(vec![2, 2, 3]));
+        assert_eq!(source_map.original_line(3), Some(3));
+        assert_eq!(source_map.original_line(0), None);
+        assert_eq!(source_map.original_line(4), None);
+
+        assert_eq!(
+            source_map.remap_line_references(
+                "script.lua",
+                "[string \"script.lua\"]:1: attempt to call a nil value, at line 3, 42 times"
+            ),
+            "[string \"script.lua\"]:2: attempt to call a nil value, at line 3, 42 times"
+        );
+        assert_eq!(
+            source_map
+                .remap_line_references("script.rhai", "Variable not found: x (line 1, position 5)"),
+            "Variable not found: x (line 2, position 5)"
+        );
+    }
+
+    #[test]
+    fn test_source_map_leaves_references_to_other_scripts_alone() {
+        let source_map = ScriptSourceMap::new(vec![2, 3, 4]);
+
+        assert_eq!(
+            source_map.remap_line_references(
+                "script.lua",
+                "[string \"module.lua\"]:1: boom\nstack traceback:\n\t[string \"module.lua\"]:1: in main chunk\n\t[string \"script.lua\"]:3: in main chunk"
+            ),
+            "[string \"module.lua\"]:1: boom\nstack traceback:\n\t[string \"module.lua\"]:1: in main chunk\n\t[string \"script.lua\"]:4: in main chunk"
+        );
+        assert_eq!(
+            source_map.remap_line_references(
+                "script.rhai",
+                "Variable not found: x (line 1, position 5)\nin module 'module.rhai' (line 2, position 1)"
+            ),
+            "Variable not found: x (line 1, position 5)\nin module 'module.rhai' (line 3, position 1)"
         );
     }
 
@@ -503,6 +1236,87 @@ mod tests {
         assert_eq!(store.remove(id), Some(meta));
     }
 
+    #[test]
+    fn test_artifact_cache() {
+        let mut cache = ScriptArtifactCache::<Vec<u8>>::default();
+        assert!(cache.is_empty());
+        let script_id = ScriptId::from("script.lua");
+        let other_id = ScriptId::from("other.lua");
+        let hash = ContentHash::new(b"script");
+        let changed_hash = ContentHash::new(b"changed script");
+
+        assert_ne!(hash, ContentHash::new(b"scrip"));
+        assert_ne!(
+            ContentHash::from_parts([b"ab".as_slice(), b"c".as_slice()]),
+            ContentHash::from_parts([b"a".as_slice(), b"bc".as_slice()])
+        );
+
+        assert!(!cache.track(script_id.clone(), hash));
+        cache.insert(script_id.clone(), hash, b"artifact".to_vec());
+        assert_eq!(cache.get(&hash), Some(&b"artifact".to_vec()));
+        assert_eq!(cache.get(&changed_hash), None);
+
+        // scripts with identical content share the artifact
+        assert!(cache.track(other_id.clone(), hash));
+        assert_eq!(cache.len(), 1);
+
+        // reloading with new content keeps the artifact the other script still uses
+        cache.insert(script_id.clone(), changed_hash, b"new artifact".to_vec());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&hash), Some(&b"artifact".to_vec()));
+        assert_eq!(cache.get(&changed_hash), Some(&b"new artifact".to_vec()));
+
+        // and evicts it once no script uses it
+        assert!(cache.track(other_id.clone(), changed_hash));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&hash), None);
+
+        assert_eq!(cache.remove(&script_id), None);
+        assert_eq!(cache.remove(&other_id), Some(b"new artifact".to_vec()));
+        assert!(cache.is_empty());
+
+        cache.insert(script_id.clone(), hash, b"artifact".to_vec());
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let mut graph = ScriptDependencyGraph::default();
+        let script: ScriptId = "script.lua".into();
+        let module: ScriptId = "module.lua".into();
+        let nested_module: ScriptId = "nested_module.lua".into();
+
+        graph.add_dependency(script.clone(), module.clone(), Handle::default(), true);
+        graph.add_dependency(
+            module.clone(),
+            nested_module.clone(),
+            Handle::default(),
+            false,
+        );
+        graph.mark_waiting(script.clone());
+
+        assert!(graph.is_library(&module));
+        assert!(!graph.is_library(&nested_module));
+        assert!(graph.is_waiting(&script));
+        assert_eq!(
+            graph.dependencies(&script).collect::<Vec<_>>(),
+            vec![&module]
+        );
+        assert_eq!(
+            graph.dependents(&nested_module).collect::<Vec<_>>(),
+            vec![&module]
+        );
+
+        let mut dependents = graph.transitive_dependents(&nested_module);
+        dependents.sort();
+        assert_eq!(dependents, vec![module.clone(), script.clone()]);
+
+        graph.clear_dependencies(&script);
+        assert!(!graph.is_waiting(&script));
+        assert_eq!(graph.transitive_dependents(&nested_module), vec![module]);
+    }
+
     #[test]
     fn test_script_asset_settings_select_language() {
         let settings = make_test_settings();
@@ -584,7 +1398,8 @@ mod tests {
         // test metadata flow
         let mut app = init_loader_test(ScriptAssetLoader {
             extensions: &[],
-            preprocessor: None,
+            preprocessors: Vec::default(),
+            precompiled_extensions: &[],
         });
         app.world_mut().insert_resource(make_test_settings());
         configure_asset_systems(&mut app);
@@ -1,13 +1,16 @@
 //! A map of access claims used to safely and dynamically access the world.
 
-use std::hash::{BuildHasherDefault, Hasher};
+use std::{
+    hash::{BuildHasherDefault, Hasher},
+    thread::ThreadId,
+};
 
 use bevy::{
     ecs::{component::ComponentId, world::unsafe_world_cell::UnsafeWorldCell},
     prelude::Resource,
     utils::hashbrown::{HashMap, HashSet},
 };
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex};
 use smallvec::SmallVec;
 
 use crate::error::InteropError;
@@ -18,6 +21,18 @@ use super::{ReflectAllocationId, ReflectBase};
 /// An owner of an access claim and the code location of the claim.
 pub struct ClaimOwner {
     location: std::panic::Location<'static>,
+    /// The thread which claimed the access
+    thread: ThreadId,
+}
+
+impl ClaimOwner {
+    #[track_caller]
+    fn new() -> Self {
+        Self {
+            location: *std::panic::Location::caller(),
+            thread: std::thread::current().id(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -59,6 +74,44 @@ impl AccessCount {
     fn readers(&self) -> usize {
         self.read_by.len()
     }
+
+    /// Removes the most recent claim made by the current thread.
+    ///
+    /// Releasing a claim the current thread does not hold is an invariant violation,
+    /// the claims of other threads are left untouched and an error is logged instead.
+    fn release_one(&mut self) {
+        let thread = std::thread::current().id();
+        match self.read_by.iter().rposition(|o| o.thread == thread) {
+            Some(idx) => {
+                self.read_by.remove(idx);
+                // a write claim is always the only claim
+                self.written = false;
+            }
+            None => {
+                bevy::log::error!(
+                    "Trying to release an access which was not claimed by the current thread, this is a bug"
+                );
+            }
+        }
+    }
+
+    /// Combines the claims of other threads from `current` with the claims of `thread` from `backup`
+    fn restore_thread_claims(current: &Self, backup: &Self, thread: ThreadId) -> Self {
+        let is_written_by = |count: &Self, f: &dyn Fn(ThreadId) -> bool| {
+            count.written && count.read_by.first().is_some_and(|o| f(o.thread))
+        };
+        Self {
+            read_by: current
+                .read_by
+                .iter()
+                .filter(|o| o.thread != thread)
+                .chain(backup.read_by.iter().filter(|o| o.thread == thread))
+                .cloned()
+                .collect(),
+            written: is_written_by(current, &|t| t != thread)
+                || is_written_by(backup, &|t| t == thread),
+        }
+    }
 }
 
 /// For structs which can be mapped to a u64 index
@@ -232,7 +285,9 @@ impl From<ReflectAccessId> for ReflectAllocationId {
 
 #[derive(Debug, Default)]
 /// A map of access claims
-pub struct AccessMap(Mutex<AccessMapInner>);
+///
+/// The condition variable is notified whenever claims are released, so threads of a parallel access section can wait for each other.
+pub struct AccessMap(Mutex<AccessMapInner>, Condvar);
 
 /// A trait for controlling system world access at runtime.
 ///
@@ -279,18 +334,26 @@ pub trait DynamicSystemMeta {
 
     /// Releases an access claimed for the provided key.
     ///
-    /// # Panics
-    ///
-    /// Panics if the access is released by a thread different from the one that claimed it.
+    /// Only claims made by the current thread are released, releasing an access claimed by another thread does nothing.
     fn release_access<K: AccessMapKey>(&self, key: K);
 
     /// Releases an active global access lock.
     ///
-    /// # Panics
-    ///
-    /// Panics if the global access is released from a thread other than the one that claimed it.
+    /// Releasing a global access claimed by another thread does nothing.
     fn release_global_access(&self);
 
+    /// Starts a section in which multiple threads access the world through the same map, until [`Self::end_parallel_access`] is called.
+    ///
+    /// Within the section, global access requests are serialised instead of failing whenever another thread holds a claim:
+    /// - a thread holding no claims which requests global access waits until the other threads release their claims
+    /// - a thread holding no claims which requests any other access waits until pending and active global accesses are released
+    ///
+    /// Threads holding claims never wait, their conflicting requests fail like outside of the section.
+    fn begin_parallel_access(&self);
+
+    /// Ends a section started by [`Self::begin_parallel_access`].
+    fn end_parallel_access(&self);
+
     /// Returns a list of active accesses.
     ///
     /// The list is provided as key and corresponding access count pairs.
@@ -346,6 +409,10 @@ impl Hasher for SmallIdentityHash {
 struct AccessMapInner {
     individual_accesses: HashMap<u64, AccessCount, BuildHasherDefault<SmallIdentityHash>>,
     global_lock: AccessCount,
+    /// While above zero, threads share the map and wait for each other instead of failing to claim global access
+    parallel_sections: usize,
+    /// The number of threads waiting to claim global access
+    pending_global_accesses: usize,
 }
 
 #[profiling::all_functions]
@@ -369,6 +436,56 @@ impl AccessMapInner {
     fn remove(&mut self, key: u64) {
         self.individual_accesses.remove(&key);
     }
+
+    /// Returns true if the given thread holds any individual or global claim
+    fn holds_claims(&self, thread: ThreadId) -> bool {
+        self.global_lock
+            .read_by
+            .iter()
+            .chain(
+                self.individual_accesses
+                    .values()
+                    .flat_map(|a| a.read_by.iter()),
+            )
+            .any(|o| o.thread == thread)
+    }
+
+    /// Returns true if a thread without claims should wait before claiming an individual access
+    fn must_wait_for_global_access(&self) -> bool {
+        self.parallel_sections > 0
+            && (!self.global_lock.can_write() || self.pending_global_accesses > 0)
+    }
+
+    /// Returns true if a thread without claims should wait before claiming global access
+    fn must_wait_for_individual_accesses(&self) -> bool {
+        self.parallel_sections > 0
+            && (!self.individual_accesses.is_empty() || !self.global_lock.can_write())
+    }
+
+    /// Rolls back the claims of the given thread to the state in `backup`, leaving the claims of other threads untouched
+    fn restore_thread_claims(&mut self, backup: &AccessMapInner, thread: ThreadId) {
+        let keys = self
+            .individual_accesses
+            .keys()
+            .chain(backup.individual_accesses.keys())
+            .copied()
+            .collect::<HashSet<_>>();
+        let empty = AccessCount::default();
+        for key in keys {
+            let restored = AccessCount::restore_thread_claims(
+                self.entry(key).unwrap_or(&empty),
+                backup.entry(key).unwrap_or(&empty),
+                thread,
+            );
+            if restored.readers() == 0 {
+                self.remove(key);
+            } else {
+                self.individual_accesses.insert(key, restored);
+            }
+        }
+        self.global_lock =
+            AccessCount::restore_thread_claims(&self.global_lock, &backup.global_lock, thread);
+    }
 }
 
 const GLOBAL_KEY: u64 = 0;
@@ -384,11 +501,12 @@ impl DynamicSystemMeta for AccessMap {
 
         let result = f();
 
-        // Roll back the inner state.
+        // Roll back the claims made by this thread, other threads might have claimed or released accesses in the meantime.
         {
             let mut inner = self.0.lock();
-            *inner = backup;
+            inner.restore_thread_claims(&backup, std::thread::current().id());
         }
+        self.1.notify_all();
 
         result
     }
@@ -407,6 +525,10 @@ impl DynamicSystemMeta for AccessMap {
     #[track_caller]
     fn claim_read_access<K: AccessMapKey>(&self, key: K) -> bool {
         let mut inner = self.0.lock();
+        if inner.must_wait_for_global_access() && !inner.holds_claims(std::thread::current().id()) {
+            self.1
+                .wait_while(&mut inner, |inner| inner.must_wait_for_global_access());
+        }
 
         if !inner.global_lock.can_write() {
             return false;
@@ -421,9 +543,7 @@ impl DynamicSystemMeta for AccessMap {
         let entry = inner.entry_or_default(key);
 
         if entry.can_read() {
-            entry.read_by.push(ClaimOwner {
-                location: *std::panic::Location::caller(),
-            });
+            entry.read_by.push(ClaimOwner::new());
             true
         } else {
             false
@@ -433,6 +553,10 @@ impl DynamicSystemMeta for AccessMap {
     #[track_caller]
     fn claim_write_access<K: AccessMapKey>(&self, key: K) -> bool {
         let mut inner = self.0.lock();
+        if inner.must_wait_for_global_access() && !inner.holds_claims(std::thread::current().id()) {
+            self.1
+                .wait_while(&mut inner, |inner| inner.must_wait_for_global_access());
+        }
 
         if !inner.global_lock.can_write() {
             return false;
@@ -447,9 +571,7 @@ impl DynamicSystemMeta for AccessMap {
         let entry = inner.entry_or_default(key);
 
         if entry.can_write() {
-            entry.read_by.push(ClaimOwner {
-                location: *std::panic::Location::caller(),
-            });
+            entry.read_by.push(ClaimOwner::new());
             entry.written = true;
             true
         } else {
@@ -460,13 +582,22 @@ impl DynamicSystemMeta for AccessMap {
     #[track_caller]
     fn claim_global_access(&self) -> bool {
         let mut inner = self.0.lock();
+        if inner.must_wait_for_individual_accesses()
+            && !inner.holds_claims(std::thread::current().id())
+        {
+            inner.pending_global_accesses += 1;
+            self.1.wait_while(&mut inner, |inner| {
+                inner.must_wait_for_individual_accesses()
+            });
+            inner.pending_global_accesses -= 1;
+            // threads waiting for this global access to be claimed re-check their conditions
+            self.1.notify_all();
+        }
 
         if !inner.individual_accesses.is_empty() || !inner.global_lock.can_write() {
             return false;
         }
-        inner.global_lock.read_by.push(ClaimOwner {
-            location: *std::panic::Location::caller(),
-        });
+        inner.global_lock.read_by.push(ClaimOwner::new());
         inner.global_lock.written = true;
         true
     }
@@ -476,18 +607,31 @@ impl DynamicSystemMeta for AccessMap {
         let key = key.as_index();
 
         if let Some(entry) = inner.entry_mut(key) {
-            entry.written = false;
-            entry.read_by.pop();
+            entry.release_one();
             if entry.readers() == 0 {
                 inner.remove(key);
             }
         }
+        drop(inner);
+        self.1.notify_all();
     }
 
     fn release_global_access(&self) {
         let mut inner = self.0.lock();
-        inner.global_lock.read_by.pop();
-        inner.global_lock.written = false;
+        inner.global_lock.release_one();
+        drop(inner);
+        self.1.notify_all();
+    }
+
+    fn begin_parallel_access(&self) {
+        self.0.lock().parallel_sections += 1;
+    }
+
+    fn end_parallel_access(&self) {
+        let mut inner = self.0.lock();
+        inner.parallel_sections = inner.parallel_sections.saturating_sub(1);
+        drop(inner);
+        self.1.notify_all();
     }
 
     fn list_accesses<K: AccessMapKey>(&self) -> Vec<(K, AccessCount)> {
@@ -514,6 +658,8 @@ impl DynamicSystemMeta for AccessMap {
         // Release global access if held.
         inner.global_lock.written = false;
         inner.global_lock.read_by.clear();
+        drop(inner);
+        self.1.notify_all();
     }
 
     fn access_location<K: AccessMapKey>(&self, key: K) -> Option<std::panic::Location<'static>> {
@@ -548,6 +694,7 @@ impl DynamicSystemMeta for AccessMap {
 pub struct SubsetAccessMap {
     inner: AccessMap,
     subset: Box<dyn Fn(u64) -> bool + Send + Sync + 'static>,
+    read_only: HashSet<u64>,
 }
 
 #[profiling::all_functions]
@@ -564,9 +711,20 @@ impl SubsetAccessMap {
         Self {
             inner: Default::default(),
             subset: Box::new(move |id| set.contains(&id) || exception(id)),
+            read_only: Default::default(),
         }
     }
 
+    /// Restricts the given keys in the subset to read access only, write access to them can never be claimed.
+    pub fn with_read_only(
+        mut self,
+        read_only: impl IntoIterator<Item = impl AccessMapKey>,
+    ) -> Self {
+        self.read_only
+            .extend(read_only.into_iter().map(|k| k.as_index()));
+        self
+    }
+
     fn in_subset(&self, key: u64) -> bool {
         (self.subset)(key)
     }
@@ -594,7 +752,7 @@ impl DynamicSystemMeta for SubsetAccessMap {
     }
 
     fn claim_write_access<K: AccessMapKey>(&self, key: K) -> bool {
-        if !self.in_subset(key.as_index()) {
+        if !self.in_subset(key.as_index()) || self.read_only.contains(&key.as_index()) {
             return false;
         }
         self.inner.claim_write_access(key)
@@ -615,6 +773,14 @@ impl DynamicSystemMeta for SubsetAccessMap {
         self.inner.release_global_access();
     }
 
+    fn begin_parallel_access(&self) {
+        self.inner.begin_parallel_access();
+    }
+
+    fn end_parallel_access(&self) {
+        self.inner.end_parallel_access();
+    }
+
     fn list_accesses<K: AccessMapKey>(&self) -> Vec<(K, AccessCount)> {
         self.inner.list_accesses()
     }
@@ -704,6 +870,20 @@ impl DynamicSystemMeta for AnyAccessMap {
         }
     }
 
+    fn begin_parallel_access(&self) {
+        match self {
+            AnyAccessMap::UnlimitedAccessMap(map) => map.begin_parallel_access(),
+            AnyAccessMap::SubsetAccessMap(map) => map.begin_parallel_access(),
+        }
+    }
+
+    fn end_parallel_access(&self) {
+        match self {
+            AnyAccessMap::UnlimitedAccessMap(map) => map.end_parallel_access(),
+            AnyAccessMap::SubsetAccessMap(map) => map.end_parallel_access(),
+        }
+    }
+
     fn list_accesses<K: AccessMapKey>(&self) -> Vec<(K, AccessCount)> {
         match self {
             AnyAccessMap::UnlimitedAccessMap(map) => map.list_accesses(),
@@ -852,6 +1032,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 1 || id == 2),
+            read_only: Default::default(),
         };
 
         subset_access_map.claim_read_access(1);
@@ -886,6 +1067,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 1),
+            read_only: Default::default(),
         };
 
         assert!(subset_access_map.claim_read_access(1));
@@ -894,6 +1076,16 @@ mod test {
         assert!(subset_access_map.claim_write_access(1));
     }
 
+    #[test]
+    fn subset_access_map_read_only_blocks_write() {
+        let subset_access_map =
+            SubsetAccessMap::new([1u64, 2u64], |_| false).with_read_only([1u64]);
+
+        assert!(!subset_access_map.claim_write_access(1));
+        assert!(subset_access_map.claim_read_access(1));
+        assert!(subset_access_map.claim_write_access(2));
+    }
+
     #[test]
     fn access_map_write_access_blocks_read() {
         let access_map = AccessMap::default();
@@ -910,6 +1102,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 1),
+            read_only: Default::default(),
         };
 
         assert!(subset_access_map.claim_write_access(1));
@@ -937,6 +1130,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 1 || id == 0),
+            read_only: Default::default(),
         };
 
         assert!(subset_access_map.claim_global_access());
@@ -966,6 +1160,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 0 || id == 1),
+            read_only: Default::default(),
         };
 
         assert!(subset_access_map.claim_read_access(1));
@@ -1044,6 +1239,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 1 || id == 2 || id == 3),
+            read_only: Default::default(),
         };
 
         // Claim a read access outside the scope
@@ -1082,12 +1278,114 @@ mod test {
         assert_eq!(accesses.len(), 0);
     }
 
+    #[test]
+    fn access_map_with_scope_only_unrolls_accesses_of_current_thread() {
+        let access_map = AccessMap::default();
+
+        access_map.with_scope(|| {
+            assert!(access_map.claim_write_access(1));
+            std::thread::scope(|s| {
+                s.spawn(|| {
+                    assert!(!access_map.claim_read_access(1));
+                    assert!(access_map.claim_write_access(2));
+                    assert!(access_map.claim_read_access(3));
+                    access_map.release_access(3);
+                });
+            });
+        });
+
+        let accesses = access_map.list_accesses::<u64>();
+        assert_eq!(accesses.len(), 1);
+        assert_eq!(accesses[0].0, 2);
+        assert!(!access_map.claim_read_access(2));
+    }
+
+    #[test]
+    fn access_map_does_not_release_claims_of_other_threads() {
+        let access_map = AccessMap::default();
+
+        assert!(access_map.claim_read_access(1));
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                access_map.release_access(1);
+                assert!(!access_map.claim_write_access(1));
+            });
+        });
+
+        assert_eq!(access_map.list_accesses::<u64>().len(), 1);
+        access_map.release_access(1);
+        assert!(access_map.claim_write_access(1));
+    }
+
+    #[test]
+    fn access_map_global_access_waits_for_other_threads_in_parallel_section() {
+        let access_map = AccessMap::default();
+        access_map.begin_parallel_access();
+
+        assert!(access_map.claim_read_access(1));
+        std::thread::scope(|s| {
+            let handle = s.spawn(|| {
+                let claimed = access_map.claim_global_access();
+                access_map.release_global_access();
+                claimed
+            });
+            while access_map.0.lock().pending_global_accesses == 0 {
+                std::thread::yield_now();
+            }
+
+            // threads holding claims cannot wait, nor be made to wait
+            assert!(!access_map.claim_global_access());
+            assert!(access_map.claim_read_access(2));
+            access_map.release_access(2);
+
+            access_map.release_access(1);
+            assert!(handle.join().unwrap());
+        });
+
+        access_map.end_parallel_access();
+        assert_eq!(access_map.count_accesses(), 0);
+    }
+
+    #[test]
+    fn access_map_accesses_wait_for_global_access_in_parallel_section() {
+        let access_map = AccessMap::default();
+        access_map.begin_parallel_access();
+
+        assert!(access_map.claim_global_access());
+        std::thread::scope(|s| {
+            let handle = s.spawn(|| {
+                let claimed = access_map.claim_write_access(1);
+                access_map.release_access(1);
+                claimed
+            });
+
+            // the thread holding global access still cannot claim anything else
+            assert!(!access_map.claim_read_access(1));
+            access_map.release_global_access();
+            assert!(handle.join().unwrap());
+        });
+
+        access_map.end_parallel_access();
+        assert_eq!(access_map.count_accesses(), 0);
+    }
+
+    #[test]
+    fn access_map_global_access_fails_outside_parallel_section() {
+        let access_map = AccessMap::default();
+
+        assert!(access_map.claim_read_access(1));
+        std::thread::scope(|s| {
+            s.spawn(|| assert!(!access_map.claim_global_access()));
+        });
+    }
+
     #[test]
     fn subset_map_with_scope_unrolls_global_accesses() {
         let access_map = AccessMap::default();
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 0 || id == 1),
+            read_only: Default::default(),
         };
 
         subset_access_map.with_scope(|| {
@@ -1130,6 +1428,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 0 || id == 1 || id == 2),
+            read_only: Default::default(),
         };
 
         // Initially, no accesses are active.
@@ -1179,6 +1478,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 0 || id == 1 || id == 2),
+            read_only: Default::default(),
         };
 
         assert!(subset_access_map.claim_global_access());
@@ -1204,6 +1504,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 1),
+            read_only: Default::default(),
         };
 
         assert!(!subset_access_map.claim_read_access(2));
@@ -1217,6 +1518,7 @@ mod test {
         let subset_access_map = SubsetAccessMap {
             inner: access_map,
             subset: Box::new(|id| id == 1),
+            read_only: Default::default(),
         };
 
         subset_access_map.with_scope(|| {
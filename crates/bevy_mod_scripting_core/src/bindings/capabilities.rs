@@ -0,0 +1,509 @@
+//! Per-script capability policies, used to sandbox scripts which should not have full access to the world.
+//!
+//! By default scripts are unrestricted. Policies are looked up by [`ScriptId`] from the [`AppScriptCapabilityRegistry`]
+//! whenever a script is loaded, reloaded or has one of its callbacks invoked, and are enforced by the [`WorldAccessGuard`]
+//! handed to the script.
+
+use super::{
+    function::namespace::Namespace, pretty_print::DisplayWithWorld, ReflectBase, ReflectReference,
+    WorldAccessGuard, WorldGuard,
+};
+use crate::{error::InteropError, script::ScriptId};
+use bevy::{
+    ecs::{component::ComponentId, entity::Entity, system::Resource},
+    utils::{HashMap, HashSet},
+};
+use parking_lot::RwLock;
+use std::{any::TypeId, borrow::Cow, sync::Arc};
+
+/// A set of capabilities granted to a script.
+///
+/// The default value grants everything, use [`ScriptCapabilities::restricted`] to start from a policy which only allows
+/// basic reference operations, and widen it from there.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScriptCapabilities {
+    /// If set, only registered functions in these namespaces (or in `allowed_functions`) can be called
+    pub allowed_namespaces: Option<HashSet<Namespace>>,
+    /// If set, only these registered functions (or functions in `allowed_namespaces`) can be called
+    pub allowed_functions: Option<HashSet<(Namespace, Cow<'static, str>)>>,
+    /// Components which the script can read but not modify, insert or remove
+    pub read_only_components: HashSet<ComponentId>,
+    /// Resources which the script can read but not modify or remove
+    pub read_only_resources: HashSet<ComponentId>,
+    /// Entities which the script cannot access at all
+    pub denied_entities: HashSet<Entity>,
+}
+
+#[profiling::all_functions]
+impl ScriptCapabilities {
+    /// Creates a policy which does not restrict the script in any way
+    pub fn unrestricted() -> Self {
+        Self::default()
+    }
+
+    /// Creates a policy which only allows calling functions in the [`ReflectReference`] namespace, i.e. basic operations on references.
+    ///
+    /// Reference access is still subject to the read-only and entity rules of the policy.
+    pub fn restricted() -> Self {
+        Self {
+            allowed_namespaces: Some(HashSet::from_iter([Namespace::OnType(TypeId::of::<
+                ReflectReference,
+            >())])),
+            allowed_functions: Some(Default::default()),
+            ..Default::default()
+        }
+    }
+
+    /// Allows calling every function registered in the given namespace, enabling the function allowlist if it was disabled
+    pub fn allow_namespace(mut self, namespace: Namespace) -> Self {
+        self.allowed_namespaces
+            .get_or_insert_with(Default::default)
+            .insert(namespace);
+        self.allowed_functions.get_or_insert_with(Default::default);
+        self
+    }
+
+    /// Allows calling the given function (including its overloads), enabling the function allowlist if it was disabled
+    pub fn allow_function(
+        mut self,
+        namespace: Namespace,
+        name: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.allowed_functions
+            .get_or_insert_with(Default::default)
+            .insert((namespace, name.into()));
+        self.allowed_namespaces.get_or_insert_with(Default::default);
+        self
+    }
+
+    /// Marks the given component as read-only
+    pub fn read_only_component(mut self, component_id: ComponentId) -> Self {
+        self.read_only_components.insert(component_id);
+        self
+    }
+
+    /// Marks the given resource as read-only
+    pub fn read_only_resource(mut self, resource_id: ComponentId) -> Self {
+        self.read_only_resources.insert(resource_id);
+        self
+    }
+
+    /// Denies access to the given entity
+    pub fn deny_entity(mut self, entity: Entity) -> Self {
+        self.denied_entities.insert(entity);
+        self
+    }
+
+    /// Returns true if the function allowlists permit calling the given function.
+    ///
+    /// Overloads registered with a `-N` suffix are allowed if the base function is.
+    pub fn allows_function(&self, namespace: Namespace, name: &str) -> bool {
+        if self.allowed_namespaces.is_none() && self.allowed_functions.is_none() {
+            return true;
+        }
+
+        if self
+            .allowed_namespaces
+            .as_ref()
+            .is_some_and(|n| n.contains(&namespace))
+        {
+            return true;
+        }
+
+        let base_name = match name.rsplit_once('-') {
+            Some((base, suffix))
+                if !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                base
+            }
+            _ => name,
+        };
+
+        self.allowed_functions.as_ref().is_some_and(|f| {
+            f.contains(&(namespace, Cow::Borrowed(name)))
+                || f.contains(&(namespace, Cow::Borrowed(base_name)))
+        })
+    }
+
+    /// Returns true if the script is allowed to access the given entity
+    pub fn allows_entity(&self, entity: Entity) -> bool {
+        !self.denied_entities.contains(&entity)
+    }
+
+    /// Returns true if the script is allowed to modify the given component
+    pub fn allows_component_write(&self, component_id: ComponentId) -> bool {
+        !self.read_only_components.contains(&component_id)
+    }
+
+    /// Returns true if the script is allowed to modify the given resource
+    pub fn allows_resource_write(&self, resource_id: ComponentId) -> bool {
+        !self.read_only_resources.contains(&resource_id)
+    }
+}
+
+/// The kind of operation a script attempted which was not permitted by its [`ScriptCapabilities`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum CapabilityViolation {
+    /// The script called a function which is not in its allowlists
+    Function {
+        /// The namespace of the function
+        namespace: Namespace,
+        /// The name of the function
+        name: Cow<'static, str>,
+    },
+    /// The script tried to modify, insert or remove a read-only component
+    ReadOnlyComponent(ComponentId),
+    /// The script tried to modify or remove a read-only resource
+    ReadOnlyResource(ComponentId),
+    /// The script tried to access a denied entity
+    DeniedEntity(Entity),
+    /// The script tried to act on behalf of another script
+    ForeignScript(ScriptId),
+}
+
+#[profiling::all_functions]
+impl DisplayWithWorld for CapabilityViolation {
+    fn display_without_world(&self) -> String {
+        match self {
+            CapabilityViolation::Function { namespace, name } => match namespace {
+                Namespace::Global => format!("calling function `{name}` is not allowed"),
+                Namespace::OnType(type_id) => format!(
+                    "calling function `{name}` on type: {} is not allowed",
+                    type_id.display_without_world()
+                ),
+            },
+            CapabilityViolation::ReadOnlyComponent(component_id) => format!(
+                "component {} is read-only",
+                component_id.display_without_world()
+            ),
+            CapabilityViolation::ReadOnlyResource(resource_id) => format!(
+                "resource {} is read-only",
+                resource_id.display_without_world()
+            ),
+            CapabilityViolation::DeniedEntity(entity) => {
+                format!("access to entity {entity} is denied")
+            }
+            CapabilityViolation::ForeignScript(script_id) => {
+                format!("acting on behalf of script `{script_id}` is not allowed")
+            }
+        }
+    }
+
+    fn display_with_world(&self, world: WorldGuard) -> String {
+        match self {
+            CapabilityViolation::Function { namespace, name } => match namespace {
+                Namespace::Global => format!("calling function `{name}` is not allowed"),
+                Namespace::OnType(type_id) => format!(
+                    "calling function `{name}` on type: {} is not allowed",
+                    type_id.display_with_world(world)
+                ),
+            },
+            CapabilityViolation::ReadOnlyComponent(component_id) => format!(
+                "component {} is read-only",
+                component_id.display_with_world(world)
+            ),
+            CapabilityViolation::ReadOnlyResource(resource_id) => format!(
+                "resource {} is read-only",
+                resource_id.display_with_world(world)
+            ),
+            CapabilityViolation::DeniedEntity(entity) => {
+                format!("access to entity {entity} is denied")
+            }
+            CapabilityViolation::ForeignScript(script_id) => {
+                format!("acting on behalf of script `{script_id}` is not allowed")
+            }
+        }
+    }
+}
+
+/// A registry of capability policies for scripts
+#[derive(Default)]
+pub struct ScriptCapabilityRegistry {
+    default: Option<Arc<ScriptCapabilities>>,
+    scripts: HashMap<ScriptId, Arc<ScriptCapabilities>>,
+}
+
+#[profiling::all_functions]
+impl ScriptCapabilityRegistry {
+    /// Sets the policy used for scripts without a policy of their own, `None` leaves those scripts unrestricted
+    pub fn set_default(&mut self, capabilities: Option<ScriptCapabilities>) {
+        self.default = capabilities.map(Arc::new);
+    }
+
+    /// Sets the policy for the given script, overriding the default policy
+    pub fn insert(&mut self, script_id: impl Into<ScriptId>, capabilities: ScriptCapabilities) {
+        self.scripts
+            .insert(script_id.into(), Arc::new(capabilities));
+    }
+
+    /// Removes the policy for the given script, falling back to the default policy
+    pub fn remove(&mut self, script_id: &ScriptId) -> Option<Arc<ScriptCapabilities>> {
+        self.scripts.remove(script_id)
+    }
+
+    /// Gets the policy applying to the given script, if any
+    pub fn get(&self, script_id: &ScriptId) -> Option<Arc<ScriptCapabilities>> {
+        self.scripts
+            .get(script_id)
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+/// The [`ScriptCapabilityRegistry`] wrapped in a resource
+#[derive(Clone, Resource, Default)]
+pub struct AppScriptCapabilityRegistry(pub Arc<RwLock<ScriptCapabilityRegistry>>);
+
+#[profiling::all_functions]
+impl AppScriptCapabilityRegistry {
+    /// Reads the underlying registry
+    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, ScriptCapabilityRegistry> {
+        self.0.read()
+    }
+
+    /// Writes to the underlying registry
+    pub fn write(&self) -> parking_lot::RwLockWriteGuard<'_, ScriptCapabilityRegistry> {
+        self.0.write()
+    }
+}
+
+#[profiling::all_functions]
+impl WorldAccessGuard<'_> {
+    /// Returns a guard which enforces the capabilities of the given script, or no capabilities if the script is unrestricted.
+    ///
+    /// The returned guard shares accesses and validity with this one.
+    pub fn with_script_capabilities(&self, script_id: &ScriptId) -> Self {
+        let capabilities = self.capability_registry().read().get(script_id);
+        let mut guard = self.clone();
+        guard.capabilities = capabilities.map(|c| (script_id.clone(), c));
+        guard
+    }
+
+    /// Returns the capabilities being enforced by this guard, and the script they belong to, if any
+    pub fn capabilities(&self) -> Option<(&ScriptId, &ScriptCapabilities)> {
+        self.capabilities.as_ref().map(|(id, c)| (id, c.as_ref()))
+    }
+
+    fn check_capability(
+        &self,
+        allowed: impl FnOnce(&ScriptCapabilities) -> bool,
+        violation: impl FnOnce() -> CapabilityViolation,
+    ) -> Result<(), InteropError> {
+        match &self.capabilities {
+            Some((script_id, capabilities)) if !allowed(capabilities) => Err(
+                InteropError::capability_violation(script_id.clone(), violation()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the script is allowed to call the given function.
+    ///
+    /// Functions which are not part of the function registry, i.e. callbacks created by scripts themselves, are always allowed.
+    pub fn check_function_call(
+        &self,
+        namespace: Namespace,
+        name: &str,
+    ) -> Result<(), InteropError> {
+        self.check_capability(
+            |c| {
+                c.allows_function(namespace, name)
+                    || !self
+                        .script_function_registry()
+                        .read()
+                        .contains(namespace, name.to_owned())
+            },
+            || CapabilityViolation::Function {
+                namespace,
+                name: name.to_owned().into(),
+            },
+        )
+    }
+
+    /// Checks the script is allowed to access the given entity
+    pub fn check_entity_access(&self, entity: Entity) -> Result<(), InteropError> {
+        self.check_capability(
+            |c| c.allows_entity(entity),
+            || CapabilityViolation::DeniedEntity(entity),
+        )
+    }
+
+    /// Checks the script is allowed to modify, insert or remove the given component on the given entity
+    pub fn check_component_write(
+        &self,
+        entity: Entity,
+        component_id: ComponentId,
+    ) -> Result<(), InteropError> {
+        self.check_entity_access(entity)?;
+        self.check_component_type_write(component_id)
+    }
+
+    /// Checks the script is allowed to modify, insert or remove the given component on the entities it can access
+    pub fn check_component_type_write(
+        &self,
+        component_id: ComponentId,
+    ) -> Result<(), InteropError> {
+        self.check_capability(
+            |c| c.allows_component_write(component_id),
+            || CapabilityViolation::ReadOnlyComponent(component_id),
+        )
+    }
+
+    /// Checks the script is allowed to modify or remove the given resource
+    pub fn check_resource_write(&self, resource_id: ComponentId) -> Result<(), InteropError> {
+        self.check_capability(
+            |c| c.allows_resource_write(resource_id),
+            || CapabilityViolation::ReadOnlyResource(resource_id),
+        )
+    }
+
+    /// Checks the script is allowed to act on behalf of the given script, i.e. add observers calling it.
+    ///
+    /// Restricted scripts can only act on behalf of themselves.
+    pub fn check_script_access(&self, script_id: &ScriptId) -> Result<(), InteropError> {
+        match &self.capabilities {
+            Some((own_id, _)) if own_id != script_id => Err(InteropError::capability_violation(
+                own_id.clone(),
+                CapabilityViolation::ForeignScript(script_id.clone()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the script is allowed to access the given reflection base, mutably or immutably
+    pub fn check_base_access(&self, base: &ReflectBase, mutable: bool) -> Result<(), InteropError> {
+        match base {
+            ReflectBase::Component(entity, component_id) if mutable => {
+                self.check_component_write(*entity, *component_id)
+            }
+            ReflectBase::Component(entity, _) => self.check_entity_access(*entity),
+            ReflectBase::Resource(resource_id) if mutable => {
+                self.check_resource_write(*resource_id)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        bindings::{function::script_function::AppScriptFunctionRegistry, ScriptValue},
+        error::InteropErrorInner,
+    };
+    use bevy::{
+        ecs::{component::Component, reflect::AppTypeRegistry, world::World},
+        hierarchy::Children,
+    };
+
+    #[derive(Component)]
+    struct Comp;
+
+    fn setup_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world.init_resource::<AppScriptCapabilityRegistry>();
+        world.register_component::<Children>();
+        // entity 0 is treated as a placeholder by the world guard
+        world.spawn_empty();
+        world
+    }
+
+    #[test]
+    fn test_function_allowlists() {
+        let capabilities = ScriptCapabilities::restricted()
+            .allow_function(Namespace::Global, "allowed")
+            .allow_namespace(Namespace::OnType(TypeId::of::<World>()));
+
+        assert!(capabilities.allows_function(Namespace::Global, "allowed"));
+        assert!(capabilities.allows_function(Namespace::Global, "allowed-1"));
+        assert!(!capabilities.allows_function(Namespace::Global, "not_allowed"));
+        assert!(capabilities.allows_function(Namespace::OnType(TypeId::of::<World>()), "anything"));
+        assert!(capabilities
+            .allows_function(Namespace::OnType(TypeId::of::<ReflectReference>()), "get"));
+        assert!(ScriptCapabilities::unrestricted().allows_function(Namespace::Global, "anything"));
+    }
+
+    #[test]
+    fn test_guard_without_policy_is_unrestricted() {
+        let mut world = setup_world();
+        let entity = world.spawn_empty().id();
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let guard = guard.with_script_capabilities(&"script.lua".into());
+
+        assert!(guard.capabilities().is_none());
+        assert!(guard.check_entity_access(entity).is_ok());
+        assert_eq!(guard.get_children(entity).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_guard_enforces_script_policy() {
+        let mut world = setup_world();
+        let allowed = world.spawn_empty().id();
+        let denied = world.spawn_empty().id();
+        let component_id = world.register_component::<Comp>();
+
+        world
+            .resource::<AppScriptCapabilityRegistry>()
+            .write()
+            .insert(
+                "script.lua",
+                ScriptCapabilities::unrestricted()
+                    .deny_entity(denied)
+                    .read_only_component(component_id),
+            );
+
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let guard = guard.with_script_capabilities(&"script.lua".into());
+
+        assert!(guard.check_entity_access(allowed).is_ok());
+        let err = guard.despawn(denied).unwrap_err();
+        assert_eq!(
+            err.inner(),
+            &InteropErrorInner::CapabilityViolation {
+                script_id: "script.lua".into(),
+                violation: CapabilityViolation::DeniedEntity(denied),
+            }
+        );
+
+        let err = guard
+            .check_base_access(&ReflectBase::Component(allowed, component_id), true)
+            .unwrap_err();
+        assert_eq!(
+            err.inner(),
+            &InteropErrorInner::CapabilityViolation {
+                script_id: "script.lua".into(),
+                violation: CapabilityViolation::ReadOnlyComponent(component_id),
+            }
+        );
+        assert!(guard
+            .check_base_access(&ReflectBase::Component(allowed, component_id), false)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_default_policy_applies_to_scripts_without_policy() {
+        let mut world = setup_world();
+        world
+            .resource::<AppScriptCapabilityRegistry>()
+            .write()
+            .set_default(Some(ScriptCapabilities::restricted()));
+
+        world
+            .get_resource_or_init::<AppScriptFunctionRegistry>()
+            .write()
+            .register(Namespace::Global, "registered", || ScriptValue::Unit);
+
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let guard = guard.with_script_capabilities(&"any_script.lua".into());
+
+        assert!(guard
+            .check_function_call(Namespace::Global, "registered")
+            .is_err());
+        // functions which are not registered, i.e. script callbacks, are not subject to the allowlist
+        assert!(guard
+            .check_function_call(Namespace::Global, "unregistered")
+            .is_ok());
+    }
+}
@@ -326,6 +326,8 @@ impl<T: FromReflect> FromScript for Mut<'_, T> {
 
                 if world.claim_write_access(raid) {
                     // Safety: we just claimed write access
+                    // the value is assumed to be written to, as the caller receives a mutable reference
+                    unsafe { reflect_reference.mark_changed(world.clone()) }?;
                     let ref_ = unsafe { reflect_reference.reflect_mut_unsafe(world) }?;
                     let type_id = ref_.get_represented_type_info().map(|i| i.type_id());
                     let cast = ref_.try_downcast_mut::<T>().ok_or_else(|| {
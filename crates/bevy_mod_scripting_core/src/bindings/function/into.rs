@@ -200,3 +200,42 @@ macro_rules! impl_into_script_tuple {
 }
 
 bevy::utils::all_tuples!(impl_into_script_tuple, 1, 14, T);
+
+/// Converts a tuple of values into the arguments of a script function call, each element of the tuple becoming one argument.
+pub trait IntoScriptArgs {
+    /// Convert these values into a list of [`ScriptValue`] arguments.
+    fn into_script_args(self, world: WorldGuard) -> Result<Vec<ScriptValue>, InteropError>;
+}
+
+impl IntoScriptArgs for Vec<ScriptValue> {
+    fn into_script_args(self, _world: WorldGuard) -> Result<Vec<ScriptValue>, InteropError> {
+        Ok(self)
+    }
+}
+
+impl IntoScriptArgs for () {
+    fn into_script_args(self, _world: WorldGuard) -> Result<Vec<ScriptValue>, InteropError> {
+        Ok(Vec::default())
+    }
+}
+
+macro_rules! impl_into_script_args_tuple {
+    ($( $ty:ident ),* ) => {
+        #[allow(non_snake_case)]
+        impl<$($ty: IntoScript),*> IntoScriptArgs for ($($ty,)*) {
+            fn into_script_args(self, world: WorldGuard) -> Result<Vec<ScriptValue>, InteropError> {
+                let ($($ty,)*) = self;
+                let mut args = Vec::default();
+                $(
+                    let arg = $ty.into_script(world.clone()).map_err(|e| {
+                        InteropError::function_arg_conversion_error(args.len().to_string(), e)
+                    })?;
+                    args.push(arg);
+                )*
+                Ok(args)
+            }
+        }
+    }
+}
+
+bevy::utils::all_tuples!(impl_into_script_args_tuple, 1, 14, T);
@@ -104,8 +104,8 @@ impl MagicFunctions {
                 <Box<dyn PartialReflect>>::from_script_ref(target_type_id, value, world.clone())?;
             r.try_apply(other.as_partial_reflect())
                 .map_err(|e| InteropError::external_error(Box::new(e)))?;
-            Ok::<_, InteropError>(())
-        })?
+            Ok(())
+        })
     }
 }
 
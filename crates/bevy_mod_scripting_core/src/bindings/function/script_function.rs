@@ -68,6 +68,21 @@ impl FunctionCallContext {
     pub fn language(&self) -> Language {
         self.language.clone()
     }
+
+    /// Checks the capabilities of the calling script allow it to call the given function.
+    ///
+    /// If no world is available there is no calling script to check against, and the call is allowed.
+    #[profiling::function]
+    pub fn check_function_call(
+        &self,
+        namespace: Namespace,
+        name: &str,
+    ) -> Result<(), InteropError> {
+        match self.world() {
+            Ok(world) => world.check_function_call(namespace, name),
+            Err(_) => Ok(()),
+        }
+    }
 }
 
 #[derive(Reflect, Clone)]
@@ -110,6 +125,7 @@ impl DynamicScriptFunction {
         context: FunctionCallContext,
     ) -> Result<ScriptValue, InteropError> {
         profiling::scope!("Dynamic Call ", self.name().deref());
+        context.check_function_call(self.info.namespace, self.name())?;
         let args = args.into_iter().collect::<VecDeque<_>>();
         // should we be inlining call errors into the return value?
         let return_val = (self.func)(context, args);
@@ -146,6 +162,7 @@ impl DynamicScriptFunctionMut {
         context: FunctionCallContext,
     ) -> Result<ScriptValue, InteropError> {
         profiling::scope!("Dynamic Call Mut", self.name().deref());
+        context.check_function_call(self.info.namespace, self.name())?;
         let args = args.into_iter().collect::<VecDeque<_>>();
         // should we be inlining call errors into the return value?
         let mut write = self.func.write();
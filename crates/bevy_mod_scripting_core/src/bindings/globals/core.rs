@@ -1,19 +1,19 @@
 //! Core globals exposed by the BMS framework
 
-use std::{cell::RefCell, collections::HashMap, sync::Arc};
+use std::{any::TypeId, cell::RefCell, collections::HashMap, sync::Arc};
 
 use bevy::{
     app::Plugin,
     ecs::{entity::Entity, reflect::AppTypeRegistry, world::World},
-    reflect::TypeRegistration,
+    reflect::{TypeRegistration, Typed},
 };
 use bevy_mod_scripting_derive::script_globals;
 
 use crate::{
     bindings::{
         function::from::{Union, Val},
-        ScriptComponentRegistration, ScriptResourceRegistration, ScriptTypeRegistration,
-        WorldGuard,
+        ScriptCommands, ScriptComponentRegistration, ScriptResourceRegistration,
+        ScriptTypeRegistration, WorldGuard,
     },
     docgen::into_through_type_info,
     error::InteropError,
@@ -62,6 +62,7 @@ impl Plugin for CoreScriptGlobalsPlugin {
         // TODO: add ability to make the generated function receive generic payload
         GLOBAL_OPTS.replace(self.filter);
         register_core_globals(app.world_mut());
+        register_commands_global(app.world_mut());
     }
 }
 
@@ -102,6 +103,19 @@ fn register_static_core_globals(
     global_registry.register_dummy::<String>("script_id", "the name/id of this script");
 }
 
+/// Registers the `commands` global, which is always available regardless of the type filter
+fn register_commands_global(world: &mut World) {
+    let global_registry = world
+        .get_resource_or_init::<AppScriptGlobalsRegistry>()
+        .clone();
+    global_registry.write().register_static_documented_dynamic(
+        TypeId::of::<ScriptCommands>(),
+        into_through_type_info(ScriptCommands::type_info()),
+        "commands".into(),
+        "Queues world mutations, which are applied once the current callback or script system finishes.".into(),
+    );
+}
+
 #[script_globals(bms_core_path = "crate", name = "core_globals")]
 impl CoreGlobals {
     /// A cache of types normally available through the `world.get_type_by_name` function.
@@ -178,7 +192,8 @@ mod test {
             .read();
 
         // check that the type is not registered
-        assert!(globals.len() == 1);
+        assert!(globals.len() == 2);
         assert!(globals.get("types").is_some());
+        assert!(globals.get("commands").is_some());
     }
 }
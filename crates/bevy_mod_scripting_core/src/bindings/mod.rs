@@ -13,5 +13,15 @@ crate::private::export_all_in_modules! {
     script_value,
     world,
     script_component,
-    type_data
+    script_resource,
+    script_event,
+    script_observer,
+    script_commands,
+    type_data,
+    capabilities
 }
+
+#[cfg(feature = "bevy_state")]
+pub mod state;
+#[cfg(feature = "bevy_state")]
+pub use state::*;
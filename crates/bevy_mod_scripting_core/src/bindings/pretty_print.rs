@@ -238,14 +238,12 @@ impl ReflectReferencePrinter {
     fn pretty_print_value_inner(&self, v: &dyn PartialReflect, output: &mut String) {
         match v.reflect_ref() {
             bevy::reflect::ReflectRef::Struct(s) => {
-                let field_names = s
-                    .get_represented_struct_info()
-                    .map(|info| info.field_names())
-                    .unwrap_or_default()
-                    .iter();
+                // field names are taken from the value rather than the type info,
+                // as script registered components have fields the static type info doesn't know about
+                let field_names = (0..s.field_len()).filter_map(|i| s.name_at(i));
                 let field_values = s.iter_fields();
 
-                self.pretty_print_value_struct(field_names.copied(), field_values, output);
+                self.pretty_print_value_struct(field_names, field_values, output);
             }
             ReflectRef::TupleStruct(t) => {
                 let fields_iter = t.iter_fields().enumerate().map(|(i, val)| {
@@ -1,20 +1,32 @@
 //! Utilities for querying the world.
 
-use super::{with_global_access, DynamicComponent, ReflectReference, WorldAccessGuard, WorldGuard};
+use super::{
+    access_map::ReflectAccessId, DynamicComponent, DynamicResource, ReflectReference,
+    WorldAccessGuard, WorldGuard,
+};
 use crate::error::InteropError;
 use bevy::{
     ecs::{
-        component::ComponentId,
-        entity::Entity,
+        archetype::{ArchetypeGeneration, ArchetypeId},
+        component::{ComponentId, ComponentTicks, Tick},
+        entity::{Entity, EntityHashSet},
         query::{QueryData, QueryState},
         reflect::ReflectComponent,
-        world::World,
+        system::Resource,
+        world::{unsafe_world_cell::UnsafeWorldCell, World},
     },
-    prelude::{EntityRef, QueryBuilder},
+    hierarchy::Parent,
+    prelude::QueryBuilder,
     ptr::OwningPtr,
     reflect::{ParsedPath, Reflect, TypeRegistration},
 };
-use std::{any::TypeId, collections::VecDeque, ptr::NonNull, sync::Arc};
+use parking_lot::RwLock;
+use std::{
+    any::TypeId,
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
+    ptr::NonNull,
+    sync::Arc,
+};
 
 /// A reference to a type which is not a `Resource` or `Component`.
 ///
@@ -45,6 +57,8 @@ pub struct ScriptComponentRegistration {
 pub struct ScriptResourceRegistration {
     pub(crate) registration: ScriptTypeRegistration,
     pub(crate) resource_id: ComponentId,
+    /// whether this is a resource registered BY a script
+    pub(crate) is_dynamic_script_resource: bool,
 }
 
 #[profiling::all_functions]
@@ -83,11 +97,18 @@ impl ScriptResourceRegistration {
     /// Creates a new [`ScriptResourceRegistration`] from a [`ScriptTypeRegistration`] and a [`ComponentId`].
     pub fn new(registration: ScriptTypeRegistration, resource_id: ComponentId) -> Self {
         Self {
+            is_dynamic_script_resource: registration.type_id()
+                == std::any::TypeId::of::<DynamicResource>(),
             registration,
             resource_id,
         }
     }
 
+    /// Returns true if this is a resource registered by a script
+    pub fn is_dynamic_script_resource(&self) -> bool {
+        self.is_dynamic_script_resource
+    }
+
     /// Returns the [`ComponentId`] for this type, if it is a resource.
     #[inline(always)]
     pub fn resource_id(&self) -> ComponentId {
@@ -117,6 +138,11 @@ impl ScriptComponentRegistration {
         }
     }
 
+    /// Returns true if this is a component registered by a script
+    pub fn is_dynamic_script_component(&self) -> bool {
+        self.is_dynamic_script_component
+    }
+
     /// Returns the [`ComponentId`] for this type, if it is a component.
     #[inline(always)]
     pub fn component_id(&self) -> ComponentId {
@@ -139,6 +165,7 @@ impl ScriptComponentRegistration {
         world: WorldGuard,
         entity: Entity,
     ) -> Result<(), InteropError> {
+        world.check_component_write(entity, self.component_id)?;
         world.with_global_access(|world| {
             let mut entity = world
                 .get_entity_mut(entity)
@@ -157,17 +184,26 @@ impl ScriptComponentRegistration {
         entity: Entity,
         instance: Box<dyn Reflect>,
     ) -> Result<(), InteropError> {
+        world.check_component_write(entity, self.component_id)?;
         if self.is_dynamic_script_component {
             // if dynamic we already know the type i.e. `ScriptComponent`
-            // so we can just insert it
+            // so we can just insert it, after checking it conforms to its schema
+            let mut cast = instance.downcast::<DynamicComponent>().map_err(|v| {
+                InteropError::type_mismatch(TypeId::of::<DynamicComponent>(), Some(v.type_id()))
+            })?;
+            {
+                let component_registry = world.component_registry();
+                let component_registry = component_registry.read();
+                let info = component_registry
+                    .get_by_id(self.component_id)
+                    .ok_or_else(|| InteropError::invalid_component(self.component_id))?;
+                info.schema.validate(&info.name, &mut cast)?;
+            }
 
             world.with_global_access(|world| {
                 let mut entity = world
                     .get_entity_mut(entity)
                     .map_err(|_| InteropError::missing_entity(entity))?;
-                let cast = instance.downcast::<DynamicComponent>().map_err(|v| {
-                    InteropError::type_mismatch(TypeId::of::<DynamicComponent>(), Some(v.type_id()))
-                })?;
                 // the reason we leak the box, is because we don't want to double drop the owning ptr
 
                 let ptr = (Box::leak(cast) as *mut DynamicComponent).cast();
@@ -227,16 +263,64 @@ impl std::fmt::Display for ScriptTypeRegistration {
     }
 }
 
-#[derive(Clone, Default, Reflect)]
+#[derive(Clone, Reflect, Debug)]
+#[reflect(opaque)]
+/// A component retrieved by a [`ScriptQueryBuilder`], along with how it is accessed.
+pub struct ScriptQueryTerm {
+    /// The component retrieved
+    pub component: ScriptComponentRegistration,
+    /// If true the component is declared as written to, otherwise it's declared as read-only and non-exclusive script systems cannot write to it
+    pub mutable: bool,
+    /// If true entities without the component still match the query, and no reference is returned for it
+    pub optional: bool,
+}
+
+impl ScriptQueryTerm {
+    /// Creates a term for a required component which is only read
+    pub fn new(component: ScriptComponentRegistration) -> Self {
+        Self {
+            component,
+            mutable: false,
+            optional: false,
+        }
+    }
+
+    /// Declares the component as written to
+    pub fn mutable(mut self) -> Self {
+        self.mutable = true;
+        self
+    }
+
+    /// Makes the component optional
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+}
+
+#[derive(Clone, Reflect, Debug)]
+#[reflect(opaque)]
+/// A filter which can be grouped with others in an `Or` group, see [`ScriptQueryBuilder::any_of`]
+pub enum ScriptQueryFilter {
+    /// Matches entities with the component
+    With(ScriptComponentRegistration),
+    /// Matches entities without the component
+    Without(ScriptComponentRegistration),
+}
+
+#[derive(Clone, Default, Reflect, Debug)]
 #[reflect(opaque)]
 /// The query builder is used to build ECS queries which retrieve spefific components filtered by specific conditions.
 ///
 /// For example:
 /// ```rust,ignore
 /// builder.component(componentA)
-///     .component(componentB)
+///     .mut_component(componentB)
+///     .optional_component(componentF)
 ///     .with(componentC)
 ///     .without(componentD)  
+///     .changed(componentE)
+///     .any_of([With(componentG), Without(componentH)])
 /// ```
 ///
 /// Will retrieve entities which:
@@ -244,26 +328,52 @@ impl std::fmt::Display for ScriptTypeRegistration {
 /// - Have componentB
 /// - Have componentC
 /// - Do not have componentD
+/// - Have componentE, which was changed since the system running the query last ran
+/// - Have componentG or do not have componentH
 ///
 /// As well as references to components:
-/// - componentA
+/// - componentA, which cannot be written to by non-exclusive script systems
 /// - componentB
+/// - componentF, if the entity has it, which cannot be written to by non-exclusive script systems
+///
+/// Queries can also be restricted to a list of entities, or to the children of an entity.
 pub struct ScriptQueryBuilder {
-    pub(crate) components: Vec<ScriptComponentRegistration>,
+    pub(crate) components: Vec<ScriptQueryTerm>,
     with: Vec<ScriptComponentRegistration>,
     without: Vec<ScriptComponentRegistration>,
+    changed: Vec<ScriptComponentRegistration>,
+    added: Vec<ScriptComponentRegistration>,
+    any_of: Vec<Vec<ScriptQueryFilter>>,
+    entities: Option<Vec<Entity>>,
+    children_of: Option<Entity>,
 }
 
 #[profiling::all_functions]
 impl ScriptQueryBuilder {
     /// Adds components to the query.
     pub fn components(&mut self, components: Vec<ScriptComponentRegistration>) -> &mut Self {
-        self.components.extend(components);
+        self.components
+            .extend(components.into_iter().map(ScriptQueryTerm::new));
         self
     }
     /// Adds a component to the query.
     pub fn component(&mut self, component: ScriptComponentRegistration) -> &mut Self {
-        self.components.push(component);
+        self.term(ScriptQueryTerm::new(component))
+    }
+
+    /// Adds a component to the query which is written to.
+    pub fn mut_component(&mut self, component: ScriptComponentRegistration) -> &mut Self {
+        self.term(ScriptQueryTerm::new(component).mutable())
+    }
+
+    /// Adds a component to the query which entities do not need to have.
+    pub fn optional_component(&mut self, component: ScriptComponentRegistration) -> &mut Self {
+        self.term(ScriptQueryTerm::new(component).optional())
+    }
+
+    /// Adds a component term to the query.
+    pub fn term(&mut self, term: ScriptQueryTerm) -> &mut Self {
+        self.components.push(term);
         self
     }
 
@@ -291,14 +401,130 @@ impl ScriptQueryBuilder {
         self
     }
 
+    /// Adds a component to the query that must have been added or changed since the system running the query last ran.
+    pub fn changed_component(&mut self, changed: ScriptComponentRegistration) -> &mut Self {
+        self.changed.push(changed);
+        self
+    }
+
+    /// Adds a component to the query that must have been added since the system running the query last ran.
+    pub fn added_component(&mut self, added: ScriptComponentRegistration) -> &mut Self {
+        self.added.push(added);
+        self
+    }
+
+    /// Adds a group of filters of which at least one must match.
+    pub fn any_of(&mut self, filters: Vec<ScriptQueryFilter>) -> &mut Self {
+        self.any_of.push(filters);
+        self
+    }
+
+    /// Restricts the query to the given entities, results are returned in the order of the list.
+    pub fn entities(&mut self, entities: Vec<Entity>) -> &mut Self {
+        self.entities = Some(entities);
+        self
+    }
+
+    /// Restricts the query to the children of the given entity.
+    pub fn children_of(&mut self, parent: Entity) -> &mut Self {
+        self.children_of = Some(parent);
+        self
+    }
+
+    /// Returns the entities the query is restricted to, if any
+    pub fn restricted_to(&self) -> Option<&[Entity]> {
+        self.entities.as_deref()
+    }
+
+    /// Checks whether an entity passes the `changed` and `added` filters of the query, given a way to retrieve the ticks of its components.
+    ///
+    /// These filters cannot be expressed in a dynamically built query state, so they need to be checked against each entity it yields.
+    pub fn matches_change_filters(
+        &self,
+        ticks: impl Fn(ComponentId) -> Option<ComponentTicks>,
+        last_run: Tick,
+        this_run: Tick,
+    ) -> bool {
+        self.changed
+            .iter()
+            .all(|c| ticks(c.component_id).is_some_and(|t| t.is_changed(last_run, this_run)))
+            && self
+                .added
+                .iter()
+                .all(|c| ticks(c.component_id).is_some_and(|t| t.is_added(last_run, this_run)))
+    }
+
+    /// Returns true if the query has `changed` or `added` filters
+    pub fn has_change_filters(&self) -> bool {
+        !self.changed.is_empty() || !self.added.is_empty()
+    }
+
+    /// Checks whether an entity with the given parent passes the `children_of` filter of the query.
+    ///
+    /// Like change filters this cannot be expressed in a query state, the query only declares read access to the [`Parent`] component.
+    pub fn matches_parent(&self, parent: Option<Entity>) -> bool {
+        self.children_of
+            .is_none_or(|expected| parent.is_some_and(|parent| parent == expected))
+    }
+
+    /// Returns true if the query has a `children_of` filter
+    pub fn has_parent_filter(&self) -> bool {
+        self.children_of.is_some()
+    }
+
+    /// Creates the result of the query for an entity, given a way to check which of the components it has
+    pub fn result_for(
+        &self,
+        entity: Entity,
+        has_component: impl Fn(ComponentId) -> bool,
+    ) -> ScriptQueryResult {
+        ScriptQueryResult {
+            entity,
+            components: self
+                .components
+                .iter()
+                .map(|term| {
+                    (!term.optional || has_component(term.component.component_id)).then(|| {
+                        ReflectReference {
+                            base: super::ReflectBaseType {
+                                type_id: term.component.type_registration().type_id(),
+                                base_id: super::ReflectBase::Component(
+                                    entity,
+                                    term.component.component_id,
+                                ),
+                            },
+                            reflect_path: ParsedPath(vec![]),
+                        }
+                    })
+                })
+                .collect(),
+        }
+    }
+
     /// Builds the query into a query state as used in systems.
+    ///
+    /// The `changed`, `added` and `children_of` filters are only reflected as read accesses, see [`Self::matches_change_filters`] and [`Self::matches_parent`].
     pub fn as_query_state<Q: QueryData>(&self, world: &mut World) -> QueryState<Q> {
+        let parent_id = self
+            .children_of
+            .map(|_| world.register_component::<Parent>());
         let mut dynamic_query = QueryBuilder::<Q>::new(world);
         // we don't actually want to fetch the data for components now, only figure out
-        // which entities match the query
-        // so we might be being slightly overkill
-        for c in &self.components {
-            dynamic_query.ref_id(c.component_id());
+        // which entities match the query, the accesses are declared so that systems are scheduled correctly
+        for term in &self.components {
+            let id = term.component.component_id();
+            let term_access = |builder: &mut QueryBuilder| {
+                if term.mutable {
+                    builder.mut_id(id);
+                } else {
+                    builder.ref_id(id);
+                }
+            };
+            if term.optional {
+                dynamic_query.optional(term_access);
+            } else {
+                dynamic_query.and(term_access);
+            }
         }
 
         for w in &self.with {
@@ -309,6 +535,26 @@ impl ScriptQueryBuilder {
             dynamic_query.without_id(without_id.component_id());
         }
 
+        for group in &self.any_of {
+            dynamic_query.or(|builder| {
+                for filter in group {
+                    match filter {
+                        ScriptQueryFilter::With(c) => builder.with_id(c.component_id()),
+                        ScriptQueryFilter::Without(c) => builder.without_id(c.component_id()),
+                    };
+                }
+            });
+        }
+
+        // change detection reads the ticks of the component
+        for c in self.changed.iter().chain(&self.added) {
+            dynamic_query.ref_id(c.component_id());
+        }
+
+        if let Some(parent_id) = parent_id {
+            dynamic_query.ref_id(parent_id);
+        }
+
         dynamic_query.build()
     }
 }
@@ -319,41 +565,542 @@ impl ScriptQueryBuilder {
 pub struct ScriptQueryResult {
     /// The entity that matched the query.
     pub entity: Entity,
-    /// The components that matched the query.
-    pub components: Vec<ReflectReference>,
+    /// The components that matched the query, in the order they were added to the query, `None` for optional components the entity does not have.
+    pub components: Vec<Option<ReflectReference>>,
+}
+
+/// The parts of a [`ScriptQueryBuilder`] which determine the query state it builds into.
+///
+/// Queries with the same signature share a cached query state, see [`AppScriptQueryCache`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ScriptQuerySignature {
+    terms: Vec<(ComponentId, bool, bool)>,
+    with: Vec<ComponentId>,
+    without: Vec<ComponentId>,
+    any_of: Vec<Vec<(ComponentId, bool)>>,
+    reads: Vec<ComponentId>,
+    parent: bool,
+}
+
+/// A query state cached by the [`AppScriptQueryCache`]
+pub struct CachedScriptQuery {
+    state: QueryState<Entity>,
+    /// The archetype generation the matched archetypes of the state are up to date with
+    generation: ArchetypeGeneration,
+}
+
+impl CachedScriptQuery {
+    /// Returns the archetypes matched by the query, if they are up to date with the given archetype generation
+    pub fn matched_archetypes(&self, generation: ArchetypeGeneration) -> Option<Vec<ArchetypeId>> {
+        (self.generation == generation).then(|| self.state.matched_archetypes().collect())
+    }
+
+    /// Updates the archetypes matched by the query, and returns them
+    pub fn update_archetypes(&mut self, world: UnsafeWorldCell) -> Vec<ArchetypeId> {
+        self.state.update_archetypes_unsafe_world_cell(world);
+        self.generation = world.archetypes().generation();
+        self.state.matched_archetypes().collect()
+    }
+}
+
+/// The query states held by the [`AppScriptQueryCache`]
+pub struct ScriptQueryCacheEntries {
+    entries: HashMap<ScriptQuerySignature, CachedScriptQuery>,
+    /// The signatures in the order they were inserted, the oldest entries are evicted first
+    insertion_order: VecDeque<ScriptQuerySignature>,
+    capacity: usize,
+}
+
+impl ScriptQueryCacheEntries {
+    /// Returns the cached query with the given signature
+    pub fn get(&self, signature: &ScriptQuerySignature) -> Option<&CachedScriptQuery> {
+        self.entries.get(signature)
+    }
+
+    /// Returns the cached query with the given signature mutably
+    pub fn get_mut(&mut self, signature: &ScriptQuerySignature) -> Option<&mut CachedScriptQuery> {
+        self.entries.get_mut(signature)
+    }
+
+    /// Caches the query state built for the given signature, evicting the oldest entry if the cache is full
+    pub fn insert(
+        &mut self,
+        signature: ScriptQuerySignature,
+        state: QueryState<Entity>,
+    ) -> &mut CachedScriptQuery {
+        if !self.entries.contains_key(&signature) {
+            while self.entries.len() >= self.capacity.max(1) {
+                let Some(oldest) = self.insertion_order.pop_front() else {
+                    break;
+                };
+                self.entries.remove(&oldest);
+            }
+            self.insertion_order.push_back(signature.clone());
+        }
+        let entry = CachedScriptQuery {
+            state,
+            generation: ArchetypeGeneration::initial(),
+        };
+        match self.entries.entry(signature) {
+            Entry::Occupied(mut occupied) => {
+                occupied.insert(entry);
+                occupied.into_mut()
+            }
+            Entry::Vacant(vacant) => vacant.insert(entry),
+        }
+    }
+
+    /// The number of cached query states
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no query states are cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The maximum number of cached query states
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Removes all the cached query states
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.insertion_order.clear();
+    }
+}
+
+#[derive(Clone, Resource)]
+/// A cache of the query states built for scripts, keyed by the signature of the query builder they were built from.
+///
+/// The cache holds at most [`AppScriptQueryCache::DEFAULT_CAPACITY`] query states by default, once full the oldest ones are evicted.
+/// Running an evicted query again requires global access to rebuild its state, so the capacity should be larger than the number of distinct queries scripts run.
+pub struct AppScriptQueryCache(Arc<RwLock<ScriptQueryCacheEntries>>);
+
+impl Default for AppScriptQueryCache {
+    fn default() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl AppScriptQueryCache {
+    /// The default maximum number of cached query states
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /// Creates a cache holding at most `capacity` query states
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Arc::new(RwLock::new(ScriptQueryCacheEntries {
+            entries: Default::default(),
+            insertion_order: Default::default(),
+            capacity,
+        })))
+    }
+
+    /// Reads the query cache.
+    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, ScriptQueryCacheEntries> {
+        self.0.read()
+    }
+
+    /// Writes to the query cache.
+    pub fn write(&self) -> parking_lot::RwLockWriteGuard<'_, ScriptQueryCacheEntries> {
+        self.0.write()
+    }
+}
+
+#[profiling::all_functions]
+impl ScriptQueryBuilder {
+    /// Returns the signature of the query, which identifies the query state it builds into.
+    pub fn signature(&self) -> ScriptQuerySignature {
+        ScriptQuerySignature {
+            terms: self
+                .components
+                .iter()
+                .map(|t| (t.component.component_id, t.mutable, t.optional))
+                .collect(),
+            with: self.with.iter().map(|c| c.component_id).collect(),
+            without: self.without.iter().map(|c| c.component_id).collect(),
+            any_of: self
+                .any_of
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .map(|f| match f {
+                            ScriptQueryFilter::With(c) => (c.component_id, true),
+                            ScriptQueryFilter::Without(c) => (c.component_id, false),
+                        })
+                        .collect()
+                })
+                .collect(),
+            reads: self
+                .changed
+                .iter()
+                .chain(&self.added)
+                .map(|c| c.component_id)
+                .collect(),
+            parent: self.children_of.is_some(),
+        }
+    }
+}
+
+/// A lazy iterator over the results of a query, see [`WorldAccessGuard::query_iter`].
+///
+/// The archetypes matching the query are determined when the iterator is created, and their entities are visited one at a time.
+/// No access to the world is held in between calls to [`Self::next`], entities which no longer match the query by the time they are visited are skipped,
+/// and entities which start matching the query after the iterator was created might not be visited.
+///
+/// Entities moving between matched archetypes are visited at most once, as long as they are moved before they are visited or while they are the latest result,
+/// i.e. when a component is inserted into the latest result. Only these moved entities are tracked, rather than every visited entity.
+#[derive(Clone)]
+pub struct ScriptQueryIter {
+    builder: ScriptQueryBuilder,
+    archetypes: Vec<ArchetypeId>,
+    matched: HashSet<ArchetypeId>,
+    archetype_cursor: usize,
+    /// The entities of the archetype currently visited, copied so that despawning entities does not shift the others
+    archetype_entities: Vec<Entity>,
+    entity_cursor: usize,
+    /// The visited entities which were found outside of the archetype they were visited in, these would otherwise be visited again
+    moved: EntityHashSet,
+    /// The latest result and the archetype it was in, checked for moves on the next call
+    last_result: Option<(Entity, ArchetypeId)>,
+    parent_id: Option<ComponentId>,
+    change_ticks: (Tick, Tick),
+}
+
+#[profiling::all_functions]
+impl ScriptQueryIter {
+    /// Finds the next entity which might match the query, without accessing any components
+    fn next_candidate(&mut self, world: UnsafeWorldCell) -> Option<Entity> {
+        if let Some((entity, archetype_id)) = self.last_result.take() {
+            if world
+                .entities()
+                .get(entity)
+                .is_some_and(|location| location.archetype_id != archetype_id)
+            {
+                self.moved.insert(entity);
+            }
+        }
+
+        loop {
+            let (entity, walked) = match self.builder.restricted_to() {
+                Some(entities) => (*entities.get(self.entity_cursor)?, None),
+                None => match self.archetype_entities.get(self.entity_cursor) {
+                    Some(entity) => (*entity, Some(self.archetypes[self.archetype_cursor - 1])),
+                    None => {
+                        let archetype_id = self.archetypes.get(self.archetype_cursor)?;
+                        self.archetype_cursor += 1;
+                        self.entity_cursor = 0;
+                        self.archetype_entities.clear();
+                        if let Some(archetype) = world.archetypes().get(*archetype_id) {
+                            self.archetype_entities
+                                .extend(archetype.entities().iter().map(|e| e.id()));
+                        }
+                        continue;
+                    }
+                },
+            };
+            self.entity_cursor += 1;
+
+            // the entity might have been despawned or moved to another archetype in the meantime
+            let Some(location) = world
+                .entities()
+                .get(entity)
+                .filter(|location| self.matched.contains(&location.archetype_id))
+            else {
+                continue;
+            };
+            let visit = match walked {
+                None => true,
+                Some(walked) if walked == location.archetype_id => !self.moved.contains(&entity),
+                // moved out of the walked archetype, visited here unless it was already visited elsewhere
+                Some(_) => self.moved.insert(entity),
+            };
+            if visit {
+                return Some(entity);
+            }
+        }
+    }
+
+    /// Checks the filters which need to read components of the entity, claiming read access to those components while doing so
+    fn matches_filters(&self, world: &WorldGuard, entity: Entity) -> Result<bool, InteropError> {
+        let reads = self
+            .builder
+            .changed
+            .iter()
+            .chain(&self.builder.added)
+            .map(|c| c.component_id)
+            .chain(self.parent_id)
+            .map(ReflectAccessId::for_component_id)
+            .collect::<Vec<_>>();
+        if reads.is_empty() {
+            return Ok(true);
+        }
+
+        for (i, raid) in reads.iter().enumerate() {
+            if !world.claim_read_access(*raid) {
+                for claimed in &reads[..i] {
+                    // Safety: we claimed this access above
+                    unsafe { world.release_access(*claimed) };
+                }
+                return Err(InteropError::cannot_claim_access(
+                    *raid,
+                    world.get_access_location(*raid),
+                    "Could not filter query results",
+                ));
+            }
+        }
+
+        let (last_run, this_run) = self.change_ticks;
+        let matches = world.as_unsafe_world_cell_readonly().map(|cell| {
+            cell.get_entity(entity).is_some_and(|e| {
+                // Safety: we hold read access to the components checked
+                self.builder.matches_change_filters(
+                    |id| unsafe { e.get_change_ticks_by_id(id) },
+                    last_run,
+                    this_run,
+                ) && self
+                    .builder
+                    .matches_parent(unsafe { e.get::<Parent>() }.map(Parent::get))
+            })
+        });
+
+        for raid in reads {
+            // Safety: we claimed this access above
+            unsafe { world.release_access(raid) };
+        }
+        matches
+    }
+
+    /// Returns the next result of the query, or `None` once all matching entities were visited.
+    pub fn next(&mut self, world: WorldGuard) -> Result<Option<ScriptQueryResult>, InteropError> {
+        let cell = world.as_unsafe_world_cell_readonly()?;
+        while let Some(entity) = self.next_candidate(cell) {
+            if world.check_entity_access(entity).is_err()
+                || !self.matches_filters(&world, entity)?
+            {
+                continue;
+            }
+            let Some(entity_cell) = cell.get_entity(entity) else {
+                continue;
+            };
+            if self.builder.restricted_to().is_none() {
+                self.last_result = Some((entity, entity_cell.location().archetype_id));
+            }
+            return Ok(Some(
+                self.builder
+                    .result_for(entity, |id| entity_cell.contains_id(id)),
+            ));
+        }
+        Ok(None)
+    }
 }
 
 #[profiling::all_functions]
 impl WorldAccessGuard<'_> {
-    /// Queries the world for entities that match the given query.
+    /// Creates a lazy iterator over the entities that match the given query.
+    ///
+    /// The query state is cached per [`ScriptQuerySignature`], only building a query with a new signature requires global access to the world.
+    /// The cache is only locked for writing if the query state needs to be built, or new archetypes were created since it was last used.
+    pub fn query_iter(&self, query: ScriptQueryBuilder) -> Result<ScriptQueryIter, InteropError> {
+        let signature = query.signature();
+        let cache = self.query_cache();
+        let cell = self.as_unsafe_world_cell_readonly()?;
+        let generation = cell.archetypes().generation();
+
+        let cached = cache
+            .read()
+            .get(&signature)
+            .and_then(|entry| entry.matched_archetypes(generation));
+        let archetypes = match cached {
+            Some(archetypes) => archetypes,
+            None => {
+                let mut cache = cache.write();
+                match cache.get_mut(&signature) {
+                    Some(entry) => entry.update_archetypes(cell),
+                    None => {
+                        let state =
+                            self.with_global_access(|world| query.as_query_state::<Entity>(world))?;
+                        cache.insert(signature, state).update_archetypes(cell)
+                    }
+                }
+            }
+        };
+
+        Ok(ScriptQueryIter {
+            matched: archetypes.iter().copied().collect(),
+            archetypes,
+            archetype_cursor: 0,
+            archetype_entities: Vec::default(),
+            entity_cursor: 0,
+            moved: EntityHashSet::default(),
+            last_result: None,
+            parent_id: query
+                .has_parent_filter()
+                .then(|| cell.components().component_id::<Parent>())
+                .flatten(),
+            change_ticks: self.change_ticks()?,
+            builder: query,
+        })
+    }
+
+    /// Queries the world for entities that match the given query, collecting all the results.
     pub fn query(
         &self,
         query: ScriptQueryBuilder,
     ) -> Result<VecDeque<ScriptQueryResult>, InteropError> {
-        with_global_access!(&self.inner.accesses, "Could not query", {
-            let world = unsafe { self.as_unsafe_world_cell()?.world_mut() };
-            let mut built_query = query.as_query_state::<EntityRef>(world);
-            let query_result = built_query.iter(world);
-
-            Ok(query_result
-                .map(|r| {
-                    let references: Vec<_> = query
-                        .components
-                        .iter()
-                        .map(|c| ReflectReference {
-                            base: super::ReflectBaseType {
-                                type_id: c.type_registration().type_id(),
-                                base_id: super::ReflectBase::Component(r.id(), c.component_id()),
-                            },
-                            reflect_path: ParsedPath(vec![]),
-                        })
-                        .collect();
-                    ScriptQueryResult {
-                        entity: r.id(),
-                        components: references,
+        let mut iter = self.query_iter(query)?;
+        let mut results = VecDeque::default();
+        while let Some(result) = iter.next(self.clone())? {
+            results.push_back(result);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::{ecs::component::Component, reflect::GetTypeRegistration};
+
+    use super::*;
+    use crate::bindings::ReflectAllocationId;
+
+    #[derive(Component, Reflect)]
+    struct Marker;
+
+    #[derive(Component, Reflect)]
+    struct Tag;
+
+    fn registration<T: Component + GetTypeRegistration>(
+        world: &mut World,
+    ) -> ScriptComponentRegistration {
+        let component_id = world.register_component::<T>();
+        ScriptComponentRegistration::new(
+            ScriptTypeRegistration::new(Arc::new(T::get_type_registration())),
+            component_id,
+        )
+    }
+
+    #[test]
+    fn test_query_iter_is_lazy_and_caches_query_state() {
+        let mut world = World::new();
+        let marker = registration::<Marker>(&mut world);
+        let tag = registration::<Tag>(&mut world);
+        let a = world.spawn(Marker).id();
+        let b = world.spawn((Marker, Tag)).id();
+        world.spawn(Tag);
+
+        let mut query = ScriptQueryBuilder::default();
+        query
+            .component(marker.clone())
+            .optional_component(tag.clone());
+
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let mut iter = guard.query_iter(query.clone()).unwrap();
+        let mut results = Vec::default();
+        while let Some(result) = iter.next(guard.clone()).unwrap() {
+            results.push((result.entity, result.components[1].is_some()));
+        }
+        results.sort();
+        assert_eq!(results, vec![(a, false), (b, true)]);
+
+        // the second query reuses the cached state, and does not need global access, which cannot be claimed while another access is held
+        let unrelated = ReflectAccessId::for_allocation(ReflectAllocationId::new(u32::MAX as u64));
+        assert!(guard.claim_read_access(unrelated));
+        let mut iter = guard.query_iter(query.clone()).unwrap();
+        assert!(iter.next(guard.clone()).unwrap().is_some());
+        // Safety: the access was claimed above
+        unsafe { guard.release_access(unrelated) };
+        assert_eq!(guard.query_cache().read().len(), 1);
+
+        // queries with a different signature are cached separately
+        let mut query = ScriptQueryBuilder::default();
+        query.component(tag).without_component(marker);
+        assert_eq!(guard.query(query).unwrap().len(), 1);
+        assert_eq!(guard.query_cache().read().len(), 2);
+    }
+
+    #[test]
+    fn test_query_iter_only_tracks_moved_entities() {
+        let mut world = World::new();
+        let marker = registration::<Marker>(&mut world);
+        registration::<Tag>(&mut world);
+        for _ in 0..3 {
+            world.spawn(Marker);
+        }
+        // makes sure the archetype the entities move to exists, and is visited after theirs
+        world.spawn((Marker, Tag));
+
+        let mut query = ScriptQueryBuilder::default();
+        query.component(marker);
+
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let mut iter = guard.query_iter(query.clone()).unwrap();
+        let mut results = Vec::default();
+        while let Some(result) = iter.next(guard.clone()).unwrap() {
+            results.push(result.entity);
+        }
+        assert_eq!(results.len(), 4);
+        assert!(iter.moved.is_empty());
+
+        let mut iter = guard.query_iter(query).unwrap();
+        let mut results = EntityHashSet::default();
+        while let Some(result) = iter.next(guard.clone()).unwrap() {
+            assert!(results.insert(result.entity), "visited twice");
+            guard
+                .with_global_access(|world| {
+                    if !world.entity(result.entity).contains::<Tag>() {
+                        world.entity_mut(result.entity).insert(Tag);
                     }
                 })
-                .collect())
-        })?
+                .unwrap();
+        }
+        assert_eq!(results.len(), 4);
+        assert_eq!(iter.moved.len(), 3);
+    }
+
+    #[test]
+    fn test_components_are_only_declared_as_written_if_mutable() {
+        let mut world = World::new();
+        let marker = registration::<Marker>(&mut world);
+        let tag = registration::<Tag>(&mut world);
+
+        let mut query = ScriptQueryBuilder::default();
+        query.component(marker.clone()).mut_component(tag.clone());
+        let state = query.as_query_state::<Entity>(&mut world);
+        let access = state.component_access().access();
+
+        assert!(access.has_component_read(marker.component_id));
+        assert!(!access.has_component_write(marker.component_id));
+        assert!(access.has_component_write(tag.component_id));
+    }
+
+    #[test]
+    fn test_query_cache_evicts_oldest_entries() {
+        let mut world = World::new();
+        let marker = registration::<Marker>(&mut world);
+        let tag = registration::<Tag>(&mut world);
+        world.insert_resource(AppScriptQueryCache::with_capacity(2));
+        world.spawn((Marker, Tag));
+
+        let mut marker_query = ScriptQueryBuilder::default();
+        marker_query.component(marker.clone());
+        let mut tag_query = ScriptQueryBuilder::default();
+        tag_query.component(tag.clone());
+        let mut both_query = ScriptQueryBuilder::default();
+        both_query.component(marker).component(tag);
+
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        for query in [&marker_query, &tag_query, &both_query] {
+            assert_eq!(guard.query(query.clone()).unwrap().len(), 1);
+        }
+
+        let cache = guard.query_cache();
+        assert_eq!(cache.read().len(), 2);
+        assert!(cache.read().get(&marker_query.signature()).is_none());
+        assert!(cache.read().get(&tag_query.signature()).is_some());
+        assert!(cache.read().get(&both_query.signature()).is_some());
     }
 }
@@ -13,7 +13,9 @@ use crate::{
 };
 use bevy::{
     ecs::{
-        change_detection::MutUntyped, component::ComponentId, entity::Entity,
+        change_detection::{DetectChangesMut, MutUntyped},
+        component::{ComponentId, ComponentTicks},
+        entity::Entity,
         world::unsafe_world_cell::UnsafeWorldCell,
     },
     prelude::{Component, ReflectDefault, Resource},
@@ -225,6 +227,7 @@ impl ReflectReference {
         world: WorldGuard,
         f: F,
     ) -> Result<O, InteropError> {
+        world.check_base_access(&self.base.base_id, false)?;
         let access_id = ReflectAccessId::for_reference(self.base.base_id.clone());
         with_access_read!(
             &world.inner.accesses,
@@ -236,21 +239,96 @@ impl ReflectReference {
 
     /// The way to access the value of the reference, that is the pointed-to value.
     /// This method is safe to use as it ensures no-one else has aliasing access to the value at the same time.
+    ///
+    /// If the closure succeeds the value is considered written to, i.e. components and resources are marked as changed.
     #[track_caller]
-    pub fn with_reflect_mut<O, F: FnOnce(&mut dyn PartialReflect) -> O>(
+    pub fn with_reflect_mut<O, F: FnOnce(&mut dyn PartialReflect) -> Result<O, InteropError>>(
         &self,
         world: WorldGuard,
         f: F,
     ) -> Result<O, InteropError> {
+        world.check_base_access(&self.base.base_id, true)?;
         let access_id = ReflectAccessId::for_reference(self.base.base_id.clone());
         with_access_write!(
             &world.inner.accesses,
             access_id,
             "Could not access reflect reference mutably",
-            { unsafe { self.reflect_mut_unsafe(world.clone()) }.map(f)? }
+            {
+                unsafe { self.reflect_mut_unsafe(world.clone()) }
+                    .and_then(f)
+                    .and_then(|out| {
+                        // Safety: we hold write access, the value is only marked as changed once it was written to successfully
+                        unsafe { self.mark_changed(world.clone()) }.map(|_| out)
+                    })
+            }
+        )?
+    }
+
+    /// Marks the component or resource the reference points to as changed, does nothing for allocated values.
+    ///
+    /// Mutable accesses via [`Self::reflect_mut_unsafe`] do not update the change ticks of the value, this needs to be called once it is written to.
+    ///
+    /// # Safety
+    /// - The caller must ensure the value is not aliased, i.e. by holding write access to it
+    pub unsafe fn mark_changed(&self, world: WorldGuard) -> Result<(), InteropError> {
+        let (_, this_run) = world.change_ticks()?;
+        // Safety: the caller ensures invariants hold
+        if let Some(mut value) = unsafe {
+            self.base
+                .base_id
+                .clone()
+                .into_ptr_mut(world.as_unsafe_world_cell()?)
+        } {
+            value.set_last_changed(this_run);
+        }
+        Ok(())
+    }
+
+    /// Retrieves the change ticks of the component or resource the reference points to, or `None` if the reference points to an allocated value.
+    pub fn change_ticks(&self, world: WorldGuard) -> Result<Option<ComponentTicks>, InteropError> {
+        world.check_base_access(&self.base.base_id, false)?;
+        let access_id = ReflectAccessId::for_reference(self.base.base_id.clone());
+        with_access_read!(
+            &world.inner.accesses,
+            access_id,
+            "could not access reflect reference",
+            {
+                // Safety: we hold read access
+                unsafe {
+                    self.base
+                        .base_id
+                        .change_ticks(world.as_unsafe_world_cell()?)
+                }
+            }
         )
     }
 
+    /// Checks if the component or resource the reference points to was added or changed since the system running the script last ran.
+    pub fn is_changed(&self, world: WorldGuard) -> Result<bool, InteropError> {
+        let (last_run, this_run) = world.change_ticks()?;
+        Ok(self
+            .change_ticks_or_err(world)?
+            .is_changed(last_run, this_run))
+    }
+
+    /// Checks if the component or resource the reference points to was added since the system running the script last ran.
+    pub fn is_added(&self, world: WorldGuard) -> Result<bool, InteropError> {
+        let (last_run, this_run) = world.change_ticks()?;
+        Ok(self
+            .change_ticks_or_err(world)?
+            .is_added(last_run, this_run))
+    }
+
+    fn change_ticks_or_err(&self, world: WorldGuard) -> Result<ComponentTicks, InteropError> {
+        self.change_ticks(world)?.ok_or_else(|| {
+            InteropError::unsupported_operation(
+                Some(self.base.type_id),
+                None,
+                "checking change ticks of a value which is not a component or resource",
+            )
+        })
+    }
+
     /// Retrieves the type id of the value the reference points to.
     pub fn tail_type_id(&self, world: WorldGuard) -> Result<Option<TypeId>, InteropError> {
         if self.reflect_path.is_empty() {
@@ -369,11 +447,12 @@ impl ReflectReference {
             .get_type_data(self.base.type_id)
             .ok_or_else(|| InteropError::unregistered_base(self.base.clone()))?;
 
+        // the pointer is retrieved without touching change ticks, which are only updated on writes, see `mark_changed`
         let ptr = self
             .base
             .base_id
             .clone()
-            .into_ptr_mut(world.as_unsafe_world_cell()?)
+            .into_ptr(world.as_unsafe_world_cell()?)
             .ok_or_else(|| InteropError::unregistered_base(self.base.clone()))?;
 
         // (Ptr) Safety: we use the same type_id to both
@@ -387,7 +466,8 @@ impl ReflectReference {
             "Invariant violated"
         );
 
-        let base = unsafe { from_ptr_data.as_reflect_mut(ptr.into_inner()) };
+        // Safety: the caller ensures no other references to the value exist
+        let base = unsafe { from_ptr_data.as_reflect_mut(ptr.assert_unique()) };
         drop(type_registry);
         self.walk_path_mut(base.as_partial_reflect_mut())
     }
@@ -513,6 +593,27 @@ impl ReflectBase {
         }
     }
 
+    /// Retrieves the change ticks of the underlying component or resource, or `None` for allocations
+    ///
+    /// # Safety
+    /// - The caller must ensure the cell has permission to access the underlying value
+    /// - The caller must ensure no aliasing mutable references to the same value exist at the same time
+    pub unsafe fn change_ticks(&self, world: UnsafeWorldCell<'_>) -> Option<ComponentTicks> {
+        match self {
+            ReflectBase::Component(entity, component_id) => {
+                // Safety: the caller ensures invariants hold
+                world
+                    .get_entity(*entity)?
+                    .get_change_ticks_by_id(*component_id)
+            }
+            ReflectBase::Resource(component_id) => {
+                // Safety: the caller ensures invariants hold
+                world.storages().resources.get(*component_id)?.get_ticks()
+            }
+            _ => None,
+        }
+    }
+
     /// Retrieves the pointer to the underlying `dyn PartialReflect` object valid for the 'w lifteime of the world cell
     ///
     /// # Safety
@@ -638,7 +739,10 @@ impl Iterator for ReflectRefIter {
 
 #[cfg(test)]
 mod test {
-    use bevy::prelude::{AppTypeRegistry, World};
+    use bevy::{
+        ecs::component::Tick,
+        prelude::{AppTypeRegistry, World},
+    };
 
     use crate::bindings::{
         function::script_function::AppScriptFunctionRegistry, AppReflectAllocator,
@@ -834,6 +938,63 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_component_ref_change_detection() {
+        let mut world = setup_world();
+
+        let entity = world.spawn(Component(vec!["hello".to_owned()])).id();
+        let spawned = world.change_tick();
+        let world_guard = WorldGuard::new_exclusive(&mut world);
+
+        let component_ref =
+            ReflectReference::new_component_ref::<Component>(entity, world_guard.clone())
+                .expect("could not create component reference");
+
+        // the component was spawned after the system last ran
+        let before_spawn = world_guard.with_change_ticks(Tick::new(0), spawned);
+        assert!(component_ref.is_added(before_spawn.clone()).unwrap());
+        assert!(component_ref.is_changed(before_spawn).unwrap());
+
+        // reading does not mark the component as changed
+        let next_run = world_guard.with_change_ticks(spawned, Tick::new(spawned.get() + 1));
+        component_ref
+            .with_reflect(next_run.clone(), |s| {
+                assert!(s.try_downcast_ref::<Component>().is_some());
+            })
+            .unwrap();
+        assert!(!component_ref.is_added(next_run.clone()).unwrap());
+        assert!(!component_ref.is_changed(next_run.clone()).unwrap());
+
+        // neither does a failed write
+        assert!(component_ref
+            .with_reflect_mut(next_run.clone(), |_| Err::<(), _>(InteropError::invariant(
+                "failed write"
+            )))
+            .is_err());
+        assert!(!component_ref.is_changed(next_run.clone()).unwrap());
+
+        // writing does
+        component_ref
+            .with_reflect_mut(next_run.clone(), |s| {
+                s.try_downcast_mut::<Component>()
+                    .unwrap()
+                    .0
+                    .push("world".to_owned());
+                Ok(())
+            })
+            .unwrap();
+        assert!(!component_ref.is_added(next_run.clone()).unwrap());
+        assert!(component_ref.is_changed(next_run.clone()).unwrap());
+
+        // allocated values are not tracked
+        let allocator = world_guard.allocator();
+        let mut allocator_write = allocator.write();
+        let allocation_ref =
+            ReflectReference::new_allocated(Component(vec![]), &mut allocator_write);
+        drop(allocator_write);
+        assert!(allocation_ref.is_changed(next_run).is_err());
+    }
+
     #[test]
     fn test_allocation_ref() {
         let mut world = setup_world();
@@ -0,0 +1,200 @@
+//! Everything necessary to support scripts deferring world mutations via commands
+
+use super::{
+    function::from_ref::FromScriptRef, ReflectReference, ScriptComponentRegistration, ScriptValue,
+    WorldAccessGuard,
+};
+use crate::{
+    error::{InteropError, ScriptError},
+    handler::handle_script_errors,
+};
+use bevy::{
+    ecs::{
+        entity::Entity,
+        world::{Command, CommandQueue, World},
+    },
+    reflect::{PartialReflect, Reflect},
+};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// The type behind the `commands` global, which scripts use to queue world mutations
+#[derive(Clone, Copy, Reflect, Debug, Default)]
+#[reflect(opaque)]
+pub struct ScriptCommands;
+
+#[derive(Default, Clone, Debug)]
+/// The commands queued by scripts, which are applied once the callback or script system which queued them finishes.
+///
+/// Each script system and handler owns its own queue, so applying it only ever applies the commands queued by that system.
+pub struct ScriptCommandQueue(Arc<Mutex<CommandQueue>>);
+
+#[profiling::all_functions]
+impl ScriptCommandQueue {
+    /// Queues a command to be applied later
+    pub fn push(&self, command: impl Command) {
+        self.0.lock().push(command);
+    }
+
+    /// Checks if no commands are queued
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().is_empty()
+    }
+
+    /// Applies all the queued commands to the world, in the order they were queued.
+    ///
+    /// The queue is taken out before applying it, so commands can themselves run scripts which queue more commands.
+    pub fn apply(&self, world: &mut World) {
+        let mut queue = std::mem::take(&mut *self.0.lock());
+        queue.apply(world);
+    }
+}
+
+/// Runs a deferred operation with exclusive access to the world, reporting any errors as script errors
+fn apply_script_command(
+    world: &mut World,
+    f: impl FnOnce(WorldAccessGuard) -> Result<(), InteropError>,
+) {
+    WorldAccessGuard::with_static_guard(world, |guard| {
+        if let Err(e) = f(guard.clone()) {
+            handle_script_errors(
+                guard,
+                [ScriptError::from(e).with_context("applying script command")].into_iter(),
+            );
+        }
+    })
+}
+
+#[profiling::all_functions]
+impl WorldAccessGuard<'_> {
+    /// Returns a guard which pushes the commands deferred by scripts into the given queue, i.e. the queue of the system it is handed to
+    pub fn with_command_queue(&self, command_queue: ScriptCommandQueue) -> Self {
+        let mut guard = self.clone();
+        guard.command_queue = command_queue;
+        guard
+    }
+
+    /// Returns the queue of commands deferred by scripts through this guard
+    pub fn command_queue(&self) -> ScriptCommandQueue {
+        self.command_queue.clone()
+    }
+
+    /// Queues a command, which is applied once the current callback or script system finishes
+    pub fn queue_command(&self, command: impl Command) {
+        self.command_queue().push(command);
+    }
+
+    /// Reserves a new entity, which is spawned without any components once the queued commands are applied.
+    ///
+    /// Unlike [`Self::spawn`], this does not require access to the world.
+    pub fn queue_spawn(&self) -> Result<Entity, InteropError> {
+        Ok(self
+            .as_unsafe_world_cell_readonly()?
+            .entities()
+            .reserve_entity())
+    }
+
+    /// Queues the insertion of a copy of the given value as a component of the entity
+    pub fn queue_insert_component(
+        &self,
+        entity: Entity,
+        registration: ScriptComponentRegistration,
+        value: ReflectReference,
+    ) -> Result<(), InteropError> {
+        self.check_component_write(entity, registration.component_id)?;
+        // the value is copied now, as it might change or be deallocated before the command is applied
+        let instance = <Box<dyn PartialReflect>>::from_script_ref(
+            registration.type_registration().type_id(),
+            ScriptValue::Reference(value),
+            self.clone(),
+        )?;
+        let reflect = instance.try_into_reflect().map_err(|v| {
+            InteropError::failed_from_reflect(
+                Some(registration.type_registration().type_id()),
+                format!("instance produced by conversion to target type when inserting component is not a full reflect type: {v:?}"),
+            )
+        })?;
+
+        self.queue_command(move |world: &mut World| {
+            apply_script_command(world, |guard| {
+                registration.insert_into_entity(guard, entity, reflect)
+            })
+        });
+        Ok(())
+    }
+
+    /// Queues the removal of the component from the entity
+    pub fn queue_remove_component(
+        &self,
+        entity: Entity,
+        registration: ScriptComponentRegistration,
+    ) -> Result<(), InteropError> {
+        self.check_component_write(entity, registration.component_id)?;
+        self.queue_command(move |world: &mut World| {
+            apply_script_command(world, |guard| {
+                registration.remove_from_entity(guard, entity)
+            })
+        });
+        Ok(())
+    }
+
+    /// Queues the despawning of the entity
+    pub fn queue_despawn(&self, entity: Entity) -> Result<(), InteropError> {
+        self.check_entity_access(entity)?;
+        self.queue_command(move |world: &mut World| {
+            apply_script_command(world, |guard| guard.despawn(entity))
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{
+        component::Component,
+        reflect::{AppTypeRegistry, ReflectComponent},
+    };
+
+    use super::*;
+    use crate::bindings::ScriptTypeRegistration;
+    use bevy::reflect::GetTypeRegistration;
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Marker(usize);
+
+    #[test]
+    fn test_commands_are_deferred_until_applied() {
+        let mut world = World::new();
+        world
+            .get_resource_or_init::<AppTypeRegistry>()
+            .write()
+            .register::<Marker>();
+        let marker = ScriptComponentRegistration::new(
+            ScriptTypeRegistration::new(Arc::new(Marker::get_type_registration())),
+            world.register_component::<Marker>(),
+        );
+        let existing = world.spawn(Marker(0)).id();
+
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let spawned = guard.queue_spawn().unwrap();
+        let allocator = guard.allocator();
+        let value = ReflectReference::new_allocated(Marker(1), &mut allocator.write());
+        guard
+            .queue_insert_component(spawned, marker.clone(), value)
+            .unwrap();
+        guard.queue_remove_component(existing, marker).unwrap();
+        let queue = guard.command_queue();
+        drop(guard);
+
+        assert!(!queue.is_empty());
+        assert!(world.get::<Marker>(existing).is_some());
+        assert!(world.get_entity(spawned).is_err());
+
+        queue.apply(&mut world);
+
+        assert!(queue.is_empty());
+        assert!(world.get::<Marker>(existing).is_none());
+        assert_eq!(world.get::<Marker>(spawned).map(|m| m.0), Some(1));
+    }
+}
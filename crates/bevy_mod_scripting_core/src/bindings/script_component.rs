@@ -1,24 +1,459 @@
 //! Everything necessary to support scripts registering their own components
 
-use super::{ScriptComponentRegistration, ScriptTypeRegistration, ScriptValue, WorldAccessGuard};
-use crate::error::InteropError;
+use super::{
+    function::from_ref::FromScriptRef, ScriptComponentRegistration, ScriptTypeRegistration,
+    ScriptValue, WorldAccessGuard,
+};
+use crate::{error::InteropError, reflection_extensions::PartialReflectExt};
 use bevy::{
     app::{App, Plugin},
     ecs::{
-        component::{Component, ComponentDescriptor, StorageType},
+        component::{Component, ComponentDescriptor, ComponentId, StorageType},
         system::Resource,
     },
-    reflect::{prelude::ReflectDefault, GetTypeRegistration, Reflect},
+    reflect::{
+        prelude::ReflectDefault, struct_debug, struct_partial_eq, utility::NonGenericTypeInfoCell,
+        ApplyError, DynamicStruct, FieldIter, FromReflect, FromType, GetTypeRegistration,
+        NamedField, PartialReflect, Reflect, ReflectFromPtr, ReflectFromReflect, ReflectMut,
+        ReflectOwned, ReflectRef, Struct, StructInfo, TypeInfo, TypePath, TypeRegistration,
+        TypeRegistry, Typed,
+    },
     utils::HashMap,
 };
 use parking_lot::RwLock;
-use std::{alloc::Layout, mem::needs_drop, sync::Arc};
+use std::{alloc::Layout, any::Any, fmt, mem::needs_drop, sync::Arc};
+
+/// The name of the untyped field every dynamic script component has
+const DATA_FIELD: &str = "data";
+
+/// The name of the field holding the typed fields of a dynamic script component
+const FIELDS_FIELD: &str = "fields";
 
-/// A dynamic script component
-#[derive(Reflect, Clone, Default)]
-#[reflect(Default)]
+/// A dynamic script component.
+///
+/// Every dynamic component has an untyped `data` field which can be set to any value,
+/// and a `fields` struct holding one typed field for each field in the [`DynamicComponentSchema`] the component was registered with.
+///
+/// The typed fields are only known at runtime, so they are nested in a [`DynamicStruct`] rather than being fields of the component itself,
+/// which keeps the reflected fields of the component in line with its static type information.
+#[derive(Default)]
 pub struct DynamicComponent {
     data: ScriptValue,
+    fields: DynamicStruct,
+}
+
+impl DynamicComponent {
+    /// Returns the value of the untyped `data` field
+    pub fn data(&self) -> &ScriptValue {
+        &self.data
+    }
+
+    /// Returns the typed fields of this component
+    pub fn fields(&self) -> &DynamicStruct {
+        &self.fields
+    }
+
+    /// Returns the names of the typed fields of this component
+    pub fn field_names(&self) -> impl Iterator<Item = &str> {
+        (0..self.fields.field_len()).filter_map(|i| self.fields.name_at(i))
+    }
+
+    /// Removes the typed fields of this component, returning them along with their names
+    fn take_fields(&mut self) -> Vec<(String, Box<dyn PartialReflect>)> {
+        let fields = std::mem::take(&mut self.fields);
+        let names = (0..fields.field_len())
+            .filter_map(|i| fields.name_at(i).map(str::to_owned))
+            .collect::<Vec<_>>();
+        names.into_iter().zip(fields).collect()
+    }
+}
+
+impl Clone for DynamicComponent {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            fields: self.fields.clone_dynamic(),
+        }
+    }
+}
+
+impl fmt::Debug for DynamicComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct_debug(self, f)
+    }
+}
+
+impl Component for DynamicComponent {
+    const STORAGE_TYPE: StorageType = StorageType::Table;
+}
+
+bevy::reflect::impl_type_path!((in bevy_mod_scripting_core::bindings::script_component) DynamicComponent);
+
+impl Typed for DynamicComponent {
+    fn type_info() -> &'static TypeInfo {
+        static CELL: NonGenericTypeInfoCell = NonGenericTypeInfoCell::new();
+        CELL.get_or_set(|| {
+            TypeInfo::Struct(StructInfo::new::<Self>(&[
+                NamedField::new::<ScriptValue>(DATA_FIELD),
+                NamedField::new::<DynamicStruct>(FIELDS_FIELD),
+            ]))
+        })
+    }
+}
+
+impl GetTypeRegistration for DynamicComponent {
+    fn get_type_registration() -> TypeRegistration {
+        let mut registration = TypeRegistration::of::<Self>();
+        registration.insert::<ReflectFromPtr>(FromType::<Self>::from_type());
+        registration.insert::<ReflectFromReflect>(FromType::<Self>::from_type());
+        registration.insert::<ReflectDefault>(FromType::<Self>::from_type());
+        registration
+    }
+
+    fn register_type_dependencies(registry: &mut TypeRegistry) {
+        registry.register::<ScriptValue>();
+    }
+}
+
+impl Struct for DynamicComponent {
+    fn field(&self, name: &str) -> Option<&dyn PartialReflect> {
+        match name {
+            DATA_FIELD => Some(&self.data),
+            FIELDS_FIELD => Some(&self.fields),
+            _ => None,
+        }
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn PartialReflect> {
+        match name {
+            DATA_FIELD => Some(&mut self.data),
+            FIELDS_FIELD => Some(&mut self.fields),
+            _ => None,
+        }
+    }
+
+    fn field_at(&self, index: usize) -> Option<&dyn PartialReflect> {
+        match index {
+            0 => Some(&self.data),
+            1 => Some(&self.fields),
+            _ => None,
+        }
+    }
+
+    fn field_at_mut(&mut self, index: usize) -> Option<&mut dyn PartialReflect> {
+        match index {
+            0 => Some(&mut self.data),
+            1 => Some(&mut self.fields),
+            _ => None,
+        }
+    }
+
+    fn name_at(&self, index: usize) -> Option<&str> {
+        match index {
+            0 => Some(DATA_FIELD),
+            1 => Some(FIELDS_FIELD),
+            _ => None,
+        }
+    }
+
+    fn field_len(&self) -> usize {
+        2
+    }
+
+    fn iter_fields(&self) -> FieldIter<'_> {
+        FieldIter::new(self)
+    }
+
+    fn clone_dynamic(&self) -> DynamicStruct {
+        let mut dynamic = DynamicStruct::default();
+        dynamic.set_represented_type(Some(Self::type_info()));
+        dynamic.insert(DATA_FIELD, self.data.clone());
+        dynamic.insert(FIELDS_FIELD, self.fields.clone_dynamic());
+        dynamic
+    }
+}
+
+impl PartialReflect for DynamicComponent {
+    fn get_represented_type_info(&self) -> Option<&'static TypeInfo> {
+        Some(Self::type_info())
+    }
+
+    fn into_partial_reflect(self: Box<Self>) -> Box<dyn PartialReflect> {
+        self
+    }
+
+    fn as_partial_reflect(&self) -> &dyn PartialReflect {
+        self
+    }
+
+    fn as_partial_reflect_mut(&mut self) -> &mut dyn PartialReflect {
+        self
+    }
+
+    fn try_into_reflect(self: Box<Self>) -> Result<Box<dyn Reflect>, Box<dyn PartialReflect>> {
+        Ok(self)
+    }
+
+    fn try_as_reflect(&self) -> Option<&dyn Reflect> {
+        Some(self)
+    }
+
+    fn try_as_reflect_mut(&mut self) -> Option<&mut dyn Reflect> {
+        Some(self)
+    }
+
+    fn try_apply(&mut self, value: &dyn PartialReflect) -> Result<(), ApplyError> {
+        let struct_value = value.reflect_ref().as_struct()?;
+        for (i, value) in struct_value.iter_fields().enumerate() {
+            if let Some(field) = struct_value.name_at(i).and_then(|n| self.field_mut(n)) {
+                field.try_apply(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn reflect_ref(&self) -> ReflectRef<'_> {
+        ReflectRef::Struct(self)
+    }
+
+    fn reflect_mut(&mut self) -> ReflectMut<'_> {
+        ReflectMut::Struct(self)
+    }
+
+    fn reflect_owned(self: Box<Self>) -> ReflectOwned {
+        ReflectOwned::Struct(self)
+    }
+
+    fn clone_value(&self) -> Box<dyn PartialReflect> {
+        Box::new(self.clone())
+    }
+
+    fn reflect_partial_eq(&self, value: &dyn PartialReflect) -> Option<bool> {
+        struct_partial_eq(self, value)
+    }
+
+    fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct_debug(self, f)
+    }
+}
+
+impl Reflect for DynamicComponent {
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_reflect(self: Box<Self>) -> Box<dyn Reflect> {
+        self
+    }
+
+    fn as_reflect(&self) -> &dyn Reflect {
+        self
+    }
+
+    fn as_reflect_mut(&mut self) -> &mut dyn Reflect {
+        self
+    }
+
+    fn set(&mut self, value: Box<dyn Reflect>) -> Result<(), Box<dyn Reflect>> {
+        *self = value.take()?;
+        Ok(())
+    }
+}
+
+impl FromReflect for DynamicComponent {
+    fn from_reflect(reflect: &dyn PartialReflect) -> Option<Self> {
+        if let Some(component) = reflect.try_downcast_ref::<Self>() {
+            return Some(component.clone());
+        }
+
+        let struct_value = reflect.reflect_ref().as_struct().ok()?;
+        let mut component = Self::default();
+        if let Some(data) = struct_value.field(DATA_FIELD) {
+            component.data = <ScriptValue as FromReflect>::from_reflect(data)?;
+        }
+        if let Some(fields) = struct_value.field(FIELDS_FIELD) {
+            component.fields = fields.reflect_ref().as_struct().ok()?.clone_dynamic();
+        }
+        Some(component)
+    }
+}
+
+/// A typed field of a script registered component
+pub struct DynamicComponentField {
+    name: String,
+    type_registration: ScriptTypeRegistration,
+    default: Box<dyn PartialReflect>,
+}
+
+impl DynamicComponentField {
+    /// Creates a new field of the given type, which defaults to the [`Default`] value of the type.
+    ///
+    /// Fails if the type does not have [`ReflectDefault`] type data.
+    pub fn new(
+        name: impl Into<String>,
+        type_registration: ScriptTypeRegistration,
+    ) -> Result<Self, InteropError> {
+        let default = type_registration
+            .type_registration()
+            .data::<ReflectDefault>()
+            .ok_or_else(|| {
+                InteropError::missing_type_data(
+                    type_registration.type_id(),
+                    "ReflectDefault".to_owned(),
+                )
+            })?
+            .default()
+            .into_partial_reflect();
+        Self::new_with_default(name, type_registration, default)
+    }
+
+    /// Creates a new field of the given type with the given default value.
+    ///
+    /// Fails if the default value is not of the given type.
+    pub fn new_with_default(
+        name: impl Into<String>,
+        type_registration: ScriptTypeRegistration,
+        default: Box<dyn PartialReflect>,
+    ) -> Result<Self, InteropError> {
+        let default_type_id = default
+            .get_represented_type_info()
+            .map(|info| info.type_id());
+        if default_type_id != Some(type_registration.type_id()) {
+            return Err(InteropError::type_mismatch(
+                type_registration.type_id(),
+                default_type_id,
+            ));
+        }
+
+        Ok(Self {
+            name: name.into(),
+            type_registration,
+            default,
+        })
+    }
+
+    /// The name of the field
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The type of the field
+    pub fn type_registration(&self) -> &ScriptTypeRegistration {
+        &self.type_registration
+    }
+
+    /// Creates a new instance of the default value of this field
+    pub fn default_value(&self) -> Box<dyn PartialReflect> {
+        self.type_registration
+            .type_registration()
+            .data::<ReflectFromReflect>()
+            .and_then(|from_reflect| from_reflect.from_reflect(self.default.as_ref()))
+            .map(|value| value.into_partial_reflect())
+            .unwrap_or_else(|| self.default.clone_value())
+    }
+}
+
+/// The typed fields of a script registered component.
+///
+/// Components registered without any fields only have the untyped `data` field, and no typed fields.
+#[derive(Default)]
+pub struct DynamicComponentSchema {
+    fields: Vec<DynamicComponentField>,
+}
+
+#[profiling::all_functions]
+impl DynamicComponentSchema {
+    /// Creates a new schema with the given fields.
+    ///
+    /// Fails if any field names are duplicated or if any field is named `data`, which is reserved for the untyped field.
+    pub fn new(
+        component_name: &str,
+        fields: Vec<DynamicComponentField>,
+    ) -> Result<Self, InteropError> {
+        for (i, field) in fields.iter().enumerate() {
+            if field.name == DATA_FIELD {
+                return Err(InteropError::invalid_script_component_schema(
+                    component_name,
+                    format!("the field name '{DATA_FIELD}' is reserved"),
+                ));
+            }
+            if fields[..i].iter().any(|f| f.name == field.name) {
+                return Err(InteropError::invalid_script_component_schema(
+                    component_name,
+                    format!("the field '{}' is defined more than once", field.name),
+                ));
+            }
+        }
+        Ok(Self { fields })
+    }
+
+    /// Returns the typed fields of the component
+    pub fn fields(&self) -> &[DynamicComponentField] {
+        &self.fields
+    }
+
+    /// Returns the field with the given name
+    pub fn field(&self, name: &str) -> Option<&DynamicComponentField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Creates a new component with all typed fields set to their defaults
+    pub fn instantiate(&self) -> DynamicComponent {
+        DynamicComponent {
+            data: ScriptValue::default(),
+            fields: self
+                .fields
+                .iter()
+                .map(|f| (f.name.clone(), f.default_value()))
+                .collect(),
+        }
+    }
+
+    /// Checks the component conforms to the schema, setting any missing fields to their defaults and ordering them as in the schema.
+    ///
+    /// Fails if the component has fields which are not in the schema, or fields of the wrong type.
+    pub fn validate(
+        &self,
+        component_name: &str,
+        component: &mut DynamicComponent,
+    ) -> Result<(), InteropError> {
+        let mut provided = component.take_fields();
+        if let Some((name, _)) = provided.iter().find(|(name, _)| self.field(name).is_none()) {
+            return Err(InteropError::invalid_script_component_schema(
+                component_name,
+                format!("the component has no field named '{name}'"),
+            ));
+        }
+
+        for field in &self.fields {
+            let value = match provided.iter().position(|(name, _)| name == &field.name) {
+                Some(i) => provided.swap_remove(i).1,
+                None => field.default_value(),
+            };
+            let value_type = value.get_represented_type_info().map(|i| i.type_id());
+            if value_type != Some(field.type_registration.type_id()) {
+                return Err(InteropError::invalid_script_component_schema(
+                    component_name,
+                    format!(
+                        "the field '{}' should be of type '{}' but got a value of type '{}'",
+                        field.name,
+                        field.type_registration.type_name(),
+                        value.reflect_type_path()
+                    ),
+                ));
+            }
+            component.fields.insert_boxed(field.name.clone(), value);
+        }
+        Ok(())
+    }
 }
 
 /// Some metadata about dynamic script components
@@ -27,10 +462,8 @@ pub struct DynamicComponentInfo {
     pub name: String,
     /// The type registration for the component
     pub registration: ScriptComponentRegistration,
-}
-
-impl Component for DynamicComponent {
-    const STORAGE_TYPE: StorageType = StorageType::Table;
+    /// The typed fields of the component
+    pub schema: DynamicComponentSchema,
 }
 
 /// A registry of dynamically registered script components
@@ -40,12 +473,12 @@ pub struct AppScriptComponentRegistry(pub Arc<RwLock<ScriptComponentRegistry>>);
 #[profiling::all_functions]
 impl AppScriptComponentRegistry {
     /// Reads the underlying registry
-    pub fn read(&self) -> parking_lot::RwLockReadGuard<ScriptComponentRegistry> {
+    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, ScriptComponentRegistry> {
         self.0.read()
     }
 
     /// Writes to the underlying registry
-    pub fn write(&self) -> parking_lot::RwLockWriteGuard<ScriptComponentRegistry> {
+    pub fn write(&self) -> parking_lot::RwLockWriteGuard<'_, ScriptComponentRegistry> {
         self.0.write()
     }
 }
@@ -67,6 +500,18 @@ impl ScriptComponentRegistry {
     pub fn get(&self, name: &str) -> Option<&DynamicComponentInfo> {
         self.components.get(name)
     }
+
+    /// Gets a dynamic script component by its component id
+    pub fn get_by_id(&self, component_id: ComponentId) -> Option<&DynamicComponentInfo> {
+        self.components
+            .values()
+            .find(|info| info.registration.component_id == component_id)
+    }
+
+    /// Iterates over all the dynamic script components
+    pub fn iter(&self) -> impl Iterator<Item = &DynamicComponentInfo> {
+        self.components.values()
+    }
 }
 
 #[profiling::all_functions]
@@ -75,6 +520,15 @@ impl WorldAccessGuard<'_> {
     pub fn register_script_component(
         &self,
         component_name: String,
+    ) -> Result<ScriptComponentRegistration, InteropError> {
+        self.register_script_component_with_schema(component_name, Default::default())
+    }
+
+    /// Registers a dynamic script component with the given typed fields, and returns a reference to its registration
+    pub fn register_script_component_with_schema(
+        &self,
+        component_name: String,
+        schema: DynamicComponentSchema,
     ) -> Result<ScriptComponentRegistration, InteropError> {
         let component_registry = self.component_registry();
         let component_registry_read = component_registry.read();
@@ -113,6 +567,7 @@ impl WorldAccessGuard<'_> {
         let component_info = DynamicComponentInfo {
             name: component_name.clone(),
             registration: registration.clone(),
+            schema,
         };
 
         component_registry.register(component_info);
@@ -121,6 +576,92 @@ impl WorldAccessGuard<'_> {
         // in addition to the existing registrations.
         Ok(registration)
     }
+
+    /// Creates a new typed field for a script component schema, converting the default value if one is given
+    pub fn new_script_component_field(
+        &self,
+        name: String,
+        type_registration: ScriptTypeRegistration,
+        default: Option<ScriptValue>,
+    ) -> Result<DynamicComponentField, InteropError> {
+        match default {
+            Some(default) => {
+                let default = <Box<dyn PartialReflect>>::from_script_ref(
+                    type_registration.type_id(),
+                    default,
+                    self.clone(),
+                )?;
+                let default =
+                    <dyn PartialReflect>::from_reflect_or_clone(default.as_ref(), self.clone());
+                DynamicComponentField::new_with_default(name, type_registration, default)
+            }
+            None => DynamicComponentField::new(name, type_registration),
+        }
+    }
+
+    /// Constructs an instance of a dynamic script component from a map of field values.
+    ///
+    /// Missing fields are set to their defaults, and the untyped field can be set via the `data` key.
+    pub fn construct_script_component(
+        &self,
+        registration: &ScriptComponentRegistration,
+        mut payload: std::collections::HashMap<String, ScriptValue>,
+    ) -> Result<DynamicComponent, InteropError> {
+        let component_registry = self.component_registry();
+        let component_registry = component_registry.read();
+        let info = component_registry
+            .get_by_id(registration.component_id)
+            .ok_or_else(|| InteropError::invalid_component(registration.component_id))?;
+
+        let mut component = DynamicComponent {
+            data: payload.remove(DATA_FIELD).unwrap_or_default(),
+            fields: DynamicStruct::default(),
+        };
+        for field in &info.schema.fields {
+            if let Some(value) = payload.remove(&field.name) {
+                let value = <Box<dyn PartialReflect>>::from_script_ref(
+                    field.type_registration.type_id(),
+                    value,
+                    self.clone(),
+                )?;
+                let value =
+                    <dyn PartialReflect>::from_reflect_or_clone(value.as_ref(), self.clone());
+                component.fields.insert_boxed(field.name.clone(), value);
+            }
+        }
+
+        if let Some(name) = payload.keys().next() {
+            return Err(InteropError::invalid_script_component_schema(
+                &info.name,
+                format!("the component has no field named '{name}'"),
+            ));
+        }
+
+        info.schema.validate(&info.name, &mut component)?;
+        Ok(component)
+    }
+
+    /// Constructs an instance of a dynamic script component without knowing which component it is for.
+    ///
+    /// Only the untyped field can be set via the `data` key, the typed fields are filled in with their defaults
+    /// once the instance is inserted as a specific component.
+    pub fn construct_untyped_script_component(
+        &self,
+        mut payload: std::collections::HashMap<String, ScriptValue>,
+    ) -> Result<DynamicComponent, InteropError> {
+        let component = DynamicComponent {
+            data: payload.remove(DATA_FIELD).unwrap_or_default(),
+            fields: DynamicStruct::default(),
+        };
+
+        if let Some(name) = payload.keys().next() {
+            return Err(InteropError::invalid_script_component_schema(
+                DynamicComponent::type_path(),
+                format!("typed field '{name}' can only be set when constructing from the component registration"),
+            ));
+        }
+        Ok(component)
+    }
 }
 
 /// A plugin to support dynamic script components
@@ -136,7 +677,7 @@ impl Plugin for DynamicScriptComponentPlugin {
 #[cfg(test)]
 mod test {
     use super::*;
-    use bevy::ecs::world::World;
+    use bevy::{ecs::world::World, reflect::GetField};
 
     #[test]
     fn test_script_component() {
@@ -164,4 +705,131 @@ mod test {
 
         assert_eq!(component.name(), "ScriptTest");
     }
+
+    fn setup_world() -> World {
+        let mut world = World::new();
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<f32>();
+        type_registry.register::<String>();
+        type_registry.register::<DynamicComponent>();
+        world.insert_resource(crate::bindings::AppReflectAllocator::default());
+        world.insert_resource(bevy::ecs::reflect::AppTypeRegistry(
+            bevy::reflect::TypeRegistryArc {
+                internal: Arc::new(type_registry.into()),
+            },
+        ));
+        world
+    }
+
+    #[test]
+    fn test_schema_rejects_duplicate_and_reserved_fields() {
+        let mut world = setup_world();
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let f32_type = guard.get_type_by_name("f32").unwrap();
+
+        let duplicate = vec![
+            DynamicComponentField::new("speed", f32_type.clone()).unwrap(),
+            DynamicComponentField::new("speed", f32_type.clone()).unwrap(),
+        ];
+        assert!(DynamicComponentSchema::new("Test", duplicate).is_err());
+
+        let reserved = vec![DynamicComponentField::new("data", f32_type).unwrap()];
+        assert!(DynamicComponentSchema::new("Test", reserved).is_err());
+    }
+
+    #[test]
+    fn test_script_component_with_schema_uses_defaults() {
+        let mut world = setup_world();
+        let entity = world.spawn_empty().id();
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let f32_type = guard.get_type_by_name("f32").unwrap();
+        let string_type = guard.get_type_by_name("String").unwrap();
+
+        let fields = vec![
+            guard
+                .new_script_component_field("speed".into(), f32_type, Some(ScriptValue::Float(2.0)))
+                .unwrap(),
+            guard
+                .new_script_component_field("name".into(), string_type, None)
+                .unwrap(),
+        ];
+        let schema = DynamicComponentSchema::new("Mover", fields).unwrap();
+        let registration = guard
+            .register_script_component_with_schema("Mover".into(), schema)
+            .unwrap();
+
+        registration
+            .insert_into_entity(guard.clone(), entity, Box::new(DynamicComponent::default()))
+            .unwrap();
+        drop(guard);
+
+        let component = world
+            .entity(entity)
+            .get_by_id(registration.component_id)
+            .unwrap();
+        // Safety: the component was registered with the layout of `DynamicComponent`
+        let component = unsafe { component.deref::<DynamicComponent>() };
+        assert_eq!(
+            component.field_names().collect::<Vec<_>>(),
+            vec!["speed", "name"]
+        );
+        assert_eq!(component.fields().get_field::<f32>("speed"), Some(&2.0));
+        assert_eq!(
+            component.fields().get_field::<String>("name"),
+            Some(&String::new())
+        );
+        assert_eq!(component.data(), &ScriptValue::Unit);
+
+        // the typed fields are nested, so the reflected fields match the static type information
+        let info = DynamicComponent::type_info().as_struct().unwrap();
+        assert_eq!(component.field_len(), info.field_len());
+        for (i, name) in info.field_names().iter().enumerate() {
+            assert_eq!(component.name_at(i), Some(*name));
+        }
+    }
+
+    #[test]
+    fn test_construct_script_component_validates_fields() {
+        let mut world = setup_world();
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let f32_type = guard.get_type_by_name("f32").unwrap();
+
+        let fields = vec![guard
+            .new_script_component_field("speed".into(), f32_type, None)
+            .unwrap()];
+        let schema = DynamicComponentSchema::new("Mover", fields).unwrap();
+        let registration = guard
+            .register_script_component_with_schema("Mover".into(), schema)
+            .unwrap();
+
+        let component = guard
+            .construct_script_component(
+                &registration,
+                [
+                    ("speed".to_owned(), ScriptValue::Float(3.0)),
+                    ("data".to_owned(), ScriptValue::Bool(true)),
+                ]
+                .into_iter()
+                .collect(),
+            )
+            .unwrap();
+        assert_eq!(component.fields().get_field::<f32>("speed"), Some(&3.0));
+        assert_eq!(component.data(), &ScriptValue::Bool(true));
+
+        let unknown_field = guard.construct_script_component(
+            &registration,
+            [("velocity".to_owned(), ScriptValue::Float(3.0))]
+                .into_iter()
+                .collect(),
+        );
+        assert!(unknown_field.is_err());
+
+        let wrong_type = guard.construct_script_component(
+            &registration,
+            [("speed".to_owned(), ScriptValue::String("fast".into()))]
+                .into_iter()
+                .collect(),
+        );
+        assert!(wrong_type.is_err());
+    }
 }
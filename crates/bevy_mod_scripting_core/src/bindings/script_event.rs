@@ -0,0 +1,372 @@
+//! Everything necessary to support sending and reading events from scripts, as well as scripts registering their own events
+
+use super::{
+    function::from_ref::FromScriptRef, ScriptTypeRegistration, ScriptValue, WorldAccessGuard,
+};
+use crate::error::InteropError;
+use bevy::{
+    app::{App, Plugin},
+    ecs::{
+        component::ComponentId,
+        entity::Entity,
+        event::{Event, EventCursor, Events},
+        observer::{Observer, Trigger},
+        system::{Commands, Resource},
+        world::{unsafe_world_cell::UnsafeWorldCell, World},
+    },
+    reflect::{FromReflect, FromType, GetTypeRegistration, PartialReflect, Reflect},
+    utils::HashMap,
+};
+use parking_lot::RwLock;
+use std::{
+    any::{Any, TypeId},
+    sync::Arc,
+};
+
+/// An event registered by a script.
+///
+/// All script registered events share this type, and are told apart by their name.
+/// Rust systems can send and read script events via `EventWriter<DynamicEvent>` and `EventReader<DynamicEvent>`.
+#[derive(Event, Reflect, Clone, Debug)]
+#[reflect(ScriptEvent)]
+pub struct DynamicEvent {
+    /// The name the event was registered with
+    pub name: String,
+    /// The payload of the event
+    pub data: ScriptValue,
+}
+
+impl DynamicEvent {
+    /// Creates a new script event with the given name and payload
+    pub fn new(name: impl Into<String>, data: ScriptValue) -> Self {
+        Self {
+            name: name.into(),
+            data,
+        }
+    }
+}
+
+/// The position of an event reader in the event queue of some type erased event
+pub struct ScriptEventCursor(Box<dyn Any + Send + Sync>);
+
+/// Called by observers of type erased events, with the entity targeted by the trigger and a copy of the triggered event
+pub type ScriptEventObserverFn = Box<dyn Fn(Entity, Box<dyn Reflect>, &mut Commands) + Send + Sync>;
+
+/// Type data which allows events to be sent and read without knowing their type.
+///
+/// Added by [`crate::RegisterScriptEvent::register_script_event`], or via `#[reflect(ScriptEvent)]`.
+#[derive(Clone)]
+pub struct ReflectScriptEvent {
+    events_type_id: TypeId,
+    send: fn(&mut World, &dyn PartialReflect) -> Result<(), InteropError>,
+    new_cursor: fn() -> ScriptEventCursor,
+    read: unsafe fn(UnsafeWorldCell<'_>, &mut ScriptEventCursor) -> Vec<Box<dyn Reflect>>,
+    observer: fn(ScriptEventObserverFn) -> Observer,
+}
+
+impl ReflectScriptEvent {
+    /// The type id of the [`Events`] resource storing events of this type
+    pub fn events_type_id(&self) -> TypeId {
+        self.events_type_id
+    }
+
+    /// Sends the given event, converting it to the concrete event type first
+    pub fn send(&self, world: &mut World, event: &dyn PartialReflect) -> Result<(), InteropError> {
+        (self.send)(world, event)
+    }
+
+    /// Creates a new cursor which will read all the events still buffered in the [`Events`] resource,
+    /// including those sent before it was created, like a newly initialized [`bevy::ecs::event::EventReader`]
+    pub fn new_cursor(&self) -> ScriptEventCursor {
+        (self.new_cursor)()
+    }
+
+    /// Reads all the events not yet seen by the cursor
+    ///
+    /// # Safety
+    /// - The caller must ensure the world cell has read access to the [`Events`] resource of this type
+    /// - The cursor must have been created by this type data
+    pub unsafe fn read(
+        &self,
+        world: UnsafeWorldCell<'_>,
+        cursor: &mut ScriptEventCursor,
+    ) -> Vec<Box<dyn Reflect>> {
+        (self.read)(world, cursor)
+    }
+
+    /// Creates an observer which calls the given function whenever an event of this type is triggered
+    pub fn observer(&self, f: ScriptEventObserverFn) -> Observer {
+        (self.observer)(f)
+    }
+}
+
+impl<E: Event + FromReflect> FromType<E> for ReflectScriptEvent {
+    fn from_type() -> Self {
+        Self {
+            events_type_id: TypeId::of::<Events<E>>(),
+            send: |world, event| {
+                let event = E::from_reflect(event).ok_or_else(|| {
+                    InteropError::failed_from_reflect(
+                        Some(TypeId::of::<E>()),
+                        "Could not convert the value to the event type",
+                    )
+                })?;
+                world.send_event(event);
+                Ok(())
+            },
+            new_cursor: || ScriptEventCursor(Box::new(EventCursor::<E>::default())),
+            read: |world, cursor| {
+                let Some(cursor) = cursor.0.downcast_mut::<EventCursor<E>>() else {
+                    return Vec::default();
+                };
+                // Safety: the caller ensures we have read access to the events resource
+                match unsafe { world.get_resource::<Events<E>>() } {
+                    Some(events) => cursor
+                        .read(events)
+                        .filter_map(|e| <E as FromReflect>::from_reflect(e))
+                        .map(|e| Box::new(e) as Box<dyn Reflect>)
+                        .collect(),
+                    None => Vec::default(),
+                }
+            },
+            observer: |f| {
+                Observer::new(move |trigger: Trigger<E>, mut commands: Commands| {
+                    if let Some(event) = <E as FromReflect>::from_reflect(trigger.event()) {
+                        f(trigger.entity(), Box::new(event), &mut commands);
+                    }
+                })
+            },
+        }
+    }
+}
+
+/// A reference to an event type, which can be sent from scripts and read in script systems.
+#[derive(Clone, Reflect, Debug)]
+pub struct ScriptEventRegistration {
+    pub(crate) registration: ScriptTypeRegistration,
+    pub(crate) events_resource_id: ComponentId,
+    /// the name of the event if this is an event registered BY a script
+    pub(crate) script_event_name: Option<String>,
+}
+
+#[profiling::all_functions]
+impl ScriptEventRegistration {
+    /// Creates a new [`ScriptEventRegistration`] from a [`ScriptTypeRegistration`] and the [`ComponentId`] of the events resource.
+    pub fn new(registration: ScriptTypeRegistration, events_resource_id: ComponentId) -> Self {
+        Self {
+            registration,
+            events_resource_id,
+            script_event_name: None,
+        }
+    }
+
+    /// Returns the [`ComponentId`] of the [`Events`] resource storing these events
+    pub fn events_resource_id(&self) -> ComponentId {
+        self.events_resource_id
+    }
+
+    /// Returns the [`ScriptTypeRegistration`] for this type.
+    pub fn type_registration(&self) -> &ScriptTypeRegistration {
+        &self.registration
+    }
+
+    /// Returns the name of the event if it was registered by a script
+    pub fn script_event_name(&self) -> Option<&str> {
+        self.script_event_name.as_deref()
+    }
+}
+
+/// Some metadata about dynamic script events
+pub struct DynamicEventInfo {
+    /// The name of the event
+    pub name: String,
+    /// The registration of the event
+    pub registration: ScriptEventRegistration,
+}
+
+/// A registry of dynamically registered script events
+#[derive(Clone, Resource, Default)]
+pub struct AppScriptEventRegistry(pub Arc<RwLock<ScriptEventRegistry>>);
+
+#[profiling::all_functions]
+impl AppScriptEventRegistry {
+    /// Reads the underlying registry
+    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, ScriptEventRegistry> {
+        self.0.read()
+    }
+
+    /// Writes to the underlying registry
+    pub fn write(&self) -> parking_lot::RwLockWriteGuard<'_, ScriptEventRegistry> {
+        self.0.write()
+    }
+}
+
+#[derive(Default)]
+/// A registry of dynamically registered script events
+pub struct ScriptEventRegistry {
+    events: HashMap<String, DynamicEventInfo>,
+}
+
+#[profiling::all_functions]
+impl ScriptEventRegistry {
+    /// Registers a dynamic script event, possibly overwriting an existing one
+    pub fn register(&mut self, info: DynamicEventInfo) {
+        self.events.insert(info.name.clone(), info);
+    }
+
+    /// Gets a dynamic script event by name
+    pub fn get(&self, name: &str) -> Option<&DynamicEventInfo> {
+        self.events.get(name)
+    }
+
+    /// Iterates over all the dynamic script events
+    pub fn iter(&self) -> impl Iterator<Item = &DynamicEventInfo> {
+        self.events.values()
+    }
+}
+
+#[profiling::all_functions]
+impl WorldAccessGuard<'_> {
+    /// Registers a dynamic script event, and returns a reference to its registration
+    pub fn register_script_event(
+        &self,
+        event_name: String,
+    ) -> Result<ScriptEventRegistration, InteropError> {
+        let event_registry = self.event_registry();
+        if event_registry.read().get(&event_name).is_some() {
+            return Err(InteropError::unsupported_operation(
+                None,
+                None,
+                "script registered event already exists",
+            ));
+        }
+
+        let events_resource_id = self
+            .get_resource_id(TypeId::of::<Events<DynamicEvent>>())?
+            .ok_or_else(|| {
+                InteropError::unregistered_component_or_resource_type(std::any::type_name::<
+                    Events<DynamicEvent>,
+                >())
+            })?;
+
+        let registration = ScriptEventRegistration {
+            registration: ScriptTypeRegistration::new(Arc::new(
+                <DynamicEvent as GetTypeRegistration>::get_type_registration(),
+            )),
+            events_resource_id,
+            script_event_name: Some(event_name.clone()),
+        };
+
+        event_registry.write().register(DynamicEventInfo {
+            name: event_name,
+            registration: registration.clone(),
+        });
+        Ok(registration)
+    }
+
+    /// Retrieves an event type by name, either a script registered event, or a reflected event with [`ReflectScriptEvent`] type data.
+    ///
+    /// Returns `None` if no such event exists or if the event was never added to the app.
+    pub fn get_event_type(
+        &self,
+        event_name: &str,
+    ) -> Result<Option<ScriptEventRegistration>, InteropError> {
+        if let Some(info) = self.event_registry().read().get(event_name) {
+            return Ok(Some(info.registration.clone()));
+        }
+
+        let Some(registration) = self.get_type_by_name(event_name) else {
+            return Ok(None);
+        };
+        let Some(reflect_event) = registration
+            .type_registration()
+            .data::<ReflectScriptEvent>()
+        else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .get_resource_id(reflect_event.events_type_id())?
+            .map(|events_resource_id| {
+                ScriptEventRegistration::new(registration, events_resource_id)
+            }))
+    }
+
+    /// Sends an event of the given type, the payload is converted to the event type,
+    /// or in the case of script registered events used as the event data as is.
+    pub fn send_event(
+        &self,
+        registration: &ScriptEventRegistration,
+        payload: ScriptValue,
+    ) -> Result<(), InteropError> {
+        self.check_resource_write(registration.events_resource_id)?;
+
+        if let Some(name) = &registration.script_event_name {
+            let event = DynamicEvent::new(name.clone(), payload);
+            return self.with_global_access(|world| {
+                world.send_event(event);
+            });
+        }
+
+        let type_id = registration.registration.type_id();
+        let reflect_event = registration
+            .registration
+            .type_registration()
+            .data::<ReflectScriptEvent>()
+            .ok_or_else(|| {
+                InteropError::missing_type_data(type_id, "ReflectScriptEvent".to_owned())
+            })?;
+
+        let event = <Box<dyn PartialReflect>>::from_script_ref(type_id, payload, self.clone())?;
+        self.with_global_access(|world| reflect_event.send(world, event.as_ref()))?
+    }
+}
+
+/// A plugin to support script events
+pub(crate) struct DynamicScriptEventPlugin;
+
+impl Plugin for DynamicScriptEventPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AppScriptEventRegistry>()
+            .add_event::<DynamicEvent>()
+            .register_type::<DynamicEvent>();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::reflect::{FromType, Reflect};
+
+    #[derive(Event, Reflect, Clone, PartialEq, Debug)]
+    struct TestEvent(usize);
+
+    #[test]
+    fn test_reflect_script_event_sends_and_reads_events() {
+        let mut world = World::new();
+        world.init_resource::<Events<TestEvent>>();
+        let reflect_event = <ReflectScriptEvent as FromType<TestEvent>>::from_type();
+        let mut cursor = reflect_event.new_cursor();
+
+        reflect_event.send(&mut world, &TestEvent(1)).unwrap();
+        reflect_event.send(&mut world, &TestEvent(2)).unwrap();
+        assert!(reflect_event.send(&mut world, &2usize).is_err());
+
+        // Safety: we have exclusive access to the world
+        let events = unsafe { reflect_event.read(world.as_unsafe_world_cell(), &mut cursor) };
+        let events = events
+            .into_iter()
+            .map(|e| *e.downcast::<TestEvent>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec![TestEvent(1), TestEvent(2)]);
+
+        // the cursor only returns new events
+        let events = unsafe { reflect_event.read(world.as_unsafe_world_cell(), &mut cursor) };
+        assert!(events.is_empty());
+
+        // a new cursor reads the events sent before it was created
+        let mut cursor = reflect_event.new_cursor();
+        let events = unsafe { reflect_event.read(world.as_unsafe_world_cell(), &mut cursor) };
+        assert_eq!(events.len(), 2);
+    }
+}
@@ -0,0 +1,268 @@
+//! Everything necessary to support scripts observing component hooks and triggered events
+
+use super::{
+    AppReflectAllocator, DynamicEvent, ReflectBase, ReflectReference, ReflectScriptEvent,
+    ScriptComponentRegistration, ScriptEventRegistration, ScriptValue, WorldAccessGuard,
+};
+use crate::{
+    commands::RunScriptCallback, error::InteropError, event::CallbackLabel, script::ScriptId,
+    IntoScriptPluginParams,
+};
+use bevy::{
+    ecs::{
+        component::ComponentId,
+        entity::Entity,
+        event::Event,
+        observer::{Observer, Trigger},
+        system::{Commands, Resource},
+        world::{Command, DeferredWorld, OnAdd, OnInsert, OnRemove, OnReplace, World},
+    },
+    reflect::{Reflect, ReflectFromPtr, ReflectFromReflect},
+    utils::HashMap,
+};
+
+/// Describes what a script observer reacts to
+#[derive(Clone, Reflect, Debug)]
+#[reflect(opaque)]
+pub enum ScriptObserverTrigger {
+    /// Triggered when the component is added to an entity which did not have it
+    OnAdd(ScriptComponentRegistration),
+    /// Triggered whenever the component is inserted, even if the entity already had it
+    OnInsert(ScriptComponentRegistration),
+    /// Triggered before the component is overwritten by an insert or removed
+    OnReplace(ScriptComponentRegistration),
+    /// Triggered before the component is removed from an entity, or the entity is despawned
+    OnRemove(ScriptComponentRegistration),
+    /// Triggered when an event of the given type is triggered via `World::trigger` or `Commands::trigger`
+    Event(ScriptEventRegistration),
+}
+
+/// The observers added by scripts, these are despawned when the script which added them is reloaded or deleted
+#[derive(Resource, Default)]
+pub struct ScriptObservers {
+    observers: HashMap<ScriptId, Vec<Entity>>,
+}
+
+#[profiling::all_functions]
+impl ScriptObservers {
+    /// Returns the observer entities added by the given script
+    pub fn get(&self, script: &ScriptId) -> &[Entity] {
+        self.observers
+            .get(script)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Records an observer entity added by the given script
+    pub fn insert(&mut self, script: ScriptId, observer: Entity) {
+        self.observers.entry(script).or_default().push(observer);
+    }
+
+    /// Stops tracking the observers of the given script, returning them
+    pub fn remove(&mut self, script: &ScriptId) -> Vec<Entity> {
+        self.observers.remove(script).unwrap_or_default()
+    }
+
+    /// Stops tracking the observers added by the given script, returning them without despawning them
+    pub fn take_script_observers(world: &mut World, script: &ScriptId) -> Vec<Entity> {
+        world
+            .get_resource_mut::<ScriptObservers>()
+            .map(|mut observers| observers.remove(script))
+            .unwrap_or_default()
+    }
+
+    /// Tracks the given observers as added by the given script again, i.e. after a failed reload
+    pub fn restore_script_observers(world: &mut World, script: &ScriptId, observers: Vec<Entity>) {
+        let mut tracked = world.get_resource_or_init::<ScriptObservers>();
+        for observer in observers {
+            tracked.insert(script.clone(), observer);
+        }
+    }
+
+    /// Despawns the given observer entities
+    pub fn despawn_observers(world: &mut World, observers: Vec<Entity>) {
+        for observer in observers {
+            if let Ok(observer) = world.get_entity_mut(observer) {
+                observer.despawn();
+            }
+        }
+    }
+
+    /// Despawns all the observers added by the given script
+    pub fn despawn_script_observers(world: &mut World, script: &ScriptId) {
+        let observers = Self::take_script_observers(world, script);
+        Self::despawn_observers(world, observers);
+    }
+}
+
+/// The event passed to the callback of a script observer
+enum ObserverPayload {
+    /// The payload of a script registered event
+    Value(ScriptValue),
+    /// A copy of a reflected event, or of a replaced or removed component
+    Reflect(Box<dyn Reflect>),
+}
+
+/// Creates an observer of one of the component lifecycle events
+fn component_observer<E: Event>(
+    component_id: ComponentId,
+    f: impl Fn(Entity, &mut DeferredWorld) + Send + Sync + 'static,
+) -> Observer {
+    Observer::new(move |trigger: Trigger<E>, mut world: DeferredWorld| {
+        f(trigger.entity(), &mut world)
+    })
+    .with_component(component_id)
+}
+
+/// Copies the value of the component on the given entity, used to pass the value of a component which is about to be replaced or removed.
+///
+/// Returns `None` if the entity does not have the component, or the component cannot be copied.
+fn snapshot_component(
+    world: &World,
+    entity: Entity,
+    component: &ScriptComponentRegistration,
+) -> Option<Box<dyn Reflect>> {
+    let registration = component.registration.type_registration();
+    let from_ptr = registration.data::<ReflectFromPtr>()?;
+    let ptr = world
+        .get_entity(entity)
+        .ok()?
+        .get_by_id(component.component_id)
+        .ok()?;
+    // Safety: the component id belongs to the type of the registration, so the pointer points to a value of that type
+    let value = unsafe { from_ptr.as_reflect(ptr) };
+    match registration.data::<ReflectFromReflect>() {
+        Some(from_reflect) => from_reflect.from_reflect(value.as_partial_reflect()),
+        None => value.clone_value().try_into_reflect().ok(),
+    }
+}
+
+#[profiling::all_functions]
+impl WorldAccessGuard<'_> {
+    /// Adds an observer which runs the given callback on the given script whenever the trigger fires, returning the observer entity.
+    ///
+    /// The callback is run as a command right after the trigger, with the entity the trigger targets as the `entity` of the callback.
+    /// It receives the entity as its first argument, and in the case of event triggers the event as its second argument.
+    /// As the callback runs after the component is gone, `OnReplace` and `OnRemove` callbacks receive a copy of the replaced or removed component as their second argument instead.
+    /// The observer is despawned when the script is reloaded or deleted.
+    ///
+    /// Restricted scripts can only add observers calling themselves, and their observers do not run for entities they cannot access.
+    pub fn add_observer<P: IntoScriptPluginParams>(
+        &self,
+        trigger: ScriptObserverTrigger,
+        callback: CallbackLabel,
+        script_id: ScriptId,
+    ) -> Result<Entity, InteropError> {
+        self.check_script_access(&script_id)?;
+        bevy::log::debug!(
+            "Adding script observer calling '{}' on script '{}'",
+            callback,
+            script_id
+        );
+        let run_callback = {
+            let script_id = script_id.clone();
+            move |commands: &mut Commands,
+                  entity: Entity,
+                  component_id: Option<ComponentId>,
+                  payload: Option<ObserverPayload>| {
+                let script_id = script_id.clone();
+                let callback = callback.clone();
+                // the payload is allocated once the callback runs, as the allocator might be locked while the observer runs
+                commands.queue(move |world: &mut World| {
+                    // like queries, observers skip the entities the script is not allowed to access
+                    let guard =
+                        WorldAccessGuard::new_exclusive(world).with_script_capabilities(&script_id);
+                    let allowed = match component_id {
+                        Some(component_id) => guard.check_base_access(
+                            &ReflectBase::Component(entity, component_id),
+                            false,
+                        ),
+                        None => guard.check_entity_access(entity),
+                    };
+                    drop(guard);
+                    if allowed.is_err() {
+                        return;
+                    }
+                    let allocator = world.get_resource_or_init::<AppReflectAllocator>().clone();
+                    let mut allocator = allocator.write();
+                    let mut args = vec![ScriptValue::Reference(ReflectReference::new_allocated(
+                        entity,
+                        &mut allocator,
+                    ))];
+                    match payload {
+                        Some(ObserverPayload::Value(value)) => args.push(value),
+                        Some(ObserverPayload::Reflect(value)) => args.push(ScriptValue::Reference(
+                            ReflectReference::new_allocated_boxed(value, &mut allocator),
+                        )),
+                        None => {}
+                    }
+                    drop(allocator);
+                    RunScriptCallback::<P>::new(script_id, entity, callback, args, false)
+                        .with_context("running observer")
+                        .apply(world);
+                });
+            }
+        };
+
+        let observer = match trigger {
+            ScriptObserverTrigger::OnAdd(component) => {
+                let component_id = component.component_id;
+                component_observer::<OnAdd>(component_id, move |entity, world| {
+                    run_callback(&mut world.commands(), entity, Some(component_id), None)
+                })
+            }
+            ScriptObserverTrigger::OnInsert(component) => {
+                let component_id = component.component_id;
+                component_observer::<OnInsert>(component_id, move |entity, world| {
+                    run_callback(&mut world.commands(), entity, Some(component_id), None)
+                })
+            }
+            ScriptObserverTrigger::OnReplace(component) => {
+                let component_id = component.component_id;
+                component_observer::<OnReplace>(component_id, move |entity, world| {
+                    let payload =
+                        snapshot_component(world, entity, &component).map(ObserverPayload::Reflect);
+                    run_callback(&mut world.commands(), entity, Some(component_id), payload)
+                })
+            }
+            ScriptObserverTrigger::OnRemove(component) => {
+                let component_id = component.component_id;
+                component_observer::<OnRemove>(component_id, move |entity, world| {
+                    let payload =
+                        snapshot_component(world, entity, &component).map(ObserverPayload::Reflect);
+                    run_callback(&mut world.commands(), entity, Some(component_id), payload)
+                })
+            }
+            ScriptObserverTrigger::Event(registration) => {
+                let type_id = registration.registration.type_id();
+                let reflect_event = registration
+                    .registration
+                    .type_registration()
+                    .data::<ReflectScriptEvent>()
+                    .ok_or_else(|| {
+                        InteropError::missing_type_data(type_id, "ReflectScriptEvent".to_owned())
+                    })?;
+                let script_event_name = registration.script_event_name;
+                reflect_event.observer(Box::new(move |entity, event, commands| {
+                    let payload = match &script_event_name {
+                        // script events are passed as their payload
+                        Some(name) => match event.downcast::<DynamicEvent>() {
+                            Ok(event) if &event.name == name => ObserverPayload::Value(event.data),
+                            _ => return,
+                        },
+                        None => ObserverPayload::Reflect(event),
+                    };
+                    run_callback(commands, entity, None, Some(payload))
+                }))
+            }
+        };
+
+        self.with_global_access(|world| {
+            let observer = world.spawn(observer).id();
+            world
+                .get_resource_or_init::<ScriptObservers>()
+                .insert(script_id, observer);
+            observer
+        })
+    }
+}
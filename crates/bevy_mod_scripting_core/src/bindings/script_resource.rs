@@ -0,0 +1,212 @@
+//! Everything necessary to support scripts registering their own resources
+
+use super::{ScriptResourceRegistration, ScriptTypeRegistration, ScriptValue, WorldAccessGuard};
+use crate::error::InteropError;
+use bevy::{
+    app::{App, Plugin},
+    ecs::{
+        component::{ComponentDescriptor, ComponentId, StorageType},
+        system::Resource,
+    },
+    prelude::ReflectDefault,
+    ptr::OwningPtr,
+    reflect::{GetTypeRegistration, Reflect},
+    utils::HashMap,
+};
+use parking_lot::RwLock;
+use std::{alloc::Layout, mem::needs_drop, sync::Arc};
+
+/// A dynamic script resource, with a single field `data` which can be set to any value.
+#[derive(Reflect, Clone, Default)]
+#[reflect(Default)]
+pub struct DynamicResource {
+    data: ScriptValue,
+}
+
+impl DynamicResource {
+    /// Returns the value stored in the resource
+    pub fn data(&self) -> &ScriptValue {
+        &self.data
+    }
+}
+
+/// Some metadata about dynamic script resources
+pub struct DynamicResourceInfo {
+    /// The name of the resource
+    pub name: String,
+    /// The type registration for the resource
+    pub registration: ScriptResourceRegistration,
+}
+
+/// A registry of dynamically registered script resources
+#[derive(Clone, Resource, Default)]
+pub struct AppScriptResourceRegistry(pub Arc<RwLock<ScriptResourceRegistry>>);
+
+#[profiling::all_functions]
+impl AppScriptResourceRegistry {
+    /// Reads the underlying registry
+    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, ScriptResourceRegistry> {
+        self.0.read()
+    }
+
+    /// Writes to the underlying registry
+    pub fn write(&self) -> parking_lot::RwLockWriteGuard<'_, ScriptResourceRegistry> {
+        self.0.write()
+    }
+}
+
+#[derive(Default)]
+/// A registry of dynamically registered script resources
+pub struct ScriptResourceRegistry {
+    resources: HashMap<String, DynamicResourceInfo>,
+}
+
+#[profiling::all_functions]
+impl ScriptResourceRegistry {
+    /// Registers a dynamic script resource, possibly overwriting an existing one
+    pub fn register(&mut self, info: DynamicResourceInfo) {
+        self.resources.insert(info.name.clone(), info);
+    }
+
+    /// Gets a dynamic script resource by name
+    pub fn get(&self, name: &str) -> Option<&DynamicResourceInfo> {
+        self.resources.get(name)
+    }
+
+    /// Gets a dynamic script resource by its resource id
+    pub fn get_by_id(&self, resource_id: ComponentId) -> Option<&DynamicResourceInfo> {
+        self.resources
+            .values()
+            .find(|info| info.registration.resource_id == resource_id)
+    }
+
+    /// Iterates over all the dynamic script resources
+    pub fn iter(&self) -> impl Iterator<Item = &DynamicResourceInfo> {
+        self.resources.values()
+    }
+}
+
+#[profiling::all_functions]
+impl WorldAccessGuard<'_> {
+    /// Registers a dynamic script resource and inserts a default instance of it into the world, returns a reference to its registration
+    pub fn register_script_resource(
+        &self,
+        resource_name: String,
+    ) -> Result<ScriptResourceRegistration, InteropError> {
+        let resource_registry = self.resource_registry();
+        let resource_registry_read = resource_registry.read();
+        if resource_registry_read.get(&resource_name).is_some() {
+            return Err(InteropError::unsupported_operation(
+                None,
+                None,
+                "script registered resource already exists",
+            ));
+        }
+
+        let resource_id = self.with_global_access(|w| {
+            let descriptor = unsafe {
+                // Safety: same safety guarantees as ComponentDescriptor::new
+                // we know the type in advance
+                // we only use this method to name the resource
+                ComponentDescriptor::new_with_layout(
+                    resource_name.clone(),
+                    StorageType::Table,
+                    Layout::new::<DynamicResource>(),
+                    needs_drop::<DynamicResource>().then_some(|x| x.drop_as::<DynamicResource>()),
+                )
+            };
+            w.register_resource_with_descriptor(descriptor)
+        })?;
+        drop(resource_registry_read);
+        let mut resource_registry = resource_registry.write();
+
+        let registration = ScriptResourceRegistration::new(
+            ScriptTypeRegistration::new(Arc::new(
+                <DynamicResource as GetTypeRegistration>::get_type_registration(),
+            )),
+            resource_id,
+        );
+
+        resource_registry.register(DynamicResourceInfo {
+            name: resource_name,
+            registration: registration.clone(),
+        });
+        drop(resource_registry);
+
+        self.insert_script_resource(&registration, DynamicResource::default())?;
+        Ok(registration)
+    }
+
+    /// Inserts the given value for a dynamic script resource, replacing the existing value if there is one
+    pub fn insert_script_resource(
+        &self,
+        registration: &ScriptResourceRegistration,
+        value: DynamicResource,
+    ) -> Result<(), InteropError> {
+        if !registration.is_dynamic_script_resource {
+            return Err(InteropError::unsupported_operation(
+                Some(registration.type_registration().type_id()),
+                None,
+                "only script registered resources can be inserted this way",
+            ));
+        }
+        self.check_resource_write(registration.resource_id)?;
+        self.with_global_access(|world| {
+            OwningPtr::make(value, |ptr| {
+                // Safety:
+                // - the resource was registered with the layout of `DynamicResource`, which is what the pointer points to
+                unsafe { world.insert_resource_by_id(registration.resource_id, ptr) }
+            })
+        })
+    }
+}
+
+/// A plugin to support dynamic script resources
+pub(crate) struct DynamicScriptResourcePlugin;
+
+impl Plugin for DynamicScriptResourcePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AppScriptResourceRegistry>()
+            .register_type::<DynamicResource>();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::ecs::world::World;
+
+    #[test]
+    fn test_script_resource() {
+        let mut world = World::new();
+        let registration = {
+            let guard = WorldAccessGuard::new_exclusive(&mut world);
+
+            guard
+                .register_script_resource("ScriptTestResource".to_string())
+                .unwrap()
+        };
+
+        let registry = world.get_resource::<AppScriptResourceRegistry>().unwrap();
+
+        let registry = registry.read();
+        let info = registry.get("ScriptTestResource").unwrap();
+        assert_eq!(info.registration.resource_id, registration.resource_id);
+        assert_eq!(info.name, "ScriptTestResource");
+
+        // the resource is inserted on registration
+        let resource = world.get_resource_by_id(registration.resource_id).unwrap();
+        // Safety: the resource was registered with the layout of `DynamicResource`
+        let resource = unsafe { resource.deref::<DynamicResource>() };
+        assert_eq!(resource.data(), &ScriptValue::Unit);
+
+        assert_eq!(
+            world
+                .components()
+                .get_info(registration.resource_id)
+                .unwrap()
+                .name(),
+            "ScriptTestResource"
+        );
+    }
+}
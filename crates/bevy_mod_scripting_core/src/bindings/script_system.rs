@@ -5,8 +5,10 @@ use super::{
     function::{from::Val, into::IntoScript, script_function::AppScriptFunctionRegistry},
     schedule::AppScheduleRegistry,
     script_value::ScriptValue,
-    AppReflectAllocator, AppScriptComponentRegistry, ReflectBaseType, ReflectReference,
-    ScriptQueryBuilder, ScriptQueryResult, ScriptResourceRegistration, WorldAccessGuard,
+    AppReflectAllocator, AppScriptCapabilityRegistry, AppScriptComponentRegistry,
+    AppScriptEventRegistry, AppScriptQueryCache, AppScriptResourceRegistry, DynamicEvent,
+    ReflectReference, ReflectScriptEvent, ScriptCommandQueue, ScriptEventCursor,
+    ScriptEventRegistration, ScriptQueryBuilder, ScriptResourceRegistration, WorldAccessGuard,
     WorldGuard,
 };
 use crate::{
@@ -15,7 +17,7 @@ use crate::{
     error::{InteropError, ScriptError},
     event::CallbackLabel,
     extractors::get_all_access_ids,
-    handler::CallbackSettings,
+    handler::{CallbackInvocation, CallbackSettings},
     runtime::RuntimeContainer,
     script::{ScriptId, Scripts},
     IntoScriptPluginParams,
@@ -31,6 +33,7 @@ use bevy::{
         system::{IntoSystem, System},
         world::{unsafe_world_cell::UnsafeWorldCell, World},
     },
+    hierarchy::Parent,
     reflect::{OffsetAccess, ParsedPath, Reflect},
     utils::hashbrown::HashSet,
 };
@@ -76,6 +79,7 @@ impl SystemSet for ScriptSystemSet {
 enum ScriptSystemParamDescriptor {
     Res(ScriptResourceRegistration),
     EntityQuery(ScriptQueryBuilder),
+    EventReader(ScriptEventRegistration),
 }
 
 /// A builder for systems living in scripts
@@ -118,6 +122,13 @@ impl ScriptSystemBuilder {
         self
     }
 
+    /// Adds an event reader to the system, the system will receive all events of the given type sent since it last ran
+    pub fn event_reader(&mut self, event: ScriptEventRegistration) -> &mut Self {
+        self.system_params
+            .push(ScriptSystemParamDescriptor::EventReader(event));
+        self
+    }
+
     /// Sets the system to be exclusive, i.e. it will be able to access everything but cannot be parallelized.
     pub fn exclusive(&mut self, exclusive: bool) -> &mut Self {
         self.is_exclusive = exclusive;
@@ -274,8 +285,10 @@ impl<'w, P: IntoScriptPluginParams> DynamicHandlerContext<'w, P> {
 
         let mut context = script.context.lock();
 
-        CallbackSettings::<P>::call(
-            handler,
+        CallbackSettings::<P>::call_with(
+            CallbackInvocation::new(handler)
+                .with_execution_budget(self.callback_settings.execution_budget)
+                .with_scripts(self.scripts),
             payload,
             entity,
             script_id,
@@ -294,8 +307,14 @@ struct ScriptSystemState {
     function_registry: AppScriptFunctionRegistry,
     schedule_registry: AppScheduleRegistry,
     component_registry: AppScriptComponentRegistry,
+    resource_registry: AppScriptResourceRegistry,
+    event_registry: AppScriptEventRegistry,
+    capability_registry: AppScriptCapabilityRegistry,
+    query_cache: AppScriptQueryCache,
+    command_queue: ScriptCommandQueue,
     allocator: AppReflectAllocator,
     subset: HashSet<ReflectAccessId>,
+    read_only: HashSet<ReflectAccessId>,
     callback_label: CallbackLabel,
     system_params: Vec<ScriptSystemParam>,
 }
@@ -315,8 +334,17 @@ pub enum ScriptSystemParam {
     EntityQuery {
         /// The internal state of the query
         query: Box<QueryState<Entity, ()>>,
-        /// the components in correct order describing the necessary references
-        components: Vec<(ComponentId, TypeId)>,
+        /// The builder the query was created from, describing the necessary references and the filters applied to each entity
+        builder: ScriptQueryBuilder,
+    },
+    /// A reader of events of some type
+    EventReader {
+        /// The type data used to read the events
+        reflect_event: ReflectScriptEvent,
+        /// The position of the reader in the event queue
+        cursor: ScriptEventCursor,
+        /// The name of the event if it's a script registered event
+        script_event_name: Option<String>,
     },
 }
 
@@ -400,7 +428,8 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
     }
 
     fn has_deferred(&self) -> bool {
-        false
+        // commands queued by the script are applied once the system finishes
+        true
     }
 
     unsafe fn run_unsafe(
@@ -408,7 +437,7 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
         _input: bevy::ecs::system::SystemIn<'_, Self>,
         world: bevy::ecs::world::unsafe_world_cell::UnsafeWorldCell,
     ) -> Self::Out {
-        let _change_tick = world.increment_change_tick();
+        let this_run = world.increment_change_tick();
 
         #[allow(
             clippy::panic,
@@ -428,13 +457,22 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
             WorldAccessGuard::new_non_exclusive(
                 world,
                 state.subset.clone(),
+                state.read_only.clone(),
                 state.type_registry.clone(),
                 state.allocator.clone(),
                 state.function_registry.clone(),
                 state.schedule_registry.clone(),
                 state.component_registry.clone(),
+                state.resource_registry.clone(),
+                state.event_registry.clone(),
+                state.capability_registry.clone(),
+                state.query_cache.clone(),
             )
         };
+        let guard = guard
+            .with_command_queue(state.command_queue.clone())
+            .with_script_capabilities(&self.target_script)
+            .with_change_ticks(self.last_run, this_run);
 
         // TODO: cache references which don't change once we have benchmarks
         for param in &mut state.system_params {
@@ -452,33 +490,65 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
                     };
                     payload.push(res_ref.into_script_inline_error(guard.clone()));
                 }
-                ScriptSystemParam::EntityQuery { query, components } => {
+                ScriptSystemParam::EntityQuery { query, builder } => {
                     // TODO: is this the right way to use this world cell for queries?
-                    let entities = query.iter_unchecked(world).collect::<Vec<_>>();
+                    let entities = match builder.restricted_to() {
+                        Some(entities) => entities
+                            .iter()
+                            .filter(|entity| query.get_unchecked(world, **entity).is_ok())
+                            .copied()
+                            .collect::<Vec<_>>(),
+                        None => query.iter_unchecked(world).collect::<Vec<_>>(),
+                    };
                     let results = entities
                         .into_iter()
-                        .map(|entity| {
-                            Val(ScriptQueryResult {
-                                entity,
-                                components: components
-                                    .iter()
-                                    .map(|(component_id, type_id)| ReflectReference {
-                                        base: ReflectBaseType {
-                                            type_id: *type_id,
-                                            base_id: super::ReflectBase::Component(
-                                                entity,
-                                                *component_id,
-                                            ),
-                                        },
-                                        reflect_path: Vec::<OffsetAccess>::default().into(),
-                                    })
-                                    .collect(),
-                            })
+                        .filter(|entity| guard.check_entity_access(*entity).is_ok())
+                        .filter_map(|entity| {
+                            let cell = world.get_entity(entity)?;
+                            let matches = builder.matches_change_filters(
+                                |id| cell.get_change_ticks_by_id(id),
+                                self.last_run,
+                                this_run,
+                            ) && (!builder.has_parent_filter()
+                                // Safety: the query declares read access to the parent component
+                                || builder.matches_parent(
+                                    unsafe { cell.get::<Parent>() }.map(Parent::get),
+                                ));
+                            matches
+                                .then(|| Val(builder.result_for(entity, |id| cell.contains_id(id))))
                         })
                         .collect::<Vec<_>>();
 
                     payload.push(results.into_script_inline_error(guard.clone()))
                 }
+                ScriptSystemParam::EventReader {
+                    reflect_event,
+                    cursor,
+                    script_event_name,
+                } => {
+                    // Safety: we declared read access to the events resource when initializing
+                    let events = unsafe { reflect_event.read(world, cursor) };
+                    let events = match script_event_name {
+                        // script events are passed as their payload
+                        Some(name) => events
+                            .into_iter()
+                            .filter_map(|e| e.downcast::<DynamicEvent>().ok())
+                            .filter(|e| &e.name == name)
+                            .map(|e| e.data)
+                            .collect::<Vec<_>>()
+                            .into_script_inline_error(guard.clone()),
+                        None => {
+                            let allocator = guard.allocator();
+                            let mut allocator = allocator.write();
+                            events
+                                .into_iter()
+                                .map(|e| ReflectReference::new_allocated_boxed(e, &mut allocator))
+                                .collect::<Vec<_>>()
+                                .into_script_inline_error(guard.clone())
+                        }
+                    };
+                    payload.push(events);
+                }
             }
         }
 
@@ -506,6 +576,8 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
                 )
             }
         }
+
+        self.last_run = this_run;
     }
 
     fn initialize(&mut self, world: &mut bevy::ecs::world::World) {
@@ -515,6 +587,8 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
 
         // start with resources
         let mut subset = HashSet::default();
+        let mut read = HashSet::<ReflectAccessId>::default();
+        let mut written = HashSet::default();
         let mut system_params = Vec::with_capacity(self.system_param_descriptors.len());
         for param in &self.system_param_descriptors {
             match param {
@@ -543,11 +617,7 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
                     subset.insert(raid);
                 }
                 ScriptSystemParamDescriptor::EntityQuery(query) => {
-                    let components: Vec<_> = query
-                        .components
-                        .iter()
-                        .map(|c| (c.component_id, c.type_registration().type_id()))
-                        .collect();
+                    let builder = query.clone();
                     let query = query.as_query_state::<Entity>(world);
 
                     // Safety: we are not removing
@@ -559,6 +629,16 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
                         .map(|(a, _)| a)
                         .collect::<HashSet<_>>();
 
+                    let (writes, reads): (Vec<_>, Vec<_>) =
+                        new_raids.iter().copied().partition(|raid| {
+                            query
+                                .component_access()
+                                .access()
+                                .has_component_write(ComponentId::new(raid.id as usize))
+                        });
+                    written.extend(writes);
+                    read.extend(reads);
+
                     #[allow(
                         clippy::panic,
                         reason = "WIP, to be dealt with in validate params better, but panic will still remain"
@@ -569,16 +649,47 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
 
                     system_params.push(ScriptSystemParam::EntityQuery {
                         query: query.into(),
-                        components,
+                        builder,
                     });
                     subset.extend(new_raids);
                 }
+                ScriptSystemParamDescriptor::EventReader(event) => {
+                    #[allow(
+                        clippy::panic,
+                        reason = "WIP, to be dealt with in validate params better, but panic will still remain"
+                    )]
+                    let reflect_event = match event
+                        .type_registration()
+                        .type_registration()
+                        .data::<ReflectScriptEvent>()
+                    {
+                        Some(reflect_event) => reflect_event.clone(),
+                        None => panic!(
+                            "Event type {} does not have ReflectScriptEvent type data.",
+                            event.type_registration().type_name()
+                        ),
+                    };
+
+                    // events are read directly from the world, not through the access guard
+                    let mut access = FilteredAccess::<ComponentId>::matches_nothing();
+                    access.add_resource_read(event.events_resource_id());
+                    self.component_access_set.add(access);
+
+                    system_params.push(ScriptSystemParam::EventReader {
+                        cursor: reflect_event.new_cursor(),
+                        reflect_event,
+                        script_event_name: event.script_event_name().map(ToOwned::to_owned),
+                    });
+                }
             }
         }
 
         // TODO: access to internal resources, i.e. handler state
         DynamicHandlerContext::<P>::init_param(world, &mut self.component_access_set);
 
+        // components which are only read by the queries of the system cannot be written to by the script
+        let read_only = read.difference(&written).copied().collect();
+
         self.state = Some(ScriptSystemState {
             type_registry: world.get_resource_or_init::<AppTypeRegistry>().clone(),
             function_registry: world
@@ -589,7 +700,19 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
             component_registry: world
                 .get_resource_or_init::<AppScriptComponentRegistry>()
                 .clone(),
+            resource_registry: world
+                .get_resource_or_init::<AppScriptResourceRegistry>()
+                .clone(),
+            event_registry: world
+                .get_resource_or_init::<AppScriptEventRegistry>()
+                .clone(),
+            capability_registry: world
+                .get_resource_or_init::<AppScriptCapabilityRegistry>()
+                .clone(),
+            query_cache: world.get_resource_or_init::<AppScriptQueryCache>().clone(),
+            command_queue: Default::default(),
             subset,
+            read_only,
             callback_label: self.name.to_string().into(),
             system_params,
         })
@@ -636,7 +759,11 @@ impl<P: IntoScriptPluginParams> System for DynamicScriptSystem<P> {
         self.last_run = last_run;
     }
 
-    fn apply_deferred(&mut self, _world: &mut World) {}
+    fn apply_deferred(&mut self, world: &mut World) {
+        if let Some(state) = &self.state {
+            state.command_queue.apply(world);
+        }
+    }
 
     fn queue_deferred(&mut self, _world: bevy::ecs::world::DeferredWorld) {}
 
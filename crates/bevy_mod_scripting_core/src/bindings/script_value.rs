@@ -1,14 +1,22 @@
 //! This module contains the `ScriptValue` enum which is used to pass values between scripting languages and Rust.
 
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, fmt};
 
-use bevy::reflect::{OffsetAccess, ParsedPath, Reflect};
+use bevy::reflect::{
+    serde::{ReflectDeserializer, ReflectSerializer},
+    OffsetAccess, ParsedPath, PartialReflect, Reflect,
+};
+use serde::{
+    de::{self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor},
+    ser::{self, SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
-use crate::error::InteropError;
+use crate::{error::InteropError, reflection_extensions::PartialReflectExt};
 
 use super::{
     function::script_function::{DynamicScriptFunction, DynamicScriptFunctionMut},
-    ReflectReference,
+    ReflectReference, WorldGuard,
 };
 
 /// An abstraction of values that can be passed to and from scripts.
@@ -209,8 +217,260 @@ impl TryFrom<ScriptValue> for ParsedPath {
     }
 }
 
+/// Serializes a [`ScriptValue`], serializing the values pointed to by [`ScriptValue::Reference`]'s through the type registry.
+///
+/// References are serialized by value, and deserialized into new allocations by [`ScriptValueDeserializer`].
+/// Functions and errors cannot be serialized.
+pub struct ScriptValueSerializer<'a> {
+    value: &'a ScriptValue,
+    world: WorldGuard<'a>,
+}
+
+impl<'a> ScriptValueSerializer<'a> {
+    /// Creates a new serializer for the given value
+    pub fn new(value: &'a ScriptValue, world: WorldGuard<'a>) -> Self {
+        Self { value, world }
+    }
+
+    fn with_value(&self, value: &'a ScriptValue) -> Self {
+        Self::new(value, self.world.clone())
+    }
+}
+
+const SCRIPT_VALUE_VARIANTS: &[&str] = &[
+    "Unit",
+    "Bool",
+    "Integer",
+    "Float",
+    "String",
+    "List",
+    "Map",
+    "Reference",
+];
+
+#[derive(Deserialize)]
+#[serde(variant_identifier)]
+enum ScriptValueVariant {
+    Unit,
+    Bool,
+    Integer,
+    Float,
+    String,
+    List,
+    Map,
+    Reference,
+}
+
+impl Serialize for ScriptValueSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.value {
+            ScriptValue::Unit => serializer.serialize_unit_variant("ScriptValue", 0, "Unit"),
+            ScriptValue::Bool(b) => {
+                serializer.serialize_newtype_variant("ScriptValue", 1, "Bool", b)
+            }
+            ScriptValue::Integer(i) => {
+                serializer.serialize_newtype_variant("ScriptValue", 2, "Integer", i)
+            }
+            ScriptValue::Float(f) => {
+                serializer.serialize_newtype_variant("ScriptValue", 3, "Float", f)
+            }
+            ScriptValue::String(s) => {
+                serializer.serialize_newtype_variant("ScriptValue", 4, "String", s)
+            }
+            ScriptValue::List(list) => serializer.serialize_newtype_variant(
+                "ScriptValue",
+                5,
+                "List",
+                &ScriptListSerializer(list.iter().map(|v| self.with_value(v)).collect()),
+            ),
+            ScriptValue::Map(map) => serializer.serialize_newtype_variant(
+                "ScriptValue",
+                6,
+                "Map",
+                &ScriptMapSerializer(
+                    map.iter()
+                        .map(|(k, v)| (k.as_str(), self.with_value(v)))
+                        .collect(),
+                ),
+            ),
+            ScriptValue::Reference(reference) => {
+                let type_registry = self.world.type_registry();
+                let type_registry = type_registry.read();
+                reference
+                    .with_reflect(self.world.clone(), |r| {
+                        serializer.serialize_newtype_variant(
+                            "ScriptValue",
+                            7,
+                            "Reference",
+                            &ReflectSerializer::new(r, &type_registry),
+                        )
+                    })
+                    .map_err(ser::Error::custom)?
+            }
+            value => Err(ser::Error::custom(format!(
+                "Cannot serialize script values of type: {}",
+                value.type_name()
+            ))),
+        }
+    }
+}
+
+struct ScriptListSerializer<'a>(Vec<ScriptValueSerializer<'a>>);
+
+impl Serialize for ScriptListSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for value in &self.0 {
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
+struct ScriptMapSerializer<'a>(Vec<(&'a str, ScriptValueSerializer<'a>)>);
+
+impl Serialize for ScriptMapSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Deserializes a [`ScriptValue`] serialized with a [`ScriptValueSerializer`].
+///
+/// Serialized references are deserialized through the type registry and allocated via the [`super::ReflectAllocator`].
+#[derive(Clone)]
+pub struct ScriptValueDeserializer<'a> {
+    world: WorldGuard<'a>,
+}
+
+impl<'a> ScriptValueDeserializer<'a> {
+    /// Creates a new deserializer allocating references in the given world
+    pub fn new(world: WorldGuard<'a>) -> Self {
+        Self { world }
+    }
+
+    fn allocate(&self, value: Box<dyn PartialReflect>) -> Result<ScriptValue, InteropError> {
+        let value = <dyn PartialReflect>::from_reflect_or_clone(value.as_ref(), self.world.clone());
+        let allocator = self.world.allocator();
+        let mut allocator = allocator.write();
+        ReflectReference::new_allocated_boxed_parial_reflect(value, &mut allocator)
+            .map(ScriptValue::Reference)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ScriptValueDeserializer<'_> {
+    type Value = ScriptValue;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_enum("ScriptValue", SCRIPT_VALUE_VARIANTS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for ScriptValueDeserializer<'_> {
+    type Value = ScriptValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a script value")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (variant, access) = data.variant::<ScriptValueVariant>()?;
+        Ok(match variant {
+            ScriptValueVariant::Unit => {
+                access.unit_variant()?;
+                ScriptValue::Unit
+            }
+            ScriptValueVariant::Bool => ScriptValue::Bool(access.newtype_variant()?),
+            ScriptValueVariant::Integer => ScriptValue::Integer(access.newtype_variant()?),
+            ScriptValueVariant::Float => ScriptValue::Float(access.newtype_variant()?),
+            ScriptValueVariant::String => {
+                ScriptValue::String(access.newtype_variant::<String>()?.into())
+            }
+            ScriptValueVariant::List => {
+                ScriptValue::List(access.newtype_variant_seed(ScriptListDeserializer(self))?)
+            }
+            ScriptValueVariant::Map => {
+                ScriptValue::Map(access.newtype_variant_seed(ScriptMapDeserializer(self))?)
+            }
+            ScriptValueVariant::Reference => {
+                let type_registry = self.world.type_registry();
+                let type_registry = type_registry.read();
+                let value =
+                    access.newtype_variant_seed(ReflectDeserializer::new(&type_registry))?;
+                drop(type_registry);
+                self.allocate(value).map_err(de::Error::custom)?
+            }
+        })
+    }
+}
+
+struct ScriptListDeserializer<'a>(ScriptValueDeserializer<'a>);
+
+impl<'de> DeserializeSeed<'de> for ScriptListDeserializer<'_> {
+    type Value = Vec<ScriptValue>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ScriptListDeserializer<'_> {
+    type Value = Vec<ScriptValue>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of script values")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(value) = seq.next_element_seed(self.0.clone())? {
+            list.push(value);
+        }
+        Ok(list)
+    }
+}
+
+struct ScriptMapDeserializer<'a>(ScriptValueDeserializer<'a>);
+
+impl<'de> DeserializeSeed<'de> for ScriptMapDeserializer<'_> {
+    type Value = HashMap<String, ScriptValue>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ScriptMapDeserializer<'_> {
+    type Value = HashMap<String, ScriptValue>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of script values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut out = HashMap::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some(key) = map.next_key::<String>()? {
+            out.insert(key, map.next_value_seed(self.0.clone())?);
+        }
+        Ok(out)
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use bevy::{
+        ecs::{reflect::AppTypeRegistry, world::World},
+        reflect::Reflect,
+    };
+
+    use crate::bindings::{
+        function::script_function::AppScriptFunctionRegistry, AppReflectAllocator,
+    };
+
     use super::*;
 
     #[test]
@@ -239,4 +499,80 @@ mod test {
         let value = ScriptValue::Float(0.0);
         assert!(ParsedPath::try_from(value).is_err());
     }
+
+    #[derive(Reflect, Debug, Clone, PartialEq)]
+    struct SaveData {
+        health: u32,
+        name: String,
+    }
+
+    fn setup_world() -> World {
+        let mut world = World::default();
+        let type_registry = AppTypeRegistry::default();
+        type_registry.write().register::<SaveData>();
+        world.insert_resource(type_registry);
+        world.insert_resource(AppReflectAllocator::default());
+        world.insert_resource(AppScriptFunctionRegistry::default());
+        world
+    }
+
+    fn round_trip(value: &ScriptValue, world: WorldGuard) -> ScriptValue {
+        let serialized =
+            serde_json::to_string(&ScriptValueSerializer::new(value, world.clone())).unwrap();
+        ScriptValueDeserializer::new(world)
+            .deserialize(&mut serde_json::Deserializer::from_str(&serialized))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_script_value_serialization_round_trip() {
+        let mut world = setup_world();
+        let world = WorldGuard::new_exclusive(&mut world);
+
+        let value = ScriptValue::List(vec![
+            ScriptValue::Unit,
+            ScriptValue::Bool(true),
+            ScriptValue::Integer(-2),
+            ScriptValue::Float(0.5),
+            ScriptValue::String("hello".into()),
+            ScriptValue::Map(HashMap::from([(
+                "key".to_owned(),
+                ScriptValue::List(vec![ScriptValue::Integer(1)]),
+            )])),
+        ]);
+
+        assert_eq!(round_trip(&value, world), value);
+    }
+
+    #[test]
+    fn test_script_value_reference_serialization_reallocates() {
+        let mut world = setup_world();
+        let world = WorldGuard::new_exclusive(&mut world);
+
+        let data = SaveData {
+            health: 10,
+            name: "player".to_owned(),
+        };
+        let reference = {
+            let allocator = world.allocator();
+            let mut allocator = allocator.write();
+            ReflectReference::new_allocated(data.clone(), &mut allocator)
+        };
+
+        let value = round_trip(&ScriptValue::Reference(reference.clone()), world.clone());
+        let ScriptValue::Reference(deserialized) = value else {
+            panic!("Expected a reference, got: {value:?}");
+        };
+        assert_ne!(deserialized, reference);
+        assert_eq!(deserialized.downcast::<SaveData>(world).unwrap(), data);
+    }
+
+    #[test]
+    fn test_script_value_serialization_fails_for_errors() {
+        let mut world = setup_world();
+        let world = WorldGuard::new_exclusive(&mut world);
+
+        let value = ScriptValue::Error(InteropError::invariant("error"));
+        assert!(serde_json::to_string(&ScriptValueSerializer::new(&value, world)).is_err());
+    }
 }
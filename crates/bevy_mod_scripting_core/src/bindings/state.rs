@@ -0,0 +1,382 @@
+//! Everything necessary to support scripts reading and transitioning bevy states
+
+use super::{AppReflectAllocator, ReflectBase, ReflectReference, ScriptValue, WorldAccessGuard};
+use crate::{
+    error::InteropError,
+    event::{CallbackLabel, ScriptCallbackEvent},
+};
+use bevy::{
+    ecs::{
+        event::{EventReader, EventWriter},
+        system::{Res, Resource},
+        world::World,
+    },
+    reflect::{
+        DynamicEnum, DynamicVariant, FromReflect, PartialReflect, Reflect, TypeInfo, TypePath,
+        Typed, VariantInfo,
+    },
+    state::state::{FreelyMutableState, NextState, State, StateTransitionEvent, States},
+};
+use parking_lot::RwLock;
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+#[derive(Default, Clone, Resource)]
+/// A Send + Sync registry of bevy states accessible to scripts.
+pub struct AppStateRegistry(Arc<RwLock<StateRegistry>>);
+
+impl AppStateRegistry {
+    /// Reads the state registry.
+    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, StateRegistry> {
+        self.0.read()
+    }
+
+    /// Writes to the state registry.
+    pub fn write(&self) -> parking_lot::RwLockWriteGuard<'_, StateRegistry> {
+        self.0.write()
+    }
+}
+
+#[derive(Default)]
+/// A registry of bevy states accessible to scripts.
+pub struct StateRegistry {
+    states: HashMap<TypeId, ScriptStateRegistration>,
+    callbacks: HashSet<CallbackLabel>,
+}
+
+#[profiling::all_functions]
+impl StateRegistry {
+    /// Retrieves a state by its short or full type path
+    pub fn get_state_by_name(&self, name: &str) -> Option<&ScriptStateRegistration> {
+        self.states
+            .values()
+            .find(|state| state.identifier == name || state.type_path == name)
+    }
+
+    /// Registers a state, along with the `on_enter_<state>_<value>` and `on_exit_<state>_<value>` callbacks of each of its values
+    pub fn register<S: FreelyMutableState + FromReflect + TypePath + Typed>(
+        &mut self,
+    ) -> &mut Self {
+        let registration = ScriptStateRegistration::new::<S>();
+        for name in state_callback_names(registration.type_info) {
+            self.callbacks
+                .insert(CallbackLabel::new_lossy(&format!("on_enter_{name}")));
+            self.callbacks
+                .insert(CallbackLabel::new_lossy(&format!("on_exit_{name}")));
+        }
+        self.states.insert(TypeId::of::<S>(), registration);
+        self
+    }
+
+    /// Retrieves the given state
+    pub fn get(&self, type_id: TypeId) -> Option<&ScriptStateRegistration> {
+        self.states.get(&type_id)
+    }
+
+    /// Checks if the given state is contained
+    pub fn contains(&self, type_id: TypeId) -> bool {
+        self.states.contains_key(&type_id)
+    }
+
+    /// Checks if the given callback is sent on transitions of any of the registered states
+    pub fn is_state_callback(&self, label: &CallbackLabel) -> bool {
+        self.callbacks.contains(label)
+    }
+
+    /// Checks if no states are registered
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Creates an iterator over all states
+    pub fn iter(&self) -> impl Iterator<Item = (&TypeId, &ScriptStateRegistration)> {
+        self.states.iter()
+    }
+}
+
+/// A type erased bevy state, which can be read and transitioned from scripts
+#[derive(Clone)]
+pub struct ScriptStateRegistration {
+    type_id: TypeId,
+    type_path: &'static str,
+    identifier: &'static str,
+    type_info: &'static TypeInfo,
+    state_type_id: TypeId,
+    next_state_type_id: TypeId,
+    get: fn(&World) -> Option<Box<dyn Reflect>>,
+    set_next: fn(&mut World, &dyn PartialReflect) -> Result<(), InteropError>,
+}
+
+impl ScriptStateRegistration {
+    /// Creates a new registration for the given state
+    pub fn new<S: FreelyMutableState + FromReflect + TypePath + Typed>() -> Self {
+        Self {
+            type_id: TypeId::of::<S>(),
+            type_path: S::type_path(),
+            identifier: S::short_type_path(),
+            type_info: S::type_info(),
+            state_type_id: TypeId::of::<State<S>>(),
+            next_state_type_id: TypeId::of::<NextState<S>>(),
+            get: |world| {
+                world
+                    .get_resource::<State<S>>()
+                    .map(|state| Box::new(state.get().clone()) as Box<dyn Reflect>)
+            },
+            set_next: |world, value| {
+                let value = S::from_reflect(value).ok_or_else(|| {
+                    InteropError::failed_from_reflect(
+                        Some(TypeId::of::<S>()),
+                        "could not convert the value into the state type",
+                    )
+                })?;
+                world
+                    .get_resource_mut::<NextState<S>>()
+                    .ok_or_else(|| InteropError::missing_state(S::type_path()))?
+                    .set(value);
+                Ok(())
+            },
+        }
+    }
+
+    /// The type id of the state
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// The full type path of the state
+    pub fn type_path(&self) -> &'static str {
+        self.type_path
+    }
+
+    /// The short type path of the state, i.e. `GameState`
+    pub fn identifier(&self) -> &'static str {
+        self.identifier
+    }
+}
+
+/// Converts a name into the snake case used for state callbacks, i.e. `MainMenu` becomes `main_menu`
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut out = String::with_capacity(name.len());
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+/// The names of the values of a state used in its callbacks.
+///
+/// These are prefixed with the type name of the state, so that states sharing variant names do not share callbacks,
+/// i.e. `game_state_main_menu` for the `MainMenu` variant of `GameState`. Non-enum states only use the type name.
+fn state_callback_names(type_info: &TypeInfo) -> Vec<String> {
+    let state = snake_case(type_info.type_path_table().ident().unwrap_or_default());
+    match type_info {
+        TypeInfo::Enum(info) => info
+            .variant_names()
+            .iter()
+            .map(|v| format!("{state}_{}", snake_case(v)))
+            .collect(),
+        _ => vec![state],
+    }
+}
+
+/// The name of the given state value used in its callbacks
+fn state_callback_name<S: Reflect + Typed>(state: &S) -> String {
+    let name = snake_case(S::type_info().type_path_table().ident().unwrap_or_default());
+    match state.reflect_ref() {
+        bevy::reflect::ReflectRef::Enum(e) => format!("{name}_{}", snake_case(e.variant_name())),
+        _ => name,
+    }
+}
+
+/// Sends the `on_exit_<state>_<value>` and `on_enter_<state>_<value>` callbacks to all scripts whenever the state transitions.
+///
+/// Each callback receives a copy of the exited or entered state as its argument.
+pub fn send_state_transition_callbacks<S: States + Reflect + Typed>(
+    mut transitions: EventReader<StateTransitionEvent<S>>,
+    mut writer: EventWriter<ScriptCallbackEvent>,
+    allocator: Res<AppReflectAllocator>,
+) {
+    for transition in transitions.read() {
+        // identity transitions do not run `OnEnter` and `OnExit` schedules either
+        if transition.exited == transition.entered {
+            continue;
+        }
+        let mut allocator = allocator.write();
+        for (prefix, state) in [
+            ("on_exit", &transition.exited),
+            ("on_enter", &transition.entered),
+        ] {
+            let Some(state) = state else {
+                continue;
+            };
+            writer.send(ScriptCallbackEvent::new_for_all(
+                CallbackLabel::new_lossy(&format!("{prefix}_{}", state_callback_name(state))),
+                vec![ScriptValue::Reference(ReflectReference::new_allocated(
+                    state.clone(),
+                    &mut allocator,
+                ))],
+            ));
+        }
+    }
+}
+
+#[profiling::all_functions]
+impl WorldAccessGuard<'_> {
+    /// Retrieves a state registered with scripts by its short or full type path
+    pub fn get_state_by_name(
+        &self,
+        name: &str,
+    ) -> Result<Option<ScriptStateRegistration>, InteropError> {
+        self.with_resource(|registry: &AppStateRegistry| {
+            registry.read().get_state_by_name(name).cloned()
+        })
+    }
+
+    /// Returns a copy of the current value of the given state, or `None` if the state is not initialized in the world
+    pub fn get_state(
+        &self,
+        state: &ScriptStateRegistration,
+    ) -> Result<Option<ReflectReference>, InteropError> {
+        let Some(resource_id) = self.get_resource_id(state.state_type_id)? else {
+            return Ok(None);
+        };
+        self.check_base_access(&ReflectBase::Resource(resource_id), false)?;
+        let Some(value) = self.with_global_access(|world| (state.get)(world))? else {
+            return Ok(None);
+        };
+        let allocator = self.allocator();
+        let mut allocator = allocator.write();
+        Ok(Some(ReflectReference::new_allocated_boxed(
+            value,
+            &mut allocator,
+        )))
+    }
+
+    /// Requests a transition of the given state, which happens the next time the `StateTransition` schedule runs.
+    ///
+    /// The next state can either be a reference to a value of the state, or the name of a unit variant of an enum state.
+    pub fn set_next_state(
+        &self,
+        state: &ScriptStateRegistration,
+        next: ScriptValue,
+    ) -> Result<(), InteropError> {
+        let resource_id = self
+            .get_resource_id(state.next_state_type_id)?
+            .ok_or_else(|| InteropError::missing_state(state.type_path))?;
+        self.check_resource_write(resource_id)?;
+        let next: Box<dyn PartialReflect> = match next {
+            ScriptValue::String(variant) => match state.type_info {
+                TypeInfo::Enum(info)
+                    if matches!(info.variant(&variant), Some(VariantInfo::Unit(_))) =>
+                {
+                    Box::new(DynamicEnum::new(variant.into_owned(), DynamicVariant::Unit))
+                }
+                _ => return Err(InteropError::invalid_enum_variant(state.type_id, variant)),
+            },
+            ScriptValue::Reference(reference) => {
+                reference.with_reflect(self.clone(), |r| r.clone_value())?
+            }
+            other => return Err(InteropError::value_mismatch(state.type_id, other)),
+        };
+        self.with_global_access(|world| (state.set_next)(world, next.as_ref()))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{reflect::Reflect, state::state::States};
+
+    use super::*;
+    use crate::{
+        bindings::capabilities::{
+            AppScriptCapabilityRegistry, CapabilityViolation, ScriptCapabilities,
+        },
+        error::InteropErrorInner,
+    };
+
+    #[derive(States, Reflect, Default, Debug, Clone, PartialEq, Eq, Hash)]
+    enum TestState {
+        #[default]
+        MainMenu,
+        InGame,
+        HUDOverlay,
+    }
+
+    #[test]
+    fn test_state_registry() {
+        let mut registry = StateRegistry::default();
+        registry.register::<TestState>();
+
+        assert!(registry.contains(TypeId::of::<TestState>()));
+
+        let state = registry.get_state_by_name("TestState").unwrap();
+        assert_eq!(state.identifier(), "TestState");
+        assert_eq!(state.type_path(), TestState::type_path());
+
+        for label in [
+            "on_enter_test_state_main_menu",
+            "on_exit_test_state_main_menu",
+            "on_enter_test_state_in_game",
+            "on_exit_test_state_in_game",
+            "on_enter_test_state_hud_overlay",
+            "on_exit_test_state_hud_overlay",
+        ] {
+            assert!(
+                registry.is_state_callback(&CallbackLabel::new(label).unwrap()),
+                "{label} is not a state callback"
+            );
+        }
+        assert!(!registry.is_state_callback(&CallbackLabel::new("on_enter_test_state").unwrap()));
+        assert!(!registry.is_state_callback(&CallbackLabel::new("on_enter_main_menu").unwrap()));
+    }
+
+    #[test]
+    fn test_state_access_respects_capabilities() {
+        let mut world = World::new();
+        world.init_resource::<State<TestState>>();
+        world.init_resource::<NextState<TestState>>();
+        let state_id = world.resource_id::<State<TestState>>().unwrap();
+        let next_state_id = world.resource_id::<NextState<TestState>>().unwrap();
+        world
+            .get_resource_or_init::<AppScriptCapabilityRegistry>()
+            .write()
+            .insert(
+                "script.lua",
+                ScriptCapabilities::unrestricted()
+                    .read_only_resource(state_id)
+                    .read_only_resource(next_state_id),
+            );
+
+        let registration = ScriptStateRegistration::new::<TestState>();
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+        let guard = guard.with_script_capabilities(&"script.lua".into());
+
+        // reading a read-only state is allowed
+        assert!(guard.get_state(&registration).unwrap().is_some());
+
+        let err = guard
+            .set_next_state(&registration, ScriptValue::String("InGame".into()))
+            .unwrap_err();
+        assert_eq!(
+            err.inner(),
+            &InteropErrorInner::CapabilityViolation {
+                script_id: "script.lua".into(),
+                violation: CapabilityViolation::ReadOnlyResource(next_state_id),
+            }
+        );
+        assert!(guard
+            .with_resource(|next: &NextState<TestState>| matches!(next, NextState::Unchanged))
+            .unwrap());
+    }
+}
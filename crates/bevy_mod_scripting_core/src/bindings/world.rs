@@ -17,9 +17,11 @@ use super::{
     pretty_print::DisplayWithWorld,
     schedule::AppScheduleRegistry,
     script_value::ScriptValue,
-    with_global_access, AppReflectAllocator, AppScriptComponentRegistry, ReflectBase,
-    ReflectBaseType, ReflectReference, ScriptComponentRegistration, ScriptResourceRegistration,
-    ScriptTypeRegistration, Union,
+    with_global_access, AppReflectAllocator, AppScriptCapabilityRegistry,
+    AppScriptComponentRegistry, AppScriptEventRegistry, AppScriptQueryCache,
+    AppScriptResourceRegistry, DynamicResource, ReflectBase, ReflectBaseType, ReflectReference,
+    ScriptCapabilities, ScriptCommandQueue, ScriptComponentRegistration,
+    ScriptResourceRegistration, ScriptTypeRegistration, Union,
 };
 use crate::{
     bindings::{
@@ -28,30 +30,33 @@ use crate::{
     },
     error::InteropError,
     reflection_extensions::PartialReflectExt,
+    script::ScriptId,
 };
 use bevy::{
     app::AppExit,
     ecs::{
-        component::{Component, ComponentId},
+        component::{Component, ComponentId, Tick},
         entity::Entity,
         reflect::{AppTypeRegistry, ReflectFromWorld, ReflectResource},
         system::{Commands, Resource},
-        world::{unsafe_world_cell::UnsafeWorldCell, CommandQueue, Mut, World},
+        world::{unsafe_world_cell::UnsafeWorldCell, CommandQueue, EntityWorldMut, Mut, World},
     },
     hierarchy::{BuildChildren, Children, DespawnRecursiveExt, Parent},
+    ptr::OwningPtr,
     reflect::{
         std_traits::ReflectDefault, DynamicEnum, DynamicStruct, DynamicTuple, DynamicTupleStruct,
-        DynamicVariant, ParsedPath, PartialReflect, TypeRegistryArc,
+        DynamicVariant, ParsedPath, PartialReflect, Reflect, TypeRegistryArc,
     },
 };
 use bevy_system_reflection::ReflectSchedule;
 use std::{
+    alloc::Layout,
     any::TypeId,
     borrow::Cow,
     cell::RefCell,
     collections::HashMap,
     fmt::Debug,
-    rc::Rc,
+    ptr::NonNull,
     sync::{atomic::AtomicBool, Arc},
 };
 
@@ -64,10 +69,16 @@ pub type WorldGuardRef<'w> = &'w WorldAccessGuard<'w>;
 #[derive(Clone, Debug)]
 pub struct WorldAccessGuard<'w> {
     /// The guard this guard pointer represents
-    pub(crate) inner: Rc<WorldAccessGuardInner<'w>>,
+    pub(crate) inner: Arc<WorldAccessGuardInner<'w>>,
     /// if true the guard is invalid and cannot be used, stored as a second pointer so that this validity can be
     /// stored separate from the contents of the guard
-    invalid: Rc<AtomicBool>,
+    invalid: Arc<AtomicBool>,
+    /// The capabilities of the script this guard was handed to, if it is restricted
+    pub(crate) capabilities: Option<(ScriptId, Arc<ScriptCapabilities>)>,
+    /// The `(last_run, this_run)` ticks used for change detection, if they differ from the ticks of the world
+    change_ticks: Option<(Tick, Tick)>,
+    /// The queue commands deferred by scripts are pushed into, owned by the system or handler this guard was handed to
+    pub(crate) command_queue: ScriptCommandQueue,
 }
 /// Used to decrease the stack size of [`WorldAccessGuard`]
 pub(crate) struct WorldAccessGuardInner<'w> {
@@ -85,6 +96,14 @@ pub(crate) struct WorldAccessGuardInner<'w> {
     schedule_registry: AppScheduleRegistry,
     /// The registry of script registered components
     script_component_registry: AppScriptComponentRegistry,
+    /// The registry of script registered resources
+    script_resource_registry: AppScriptResourceRegistry,
+    /// The registry of script registered events
+    script_event_registry: AppScriptEventRegistry,
+    /// The registry of per-script capability policies
+    capability_registry: AppScriptCapabilityRegistry,
+    /// The cache of query states built for scripts
+    query_cache: AppScriptQueryCache,
 }
 
 impl std::fmt::Debug for WorldAccessGuardInner<'_> {
@@ -106,7 +125,7 @@ impl<'w> WorldAccessGuard<'w> {
     /// creates a new guard derived from this one, which if invalidated, will not invalidate the original
     fn scope(&self) -> Self {
         let mut new_guard = self.clone();
-        new_guard.invalid = Rc::new(
+        new_guard.invalid = Arc::new(
             new_guard
                 .invalid
                 .load(std::sync::atomic::Ordering::Relaxed)
@@ -129,16 +148,19 @@ impl<'w> WorldAccessGuard<'w> {
     /// Safely allows access to the world for the duration of the closure via a static [`WorldAccessGuard`].
     ///
     /// The guard is invalidated at the end of the closure, meaning the world cannot be accessed at all after the closure ends.
+    /// Any commands queued through the guard are applied right after.
     pub fn with_static_guard<O>(
         world: &'w mut World,
         f: impl FnOnce(WorldGuard<'static>) -> O,
     ) -> O {
-        let guard = WorldAccessGuard::new_exclusive(world);
+        let guard = WorldAccessGuard::new_exclusive(&mut *world);
+        let command_queue = guard.command_queue();
         // safety: we invalidate the guard after the closure is called, meaning the world cannot be accessed at all after the 'w lifetime ends
         let static_guard: WorldAccessGuard<'static> = unsafe { std::mem::transmute(guard) };
         let o = f(static_guard.clone());
 
         static_guard.invalidate();
+        command_queue.apply(world);
         o
     }
 
@@ -159,7 +181,8 @@ impl<'w> WorldAccessGuard<'w> {
     /// Creates a new [`WorldAccessGuard`] from a possibly non-exclusive access to the world.
     ///
     /// It requires specyfing the exact accesses that are allowed to be given out by the guard.
-    /// Those accesses need to be safe to be given out to the script, as the guard will assume that it is safe to give them out in any way.
+    /// Those accesses need to be safe to be given out to the script, as the guard will assume that it is safe to give them out in any way,
+    /// except for the accesses in `read_only`, which are only ever given out as read accesses.
     ///
     /// # Safety
     /// - The caller must ensure that the accesses in subset are not aliased by any other access
@@ -168,27 +191,42 @@ impl<'w> WorldAccessGuard<'w> {
     pub unsafe fn new_non_exclusive(
         world: UnsafeWorldCell<'w>,
         subset: impl IntoIterator<Item = ReflectAccessId>,
+        read_only: impl IntoIterator<Item = ReflectAccessId>,
         type_registry: AppTypeRegistry,
         allocator: AppReflectAllocator,
         function_registry: AppScriptFunctionRegistry,
         schedule_registry: AppScheduleRegistry,
         script_component_registry: AppScriptComponentRegistry,
+        script_resource_registry: AppScriptResourceRegistry,
+        script_event_registry: AppScriptEventRegistry,
+        capability_registry: AppScriptCapabilityRegistry,
+        query_cache: AppScriptQueryCache,
     ) -> Self {
         Self {
-            inner: Rc::new(WorldAccessGuardInner {
+            inner: Arc::new(WorldAccessGuardInner {
                 cell: world,
-                accesses: AnyAccessMap::SubsetAccessMap(SubsetAccessMap::new(
-                    subset,
-                    // allocations live beyond the world, and can be safely accessed
-                    |id| ReflectAccessId::from_index(id).kind == ReflectAccessKind::Allocation,
-                )),
+                accesses: AnyAccessMap::SubsetAccessMap(
+                    SubsetAccessMap::new(
+                        subset,
+                        // allocations live beyond the world, and can be safely accessed
+                        |id| ReflectAccessId::from_index(id).kind == ReflectAccessKind::Allocation,
+                    )
+                    .with_read_only(read_only),
+                ),
                 type_registry: type_registry.0,
                 allocator,
                 function_registry,
                 schedule_registry,
                 script_component_registry,
+                script_resource_registry,
+                script_event_registry,
+                capability_registry,
+                query_cache,
             }),
-            invalid: Rc::new(false.into()),
+            invalid: Arc::new(false.into()),
+            capabilities: None,
+            change_ticks: None,
+            command_queue: Default::default(),
         }
     }
 
@@ -200,6 +238,8 @@ impl<'w> WorldAccessGuard<'w> {
     /// - [`AppScriptFunctionRegistry`]
     ///
     /// If these resources do not exist, they will be initialized.
+    ///
+    /// Commands deferred by scripts are pushed into a new queue, see [`Self::with_command_queue`].
     pub fn new_exclusive(world: &'w mut World) -> Self {
         let type_registry = world.get_resource_or_init::<AppTypeRegistry>().0.clone();
 
@@ -213,9 +253,24 @@ impl<'w> WorldAccessGuard<'w> {
             .get_resource_or_init::<AppScriptComponentRegistry>()
             .clone();
 
+        let script_resource_registry = world
+            .get_resource_or_init::<AppScriptResourceRegistry>()
+            .clone();
+
+        let script_event_registry = world
+            .get_resource_or_init::<AppScriptEventRegistry>()
+            .clone();
+
         let schedule_registry = world.get_resource_or_init::<AppScheduleRegistry>().clone();
+
+        let capability_registry = world
+            .get_resource_or_init::<AppScriptCapabilityRegistry>()
+            .clone();
+
+        let query_cache = world.get_resource_or_init::<AppScriptQueryCache>().clone();
+
         Self {
-            inner: Rc::new(WorldAccessGuardInner {
+            inner: Arc::new(WorldAccessGuardInner {
                 cell: world.as_unsafe_world_cell(),
                 accesses: AnyAccessMap::UnlimitedAccessMap(Default::default()),
                 allocator,
@@ -223,8 +278,15 @@ impl<'w> WorldAccessGuard<'w> {
                 function_registry,
                 schedule_registry,
                 script_component_registry,
+                script_resource_registry,
+                script_event_registry,
+                capability_registry,
+                query_cache,
             }),
-            invalid: Rc::new(false.into()),
+            invalid: Arc::new(false.into()),
+            capabilities: None,
+            change_ticks: None,
+            command_queue: Default::default(),
         }
     }
 
@@ -264,6 +326,26 @@ impl<'w> WorldAccessGuard<'w> {
         Ok(self.inner.cell)
     }
 
+    /// Returns a guard which uses the given ticks for change detection, i.e. the ticks of the script system it is handed to.
+    pub fn with_change_ticks(&self, last_run: Tick, this_run: Tick) -> Self {
+        let mut guard = self.clone();
+        guard.change_ticks = Some((last_run, this_run));
+        guard
+    }
+
+    /// Returns the `(last_run, this_run)` ticks used for change detection.
+    ///
+    /// Unless set via [`Self::with_change_ticks`], these are the ticks of the exclusive system currently running on the world, i.e. the event handler.
+    pub fn change_ticks(&self) -> Result<(Tick, Tick), InteropError> {
+        match self.change_ticks {
+            Some(ticks) => Ok(ticks),
+            None => {
+                let cell = self.as_unsafe_world_cell()?;
+                Ok((cell.last_change_tick(), cell.change_tick()))
+            }
+        }
+    }
+
     /// Retrieves the underlying read only unsafe world cell, with no additional guarantees of safety
     /// proceed with caution and only use this if you understand what you're doing
     pub fn as_unsafe_world_cell_readonly(&self) -> Result<UnsafeWorldCell<'w>, InteropError> {
@@ -325,6 +407,17 @@ impl<'w> WorldAccessGuard<'w> {
         self.inner.accesses.claim_global_access()
     }
 
+    /// Runs the closure in a parallel access section, in which the guard can be shared between threads.
+    ///
+    /// Global access requests of threads holding no other claims wait for the other threads instead of failing,
+    /// see [`DynamicSystemMeta::begin_parallel_access`].
+    pub fn with_parallel_access<O>(&self, f: impl FnOnce() -> O) -> O {
+        self.inner.accesses.begin_parallel_access();
+        let o = f();
+        self.inner.accesses.end_parallel_access();
+        o
+    }
+
     /// Releases global access to the world
     ///
     /// # Safety
@@ -348,6 +441,26 @@ impl<'w> WorldAccessGuard<'w> {
         self.inner.script_component_registry.clone()
     }
 
+    /// Returns the resource registry for the world
+    pub fn resource_registry(&self) -> AppScriptResourceRegistry {
+        self.inner.script_resource_registry.clone()
+    }
+
+    /// Returns the event registry for the world
+    pub fn event_registry(&self) -> AppScriptEventRegistry {
+        self.inner.script_event_registry.clone()
+    }
+
+    /// Returns the registry of per-script capability policies
+    pub fn capability_registry(&self) -> AppScriptCapabilityRegistry {
+        self.inner.capability_registry.clone()
+    }
+
+    /// Returns the cache of query states built for scripts
+    pub fn query_cache(&self) -> AppScriptQueryCache {
+        self.inner.query_cache.clone()
+    }
+
     /// Returns the script allocator for the world
     pub fn allocator(&self) -> AppReflectAllocator {
         self.inner.allocator.clone()
@@ -886,12 +999,19 @@ impl WorldAccessGuard<'_> {
         Ok(match val {
             Some(registration) => Some(self.get_type_registration(registration)?),
             None => {
-                // try the component registry
+                // try the component and resource registries
                 let components = self.component_registry();
                 let components = components.read();
+                let resources = self.resource_registry();
+                let resources = resources.read();
                 components
                     .get(&type_name)
                     .map(|c| Union::new_right(Union::new_left(c.registration.clone())))
+                    .or_else(|| {
+                        resources
+                            .get(&type_name)
+                            .map(|r| Union::new_right(Union::new_right(r.registration.clone())))
+                    })
             }
         })
     }
@@ -980,12 +1100,168 @@ impl WorldAccessGuard<'_> {
         registration.insert_into_entity(self.clone(), entity, reflect)
     }
 
+    /// Constructs an instance of the component from a map of values, see [`Self::construct`].
+    ///
+    /// Unlike [`Self::construct`] the instance is always of the concrete type of the component.
+    pub fn construct_component(
+        &self,
+        registration: &ScriptComponentRegistration,
+        payload: HashMap<String, ScriptValue>,
+        one_indexed: bool,
+    ) -> Result<Box<dyn Reflect>, InteropError> {
+        // script registered components know their fields at runtime only
+        if registration.is_dynamic_script_component() {
+            return Ok(Box::new(
+                self.construct_script_component(registration, payload)?,
+            ));
+        }
+
+        let type_id = registration.type_registration().type_id();
+        let dynamic = self.construct(
+            registration.type_registration().clone(),
+            payload,
+            one_indexed,
+        )?;
+        let instance = <dyn PartialReflect>::from_reflect(dynamic.as_ref(), self.clone())?;
+        let instance_type_id = instance.as_any().type_id();
+        if instance_type_id != type_id {
+            return Err(InteropError::type_mismatch(type_id, Some(instance_type_id)));
+        }
+        Ok(instance)
+    }
+
+    /// Constructs each of the given components from a map of values, and inserts all of them into the entity at once.
+    ///
+    /// The entity is only moved between archetypes once, no matter how many components are inserted.
+    pub fn insert_components(
+        &self,
+        entity: Entity,
+        components: Vec<(ScriptComponentRegistration, HashMap<String, ScriptValue>)>,
+        one_indexed: bool,
+    ) -> Result<(), InteropError> {
+        self.check_entity_access(entity)?;
+        let (component_ids, instances) = self.construct_components(components, one_indexed)?;
+
+        self.with_global_access(|world| {
+            let mut entity = world
+                .get_entity_mut(entity)
+                .map_err(|_| InteropError::missing_entity(entity))?;
+            // Safety: the instances were constructed for the components with the given ids
+            unsafe { Self::insert_constructed_components(&mut entity, &component_ids, instances) };
+            Ok(())
+        })?
+    }
+
+    /// Spawns a new entity with the given components, constructing each of them from a map of values.
+    ///
+    /// All the components are constructed before the entity is spawned, so no entity is left behind if any of them fails.
+    ///
+    /// See [`Self::insert_components`].
+    pub fn spawn_with(
+        &self,
+        components: Vec<(ScriptComponentRegistration, HashMap<String, ScriptValue>)>,
+        one_indexed: bool,
+    ) -> Result<Entity, InteropError> {
+        let (component_ids, instances) = self.construct_components(components, one_indexed)?;
+
+        self.with_global_access(|world| {
+            let mut entity = world.spawn_empty();
+            // Safety: the instances were constructed for the components with the given ids
+            unsafe { Self::insert_constructed_components(&mut entity, &component_ids, instances) };
+            entity.id()
+        })
+    }
+
+    /// Constructs each of the given components from a map of values, returning their ids along with the instances.
+    ///
+    /// Fails if the script cannot write one of the components, if a component is given more than once or if it cannot be constructed.
+    fn construct_components(
+        &self,
+        components: Vec<(ScriptComponentRegistration, HashMap<String, ScriptValue>)>,
+        one_indexed: bool,
+    ) -> Result<(Vec<ComponentId>, Vec<Box<dyn Reflect>>), InteropError> {
+        let mut component_ids = Vec::with_capacity(components.len());
+        let mut instances = Vec::with_capacity(components.len());
+        for (registration, payload) in components {
+            self.check_component_type_write(registration.component_id)?;
+            if component_ids.contains(&registration.component_id) {
+                return Err(InteropError::unsupported_operation(
+                    Some(registration.type_registration().type_id()),
+                    None,
+                    "the component was given more than once",
+                ));
+            }
+            instances.push(self.construct_component(&registration, payload, one_indexed)?);
+            component_ids.push(registration.component_id);
+        }
+        Ok((component_ids, instances))
+    }
+
+    /// Moves the given instances into the entity as the components with the given ids.
+    ///
+    /// # Safety
+    /// - the component ids must come from the world the entity belongs to
+    /// - each instance must be a value of the type of the component at the same index
+    unsafe fn insert_constructed_components(
+        entity: &mut EntityWorldMut,
+        component_ids: &[ComponentId],
+        instances: Vec<Box<dyn Reflect>>,
+    ) {
+        // the values are moved out of their boxes by the insertion, so the boxes are deallocated without dropping them
+        let instances = instances
+            .into_iter()
+            .map(|instance| {
+                let layout = Layout::for_value(instance.as_ref());
+                (Box::into_raw(instance).cast::<u8>(), layout)
+            })
+            .collect::<Vec<_>>();
+        // Safety:
+        // - the component ids come from the world of the entity, as required by the caller
+        // - each pointer points to a valid value of the type of the component at the same index, as required by the caller
+        // - the pointers are not used again after the values are moved into the world
+        unsafe {
+            entity.insert_by_ids(
+                component_ids,
+                instances
+                    .iter()
+                    .map(|(ptr, _)| OwningPtr::new(NonNull::new_unchecked(*ptr))),
+            )
+        };
+        for (ptr, layout) in instances {
+            if layout.size() != 0 {
+                // Safety: the pointer was allocated by a box with this layout, and the value was moved out of it
+                unsafe { std::alloc::dealloc(ptr, layout) };
+            }
+        }
+    }
+
+    /// Retrieves the registration of a component by the name of its type, or the name it was registered with by a script
+    pub fn get_component_type_by_name(
+        &self,
+        type_name: String,
+    ) -> Result<ScriptComponentRegistration, InteropError> {
+        match self.get_type_registration_by_name(type_name.clone())? {
+            Some(registration) => match registration.into_right() {
+                Ok(registration) => registration
+                    .into_left()
+                    .map_err(|_| InteropError::unregistered_component_or_resource_type(type_name)),
+                Err(_) => Err(InteropError::unregistered_component_or_resource_type(
+                    type_name,
+                )),
+            },
+            None => Err(InteropError::unregistered_component_or_resource_type(
+                type_name,
+            )),
+        }
+    }
+
     /// get the component from the entity
     pub fn get_component(
         &self,
         entity: Entity,
         component_registration: ScriptComponentRegistration,
     ) -> Result<Option<ReflectReference>, InteropError> {
+        self.check_entity_access(entity)?;
         let cell = self.as_unsafe_world_cell()?;
         let entity = cell
             .get_entity(entity)
@@ -1013,6 +1289,7 @@ impl WorldAccessGuard<'_> {
         entity: Entity,
         component_id: ComponentId,
     ) -> Result<bool, InteropError> {
+        self.check_entity_access(entity)?;
         let cell = self.as_unsafe_world_cell()?;
         let entity = cell
             .get_entity(entity)
@@ -1041,10 +1318,18 @@ impl WorldAccessGuard<'_> {
             None => return Ok(None),
         };
 
+        // script registered resources are not backed by a rust type of their own
+        let script_resource_type = self
+            .resource_registry()
+            .read()
+            .get_by_id(resource_id)
+            .map(|_| TypeId::of::<DynamicResource>());
+
         Ok(Some(ReflectReference {
             base: ReflectBaseType {
                 type_id: component_info
                     .type_id()
+                    .or(script_resource_type)
                     .ok_or_else(|| {
                         InteropError::unsupported_operation(
                             None,
@@ -1066,6 +1351,12 @@ impl WorldAccessGuard<'_> {
         &self,
         registration: ScriptResourceRegistration,
     ) -> Result<(), InteropError> {
+        self.check_resource_write(registration.resource_id)?;
+        if registration.is_dynamic_script_resource {
+            return self.with_global_access(|world| {
+                world.remove_resource_by_id(registration.resource_id);
+            });
+        }
         let component_data = registration
             .type_registration()
             .type_registration()
@@ -1099,9 +1390,16 @@ impl WorldAccessGuard<'_> {
         if !self.is_valid_entity(entity)? {
             return Err(InteropError::missing_entity(entity));
         }
+        self.check_entity_access(entity)?;
 
         self.with_component(entity, |c: Option<&Children>| {
-            c.map(|c| c.to_vec()).unwrap_or_default()
+            c.map(|c| {
+                c.iter()
+                    .copied()
+                    .filter(|c| self.check_entity_access(*c).is_ok())
+                    .collect()
+            })
+            .unwrap_or_default()
         })
     }
 
@@ -1110,8 +1408,12 @@ impl WorldAccessGuard<'_> {
         if !self.is_valid_entity(entity)? {
             return Err(InteropError::missing_entity(entity));
         }
+        self.check_entity_access(entity)?;
 
-        self.with_component(entity, |c: Option<&Parent>| c.map(|c| c.get()))
+        self.with_component(entity, |c: Option<&Parent>| {
+            c.map(|c| c.get())
+                .filter(|p| self.check_entity_access(*p).is_ok())
+        })
     }
 
     /// insert children into the given entity
@@ -1125,6 +1427,10 @@ impl WorldAccessGuard<'_> {
                 return Err(InteropError::missing_entity(*c));
             }
         }
+        self.check_entity_access(parent)?;
+        for c in children {
+            self.check_entity_access(*c)?;
+        }
         self.with_global_access(|world| {
             let mut queue = CommandQueue::default();
             let mut commands = Commands::new(&mut queue, world);
@@ -1144,6 +1450,10 @@ impl WorldAccessGuard<'_> {
                 return Err(InteropError::missing_entity(*c));
             }
         }
+        self.check_entity_access(parent)?;
+        for c in children {
+            self.check_entity_access(*c)?;
+        }
         self.with_global_access(|world| {
             let mut queue = CommandQueue::default();
             let mut commands = Commands::new(&mut queue, world);
@@ -1169,6 +1479,11 @@ impl WorldAccessGuard<'_> {
             }
         }
 
+        self.check_entity_access(parent)?;
+        for c in children {
+            self.check_entity_access(*c)?;
+        }
+
         self.with_global_access(|world| {
             let mut queue = CommandQueue::default();
             let mut commands = Commands::new(&mut queue, world);
@@ -1182,6 +1497,8 @@ impl WorldAccessGuard<'_> {
         if !self.is_valid_entity(parent)? {
             return Err(InteropError::missing_entity(parent));
         }
+        self.check_entity_access(parent)?;
+        self.check_descendants_access(parent)?;
         self.with_global_access(|world| {
             let mut queue = CommandQueue::default();
             let mut commands = Commands::new(&mut queue, world);
@@ -1195,6 +1512,7 @@ impl WorldAccessGuard<'_> {
         if !self.is_valid_entity(entity)? {
             return Err(InteropError::missing_entity(entity));
         }
+        self.check_entity_access(entity)?;
 
         self.with_global_access(|world| {
             let mut queue = CommandQueue::default();
@@ -1209,6 +1527,8 @@ impl WorldAccessGuard<'_> {
         if !self.is_valid_entity(parent)? {
             return Err(InteropError::missing_entity(parent));
         }
+        self.check_entity_access(parent)?;
+        self.check_descendants_access(parent)?;
 
         self.with_global_access(|world| {
             let mut queue = CommandQueue::default();
@@ -1218,6 +1538,28 @@ impl WorldAccessGuard<'_> {
         })
     }
 
+    /// Checks the script is allowed to access every descendant of the given entity
+    fn check_descendants_access(&self, parent: Entity) -> Result<(), InteropError> {
+        if self
+            .capabilities()
+            .is_none_or(|(_, c)| c.denied_entities.is_empty())
+        {
+            return Ok(());
+        }
+
+        let mut stack = vec![parent];
+        while let Some(entity) = stack.pop() {
+            let children = self.with_component(entity, |c: Option<&Children>| {
+                c.map(|c| c.to_vec()).unwrap_or_default()
+            })?;
+            for child in children {
+                self.check_entity_access(child)?;
+                stack.push(child);
+            }
+        }
+        Ok(())
+    }
+
     /// Sends AppExit event to the world with success status
     pub fn exit(&self) -> Result<(), InteropError> {
         self.with_global_access(|world| {
@@ -1413,6 +1755,89 @@ mod test {
         pretty_assertions::assert_str_eq!(format!("{result:#?}"), format!("{expected:#?}"));
     }
 
+    #[test]
+    fn test_spawn_with_and_insert_components() {
+        let mut world = setup_world(|_, _| {});
+        world.register_component::<SimpleStruct>();
+        world.register_component::<SimpleTupleStruct>();
+        world.register_component::<SimpleEnum>();
+        let guard = WorldAccessGuard::new_exclusive(&mut world);
+
+        let simple_struct = guard
+            .get_component_type_by_name("SimpleStruct".to_owned())
+            .unwrap();
+        let simple_tuple_struct = guard
+            .get_component_type_by_name("SimpleTupleStruct".to_owned())
+            .unwrap();
+        let simple_enum = guard
+            .get_component_type_by_name("SimpleEnum".to_owned())
+            .unwrap();
+
+        let entity = guard
+            .spawn_with(
+                vec![
+                    (
+                        simple_struct.clone(),
+                        HashMap::from_iter([("foo".to_owned(), ScriptValue::Integer(1))]),
+                    ),
+                    (
+                        simple_tuple_struct,
+                        HashMap::from_iter([("_0".to_owned(), ScriptValue::Integer(2))]),
+                    ),
+                ],
+                false,
+            )
+            .unwrap();
+
+        guard
+            .insert_components(
+                entity,
+                vec![(
+                    simple_enum.clone(),
+                    HashMap::from_iter([(
+                        "variant".to_owned(),
+                        ScriptValue::String("Unit".into()),
+                    )]),
+                )],
+                false,
+            )
+            .unwrap();
+
+        // components can only be given once
+        assert!(guard
+            .insert_components(
+                entity,
+                vec![
+                    (simple_struct.clone(), HashMap::default()),
+                    (simple_struct, HashMap::default()),
+                ],
+                false,
+            )
+            .is_err());
+
+        // nothing is spawned if a component cannot be constructed
+        let entities = guard.with_global_access(|w| w.entities().len()).unwrap();
+        assert!(guard
+            .spawn_with(vec![(simple_enum, HashMap::default())], false)
+            .is_err());
+        assert_eq!(
+            guard.with_global_access(|w| w.entities().len()).unwrap(),
+            entities
+        );
+
+        assert!(guard
+            .get_component_type_by_name("TestResource".to_owned())
+            .is_err());
+
+        let entity = world.entity(entity);
+        assert_eq!(entity.get::<SimpleStruct>(), Some(&SimpleStruct { foo: 1 }));
+        assert_eq!(
+            entity.get::<SimpleTupleStruct>(),
+            Some(&SimpleTupleStruct(2))
+        );
+        assert_eq!(entity.get::<SimpleEnum>(), Some(&SimpleEnum::Unit));
+    }
+
     #[test]
     fn test_scoped_handle_invalidate_doesnt_invalidate_parent() {
         let mut world = setup_world(|_, _| {});
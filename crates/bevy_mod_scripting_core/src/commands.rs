@@ -1,23 +1,41 @@
 //! Commands for creating, updating and deleting scripts
 
 use crate::{
-    asset::ScriptAsset,
-    bindings::{ScriptValue, WorldGuard},
-    context::ContextBuilder,
+    asset::{ScriptAsset, ScriptDependencyGraph, ScriptMetadataStore},
+    bindings::{ScriptObservers, ScriptValue, WorldGuard},
+    context::{ContextAssignmentStrategy, ContextBuilder, ContextLoadingSettings},
     error::{InteropError, ScriptError},
     event::{
-        CallbackLabel, IntoCallbackLabel, OnScriptLoaded, OnScriptUnloaded,
-        ScriptCallbackResponseEvent,
+        CallbackLabel, IntoCallbackLabel, OnRestoreState, OnSaveState, OnScriptLoaded,
+        OnScriptUnloaded, ScriptCallbackResponseEvent, ScriptLoadFailed, ScriptLoaded,
+        ScriptReloaded, ScriptUnloaded,
     },
     extractors::{with_handler_system_state, HandlerContext},
-    handler::{handle_script_errors, send_callback_response},
-    script::{Script, ScriptId, Scripts, StaticScripts},
+    handler::{handle_script_errors, send_callback_response, CallbackSettings},
+    script::{Script, ScriptComponent, ScriptId, Scripts, StaticScripts},
     IntoScriptPluginParams,
 };
-use bevy::{asset::Handle, ecs::entity::Entity, log::debug, prelude::Command};
+use bevy::{
+    asset::Handle,
+    ecs::{
+        entity::Entity,
+        event::{Event, Events},
+        world::World,
+    },
+    log::debug,
+    prelude::Command,
+};
 use parking_lot::Mutex;
 use std::{marker::PhantomData, sync::Arc};
 
+/// Sends a script lifecycle event to event readers, and triggers it for observers
+fn publish_lifecycle_event<E: Event + Clone>(world: &mut World, event: E) {
+    if let Some(mut events) = world.get_resource_mut::<Events<E>>() {
+        events.send(event.clone());
+    }
+    world.trigger(event);
+}
+
 /// Deletes a script with the given ID
 pub struct DeleteScript<P: IntoScriptPluginParams> {
     /// The ID of the script to delete
@@ -48,9 +66,23 @@ impl<P: IntoScriptPluginParams> Command for DeleteScript<P> {
         )
         .apply(world);
 
+        ScriptObservers::despawn_script_observers(world, &self.id);
+
         let mut scripts = world.get_resource_or_init::<Scripts<P>>();
+        let asset = scripts
+            .scripts
+            .get(&self.id)
+            .and_then(|script| script.asset.clone());
         if scripts.remove(self.id.clone()) {
             debug!("Deleted script with id: {}", self.id);
+            publish_lifecycle_event(
+                world,
+                ScriptUnloaded {
+                    script: self.id,
+                    language: P::LANGUAGE,
+                    asset,
+                },
+            );
         } else {
             bevy::log::error!(
                 "Attempted to delete script with id: {} but it does not exist, doing nothing!",
@@ -83,6 +115,88 @@ impl<P: IntoScriptPluginParams> CreateOrUpdateScript<P> {
         }
     }
 
+    /// Returns the scripts attached to each entity with a [`ScriptComponent`] containing this script
+    fn attached_scripts(&self, world: &mut World) -> Vec<Vec<ScriptId>> {
+        world
+            .query::<&ScriptComponent>()
+            .iter(world)
+            .filter(|component| component.contains(&self.id))
+            .map(|component| component.0.clone())
+            .collect()
+    }
+
+    /// Finds the context of an existing script which this script should share according to the assignment strategy
+    fn shared_context(
+        &self,
+        guard: &WorldGuard,
+        handler_ctxt: &HandlerContext<P>,
+        attached_scripts: &[Vec<ScriptId>],
+    ) -> Result<Option<Arc<Mutex<P::C>>>, ScriptError> {
+        let scripts = &handler_ctxt.scripts.scripts;
+        let shared = match handler_ctxt.context_loading_settings.assignment_strategy {
+            ContextAssignmentStrategy::Individual => None,
+            ContextAssignmentStrategy::Global => scripts.values().next(),
+            ContextAssignmentStrategy::PerEntity => {
+                let mut entity_contexts = attached_scripts.iter().filter_map(|entity_scripts| {
+                    entity_scripts.iter().find_map(|id| scripts.get(id))
+                });
+                let shared = entity_contexts.next();
+                // the order entities are found in is arbitrary, so the context to share must be the same on every entity
+                if let Some(shared) = shared {
+                    if entity_contexts.any(|other| !Arc::ptr_eq(&other.context, &shared.context)) {
+                        return Err(ScriptError::new(format!(
+                            "Script is attached to multiple entities alongside scripts with different contexts, cannot determine which context to share: {}",
+                            self.id
+                        )));
+                    }
+                }
+                shared
+            }
+            ContextAssignmentStrategy::Grouped(group) => {
+                let group_of = |id: &ScriptId, asset: Option<&Handle<ScriptAsset>>| {
+                    guard
+                        .with_resource(|store: &ScriptMetadataStore| {
+                            group(id, asset.and_then(|asset| store.get(asset.id())))
+                        })
+                        .unwrap_or_else(|_| group(id, None))
+                };
+                let Some(key) = group_of(&self.id, self.asset.as_ref()) else {
+                    return Ok(None);
+                };
+                scripts.values().find(|script| {
+                    group_of(&script.id, script.asset.as_ref()).is_some_and(|k| k == key)
+                })
+            }
+        };
+        Ok(shared.map(|script| script.context.clone()))
+    }
+
+    /// Calls the `on_save_state` callback of an existing script, returning the state to restore after reloading if any.
+    ///
+    /// Scripts which do not define the callback, or return nothing from it, do not have their state restored.
+    fn save_state(
+        &self,
+        guard: WorldGuard,
+        handler_ctxt: &HandlerContext<P>,
+    ) -> Option<ScriptValue> {
+        match handler_ctxt.call::<OnSaveState>(&self.id, Entity::from_raw(0), vec![], guard.clone())
+        {
+            Ok(ScriptValue::Unit) => None,
+            Ok(state) => Some(state),
+            Err(err) => {
+                handle_script_errors(
+                    guard,
+                    vec![err
+                        .with_script(self.id.clone())
+                        .with_context(P::LANGUAGE)
+                        .with_context("saving state")]
+                    .into_iter(),
+                );
+                None
+            }
+        }
+    }
+
     fn reload_context(
         &self,
         guard: WorldGuard,
@@ -113,9 +227,29 @@ impl<P: IntoScriptPluginParams> CreateOrUpdateScript<P> {
             &handler_ctxt.runtime_container.runtime,
         )?;
 
+        // continuations might refer to code which no longer exists
+        handler_ctxt
+            .scripts
+            .suspended_callbacks()
+            .remove_script(&self.id);
+
         Ok(())
     }
 
+    /// Records the callbacks defined by the loaded or reloaded context in the callback index
+    fn index_callbacks(&self, world: &mut World) {
+        let Some(defined_callbacks) = world
+            .get_resource::<CallbackSettings<P>>()
+            .filter(|settings| settings.index_callbacks)
+            .map(|settings| settings.defined_callbacks)
+        else {
+            return;
+        };
+        if let Some(mut scripts) = world.get_resource_mut::<Scripts<P>>() {
+            scripts.index_callbacks(&self.id, defined_callbacks);
+        }
+    }
+
     fn load_context(
         &self,
         guard: WorldGuard,
@@ -150,26 +284,38 @@ impl<P: IntoScriptPluginParams> CreateOrUpdateScript<P> {
 #[profiling::all_functions]
 impl<P: IntoScriptPluginParams> Command for CreateOrUpdateScript<P> {
     fn apply(self, world: &mut bevy::prelude::World) {
+        let attached_scripts = match world
+            .get_resource::<ContextLoadingSettings<P>>()
+            .map(|settings| settings.assignment_strategy)
+        {
+            Some(ContextAssignmentStrategy::PerEntity) => self.attached_scripts(world),
+            _ => Vec::new(),
+        };
+        // the dependencies of the script are recorded again as it imports its modules
+        if let Some(mut dependency_graph) = world.get_resource_mut::<ScriptDependencyGraph>() {
+            dependency_graph.clear_dependencies(&self.id);
+        }
+        // as are its observers, the previous ones are only despawned once the new context loads
+        let previous_observers = ScriptObservers::take_script_observers(world, &self.id);
+        let mut saved_state = None;
+        let mut is_new_script = true;
+        let mut load_error = None;
         let success = with_handler_system_state(
             world,
             |guard, handler_ctxt: &mut HandlerContext<P>| {
-                let is_new_script = !handler_ctxt.scripts.scripts.contains_key(&self.id);
+                is_new_script = !handler_ctxt.scripts.scripts.contains_key(&self.id);
 
                 let assigned_shared_context =
-                    match handler_ctxt.context_loading_settings.assignment_strategy {
-                        crate::context::ContextAssignmentStrategy::Individual => None,
-                        crate::context::ContextAssignmentStrategy::Global => {
-                            let is_new_context = handler_ctxt.scripts.scripts.is_empty();
-                            if !is_new_context {
-                                handler_ctxt
-                                    .scripts
-                                    .scripts
-                                    .values()
-                                    .next()
-                                    .map(|s| s.context.clone())
-                            } else {
-                                None
-                            }
+                    match self.shared_context(&guard, handler_ctxt, &attached_scripts) {
+                        Ok(context) => context,
+                        Err(err) => {
+                            let err = err
+                                .with_script(self.id.clone())
+                                .with_context(P::LANGUAGE)
+                                .with_context("assigning context");
+                            load_error = Some(err.clone());
+                            handle_script_errors(guard, vec![err].into_iter());
+                            return false;
                         }
                     };
 
@@ -181,6 +327,10 @@ impl<P: IntoScriptPluginParams> Command for CreateOrUpdateScript<P> {
                 is_new_script
             );
 
+                if !is_new_script {
+                    saved_state = self.save_state(guard.clone(), handler_ctxt);
+                }
+
                 let result = match &assigned_shared_context {
                     Some(assigned_shared_context) => {
                         if is_new_script {
@@ -211,14 +361,26 @@ impl<P: IntoScriptPluginParams> Command for CreateOrUpdateScript<P> {
                 };
 
                 if let Err(err) = result {
-                    handle_script_errors(
-                        guard,
-                        vec![err
-                            .with_script(self.id.clone())
-                            .with_context(P::LANGUAGE)
-                            .with_context(phrase)]
-                        .into_iter(),
-                    );
+                    // the script is reloaded once its modules are available
+                    let is_waiting = guard
+                        .with_resource(|graph: &ScriptDependencyGraph| graph.is_waiting(&self.id))
+                        .unwrap_or_default();
+                    if is_waiting {
+                        debug!(
+                            "{}: script with id: {} is waiting for its modules to load: {}",
+                            P::LANGUAGE,
+                            self.id,
+                            err
+                        );
+                        return false;
+                    }
+
+                    let err = err
+                        .with_script(self.id.clone())
+                        .with_context(P::LANGUAGE)
+                        .with_context(phrase);
+                    load_error = Some(err.clone());
+                    handle_script_errors(guard, vec![err].into_iter());
                     return false;
                 }
 
@@ -234,14 +396,67 @@ impl<P: IntoScriptPluginParams> Command for CreateOrUpdateScript<P> {
 
         // immediately run command for callback, but only if loading went fine
         if success {
+            ScriptObservers::despawn_observers(world, previous_observers);
+
             RunScriptCallback::<P>::new(
-                self.id,
+                self.id.clone(),
                 Entity::from_raw(0),
                 OnScriptLoaded::into_callback_label(),
                 vec![],
                 false,
             )
-            .apply(world)
+            .apply(world);
+
+            if let Some(state) = saved_state {
+                RunScriptCallback::<P>::new(
+                    self.id.clone(),
+                    Entity::from_raw(0),
+                    OnRestoreState::into_callback_label(),
+                    vec![state],
+                    false,
+                )
+                .with_context("restoring state")
+                .apply(world);
+            }
+
+            // callbacks can also be defined while running the ones above
+            self.index_callbacks(world);
+
+            if is_new_script {
+                publish_lifecycle_event(
+                    world,
+                    ScriptLoaded {
+                        script: self.id,
+                        language: P::LANGUAGE,
+                        asset: self.asset,
+                    },
+                );
+            } else {
+                publish_lifecycle_event(
+                    world,
+                    ScriptReloaded {
+                        script: self.id,
+                        language: P::LANGUAGE,
+                        asset: self.asset,
+                    },
+                );
+            }
+        } else {
+            // keep the observers of the previous context, dropping any added by the failed load
+            ScriptObservers::despawn_script_observers(world, &self.id);
+            ScriptObservers::restore_script_observers(world, &self.id, previous_observers);
+
+            if let Some(error) = load_error {
+                publish_lifecycle_event(
+                    world,
+                    ScriptLoadFailed {
+                        script: self.id,
+                        language: P::LANGUAGE,
+                        asset: self.asset,
+                        error,
+                    },
+                );
+            }
         }
     }
 }
@@ -310,6 +525,7 @@ impl<P: IntoScriptPluginParams> Command for RunScriptCallback<P> {
                 self.args,
                 guard.clone(),
             );
+            handler_ctxt.reindex_callbacks_after_running([&self.id]);
 
             if self.trigger_response {
                 send_callback_response(
@@ -379,13 +595,14 @@ impl Command for RemoveStaticScript {
 mod test {
     use bevy::{
         app::App,
+        asset::{Assets, Handle},
         ecs::event::Events,
         log::{Level, LogPlugin},
-        prelude::{Entity, World},
+        prelude::{Entity, ResMut, Resource, Trigger, World},
     };
 
     use crate::{
-        asset::Language,
+        asset::{Language, ScriptAsset, ScriptMetadata},
         bindings::script_value::ScriptValue,
         context::{ContextBuilder, ContextLoadingSettings},
         handler::CallbackSettings,
@@ -399,7 +616,11 @@ mod test {
         // setup all the resources necessary
         let mut app = App::new();
 
-        app.add_event::<ScriptCallbackResponseEvent>();
+        app.add_event::<ScriptCallbackResponseEvent>()
+            .add_event::<ScriptLoaded>()
+            .add_event::<ScriptReloaded>()
+            .add_event::<ScriptUnloaded>()
+            .add_event::<ScriptLoadFailed>();
         app.add_plugins(LogPlugin {
             filter: "bevy_mod_scripting_core=debug,info".to_owned(),
             level: Level::TRACE,
@@ -448,9 +669,16 @@ mod test {
                 c.push_str(format!(" callback-ran-{}", callback).as_str());
                 Ok(ScriptValue::Unit)
             },
+            execution_budget: Default::default(),
+            defined_callbacks: |_| None,
+            index_callbacks: false,
+            reindex_after_callbacks: false,
+            parallel_callbacks: false,
         })
         .insert_resource(Scripts::<DummyPlugin> {
             scripts: Default::default(),
+            suspended: Default::default(),
+            callback_index: Default::default(),
         });
 
         app
@@ -698,6 +926,539 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_commands_with_per_entity_assigner() {
+        let mut app = setup_app();
+        app.world_mut()
+            .resource_mut::<ContextLoadingSettings<DummyPlugin>>()
+            .assignment_strategy = ContextAssignmentStrategy::PerEntity;
+        app.world_mut()
+            .spawn(ScriptComponent::new(["script", "script2"]));
+        app.world_mut().spawn(ScriptComponent::new(["script3"]));
+
+        for (id, content) in [
+            ("script", "content"),
+            ("script2", "content2"),
+            ("script3", "content3"),
+        ] {
+            let content = content.as_bytes().to_vec().into_boxed_slice();
+            CreateOrUpdateScript::<DummyPlugin>::new(id.into(), content, None)
+                .apply(app.world_mut());
+        }
+
+        assert_context_and_script(
+            app.world(),
+            "script",
+            "content2 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts on the same entity should share a context",
+        );
+        assert_context_and_script(
+            app.world(),
+            "script2",
+            "content2 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts on the same entity should share a context",
+        );
+        assert_context_and_script(
+            app.world(),
+            "script3",
+            "content3 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts on different entities should not share a context",
+        );
+    }
+
+    #[test]
+    fn test_commands_with_grouped_assigner() {
+        let mut app = setup_app();
+        app.world_mut()
+            .resource_mut::<ContextLoadingSettings<DummyPlugin>>()
+            .assignment_strategy = ContextAssignmentStrategy::Grouped(|id, _| {
+            id.split_once('/').map(|(group, _)| group.to_owned())
+        });
+
+        for (id, content) in [
+            ("mod_a/script", "content"),
+            ("mod_a/script2", "content2"),
+            ("mod_b/script", "content3"),
+            ("script", "content4"),
+        ] {
+            let content = content.as_bytes().to_vec().into_boxed_slice();
+            CreateOrUpdateScript::<DummyPlugin>::new(id.into(), content, None)
+                .apply(app.world_mut());
+        }
+
+        assert_context_and_script(
+            app.world(),
+            "mod_a/script",
+            "content2 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts in the same group should share a context",
+        );
+        assert_context_and_script(
+            app.world(),
+            "mod_b/script",
+            "content3 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts in different groups should not share a context",
+        );
+        assert_context_and_script(
+            app.world(),
+            "script",
+            "content4 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts without a group should get their own context",
+        );
+    }
+
+    #[test]
+    fn test_per_entity_assigner_rejects_ambiguous_contexts() {
+        let mut app = setup_app();
+        app.add_event::<crate::event::ScriptErrorEvent>();
+        app.world_mut()
+            .resource_mut::<ContextLoadingSettings<DummyPlugin>>()
+            .assignment_strategy = ContextAssignmentStrategy::PerEntity;
+        app.world_mut()
+            .spawn(ScriptComponent::new(["a", "ambiguous"]));
+        app.world_mut()
+            .spawn(ScriptComponent::new(["b", "ambiguous"]));
+        app.world_mut().spawn(ScriptComponent::new(["c", "shared"]));
+        app.world_mut().spawn(ScriptComponent::new(["c", "shared"]));
+
+        for (id, content) in [
+            ("a", "content"),
+            ("b", "content2"),
+            ("ambiguous", "content3"),
+            ("c", "content4"),
+            ("shared", "content5"),
+        ] {
+            let content = content.as_bytes().to_vec().into_boxed_slice();
+            CreateOrUpdateScript::<DummyPlugin>::new(id.into(), content, None)
+                .apply(app.world_mut());
+        }
+
+        let scripts = app.world().resource::<Scripts<DummyPlugin>>();
+        assert!(
+            !scripts.scripts.contains_key("ambiguous"),
+            "Scripts attached alongside different contexts should not be loaded"
+        );
+        assert_context_and_script(
+            app.world(),
+            "shared",
+            "content5 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts attached alongside the same context on every entity should share it",
+        );
+        assert_eq!(
+            app.world()
+                .resource::<Events<crate::event::ScriptErrorEvent>>()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_grouped_assigner_receives_asset_metadata() {
+        let mut app = setup_app();
+        app.world_mut()
+            .resource_mut::<ContextLoadingSettings<DummyPlugin>>()
+            .assignment_strategy = ContextAssignmentStrategy::Grouped(|_, metadata| {
+            metadata.map(|metadata| metadata.language.to_string())
+        });
+
+        let mut assets = Assets::<ScriptAsset>::default();
+        let mut store = ScriptMetadataStore::default();
+        let mut handles = Vec::new();
+        for id in ["script", "script2"] {
+            let asset = assets.add(ScriptAsset {
+                content: b"content".to_vec().into(),
+                asset_path: format!("{id}.lua").into(),
+                source_map: None,
+            });
+            store.insert(
+                asset.id(),
+                ScriptMetadata {
+                    asset_id: asset.id(),
+                    script_id: id.into(),
+                    language: Language::Lua,
+                },
+            );
+            handles.push(Some(asset));
+        }
+        app.insert_resource(store);
+        handles.push(None);
+
+        for ((id, content), asset) in [
+            ("script", "content"),
+            ("script2", "content2"),
+            ("script3", "content3"),
+        ]
+        .into_iter()
+        .zip(handles)
+        {
+            let content = content.as_bytes().to_vec().into_boxed_slice();
+            CreateOrUpdateScript::<DummyPlugin>::new(id.into(), content, asset)
+                .apply(app.world_mut());
+        }
+
+        assert_context_and_script(
+            app.world(),
+            "script",
+            "content2 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts whose metadata maps to the same group should share a context",
+        );
+        assert_context_and_script(
+            app.world(),
+            "script3",
+            "content3 initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Scripts without metadata should get their own context",
+        );
+    }
+
+    #[test]
+    fn test_reload_saves_and_restores_state() {
+        let mut app = setup_app();
+        app.insert_resource(CallbackSettings::<DummyPlugin> {
+            callback_handler: |args, _, _, callback, c, _, _| {
+                c.push_str(format!(" callback-ran-{}", callback).as_str());
+                if let Some(ScriptValue::String(state)) = args.first() {
+                    c.push_str(format!("-{}", state).as_str());
+                }
+                if callback == &OnSaveState::into_callback_label() {
+                    return Ok(ScriptValue::String(c.clone().into()));
+                }
+                Ok(ScriptValue::Unit)
+            },
+            execution_budget: Default::default(),
+            defined_callbacks: |_| None,
+            index_callbacks: false,
+            reindex_after_callbacks: false,
+            parallel_callbacks: false,
+        });
+
+        let content = "content".as_bytes().to_vec().into_boxed_slice();
+        let command = CreateOrUpdateScript::<DummyPlugin>::new("script".into(), content, None);
+        command.apply(app.world_mut());
+
+        assert_context_and_script(
+            app.world(),
+            "script",
+            "content initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Initial script creation should not save or restore state",
+        );
+
+        let content = "new content".as_bytes().to_vec().into_boxed_slice();
+        let command = CreateOrUpdateScript::<DummyPlugin>::new("script".into(), content, None);
+        command.apply(app.world_mut());
+
+        assert_context_and_script(
+            app.world(),
+            "script",
+            "new content initialized pre-handling-initialized callback-ran-on_script_loaded callback-ran-on_restore_state-content initialized pre-handling-initialized callback-ran-on_script_loaded callback-ran-on_save_state",
+            "State was not restored after reload",
+        );
+    }
+
+    #[test]
+    fn test_commands_publish_lifecycle_events() {
+        #[derive(Resource, Default)]
+        struct ObservedEvents(Vec<String>);
+
+        let mut app = setup_app();
+        app.init_resource::<ObservedEvents>()
+            .add_observer(
+                |trigger: Trigger<ScriptLoaded>, mut observed: ResMut<ObservedEvents>| {
+                    observed.0.push(format!("loaded {}", trigger.script));
+                },
+            )
+            .add_observer(
+                |trigger: Trigger<ScriptReloaded>, mut observed: ResMut<ObservedEvents>| {
+                    observed.0.push(format!("reloaded {}", trigger.script));
+                },
+            )
+            .add_observer(
+                |trigger: Trigger<ScriptUnloaded>, mut observed: ResMut<ObservedEvents>| {
+                    observed.0.push(format!("unloaded {}", trigger.script));
+                },
+            )
+            .add_observer(
+                |trigger: Trigger<ScriptLoadFailed>, mut observed: ResMut<ObservedEvents>| {
+                    observed.0.push(format!("failed {}", trigger.script));
+                },
+            );
+        app.insert_resource(ContextLoadingSettings::<DummyPlugin> {
+            loader: ContextBuilder {
+                load: |_, c, _, _, _| match c {
+                    b"invalid" => Err(InteropError::invariant("invalid script").into()),
+                    _ => Ok(String::from_utf8_lossy(c).into()),
+                },
+                reload: |_, new, existing, _, _, _| {
+                    *existing = String::from_utf8_lossy(new).into();
+                    Ok(())
+                },
+            },
+            assignment_strategy: Default::default(),
+            context_initializers: vec![],
+            context_pre_handling_initializers: vec![],
+        });
+        app.add_event::<crate::event::ScriptErrorEvent>();
+
+        let mut assets = Assets::<ScriptAsset>::default();
+        let asset = assets.add(ScriptAsset {
+            content: b"content".to_vec().into(),
+            asset_path: "script.lua".into(),
+            source_map: None,
+        });
+        app.insert_resource(assets);
+
+        let world = app.world_mut();
+        // registers the observers
+        world.flush();
+        CreateOrUpdateScript::<DummyPlugin>::new(
+            "script".into(),
+            b"content".to_vec().into(),
+            Some(Handle::Weak(asset.id())),
+        )
+        .apply(world);
+        CreateOrUpdateScript::<DummyPlugin>::new(
+            "script".into(),
+            b"new".to_vec().into(),
+            Some(Handle::Weak(asset.id())),
+        )
+        .apply(world);
+        CreateOrUpdateScript::<DummyPlugin>::new(
+            "invalid".into(),
+            b"invalid".to_vec().into(),
+            None,
+        )
+        .apply(world);
+        DeleteScript::<DummyPlugin>::new("script".into()).apply(world);
+
+        assert_eq!(
+            world.resource::<ObservedEvents>().0,
+            vec![
+                "loaded script",
+                "reloaded script",
+                "failed invalid",
+                "unloaded script"
+            ]
+        );
+
+        let loaded = world
+            .resource_mut::<Events<ScriptLoaded>>()
+            .drain()
+            .map(|e| e.asset)
+            .collect::<Vec<_>>();
+        let reloaded = world
+            .resource_mut::<Events<ScriptReloaded>>()
+            .drain()
+            .map(|e| e.asset)
+            .collect::<Vec<_>>();
+        let unloaded = world
+            .resource_mut::<Events<ScriptUnloaded>>()
+            .drain()
+            .map(|e| e.asset)
+            .collect::<Vec<_>>();
+        let failed = world
+            .resource_mut::<Events<ScriptLoadFailed>>()
+            .drain()
+            .collect::<Vec<_>>();
+        assert_eq!((loaded.len(), reloaded.len(), unloaded.len()), (1, 1, 1));
+
+        // the handles resolve to the asset the script was loaded from
+        let assets = world.resource::<Assets<ScriptAsset>>();
+        for handle in loaded.iter().chain(&reloaded).chain(&unloaded) {
+            let asset = handle.as_ref().and_then(|handle| assets.get(handle));
+            assert_eq!(asset.map(|a| &*a.content), Some(&b"content"[..]));
+        }
+
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].asset.is_none());
+        assert_eq!(failed[0].language, Language::Unknown);
+        assert!(failed[0].error.to_string().contains("invalid script"));
+    }
+
+    #[test]
+    fn test_script_observers_run_callbacks_and_are_removed_with_script() {
+        use crate::bindings::{
+            ReflectScriptEvent, ScriptComponentRegistration, ScriptEventRegistration,
+            ScriptObserverTrigger, ScriptTypeRegistration, WorldAccessGuard,
+        };
+        use bevy::reflect::{FromType, Reflect, TypeRegistration};
+
+        #[derive(bevy::ecs::component::Component, Reflect)]
+        struct Marker;
+
+        #[derive(Event, Reflect, Clone)]
+        struct Ping;
+
+        fn add_observers(world: &mut World) -> Vec<Entity> {
+            let marker = ScriptComponentRegistration::new(
+                ScriptTypeRegistration::new(Arc::new(TypeRegistration::of::<Marker>())),
+                world.register_component::<Marker>(),
+            );
+            let mut ping = TypeRegistration::of::<Ping>();
+            ping.insert(<ReflectScriptEvent as FromType<Ping>>::from_type());
+            let ping = ScriptEventRegistration::new(
+                ScriptTypeRegistration::new(Arc::new(ping)),
+                world.init_resource::<Events<Ping>>(),
+            );
+
+            let guard = WorldAccessGuard::new_exclusive(world);
+            [
+                (ScriptObserverTrigger::OnAdd(marker), "on_marker_added"),
+                (ScriptObserverTrigger::Event(ping), "on_ping"),
+            ]
+            .into_iter()
+            .map(|(trigger, callback)| {
+                guard
+                    .add_observer::<DummyPlugin>(trigger, callback.into(), "script".into())
+                    .unwrap()
+            })
+            .collect()
+        }
+
+        let mut app = setup_app();
+        app.init_resource::<ScriptObservers>()
+            .add_event::<crate::event::ScriptErrorEvent>();
+        app.world_mut()
+            .resource_mut::<ContextLoadingSettings<DummyPlugin>>()
+            .loader
+            .load = |_, content, _, _, _| match content {
+            b"invalid" => Err(InteropError::invariant("invalid script").into()),
+            _ => Ok(format!(
+                "{} initialized pre-handling-initialized",
+                String::from_utf8_lossy(content)
+            )),
+        };
+        let world = app.world_mut();
+        CreateOrUpdateScript::<DummyPlugin>::new("script".into(), b"content".to_vec().into(), None)
+            .apply(world);
+
+        let observers = add_observers(world);
+        world.flush();
+        world.spawn(Marker);
+        world.trigger(Ping);
+        world.flush();
+        assert_context_and_script(
+            world,
+            "script",
+            "content initialized pre-handling-initialized callback-ran-on_script_loaded callback-ran-on_marker_added callback-ran-on_ping",
+            "Observers did not run the callbacks",
+        );
+
+        // a failed reload keeps the observers of the previous context
+        CreateOrUpdateScript::<DummyPlugin>::new("script".into(), b"invalid".to_vec().into(), None)
+            .apply(world);
+        assert!(observers.iter().all(|o| world.get_entity(*o).is_ok()));
+        assert_eq!(
+            world.resource::<ScriptObservers>().get(&"script".into()),
+            observers.as_slice()
+        );
+
+        // reloading removes the observers
+        CreateOrUpdateScript::<DummyPlugin>::new("script".into(), b"new".to_vec().into(), None)
+            .apply(world);
+        assert!(observers.iter().all(|o| world.get_entity(*o).is_err()));
+        world.spawn(Marker);
+        world.flush();
+        assert_context_and_script(
+            world,
+            "script",
+            "new initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Observers ran after the script was reloaded",
+        );
+
+        // as does deleting the script
+        let observers = add_observers(world);
+        assert_eq!(
+            world.resource::<ScriptObservers>().get(&"script".into()),
+            observers.as_slice()
+        );
+        DeleteScript::<DummyPlugin>::new("script".into()).apply(world);
+        assert!(observers.iter().all(|o| world.get_entity(*o).is_err()));
+        assert!(world
+            .resource::<ScriptObservers>()
+            .get(&"script".into())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_script_observers_respect_capabilities() {
+        use crate::{
+            bindings::{
+                capabilities::{
+                    AppScriptCapabilityRegistry, CapabilityViolation, ScriptCapabilities,
+                },
+                ScriptComponentRegistration, ScriptObserverTrigger, ScriptTypeRegistration,
+                WorldAccessGuard,
+            },
+            error::InteropErrorInner,
+        };
+        use bevy::reflect::{Reflect, TypeRegistration};
+
+        #[derive(bevy::ecs::component::Component, Reflect)]
+        struct Marker;
+
+        let mut app = setup_app();
+        app.init_resource::<ScriptObservers>();
+        let world = app.world_mut();
+        CreateOrUpdateScript::<DummyPlugin>::new("script".into(), b"content".to_vec().into(), None)
+            .apply(world);
+        // entity 0 is treated as a placeholder by the world guard
+        world.spawn_empty();
+        let denied = world.spawn_empty().id();
+        world
+            .get_resource_or_init::<AppScriptCapabilityRegistry>()
+            .write()
+            .insert(
+                "script",
+                ScriptCapabilities::unrestricted().deny_entity(denied),
+            );
+        let marker = ScriptComponentRegistration::new(
+            ScriptTypeRegistration::new(Arc::new(TypeRegistration::of::<Marker>())),
+            world.register_component::<Marker>(),
+        );
+
+        let guard = WorldAccessGuard::new_exclusive(world);
+        let guard = guard.with_script_capabilities(&"script".into());
+        let err = guard
+            .add_observer::<DummyPlugin>(
+                ScriptObserverTrigger::OnAdd(marker.clone()),
+                "on_marker_added".into(),
+                "other".into(),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.inner(),
+            &InteropErrorInner::CapabilityViolation {
+                script_id: "script".into(),
+                violation: CapabilityViolation::ForeignScript("other".into()),
+            }
+        );
+        guard
+            .add_observer::<DummyPlugin>(
+                ScriptObserverTrigger::OnAdd(marker),
+                "on_marker_added".into(),
+                "script".into(),
+            )
+            .unwrap();
+        drop(guard);
+
+        world.flush();
+        world.entity_mut(denied).insert(Marker);
+        world.flush();
+        assert_context_and_script(
+            world,
+            "script",
+            "content initialized pre-handling-initialized callback-ran-on_script_loaded",
+            "Observer ran for a denied entity",
+        );
+
+        world.spawn(Marker);
+        world.flush();
+        assert_context_and_script(
+            world,
+            "script",
+            "content initialized pre-handling-initialized callback-ran-on_script_loaded callback-ran-on_marker_added",
+            "Observer did not run for an allowed entity",
+        );
+    }
+
     #[test]
     fn test_static_scripts() {
         let mut app = setup_app();
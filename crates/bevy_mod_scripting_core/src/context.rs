@@ -1,6 +1,7 @@
 //! Traits and types for managing script contexts.
 
 use crate::{
+    asset::ScriptMetadata,
     bindings::{ThreadWorldContainer, WorldContainer, WorldGuard},
     error::{InteropError, ScriptError},
     script::ScriptId,
@@ -106,7 +107,8 @@ impl<P: IntoScriptPluginParams> ContextBuilder<P> {
         world: WorldGuard,
         runtime: &P::R,
     ) -> Result<P::C, ScriptError> {
-        WorldGuard::with_existing_static_guard(world.clone(), |world| {
+        let world = world.with_script_capabilities(script);
+        WorldGuard::with_existing_static_guard(world, |world| {
             ThreadWorldContainer.set_world(world)?;
             (loader)(
                 script,
@@ -129,6 +131,7 @@ impl<P: IntoScriptPluginParams> ContextBuilder<P> {
         world: WorldGuard,
         runtime: &P::R,
     ) -> Result<(), ScriptError> {
+        let world = world.with_script_capabilities(script);
         WorldGuard::with_existing_static_guard(world, |world| {
             ThreadWorldContainer.set_world(world)?;
             (reloader)(
@@ -152,7 +155,14 @@ impl<P: IntoScriptPluginParams> Clone for ContextBuilder<P> {
     }
 }
 
+/// Maps a script to the key of the group whose context it shares, scripts mapped to `None` are assigned their own context.
+///
+/// Receives the id of the script, and the metadata of its asset if the script was loaded from one.
+pub type ContextGroupingFn = fn(&ScriptId, Option<&ScriptMetadata>) -> Option<String>;
+
 /// The strategy used in assigning contexts to scripts
+///
+/// Contexts are assigned when a script is first loaded, and kept when it is reloaded.
 #[derive(Default, Clone, Copy)]
 pub enum ContextAssignmentStrategy {
     /// Assign a new context to each script
@@ -160,4 +170,12 @@ pub enum ContextAssignmentStrategy {
     Individual,
     /// Share contexts with all other scripts
     Global,
+    /// Share contexts with the other scripts attached to the same entity via its [`crate::script::ScriptComponent`].
+    ///
+    /// Scripts attached to multiple entities must be attached alongside scripts sharing the same context on each entity,
+    /// otherwise which context to share is ambiguous and loading the script fails.
+    /// Scripts which are not attached to any entity, such as static scripts, are assigned their own context.
+    PerEntity,
+    /// Share contexts with the other scripts in the same group, as determined by the given function
+    Grouped(ContextGroupingFn),
 }
@@ -0,0 +1,379 @@
+//! Support for callbacks which suspend themselves part way through and are resumed on later frames
+
+use crate::{
+    bindings::{script_value::ScriptValue, WorldGuard},
+    error::InteropError,
+    event::CallbackLabel,
+    extractors::{HandlerContext, WithWorldGuard},
+    handler::handle_script_errors,
+    script::ScriptId,
+    IntoScriptPluginParams,
+};
+use bevy::{
+    ecs::{entity::Entity, system::SystemState, world::World},
+    reflect::Reflect,
+    time::Time,
+    utils::HashSet,
+};
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    time::Duration,
+};
+
+/// The condition a suspended callback is waiting on before it is resumed
+#[derive(Debug, Clone, PartialEq, Default, Reflect)]
+pub enum YieldCondition {
+    /// Resume on the next frame
+    #[default]
+    NextFrame,
+    /// Resume after the given number of frames
+    Frames(u32),
+    /// Resume once the given amount of time has passed, as measured by the [`bevy::time::Time`] resource
+    Duration(Duration),
+    /// Resume the next time a [`crate::event::ScriptCallbackEvent`] with the given label is handled for the same script and entity.
+    ///
+    /// The event payload is passed to the callback as the result of the yield.
+    Event(CallbackLabel),
+}
+
+impl YieldCondition {
+    /// Converts a value yielded by a script into a yield condition.
+    ///
+    /// Yielding nothing is equivalent to yielding [`YieldCondition::NextFrame`].
+    pub fn from_script_value(value: ScriptValue, world: WorldGuard) -> Result<Self, InteropError> {
+        match value {
+            ScriptValue::Unit => Ok(Self::NextFrame),
+            ScriptValue::Reference(reference) => reference.downcast::<Self>(world),
+            value => Err(InteropError::value_mismatch(TypeId::of::<Self>(), value)),
+        }
+    }
+}
+
+/// The language specific state needed to resume a suspended callback, i.e. a lua thread
+pub struct ScriptContinuation(Box<dyn Any + Send + Sync>);
+
+impl ScriptContinuation {
+    /// Wraps a language specific continuation
+    pub fn new<T: Any + Send + Sync>(continuation: T) -> Self {
+        Self(Box::new(continuation))
+    }
+
+    /// Attempts to unwrap the language specific continuation
+    pub fn downcast<T: Any + Send + Sync>(self) -> Result<T, Self> {
+        self.0.downcast::<T>().map(|c| *c).map_err(Self)
+    }
+}
+
+/// A callback which suspended itself and is waiting to be resumed
+pub struct SuspendedCallback {
+    /// The script which suspended
+    pub script_id: ScriptId,
+    /// The entity the callback was running for
+    pub entity: Entity,
+    /// The callback which suspended
+    pub callback: CallbackLabel,
+    /// The condition the callback is waiting on
+    pub condition: YieldCondition,
+    /// The state needed to resume the callback
+    pub continuation: ScriptContinuation,
+    /// The frame the callback was suspended in
+    suspended_at: u64,
+    /// The time waited so far for [`YieldCondition::Duration`] conditions
+    waited: Duration,
+}
+
+/// All the suspended callbacks of a single scripting plugin
+#[derive(Default)]
+pub struct SuspendedCallbacks {
+    /// Incremented every time suspended callbacks are checked for resumption
+    frame: u64,
+    callbacks: Vec<SuspendedCallback>,
+}
+
+#[profiling::all_functions]
+impl SuspendedCallbacks {
+    /// Stores a suspended callback until its condition is met
+    pub fn suspend(
+        &mut self,
+        script_id: ScriptId,
+        entity: Entity,
+        callback: CallbackLabel,
+        condition: YieldCondition,
+        continuation: ScriptContinuation,
+    ) {
+        self.callbacks.push(SuspendedCallback {
+            script_id,
+            entity,
+            callback,
+            condition,
+            continuation,
+            suspended_at: self.frame,
+            waited: Duration::ZERO,
+        });
+    }
+
+    /// Advances to the next frame, removing and returning all the callbacks which are ready to be resumed.
+    ///
+    /// Callbacks waiting on events are never returned.
+    pub fn advance_frame(&mut self, delta: Duration) -> Vec<SuspendedCallback> {
+        self.frame += 1;
+        let frame = self.frame;
+        self.take_where(|suspended| match &suspended.condition {
+            YieldCondition::NextFrame => true,
+            YieldCondition::Frames(frames) => {
+                frame >= suspended.suspended_at + u64::from((*frames).max(1))
+            }
+            YieldCondition::Duration(duration) => {
+                suspended.waited += delta;
+                suspended.waited >= *duration
+            }
+            YieldCondition::Event(_) => false,
+        })
+    }
+
+    /// Removes and returns all the callbacks of the given script and entity waiting on an event with the given label
+    pub fn take_waiting_for_event(
+        &mut self,
+        label: &CallbackLabel,
+        script_id: &ScriptId,
+        entity: Entity,
+    ) -> Vec<SuspendedCallback> {
+        self.take_where(|suspended| {
+            matches!(&suspended.condition, YieldCondition::Event(l) if l == label)
+                && &suspended.script_id == script_id
+                && suspended.entity == entity
+        })
+    }
+
+    /// Returns the scripts with callbacks waiting on an event with the given label
+    pub fn scripts_waiting_for_event(&self, label: &CallbackLabel) -> HashSet<ScriptId> {
+        self.callbacks
+            .iter()
+            .filter(|s| matches!(&s.condition, YieldCondition::Event(l) if l == label))
+            .map(|s| s.script_id.clone())
+            .collect()
+    }
+
+    /// Drops all the suspended callbacks of the given script
+    pub fn remove_script(&mut self, script_id: &ScriptId) {
+        self.callbacks.retain(|s| &s.script_id != script_id);
+    }
+
+    /// Returns an iterator over all the suspended callbacks
+    pub fn iter(&self) -> impl Iterator<Item = &SuspendedCallback> {
+        self.callbacks.iter()
+    }
+
+    /// Returns the number of suspended callbacks
+    pub fn len(&self) -> usize {
+        self.callbacks.len()
+    }
+
+    /// Returns true if there are no suspended callbacks
+    pub fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    fn take_where(
+        &mut self,
+        mut f: impl FnMut(&mut SuspendedCallback) -> bool,
+    ) -> Vec<SuspendedCallback> {
+        let mut taken = Vec::new();
+        let mut i = 0;
+        while i < self.callbacks.len() {
+            if f(&mut self.callbacks[i]) {
+                taken.push(self.callbacks.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        taken
+    }
+}
+
+thread_local! {
+    static RESUMED_CONTINUATION: RefCell<Option<ScriptContinuation>> = const { RefCell::new(None) };
+    static SUSPENDED_CONTINUATION: RefCell<Option<(YieldCondition, ScriptContinuation)>> = const { RefCell::new(None) };
+}
+
+/// Passes continuations between the callback handlers of language implementations and the core.
+///
+/// Language implementations which support suspending callbacks:
+/// - call [`ThreadScriptCoroutine::take_resumed`] at the start of their handler, and resume the continuation instead of calling the callback if one is returned
+/// - call [`ThreadScriptCoroutine::suspend`] when the callback suspends itself, and return from the handler
+pub struct ThreadScriptCoroutine;
+
+#[profiling::all_functions]
+impl ThreadScriptCoroutine {
+    /// Marks the callback currently running on this thread as suspended until the condition is met.
+    pub fn suspend<T: Any + Send + Sync>(condition: YieldCondition, continuation: T) {
+        SUSPENDED_CONTINUATION
+            .with(|s| s.replace(Some((condition, ScriptContinuation::new(continuation)))));
+    }
+
+    /// Takes the continuation which should be resumed instead of calling the callback, if the handler is resuming a suspended callback.
+    pub fn take_resumed<T: Any + Send + Sync>() -> Result<Option<T>, InteropError> {
+        match RESUMED_CONTINUATION.with(|r| r.take()) {
+            Some(continuation) => continuation.downcast::<T>().map(Some).map_err(|_| {
+                InteropError::invariant("Tried to resume a continuation of another language")
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Runs the closure with the given continuation ready to be resumed, returning the continuation the closure suspended with if any.
+    ///
+    /// Any continuations belonging to callers up the stack are restored afterwards.
+    pub(crate) fn with_continuation<O>(
+        continuation: Option<ScriptContinuation>,
+        f: impl FnOnce() -> O,
+    ) -> (O, Option<(YieldCondition, ScriptContinuation)>) {
+        let previous_resumed = RESUMED_CONTINUATION.with(|r| r.replace(continuation));
+        let previous_suspended = SUSPENDED_CONTINUATION.with(|s| s.take());
+        let out = f();
+        RESUMED_CONTINUATION.with(|r| r.replace(previous_resumed));
+        let suspended = SUSPENDED_CONTINUATION.with(|s| s.replace(previous_suspended));
+        (out, suspended)
+    }
+}
+
+/// Resumes suspended callbacks whose [`YieldCondition`] has been met.
+///
+/// Runs every frame in the [`crate::ScriptingSystemSet::CallbackResumption`] set, callbacks waiting on events are resumed by the event handlers instead.
+#[allow(deprecated)]
+pub fn resume_suspended_callbacks<P: IntoScriptPluginParams>(
+    world: &mut World,
+    state: &mut SystemState<WithWorldGuard<HandlerContext<P>>>,
+) {
+    let delta = world
+        .get_resource::<Time>()
+        .map(|time| time.delta())
+        .unwrap_or_default();
+
+    {
+        let mut handler_ctxt = state.get_mut(world);
+        let (guard, handler_ctxt) = handler_ctxt.get_mut();
+
+        let ready = handler_ctxt
+            .scripts
+            .suspended_callbacks()
+            .advance_frame(delta);
+
+        let errors = ready
+            .into_iter()
+            .filter_map(|suspended| {
+                let script_id = suspended.script_id.clone();
+                handler_ctxt
+                    .resume(suspended, vec![], guard.clone())
+                    .map_err(|e| {
+                        e.with_script(script_id).with_context(format!(
+                            "Resuming suspended callback for: Language: {}",
+                            P::LANGUAGE
+                        ))
+                    })
+                    .err()
+            })
+            .collect::<Vec<_>>();
+
+        if !errors.is_empty() {
+            handle_script_errors(guard, errors.into_iter());
+        }
+    }
+    state.apply(world);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn suspend(suspended: &mut SuspendedCallbacks, script_id: &str, condition: YieldCondition) {
+        suspended.suspend(
+            ScriptId::from(script_id.to_owned()),
+            Entity::from_raw(0),
+            CallbackLabel::new_lossy("on_test"),
+            condition,
+            ScriptContinuation::new(script_id.to_owned()),
+        );
+    }
+
+    fn ready_scripts(ready: Vec<SuspendedCallback>) -> Vec<String> {
+        ready.into_iter().map(|s| s.script_id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_suspended_callbacks_resume_when_conditions_are_met() {
+        let mut suspended = SuspendedCallbacks::default();
+        suspend(&mut suspended, "next_frame", YieldCondition::NextFrame);
+        suspend(&mut suspended, "frames", YieldCondition::Frames(2));
+        suspend(
+            &mut suspended,
+            "duration",
+            YieldCondition::Duration(Duration::from_millis(250)),
+        );
+        suspend(
+            &mut suspended,
+            "event",
+            YieldCondition::Event(CallbackLabel::new_lossy("on_event")),
+        );
+
+        let delta = Duration::from_millis(100);
+        assert_eq!(
+            ready_scripts(suspended.advance_frame(delta)),
+            vec!["next_frame"]
+        );
+        assert_eq!(
+            ready_scripts(suspended.advance_frame(delta)),
+            vec!["frames"]
+        );
+        assert_eq!(
+            ready_scripts(suspended.advance_frame(delta)),
+            vec!["duration"]
+        );
+        assert!(suspended.advance_frame(delta).is_empty());
+        assert_eq!(suspended.len(), 1);
+    }
+
+    #[test]
+    fn test_suspended_callbacks_resume_on_matching_events() {
+        let mut suspended = SuspendedCallbacks::default();
+        let label = CallbackLabel::new_lossy("on_event");
+        suspend(&mut suspended, "a", YieldCondition::Event(label.clone()));
+        suspend(&mut suspended, "b", YieldCondition::Event(label.clone()));
+
+        let other_label = CallbackLabel::new_lossy("on_other_event");
+        assert!(suspended
+            .take_waiting_for_event(&other_label, &"a".into(), Entity::from_raw(0))
+            .is_empty());
+        assert!(suspended
+            .take_waiting_for_event(&label, &"a".into(), Entity::from_raw(1))
+            .is_empty());
+
+        let ready = suspended.take_waiting_for_event(&label, &"a".into(), Entity::from_raw(0));
+        assert_eq!(ready_scripts(ready), vec!["a"]);
+
+        suspended.remove_script(&"b".into());
+        assert!(suspended.is_empty());
+    }
+
+    #[test]
+    fn test_thread_script_coroutine_passes_continuations() {
+        let (resumed, suspended) =
+            ThreadScriptCoroutine::with_continuation(Some(ScriptContinuation::new(1u32)), || {
+                let resumed = ThreadScriptCoroutine::take_resumed::<u32>().unwrap();
+                ThreadScriptCoroutine::suspend(YieldCondition::Frames(2), 2u32);
+                resumed
+            });
+
+        assert_eq!(resumed, Some(1));
+        let (condition, continuation) = suspended.unwrap();
+        assert_eq!(condition, YieldCondition::Frames(2));
+        assert_eq!(continuation.downcast::<u32>().ok(), Some(2));
+
+        let (resumed, suspended) = ThreadScriptCoroutine::with_continuation(None, || {
+            ThreadScriptCoroutine::take_resumed::<u32>().unwrap()
+        });
+        assert_eq!(resumed, None);
+        assert!(suspended.is_none());
+    }
+}
@@ -1,13 +1,17 @@
 //! Errors that can occur when interacting with the scripting system
 
 use crate::{
+    asset::ScriptSourceMap,
     bindings::{
         access_map::{DisplayCodeLocation, ReflectAccessId},
+        capabilities::CapabilityViolation,
         function::namespace::Namespace,
         pretty_print::DisplayWithWorld,
         script_value::ScriptValue,
         ReflectBaseType, ReflectReference,
     },
+    event::CallbackLabel,
+    handler::ExecutionBudget,
     script::ScriptId,
 };
 use bevy::{
@@ -25,6 +29,7 @@ use std::{
     ops::Deref,
     str::Utf8Error,
     sync::Arc,
+    time::Duration,
 };
 
 /// An error with an optional script Context
@@ -145,6 +150,12 @@ impl ScriptError {
         }
     }
 
+    #[cfg(feature = "rune_impls")]
+    /// destructures a rune error into a script error
+    pub fn from_rune_error(error: rune::runtime::VmError) -> Self {
+        Self::new_external(error)
+    }
+
     /// Creates a new script error with an external error
     pub fn new_external(reason: impl std::error::Error + Send + Sync + 'static) -> Self {
         Self::new_external_boxed(Box::new(reason))
@@ -185,6 +196,25 @@ impl ScriptError {
             reason: self.0.reason.clone(),
         }))
     }
+
+    /// Maps line numbers in the error, which refer to a preprocessed script, back to the lines of the original script.
+    ///
+    /// The chunk name is the name the script was loaded under, see [`ScriptSourceMap::remap_line_references`].
+    pub fn with_source_map(self, chunk_name: &str, source_map: &ScriptSourceMap) -> Self {
+        let reason = match self.0.reason.as_ref() {
+            ErrorKind::Display(e) => Arc::new(ErrorKind::Display(
+                source_map
+                    .remap_line_references(chunk_name, &e.to_string())
+                    .into(),
+            )),
+            ErrorKind::WithWorld(_) => self.0.reason.clone(),
+        };
+        Self(Arc::new(ScriptErrorInner {
+            script: self.0.script.clone(),
+            context: source_map.remap_line_references(chunk_name, &self.0.context),
+            reason,
+        }))
+    }
 }
 
 impl std::fmt::Display for ScriptError {
@@ -290,6 +320,52 @@ impl From<InteropError> for Box<rhai::EvalAltResult> {
     }
 }
 
+#[cfg(feature = "rune_impls")]
+impl From<rune::runtime::VmError> for ScriptError {
+    fn from(value: rune::runtime::VmError) -> Self {
+        ScriptError::from_rune_error(value)
+    }
+}
+
+#[cfg(feature = "rune_impls")]
+impl From<rune::BuildError> for ScriptError {
+    fn from(value: rune::BuildError) -> Self {
+        ScriptError::new_external(value)
+    }
+}
+
+#[cfg(feature = "rune_impls")]
+impl From<rune::ContextError> for ScriptError {
+    fn from(value: rune::ContextError) -> Self {
+        ScriptError::new_external(value)
+    }
+}
+
+#[cfg(feature = "rune_impls")]
+impl From<ScriptError> for rune::runtime::VmError {
+    fn from(value: ScriptError) -> Self {
+        use crate::bindings::WorldContainer;
+        // rune panics only carry a message, so render the error with the world while we still can
+        let message = match crate::bindings::ThreadWorldContainer.try_get_world() {
+            Ok(world) => value.display_with_world(world),
+            Err(_) => value.display_without_world(),
+        };
+        rune::runtime::VmError::panic(message)
+    }
+}
+
+#[cfg(feature = "rune_impls")]
+impl From<InteropError> for rune::runtime::VmError {
+    fn from(value: InteropError) -> Self {
+        use crate::bindings::WorldContainer;
+        let message = match crate::bindings::ThreadWorldContainer.try_get_world() {
+            Ok(world) => value.display_with_world(world),
+            Err(_) => value.display_without_world(),
+        };
+        rune::runtime::VmError::panic(message)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 /// An error thrown when a resource is missing
 pub struct MissingResourceError(&'static str);
@@ -612,6 +688,63 @@ impl InteropError {
         }))
     }
 
+    /// Thrown when a state is missing from the state registry, or has not been initialized in the world.
+    pub fn missing_state(state_name: impl Into<Cow<'static, str>>) -> Self {
+        Self(Arc::new(InteropErrorInner::MissingState {
+            state_name: state_name.into(),
+        }))
+    }
+
+    /// Thrown when a script attempts an operation which its capability policy does not allow.
+    pub fn capability_violation(
+        script_id: impl Into<ScriptId>,
+        violation: CapabilityViolation,
+    ) -> Self {
+        Self(Arc::new(InteropErrorInner::CapabilityViolation {
+            script_id: script_id.into(),
+            violation,
+        }))
+    }
+
+    /// Thrown when a script registered component schema is invalid, or a component instance does not conform to its schema.
+    pub fn invalid_script_component_schema(
+        component_name: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self(Arc::new(InteropErrorInner::InvalidScriptComponentSchema {
+            component_name: component_name.into(),
+            reason: reason.into(),
+        }))
+    }
+
+    /// Thrown when a callback is aborted for running over its [`ExecutionBudget`].
+    pub fn execution_budget_exceeded(
+        script_id: impl Into<ScriptId>,
+        callback: CallbackLabel,
+        budget: ExecutionBudget,
+        instructions: u64,
+        elapsed: Duration,
+    ) -> Self {
+        Self(Arc::new(InteropErrorInner::ExecutionBudgetExceeded {
+            script_id: script_id.into(),
+            callback,
+            budget,
+            instructions,
+            elapsed,
+        }))
+    }
+
+    /// Thrown when a script module imported by a script cannot be loaded, or is not loaded yet.
+    pub fn script_module_load_error(
+        module_path: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self(Arc::new(InteropErrorInner::ScriptModuleLoadError {
+            module_path: module_path.into(),
+            reason: reason.into(),
+        }))
+    }
+
     /// Returns the inner error
     pub fn inner(&self) -> &InteropErrorInner {
         &self.0
@@ -819,6 +952,45 @@ pub enum InteropErrorInner {
         /// The name of the schedule that was missing
         schedule_name: Cow<'static, str>,
     },
+    /// Thrown when a state is missing from the state registry, or has not been initialized in the world.
+    MissingState {
+        /// The name of the state that was missing
+        state_name: Cow<'static, str>,
+    },
+    /// Thrown when a script attempts an operation which its capability policy does not allow.
+    CapabilityViolation {
+        /// The script which attempted the operation
+        script_id: ScriptId,
+        /// The operation which was not allowed
+        violation: CapabilityViolation,
+    },
+    /// Thrown when a script registered component schema is invalid, or a component instance does not conform to its schema.
+    InvalidScriptComponentSchema {
+        /// The name of the script registered component
+        component_name: String,
+        /// The reason the schema was violated
+        reason: String,
+    },
+    /// Thrown when a callback is aborted for running over its [`ExecutionBudget`].
+    ExecutionBudgetExceeded {
+        /// The script which ran over budget
+        script_id: ScriptId,
+        /// The callback which ran over budget
+        callback: CallbackLabel,
+        /// The budget which was exceeded
+        budget: ExecutionBudget,
+        /// The number of instructions executed before the callback was aborted
+        instructions: u64,
+        /// The time spent in the callback before it was aborted
+        elapsed: Duration,
+    },
+    /// Thrown when a script module imported by a script cannot be loaded, or is not loaded yet.
+    ScriptModuleLoadError {
+        /// The asset path of the module
+        module_path: String,
+        /// The reason the module could not be loaded
+        reason: String,
+    },
 }
 
 /// For test purposes
@@ -1057,6 +1229,54 @@ impl PartialEq for InteropErrorInner {
                 InteropErrorInner::MissingSchedule { schedule_name: a },
                 InteropErrorInner::MissingSchedule { schedule_name: b },
             ) => a == b,
+            (
+                InteropErrorInner::MissingState { state_name: a },
+                InteropErrorInner::MissingState { state_name: b },
+            ) => a == b,
+            (
+                InteropErrorInner::CapabilityViolation {
+                    script_id: a,
+                    violation: b,
+                },
+                InteropErrorInner::CapabilityViolation {
+                    script_id: c,
+                    violation: d,
+                },
+            ) => a == c && b == d,
+            (
+                InteropErrorInner::InvalidScriptComponentSchema {
+                    component_name: a,
+                    reason: b,
+                },
+                InteropErrorInner::InvalidScriptComponentSchema {
+                    component_name: c,
+                    reason: d,
+                },
+            ) => a == c && b == d,
+            (
+                InteropErrorInner::ExecutionBudgetExceeded {
+                    script_id: a,
+                    callback: b,
+                    budget: c,
+                    ..
+                },
+                InteropErrorInner::ExecutionBudgetExceeded {
+                    script_id: d,
+                    callback: e,
+                    budget: f,
+                    ..
+                },
+            ) => a == d && b == e && c == f,
+            (
+                InteropErrorInner::ScriptModuleLoadError {
+                    module_path: a,
+                    reason: b,
+                },
+                InteropErrorInner::ScriptModuleLoadError {
+                    module_path: c,
+                    reason: d,
+                },
+            ) => a == c && b == d,
             _ => false,
         }
     }
@@ -1289,12 +1509,54 @@ macro_rules! missing_context_for_callback {
     };
 }
 
+macro_rules! capability_violation {
+    ($script_id:expr, $violation:expr) => {
+        format!(
+            "Script '{}' was denied by its capability policy: {}",
+            $script_id, $violation
+        )
+    };
+}
+
+macro_rules! invalid_script_component_schema {
+    ($component_name:expr, $reason:expr) => {
+        format!(
+            "Invalid schema for script component '{}': {}",
+            $component_name, $reason
+        )
+    };
+}
+
+macro_rules! execution_budget_exceeded {
+    ($script_id:expr, $callback:expr, $budget:expr, $instructions:expr, $elapsed:expr) => {
+        format!(
+            "Callback '{}' in script '{}' was aborted for running over its execution budget of {:?}. Executed {} instructions in {:?}.",
+            $callback, $script_id, $budget, $instructions, $elapsed
+        )
+    };
+}
+
+macro_rules! script_module_load_error {
+    ($module_path:expr, $reason:expr) => {
+        format!(
+            "Could not load script module '{}': {}",
+            $module_path, $reason
+        )
+    };
+}
+
 macro_rules! missing_schedule_error {
     ($schedule:expr) => {
         format!("Missing schedule: '{}'. This can happen if you try to access a schedule from within itself. Have all schedules been registered?", $schedule)
     };
 }
 
+macro_rules! missing_state_error {
+    ($state:expr) => {
+        format!("Missing state: '{}'. Has the state been registered with `register_script_state` and initialized in the app?", $state)
+    };
+}
+
 impl DisplayWithWorld for InteropErrorInner {
     fn display_with_world(&self, world: crate::bindings::WorldGuard) -> String {
         match self {
@@ -1438,6 +1700,33 @@ impl DisplayWithWorld for InteropErrorInner {
             InteropErrorInner::MissingSchedule { schedule_name } => {
                 missing_schedule_error!(schedule_name)
             },
+            InteropErrorInner::MissingState { state_name } => {
+                missing_state_error!(state_name)
+            },
+            InteropErrorInner::CapabilityViolation { script_id, violation } => {
+                capability_violation!(script_id, violation.display_with_world(world))
+            },
+            InteropErrorInner::InvalidScriptComponentSchema {
+                component_name,
+                reason,
+            } => {
+                invalid_script_component_schema!(component_name, reason)
+            },
+            InteropErrorInner::ExecutionBudgetExceeded {
+                script_id,
+                callback,
+                budget,
+                instructions,
+                elapsed,
+            } => {
+                execution_budget_exceeded!(script_id, callback, budget, instructions, elapsed)
+            },
+            InteropErrorInner::ScriptModuleLoadError {
+                module_path,
+                reason,
+            } => {
+                script_module_load_error!(module_path, reason)
+            },
         }
     }
 
@@ -1584,6 +1873,33 @@ impl DisplayWithWorld for InteropErrorInner {
             InteropErrorInner::MissingSchedule { schedule_name } => {
                 missing_schedule_error!(schedule_name)
             },
+            InteropErrorInner::MissingState { state_name } => {
+                missing_state_error!(state_name)
+            },
+            InteropErrorInner::CapabilityViolation { script_id, violation } => {
+                capability_violation!(script_id, violation.display_without_world())
+            },
+            InteropErrorInner::InvalidScriptComponentSchema {
+                component_name,
+                reason,
+            } => {
+                invalid_script_component_schema!(component_name, reason)
+            },
+            InteropErrorInner::ExecutionBudgetExceeded {
+                script_id,
+                callback,
+                budget,
+                instructions,
+                elapsed,
+            } => {
+                execution_budget_exceeded!(script_id, callback, budget, instructions, elapsed)
+            },
+            InteropErrorInner::ScriptModuleLoadError {
+                module_path,
+                reason,
+            } => {
+                script_module_load_error!(module_path, reason)
+            },
         }
     }
 }
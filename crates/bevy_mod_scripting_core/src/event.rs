@@ -1,7 +1,18 @@
 //! Event handlers and event types for scripting.
 
-use crate::{bindings::script_value::ScriptValue, error::ScriptError, script::ScriptId};
-use bevy::{ecs::entity::Entity, prelude::Event, reflect::Reflect};
+use crate::{
+    asset::{Language, ScriptAsset},
+    bindings::{query::ScriptQueryBuilder, script_value::ScriptValue},
+    error::ScriptError,
+    script::ScriptId,
+};
+use bevy::{
+    asset::Handle,
+    ecs::entity::{Entity, EntityHashSet},
+    prelude::Event,
+    reflect::Reflect,
+    utils::HashSet,
+};
 
 /// An error coming from a script
 #[derive(Debug, Event)]
@@ -75,6 +86,8 @@ macro_rules! callback_labels {
 callback_labels!(
     OnScriptLoaded => "on_script_loaded",
     OnScriptUnloaded => "on_script_unloaded",
+    OnSaveState => "on_save_state",
+    OnRestoreState => "on_restore_state",
 );
 
 /// A trait for types that can be converted into a callback label
@@ -123,7 +136,60 @@ pub enum Recipients {
     /// The event is to be handled by all the scripts on the specified entity
     Entity(Entity),
     /// The event is to be handled by all the scripts of one language
-    Language(crate::asset::Language),
+    Language(Language),
+    /// The event is to be handled by all the scripts on any of the specified entities
+    Entities(EntityHashSet),
+    /// The event is to be handled by each of the specified scripts
+    Scripts(HashSet<ScriptId>),
+    /// The event is to be handled by all the scripts on entities matching the query, the query is run once per event
+    Query(ScriptQueryBuilder),
+    /// The event is to be handled by the recipients of the first set, except the ones which are also recipients of the second
+    Except(Box<Recipients>, Box<Recipients>),
+}
+
+impl Recipients {
+    /// Creates recipients targeting all the scripts on any of the given entities
+    pub fn entities(entities: impl IntoIterator<Item = Entity>) -> Self {
+        Self::Entities(entities.into_iter().collect())
+    }
+
+    /// Creates recipients targeting each of the given scripts
+    pub fn scripts(scripts: impl IntoIterator<Item = impl Into<ScriptId>>) -> Self {
+        Self::Scripts(scripts.into_iter().map(Into::into).collect())
+    }
+
+    /// Excludes the given recipients from these recipients, i.e. to target everyone except the sender:
+    /// ```rust,ignore
+    /// Recipients::All.except(Recipients::Entity(sender))
+    /// ```
+    pub fn except(self, excluded: Recipients) -> Self {
+        Self::Except(Box::new(self), Box::new(excluded))
+    }
+
+    /// Returns true if the given script, attached to the given entity, is one of the recipients.
+    ///
+    /// `matches_query` decides whether the entity matches a [`Recipients::Query`].
+    pub fn contains(
+        &self,
+        entity: Entity,
+        script: &ScriptId,
+        language: &Language,
+        matches_query: &mut impl FnMut(&ScriptQueryBuilder, Entity) -> bool,
+    ) -> bool {
+        match self {
+            Recipients::All => true,
+            Recipients::Script(target) => target == script,
+            Recipients::Entity(target) => *target == entity,
+            Recipients::Language(target) => target == language,
+            Recipients::Entities(targets) => targets.contains(&entity),
+            Recipients::Scripts(targets) => targets.contains(script),
+            Recipients::Query(query) => matches_query(query, entity),
+            Recipients::Except(recipients, excluded) => {
+                recipients.contains(entity, script, language, matches_query)
+                    && !excluded.contains(entity, script, language, matches_query)
+            }
+        }
+    }
 }
 
 /// A callback event meant to trigger a callback in a subset/set of scripts in the world with the given arguments
@@ -196,6 +262,61 @@ impl ScriptCallbackResponseEvent {
     }
 }
 
+/// Published when a script is loaded for the first time, after its `on_script_loaded` callback ran.
+///
+/// Also triggered for observers.
+#[derive(Clone, Event, Debug)]
+pub struct ScriptLoaded {
+    /// The script which was loaded
+    pub script: ScriptId,
+    /// The language of the script
+    pub language: Language,
+    /// The asset the script was loaded from, if any
+    pub asset: Option<Handle<ScriptAsset>>,
+}
+
+/// Published when an existing script is reloaded, i.e. when its asset changes, after its state was restored.
+///
+/// Also triggered for observers.
+#[derive(Clone, Event, Debug)]
+pub struct ScriptReloaded {
+    /// The script which was reloaded
+    pub script: ScriptId,
+    /// The language of the script
+    pub language: Language,
+    /// The asset the script was reloaded from, if any
+    pub asset: Option<Handle<ScriptAsset>>,
+}
+
+/// Published when a script is deleted, after its `on_script_unloaded` callback ran.
+///
+/// Also triggered for observers.
+#[derive(Clone, Event, Debug)]
+pub struct ScriptUnloaded {
+    /// The script which was unloaded
+    pub script: ScriptId,
+    /// The language of the script
+    pub language: Language,
+    /// The asset the script was loaded from, if any
+    pub asset: Option<Handle<ScriptAsset>>,
+}
+
+/// Published when a script fails to load or reload, the error is also reported via [`ScriptErrorEvent`].
+///
+/// Scripts waiting for their modules to load do not publish this event.
+/// Also triggered for observers.
+#[derive(Clone, Event, Debug)]
+pub struct ScriptLoadFailed {
+    /// The script which failed to load
+    pub script: ScriptId,
+    /// The language of the script
+    pub language: Language,
+    /// The asset the script was loaded from, if any
+    pub asset: Option<Handle<ScriptAsset>>,
+    /// The error which caused the failure
+    pub error: ScriptError,
+}
+
 static FORBIDDEN_KEYWORDS: [&str; 82] = [
     // Lua
     "and",
@@ -2,7 +2,10 @@
 //!
 //! These are designed to be used to pipe inputs into other systems which require them, while handling any configuration erorrs nicely.
 #![allow(deprecated)]
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
 
 use bevy::ecs::{
     component::ComponentId,
@@ -13,17 +16,22 @@ use bevy::ecs::{
     system::{Local, Resource, SystemParam, SystemState},
     world::World,
 };
+use bevy::utils::HashSet;
 use fixedbitset::FixedBitSet;
 
 use crate::{
     bindings::{
-        access_map::ReflectAccessId, pretty_print::DisplayWithWorld, script_value::ScriptValue,
-        WorldAccessGuard, WorldGuard,
+        access_map::ReflectAccessId,
+        function::{from::FromScript, into::IntoScriptArgs},
+        pretty_print::DisplayWithWorld,
+        script_value::ScriptValue,
+        ScriptCommandQueue, WorldAccessGuard, WorldGuard,
     },
     context::ContextLoadingSettings,
+    coroutine::{ScriptContinuation, SuspendedCallback},
     error::{InteropError, ScriptError},
     event::{CallbackLabel, IntoCallbackLabel},
-    handler::CallbackSettings,
+    handler::{CallbackInvocation, CallbackSettings},
     runtime::RuntimeContainer,
     script::{ScriptId, Scripts, StaticScripts},
     IntoScriptPluginParams,
@@ -201,6 +209,31 @@ impl<P: IntoScriptPluginParams> HandlerContext<'_, P> {
         self.scripts.scripts.contains_key(&script_id)
     }
 
+    /// Indexes the callbacks defined in the contexts of the given scripts again after they ran callbacks,
+    /// if enabled by [`CallbackSettings::reindex_after_callbacks`].
+    ///
+    /// Contexts shared by several of the given scripts are only indexed once.
+    pub fn reindex_callbacks_after_running<'a>(
+        &mut self,
+        script_ids: impl IntoIterator<Item = &'a ScriptId>,
+    ) {
+        if !self.callback_settings.index_callbacks
+            || !self.callback_settings.reindex_after_callbacks
+        {
+            return;
+        }
+        let defined_callbacks = self.callback_settings.defined_callbacks;
+        let mut indexed = HashSet::<*const ()>::default();
+        for script_id in script_ids {
+            let Some(script) = self.scripts.scripts.get(script_id) else {
+                continue;
+            };
+            if indexed.insert(Arc::as_ptr(&script.context) as *const ()) {
+                self.scripts.index_callbacks(script_id, defined_callbacks);
+            }
+        }
+    }
+
     /// Equivalent to [`Self::call`] but with a dynamically passed in label
     pub fn call_dynamic_label(
         &self,
@@ -209,6 +242,37 @@ impl<P: IntoScriptPluginParams> HandlerContext<'_, P> {
         entity: Entity,
         payload: Vec<ScriptValue>,
         guard: WorldGuard<'_>,
+    ) -> Result<ScriptValue, ScriptError> {
+        self.call_with_continuation(label, script_id, entity, None, payload, guard)
+    }
+
+    /// Resumes a callback which suspended itself, passing the payload in as the result of the yield.
+    ///
+    /// If the callback suspends itself again it is stored until it's ready to be resumed once more.
+    pub fn resume(
+        &self,
+        suspended: SuspendedCallback,
+        payload: Vec<ScriptValue>,
+        guard: WorldGuard<'_>,
+    ) -> Result<ScriptValue, ScriptError> {
+        self.call_with_continuation(
+            &suspended.callback,
+            &suspended.script_id,
+            suspended.entity,
+            Some(suspended.continuation),
+            payload,
+            guard,
+        )
+    }
+
+    fn call_with_continuation(
+        &self,
+        label: &CallbackLabel,
+        script_id: &ScriptId,
+        entity: Entity,
+        continuation: Option<ScriptContinuation>,
+        payload: Vec<ScriptValue>,
+        guard: WorldGuard<'_>,
     ) -> Result<ScriptValue, ScriptError> {
         // find script
         let script = match self.scripts.scripts.get(script_id) {
@@ -225,8 +289,11 @@ impl<P: IntoScriptPluginParams> HandlerContext<'_, P> {
 
         let mut context = script.context.lock();
 
-        CallbackSettings::<P>::call(
-            handler,
+        CallbackSettings::<P>::call_with(
+            CallbackInvocation::new(handler)
+                .with_execution_budget(self.callback_settings.execution_budget)
+                .with_continuation(continuation)
+                .with_scripts(&self.scripts),
             payload,
             entity,
             script_id,
@@ -253,6 +320,64 @@ impl<P: IntoScriptPluginParams> HandlerContext<'_, P> {
     }
 }
 
+/// A system parameter for calling functions defined in scripts immediately, from within Rust systems.
+///
+/// Arguments are converted via [`IntoScriptArgs`], i.e. tuples of [`crate::bindings::function::into::IntoScript`] values, and the result via [`FromScript`]:
+/// ```rust,ignore
+/// fn ask_script(caller: ScriptCaller<LuaScriptingPlugin>) {
+///     let damage: i64 = caller
+///         .call(&"combat.lua".into(), Entity::from_raw(0), "compute_damage", (10, 2.5))
+///         .unwrap();
+/// }
+/// ```
+///
+/// Like [`WithWorldGuard`], which it wraps, this must be the only top-level system param.
+#[derive(SystemParam)]
+pub struct ScriptCaller<'w, 's, P: IntoScriptPluginParams> {
+    inner: WithWorldGuard<'w, 's, HandlerContext<'static, P>>,
+}
+
+impl<'w, P: IntoScriptPluginParams> ScriptCaller<'w, '_, P> {
+    /// Get the world guard scripts are called with
+    pub fn world(&self) -> WorldGuard<'w> {
+        self.inner.get().0
+    }
+
+    /// checks if the script is loaded such that it can be called.
+    pub fn is_script_fully_loaded(&self, script_id: ScriptId) -> bool {
+        self.inner.get().1.is_script_fully_loaded(script_id)
+    }
+
+    /// Calls the function with the given name on the script with the given ID immediately, converting the arguments and result.
+    ///
+    /// The `entity` is passed to the script as its `entity` global, use `Entity::from_raw(0)` for static scripts.
+    ///
+    /// Interop errors are preserved inside the returned [`ScriptError`] and can be retrieved with [`ScriptError::downcast_interop_inner`], notably:
+    /// - [`crate::error::InteropErrorInner::MissingScript`] or [`crate::error::InteropErrorInner::MissingContext`] while the script is loading
+    /// - [`crate::error::InteropErrorInner::FunctionArgConversionError`] if an argument could not be converted, naming the index of the argument
+    /// - conversion errors if the result could not be converted to `O`
+    pub fn call<O: FromScript>(
+        &self,
+        script_id: &ScriptId,
+        entity: Entity,
+        function: impl Into<CallbackLabel>,
+        args: impl IntoScriptArgs,
+    ) -> Result<O::This<'w>, ScriptError> {
+        let (guard, handler_ctxt) = self.inner.get();
+        let function = function.into();
+        let args = args
+            .into_script_args(guard.clone())
+            .map_err(|e| ScriptError::from(e).with_script(script_id))?;
+        let result =
+            handler_ctxt.call_dynamic_label(&function, script_id, entity, args, guard.clone())?;
+        O::from_script(result, guard).map_err(|e| {
+            ScriptError::from(e)
+                .with_script(script_id)
+                .with_context(format!("converting the result of `{function}`"))
+        })
+    }
+}
+
 /// A wrapper around a world which pre-populates access, to safely co-exist with other system params,
 /// acts exactly like `&mut World` so this should be your only top-level system param
 ///
@@ -275,7 +400,7 @@ impl<'w, 's, T: SystemParam> WithWorldGuard<'w, 's, T> {
 }
 
 unsafe impl<T: SystemParam> SystemParam for WithWorldGuard<'_, '_, T> {
-    type State = (T::State, Vec<(ReflectAccessId, bool)>);
+    type State = (T::State, Vec<(ReflectAccessId, bool)>, ScriptCommandQueue);
 
     type Item<'world, 'state> = WithWorldGuard<'world, 'state, T>;
 
@@ -305,7 +430,9 @@ unsafe impl<T: SystemParam> SystemParam for WithWorldGuard<'_, '_, T> {
         // Safety: not removing any accesses
         unsafe { system_meta.component_access_set_mut().write_all() }
         unsafe { system_meta.archetype_component_access_mut().write_all() }
-        (inner_state, access_ids)
+        // commands queued by scripts are applied along with the other deferred operations of the system
+        system_meta.set_has_deferred();
+        (inner_state, access_ids, ScriptCommandQueue::default())
     }
 
     unsafe fn get_param<'world, 'state>(
@@ -315,7 +442,8 @@ unsafe impl<T: SystemParam> SystemParam for WithWorldGuard<'_, '_, T> {
         change_tick: bevy::ecs::component::Tick,
     ) -> Self::Item<'world, 'state> {
         // create a guard which can only access the resources/components specified by the system.
-        let guard = WorldAccessGuard::new_exclusive(world.world_mut());
+        let guard =
+            WorldAccessGuard::new_exclusive(world.world_mut()).with_command_queue(state.2.clone());
 
         #[allow(
             clippy::panic,
@@ -350,7 +478,9 @@ unsafe impl<T: SystemParam> SystemParam for WithWorldGuard<'_, '_, T> {
         system_meta: &bevy::ecs::system::SystemMeta,
         world: &mut World,
     ) {
-        T::apply(&mut state.0, system_meta, world)
+        T::apply(&mut state.0, system_meta, world);
+        // the inner param might have put resources needed by the commands back into the world
+        state.2.apply(world);
     }
 
     fn queue(
@@ -495,6 +625,30 @@ mod test {
         app.update();
     }
 
+    #[test]
+    pub fn with_world_guard_only_applies_its_own_commands() {
+        let mut world = World::new();
+        let mut first = SystemState::<WithWorldGuard<()>>::new(&mut world);
+        let mut second = SystemState::<WithWorldGuard<()>>::new(&mut world);
+
+        let entity = {
+            let guard = first.get_mut(&mut world);
+            let (guard, _) = guard.get();
+            let entity = guard.queue_spawn().unwrap();
+            guard.queue_command(move |world: &mut World| {
+                world.entity_mut(entity).insert(Comp);
+            });
+            entity
+        };
+
+        // the sync point of another system leaves the commands alone
+        second.apply(&mut world);
+        assert!(world.get::<Comp>(entity).is_none());
+
+        first.apply(&mut world);
+        assert!(world.get::<Comp>(entity).is_some());
+    }
+
     #[test]
     pub fn resscope_reinserts_resource() {
         // apply deffered system should be inserted after the system automatically
@@ -1,28 +1,38 @@
 //! Contains the logic for handling script callback events
 use crate::{
+    asset::{ScriptAsset, ScriptMetadataStore},
     bindings::{
-        pretty_print::DisplayWithWorld, script_value::ScriptValue, ThreadWorldContainer,
-        WorldContainer, WorldGuard,
+        pretty_print::DisplayWithWorld, query::ScriptQueryBuilder, script_value::ScriptValue,
+        ThreadWorldContainer, WorldContainer, WorldGuard,
     },
     context::ContextPreHandlingInitializer,
-    error::{InteropErrorInner, ScriptError},
+    coroutine::{ScriptContinuation, ThreadScriptCoroutine},
+    error::{InteropError, InteropErrorInner, ScriptError},
     event::{
         CallbackLabel, IntoCallbackLabel, ScriptCallbackEvent, ScriptCallbackResponseEvent,
         ScriptErrorEvent,
     },
     extractors::{HandlerContext, WithWorldGuard},
-    script::{ScriptComponent, ScriptId},
+    script::{ScriptComponent, ScriptId, Scripts},
     IntoScriptPluginParams,
 };
 use bevy::{
+    asset::Assets,
     ecs::{
-        entity::Entity,
+        entity::{Entity, EntityHashSet},
         query::QueryState,
         system::{Local, Resource, SystemState},
         world::{Mut, World},
     },
     log::trace_once,
     prelude::{Events, Ref},
+    tasks::{ComputeTaskPool, TaskPool},
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 /// A function that handles a callback event
@@ -36,17 +46,41 @@ pub type HandlerFn<P> = fn(
     runtime: &<P as IntoScriptPluginParams>::R,
 ) -> Result<ScriptValue, ScriptError>;
 
+/// A function that lists the callbacks defined in a context, or returns `None` if they cannot be known ahead of time.
+///
+/// Used to build the [`crate::script::CallbackIndex`], so that events are only dispatched to scripts which can handle them.
+pub type DefinedCallbacksFn<P> =
+    fn(context: &<P as IntoScriptPluginParams>::C) -> Option<Vec<CallbackLabel>>;
+
 /// A resource that holds the settings for the callback handler for a specific combination of type parameters
 #[derive(Resource)]
 pub struct CallbackSettings<P: IntoScriptPluginParams> {
     /// The callback handler function
     pub callback_handler: HandlerFn<P>,
+    /// The budget each callback invocation is allowed to use up before being aborted
+    pub execution_budget: ExecutionBudget,
+    /// Lists the callbacks defined in a context, run whenever a context is loaded or reloaded
+    pub defined_callbacks: DefinedCallbacksFn<P>,
+    /// Whether [`Self::defined_callbacks`] is used to index the callbacks of each script, on by default.
+    pub index_callbacks: bool,
+    /// Whether the callbacks of a context are indexed again after any of its callbacks run, on by default.
+    ///
+    /// Needed if callbacks can define new callbacks, i.e. Lua functions assigned to globals, otherwise these would never be called.
+    /// Only the contexts of scripts which ran a callback are indexed again.
+    pub reindex_after_callbacks: bool,
+    /// Whether callbacks of scripts with different contexts run in parallel
+    pub parallel_callbacks: bool,
 }
 
 impl<P: IntoScriptPluginParams> Default for CallbackSettings<P> {
     fn default() -> Self {
         Self {
             callback_handler: |_, _, _, _, _, _, _| Ok(ScriptValue::Unit),
+            execution_budget: Default::default(),
+            defined_callbacks: |_| None,
+            index_callbacks: true,
+            reindex_after_callbacks: true,
+            parallel_callbacks: false,
         }
     }
 }
@@ -55,6 +89,11 @@ impl<P: IntoScriptPluginParams> Clone for CallbackSettings<P> {
     fn clone(&self) -> Self {
         Self {
             callback_handler: self.callback_handler,
+            execution_budget: self.execution_budget,
+            defined_callbacks: self.defined_callbacks,
+            index_callbacks: self.index_callbacks,
+            reindex_after_callbacks: self.reindex_after_callbacks,
+            parallel_callbacks: self.parallel_callbacks,
         }
     }
 }
@@ -63,10 +102,33 @@ impl<P: IntoScriptPluginParams> Clone for CallbackSettings<P> {
 impl<P: IntoScriptPluginParams> CallbackSettings<P> {
     /// Creates a new callback settings resource with the given handler function
     pub fn new(callback_handler: HandlerFn<P>) -> Self {
-        Self { callback_handler }
+        Self {
+            callback_handler,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the budget each callback invocation is allowed to use up
+    pub fn with_execution_budget(mut self, execution_budget: ExecutionBudget) -> Self {
+        self.execution_budget = execution_budget;
+        self
+    }
+
+    /// Runs the callbacks of scripts with different contexts in parallel on the [`ComputeTaskPool`]
+    pub fn with_parallel_callbacks(mut self) -> Self {
+        self.parallel_callbacks = true;
+        self
+    }
+
+    /// Sets the function used to list the callbacks defined in a context
+    pub fn with_defined_callbacks(mut self, defined_callbacks: DefinedCallbacksFn<P>) -> Self {
+        self.defined_callbacks = defined_callbacks;
+        self
     }
 
     /// Calls the handler function while providing the necessary thread local context
+    ///
+    /// The callback runs without a budget, see [`Self::call_with`] for control over how the callback is invoked.
     pub fn call(
         handler: HandlerFn<P>,
         args: Vec<ScriptValue>,
@@ -78,31 +140,264 @@ impl<P: IntoScriptPluginParams> CallbackSettings<P> {
         runtime: &P::R,
         world: WorldGuard,
     ) -> Result<ScriptValue, ScriptError> {
-        WorldGuard::with_existing_static_guard(world.clone(), |world| {
+        Self::call_with(
+            CallbackInvocation::new(handler),
+            args,
+            entity,
+            script_id,
+            callback,
+            script_ctxt,
+            pre_handling_initializers,
+            runtime,
+            world,
+        )
+    }
+
+    /// Calls the handler function of the invocation while providing the necessary thread local context.
+    ///
+    /// If the invocation has a continuation, the handler resumes it instead of calling the callback from the start.
+    /// If the callback suspends itself, it is stored in the scripts of the invocation until it's resumed, and unit is returned.
+    ///
+    /// If the handler is aborted for going over the budget of the invocation, the error will be a [`InteropErrorInner::ExecutionBudgetExceeded`] error.
+    pub fn call_with(
+        invocation: CallbackInvocation<'_, P>,
+        args: Vec<ScriptValue>,
+        entity: Entity,
+        script_id: &ScriptId,
+        callback: &CallbackLabel,
+        script_ctxt: &mut P::C,
+        pre_handling_initializers: &[ContextPreHandlingInitializer<P>],
+        runtime: &P::R,
+        world: WorldGuard,
+    ) -> Result<ScriptValue, ScriptError> {
+        let CallbackInvocation {
+            handler,
+            execution_budget,
+            continuation,
+            scripts,
+        } = invocation;
+        let world = world.with_script_capabilities(script_id);
+        WorldGuard::with_existing_static_guard(world, |world| {
             ThreadWorldContainer.set_world(world)?;
-            (handler)(
-                args,
-                entity,
-                script_id,
-                callback,
-                script_ctxt,
-                pre_handling_initializers,
-                runtime,
-            )
+            let (out, suspended) = ThreadScriptCoroutine::with_continuation(continuation, || {
+                ThreadExecutionBudget::with_budget(execution_budget, script_id, callback, || {
+                    (handler)(
+                        args,
+                        entity,
+                        script_id,
+                        callback,
+                        script_ctxt,
+                        pre_handling_initializers,
+                        runtime,
+                    )
+                })
+            });
+            let out = out?;
+
+            match (suspended, scripts) {
+                (Some((condition, continuation)), Some(scripts)) => {
+                    scripts.suspended_callbacks().suspend(
+                        script_id.clone(),
+                        entity,
+                        callback.clone(),
+                        condition,
+                        continuation,
+                    );
+                    Ok(ScriptValue::Unit)
+                }
+                (Some(_), None) => Err(InteropError::unsupported_operation(
+                    None,
+                    None,
+                    "suspending a callback which was called without anywhere to store it",
+                )
+                .into()),
+                (None, _) => Ok(out),
+            }
         })
     }
 }
 
-macro_rules! push_err_and_continue {
-    ($errors:ident, $expr:expr) => {
-        match $expr {
-            Ok(v) => v,
-            Err(e) => {
-                $errors.push(e);
-                continue;
-            }
+/// How a callback is invoked by [`CallbackSettings::call_with`], besides the arguments passed to the handler
+pub struct CallbackInvocation<'a, P: IntoScriptPluginParams> {
+    /// The callback handler function
+    pub handler: HandlerFn<P>,
+    /// The budget the callback is allowed to use up before being aborted
+    pub execution_budget: ExecutionBudget,
+    /// The continuation of a suspended callback, resumed instead of calling the callback from the start
+    pub continuation: Option<ScriptContinuation>,
+    /// The scripts the callback is stored in if it suspends itself, without them suspending the callback is an error
+    pub scripts: Option<&'a Scripts<P>>,
+}
+
+impl<'a, P: IntoScriptPluginParams> CallbackInvocation<'a, P> {
+    /// Creates a new invocation of the given handler, without a budget, continuation or scripts to store suspended callbacks in
+    pub fn new(handler: HandlerFn<P>) -> Self {
+        Self {
+            handler,
+            execution_budget: ExecutionBudget::unlimited(),
+            continuation: None,
+            scripts: None,
         }
-    };
+    }
+
+    /// Sets the budget the callback is allowed to use up
+    pub fn with_execution_budget(mut self, execution_budget: ExecutionBudget) -> Self {
+        self.execution_budget = execution_budget;
+        self
+    }
+
+    /// Resumes the given continuation instead of calling the callback from the start
+    pub fn with_continuation(mut self, continuation: Option<ScriptContinuation>) -> Self {
+        self.continuation = continuation;
+        self
+    }
+
+    /// Stores the callback in the given scripts if it suspends itself
+    pub fn with_scripts(mut self, scripts: &'a Scripts<P>) -> Self {
+        self.scripts = Some(scripts);
+        self
+    }
+}
+
+/// Limits on the amount of work a single callback invocation can do before it is aborted.
+///
+/// Language implementations are responsible for periodically reporting progress via [`ThreadExecutionBudget::consume`],
+/// what counts as an instruction is language specific.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionBudget {
+    /// The maximum number of instructions a callback can execute, if any
+    pub max_instructions: Option<u64>,
+    /// The maximum amount of time a callback can run for, if any
+    pub max_duration: Option<Duration>,
+}
+
+impl ExecutionBudget {
+    /// A budget which never runs out
+    pub const fn unlimited() -> Self {
+        Self {
+            max_instructions: None,
+            max_duration: None,
+        }
+    }
+
+    /// Limits the number of instructions a callback can execute
+    pub const fn with_max_instructions(mut self, max_instructions: u64) -> Self {
+        self.max_instructions = Some(max_instructions);
+        self
+    }
+
+    /// Limits the amount of time a callback can run for
+    pub const fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Returns true if neither limit is set
+    pub const fn is_unlimited(&self) -> bool {
+        self.max_instructions.is_none() && self.max_duration.is_none()
+    }
+}
+
+/// The progress of the callback currently running on this thread against its budget
+struct ActiveExecutionBudget {
+    budget: ExecutionBudget,
+    script_id: ScriptId,
+    callback: CallbackLabel,
+    started: Instant,
+    instructions: u64,
+    exceeded: Option<InteropError>,
+}
+
+/// How many instructions can pass between checks of the elapsed time, checking the clock on every instruction is expensive
+const DURATION_CHECK_INTERVAL: u64 = 64;
+
+thread_local! {
+    static ACTIVE_EXECUTION_BUDGET: RefCell<Option<ActiveExecutionBudget>> = const { RefCell::new(None) };
+}
+
+/// Tracks the [`ExecutionBudget`] of the callback currently running on this thread.
+///
+/// Set up by [`CallbackSettings::call`], language implementations use it to abort scripts which run over budget.
+pub struct ThreadExecutionBudget;
+
+#[profiling::all_functions]
+impl ThreadExecutionBudget {
+    /// Runs the closure with the given budget active for the given script and callback.
+    ///
+    /// If the closure fails after running over budget, the error is replaced by the budget error.
+    /// Any budget active beforehand is restored afterwards, so nested callbacks are tracked separately.
+    pub fn with_budget<O>(
+        budget: ExecutionBudget,
+        script_id: &ScriptId,
+        callback: &CallbackLabel,
+        f: impl FnOnce() -> Result<O, ScriptError>,
+    ) -> Result<O, ScriptError> {
+        let active = (!budget.is_unlimited()).then(|| ActiveExecutionBudget {
+            budget,
+            script_id: script_id.clone(),
+            callback: callback.clone(),
+            started: Instant::now(),
+            instructions: 0,
+            exceeded: None,
+        });
+        let previous = ACTIVE_EXECUTION_BUDGET.with(|b| b.replace(active));
+        let out = f();
+        let active = ACTIVE_EXECUTION_BUDGET.with(|b| b.replace(previous));
+
+        match (out, active.and_then(|a| a.exceeded)) {
+            (Err(_), Some(exceeded)) => Err(exceeded.into()),
+            (out, _) => out,
+        }
+    }
+
+    /// Returns the budget of the callback currently running on this thread, if it is limited
+    pub fn current() -> Option<ExecutionBudget> {
+        ACTIVE_EXECUTION_BUDGET.with(|b| b.borrow().as_ref().map(|a| a.budget))
+    }
+
+    /// Records that the running callback executed the given number of instructions.
+    ///
+    /// Returns an error if the callback is now over budget, in which case the language implementation should abort the callback as soon as possible.
+    pub fn consume(instructions: u64) -> Result<(), InteropError> {
+        ACTIVE_EXECUTION_BUDGET.with(|b| {
+            let mut active = b.borrow_mut();
+            let Some(active) = active.as_mut() else {
+                return Ok(());
+            };
+
+            if let Some(exceeded) = &active.exceeded {
+                return Err(exceeded.clone());
+            }
+
+            let previous = active.instructions;
+            active.instructions = previous.saturating_add(instructions);
+            let over_instructions = active
+                .budget
+                .max_instructions
+                .is_some_and(|max| active.instructions > max);
+            let check_duration = instructions == 0
+                || previous / DURATION_CHECK_INTERVAL
+                    != active.instructions / DURATION_CHECK_INTERVAL;
+            let over_duration = check_duration
+                && active
+                    .budget
+                    .max_duration
+                    .is_some_and(|max| active.started.elapsed() > max);
+
+            if over_instructions || over_duration {
+                let error = InteropError::execution_budget_exceeded(
+                    active.script_id.clone(),
+                    active.callback.clone(),
+                    active.budget,
+                    active.instructions,
+                    active.started.elapsed(),
+                );
+                active.exceeded = Some(error.clone());
+                return Err(error);
+            }
+            Ok(())
+        })
+    }
 }
 
 /// Passes events with the specified label to the script callback with the same name and runs the callback.
@@ -116,17 +411,65 @@ pub fn event_handler<L: IntoCallbackLabel, P: IntoScriptPluginParams>(
     // we wrap the inner event handler, so that we can immediately re-insert all the resources back.
     // otherwise this would happen in the next schedule
     {
-        let (entity_query_state, script_events, handler_ctxt) = state.get_mut(world);
+        let (mut entity_query_state, mut script_events, mut handler_ctxt) = state.get_mut(world);
+        let callback_label = L::into_callback_label();
+        let events = script_events
+            .read()
+            .filter(|e| e.label == callback_label)
+            .cloned()
+            .collect::<Vec<_>>();
         event_handler_inner::<P>(
-            L::into_callback_label(),
-            entity_query_state,
-            script_events,
-            handler_ctxt,
+            callback_label,
+            events,
+            &mut entity_query_state,
+            &mut handler_ctxt,
         );
     }
     state.apply(world);
 }
 
+/// Passes events sent on transitions of states registered with [`crate::RegisterScriptState`] to the `on_enter_<state>_<value>` and `on_exit_<state>_<value>` callbacks of scripts.
+///
+/// Added to the `StateTransition` schedule by the scripting plugins if any states are registered.
+#[cfg(feature = "bevy_state")]
+#[allow(deprecated)]
+pub fn state_transition_handler<P: IntoScriptPluginParams>(
+    world: &mut World,
+    state: &mut EventHandlerSystemState<P>,
+) {
+    let Some(registry) = world
+        .get_resource::<crate::bindings::AppStateRegistry>()
+        .cloned()
+    else {
+        return;
+    };
+    {
+        let (mut entity_query_state, mut script_events, mut handler_ctxt) = state.get_mut(world);
+        let registry = registry.read();
+        // events are handled in order, grouping consecutive events with the same label
+        let mut groups: Vec<(CallbackLabel, Vec<ScriptCallbackEvent>)> = Vec::default();
+        for event in script_events
+            .read()
+            .filter(|e| registry.is_state_callback(&e.label))
+        {
+            match groups.last_mut() {
+                Some((label, events)) if label == &event.label => events.push(event.clone()),
+                _ => groups.push((event.label.clone(), vec![event.clone()])),
+            }
+        }
+        drop(registry);
+        for (callback_label, events) in groups {
+            event_handler_inner::<P>(
+                callback_label,
+                events,
+                &mut entity_query_state,
+                &mut handler_ctxt,
+            );
+        }
+    }
+    state.apply(world);
+}
+
 #[allow(deprecated)]
 pub(crate) type EventHandlerSystemState<'w, 's, P> = SystemState<(
     Local<'s, QueryState<(Entity, Ref<'w, ScriptComponent>)>>,
@@ -138,26 +481,53 @@ pub(crate) type EventHandlerSystemState<'w, 's, P> = SystemState<(
 #[allow(deprecated)]
 pub(crate) fn event_handler_inner<P: IntoScriptPluginParams>(
     callback_label: CallbackLabel,
-    mut entity_query_state: Local<QueryState<(Entity, Ref<ScriptComponent>)>>,
-    mut script_events: crate::extractors::EventReaderScope<ScriptCallbackEvent>,
-    mut handler_ctxt: WithWorldGuard<HandlerContext<P>>,
+    events: Vec<ScriptCallbackEvent>,
+    entity_query_state: &mut QueryState<(Entity, Ref<ScriptComponent>)>,
+    handler_ctxt: &mut WithWorldGuard<HandlerContext<P>>,
 ) {
     let (guard, handler_ctxt) = handler_ctxt.get_mut();
 
     let mut errors = Vec::default();
 
-    let events = script_events.read().cloned().collect::<Vec<_>>();
+    if events.is_empty() {
+        return;
+    }
+
+    // only visit scripts which define the callback, or have suspended callbacks waiting on it
+    let waiting = handler_ctxt
+        .scripts
+        .suspended_callbacks()
+        .scripts_waiting_for_event(&callback_label);
+    let can_handle = |script_id: &ScriptId| {
+        handler_ctxt.scripts.scripts.contains_key(script_id)
+            && (waiting.contains(script_id)
+                || handler_ctxt
+                    .scripts
+                    .callback_index()
+                    .may_handle(script_id, &callback_label))
+    };
+    if !handler_ctxt.scripts.scripts.keys().any(can_handle) {
+        return;
+    }
 
     // query entities + chain static scripts
     let entity_and_static_scripts = guard.with_global_access(|world| {
         entity_query_state
             .iter(world)
-            .map(|(e, s)| (e, s.0.clone()))
+            .filter_map(|(e, s)| {
+                let scripts = s
+                    .iter()
+                    .filter(|id| can_handle(id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (!scripts.is_empty()).then_some((e, scripts))
+            })
             .chain(
                 handler_ctxt
                     .static_scripts
                     .scripts
                     .iter()
+                    .filter(|s| can_handle(s))
                     .map(|s| (Entity::from_raw(0), vec![s.clone()])),
             )
             .collect::<Vec<_>>()
@@ -175,78 +545,176 @@ pub(crate) fn event_handler_inner<P: IntoScriptPluginParams>(
         }
     };
 
-    for event in events.into_iter().filter(|e| e.label == callback_label) {
+    // the callbacks to run, in order
+    let mut targets = Vec::default();
+    for event in events.iter() {
+        // queries are only run once per event
+        let mut query_matches = HashMap::<*const ScriptQueryBuilder, EntityHashSet>::default();
+        let mut matches_query = |query: &ScriptQueryBuilder, entity: Entity| {
+            query_matches
+                .entry(query as *const _)
+                .or_insert_with(|| match guard.query(query.clone()) {
+                    Ok(results) => results.into_iter().map(|r| r.entity).collect(),
+                    Err(e) => {
+                        errors.push(e.into());
+                        Default::default()
+                    }
+                })
+                .contains(&entity)
+        };
         for (entity, entity_scripts) in entity_and_static_scripts.iter() {
             for script_id in entity_scripts.iter() {
-                match &event.recipients {
-                    crate::event::Recipients::Script(target_script_id)
-                        if target_script_id != script_id =>
-                    {
-                        continue
-                    }
-                    crate::event::Recipients::Entity(target_entity) if target_entity != entity => {
-                        continue
-                    }
-                    crate::event::Recipients::Language(target_language)
-                        if *target_language != P::LANGUAGE =>
-                    {
-                        continue
-                    }
-                    _ => {}
+                if event
+                    .recipients
+                    .contains(*entity, script_id, &P::LANGUAGE, &mut matches_query)
+                {
+                    targets.push((event, *entity, script_id));
                 }
+            }
+        }
+    }
 
-                let call_result = handler_ctxt.call_dynamic_label(
-                    &callback_label,
-                    script_id,
-                    *entity,
-                    event.args.clone(),
-                    guard.clone(),
-                );
+    // callbacks can define new callbacks, so the contexts which ran are indexed again afterwards
+    let ran = targets
+        .iter()
+        .map(|(_, _, script_id)| *script_id)
+        .collect::<Vec<_>>();
 
-                if event.trigger_response {
-                    send_callback_response(
-                        guard.clone(),
-                        ScriptCallbackResponseEvent::new(
-                            callback_label.clone(),
-                            script_id.clone(),
-                            call_result.clone(),
-                        ),
-                    );
-                }
+    if handler_ctxt.callback_settings.parallel_callbacks && targets.len() > 1 {
+        // callbacks sharing a context run one after another in the same task
+        let mut groups: Vec<Vec<_>> = Vec::default();
+        let mut group_of_context = HashMap::<*const (), usize>::default();
+        for target in targets {
+            let Some(script) = handler_ctxt.scripts.scripts.get(target.2) else {
+                continue;
+            };
+            let context = Arc::as_ptr(&script.context) as *const ();
+            let group = *group_of_context.entry(context).or_insert_with(|| {
+                groups.push(Vec::default());
+                groups.len() - 1
+            });
+            groups[group].push(target);
+        }
 
-                match call_result {
-                    Ok(_) => {}
-                    Err(e) => {
-                        match e.downcast_interop_inner() {
-                            Some(InteropErrorInner::MissingScript { script_id }) => {
-                                trace_once!(
-                                "{}: Script `{}` on entity `{:?}` is either still loading, doesn't exist, or is for another language, ignoring until the corresponding script is loaded.",
-                                P::LANGUAGE,
-                                script_id, entity
-                            );
-                                continue;
-                            }
-                            Some(InteropErrorInner::MissingContext { .. }) => {
-                                // if we don't have a context for the script, it's either:
-                                // 1. a script for a different language, in which case we ignore it
-                                // 2. something went wrong. This should not happen though and it's best we ignore this
-                                continue;
-                            }
-                            _ => {}
+        let handler_ctxt = &*handler_ctxt;
+        let callback_label = &callback_label;
+        let results = guard.with_parallel_access(|| {
+            ComputeTaskPool::get_or_init(TaskPool::default).scope(|scope| {
+                for group in groups {
+                    let guard = guard.clone();
+                    scope.spawn(async move {
+                        let mut responses = Vec::default();
+                        let mut errors = Vec::default();
+                        for (event, entity, script_id) in group {
+                            responses.extend(handle_event_for_script(
+                                handler_ctxt,
+                                callback_label,
+                                event,
+                                entity,
+                                script_id,
+                                guard.clone(),
+                                &mut errors,
+                            ));
                         }
-                        let e = e
-                            .with_script(script_id.clone())
-                            .with_context(format!("Event handling for: Language: {}", P::LANGUAGE));
-                        push_err_and_continue!(errors, Err(e));
-                    }
-                };
+                        (responses, errors)
+                    });
+                }
+            })
+        });
+
+        for (responses, task_errors) in results {
+            for response in responses {
+                send_callback_response(guard.clone(), response);
+            }
+            errors.extend(task_errors);
+        }
+    } else {
+        for (event, entity, script_id) in targets {
+            if let Some(response) = handle_event_for_script(
+                handler_ctxt,
+                &callback_label,
+                event,
+                entity,
+                script_id,
+                guard.clone(),
+                &mut errors,
+            ) {
+                send_callback_response(guard.clone(), response);
             }
         }
     }
 
+    handler_ctxt.reindex_callbacks_after_running(ran);
+
     handle_script_errors(guard, errors.into_iter());
 }
 
+/// Runs the callback for a single event on a single script, resuming any callbacks of the script waiting on the event first.
+///
+/// Errors are pushed to `errors`, the response is returned if the event requested one.
+fn handle_event_for_script<P: IntoScriptPluginParams>(
+    handler_ctxt: &HandlerContext<P>,
+    callback_label: &CallbackLabel,
+    event: &ScriptCallbackEvent,
+    entity: Entity,
+    script_id: &ScriptId,
+    guard: WorldGuard,
+    errors: &mut Vec<ScriptError>,
+) -> Option<ScriptCallbackResponseEvent> {
+    // resume callbacks which were waiting on this event before running new ones, so they don't immediately resume
+    let waiting = handler_ctxt
+        .scripts
+        .suspended_callbacks()
+        .take_waiting_for_event(callback_label, script_id, entity);
+    for suspended in waiting {
+        if let Err(e) = handler_ctxt.resume(suspended, event.args.clone(), guard.clone()) {
+            errors.push(e.with_script(script_id.clone()).with_context(format!(
+                "Resuming suspended callback for: Language: {}",
+                P::LANGUAGE
+            )));
+        }
+    }
+
+    let call_result = handler_ctxt.call_dynamic_label(
+        callback_label,
+        script_id,
+        entity,
+        event.args.clone(),
+        guard,
+    );
+
+    let response = event.trigger_response.then(|| {
+        ScriptCallbackResponseEvent::new(
+            callback_label.clone(),
+            script_id.clone(),
+            call_result.clone(),
+        )
+    });
+
+    if let Err(e) = call_result {
+        match e.downcast_interop_inner() {
+            Some(InteropErrorInner::MissingScript { script_id }) => {
+                trace_once!(
+                    "{}: Script `{}` on entity `{:?}` is either still loading, doesn't exist, or is for another language, ignoring until the corresponding script is loaded.",
+                    P::LANGUAGE,
+                    script_id, entity
+                );
+            }
+            Some(InteropErrorInner::MissingContext { .. }) => {
+                // if we don't have a context for the script, it's either:
+                // 1. a script for a different language, in which case we ignore it
+                // 2. something went wrong. This should not happen though and it's best we ignore this
+            }
+            _ => errors.push(
+                e.with_script(script_id.clone())
+                    .with_context(format!("Event handling for: Language: {}", P::LANGUAGE)),
+            ),
+        }
+    }
+
+    response
+}
+
 /// Sends a callback response event to the world
 pub fn send_callback_response(world: WorldGuard, response: ScriptCallbackResponseEvent) {
     let err = world.with_resource_mut(|mut events: Mut<Events<ScriptCallbackResponseEvent>>| {
@@ -263,6 +731,10 @@ pub fn send_callback_response(world: WorldGuard, response: ScriptCallbackRespons
 
 /// Handles errors caused by script execution and sends them to the error event channel
 pub fn handle_script_errors<I: Iterator<Item = ScriptError> + Clone>(world: WorldGuard, errors: I) {
+    let errors = errors
+        .map(|error| with_original_lines(&world, error))
+        .collect::<Vec<_>>()
+        .into_iter();
     let err = world.with_resource_mut(|mut error_events: Mut<Events<ScriptErrorEvent>>| {
         for error in errors.clone() {
             error_events.send(ScriptErrorEvent { error });
@@ -281,16 +753,57 @@ pub fn handle_script_errors<I: Iterator<Item = ScriptError> + Clone>(world: Worl
     }
 }
 
+/// Maps line numbers in errors coming from preprocessed script assets, back to the lines of the original script
+fn with_original_lines(world: &WorldGuard, error: ScriptError) -> ScriptError {
+    let Some(script_id) = error.script.clone() else {
+        return error;
+    };
+    let asset_id = world
+        .with_resource(|store: &ScriptMetadataStore| {
+            store
+                .get_by_script_id(&ScriptId::from(script_id.clone()))
+                .map(|meta| meta.asset_id)
+        })
+        .ok()
+        .flatten();
+    let source_map = asset_id.and_then(|asset_id| {
+        world
+            .with_resource(|assets: &Assets<ScriptAsset>| {
+                assets
+                    .get(asset_id)
+                    .and_then(|asset| asset.source_map.clone())
+            })
+            .ok()
+            .flatten()
+    });
+    match source_map {
+        // scripts are loaded under their script id as the chunk name
+        Some(source_map) => error.with_source_map(&script_id, &source_map),
+        None => error,
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::todo)]
 mod test {
     use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
+    #[cfg(feature = "bevy_state")]
+    use bevy::{
+        app::Plugin,
+        ecs::schedule::IntoSystemConfigs,
+        prelude::IntoSystemSetConfigs,
+        reflect::Reflect,
+        state::{
+            app::{AppExtStates, StatesPlugin},
+            state::{State, StateTransition, StateTransitionSteps, States},
+        },
+    };
     use bevy::{
         app::{App, Update},
         asset::AssetPlugin,
         diagnostic::DiagnosticsPlugin,
-        ecs::world::FromWorld,
+        ecs::{system::RunSystemOnce, world::FromWorld},
     };
     use parking_lot::Mutex;
     use test_utils::make_test_plugin;
@@ -298,11 +811,18 @@ mod test {
     use crate::{
         bindings::script_value::ScriptValue,
         context::{ContextBuilder, ContextLoadingSettings},
-        event::{CallbackLabel, IntoCallbackLabel, ScriptCallbackEvent, ScriptErrorEvent},
+        event::{
+            CallbackLabel, IntoCallbackLabel, Recipients, ScriptCallbackEvent, ScriptErrorEvent,
+        },
         runtime::RuntimeContainer,
         script::{Script, ScriptComponent, ScriptId, Scripts, StaticScripts},
         BMSScriptingInfrastructurePlugin,
     };
+    #[cfg(feature = "bevy_state")]
+    use crate::{
+        bindings::{AppReflectAllocator, WorldAccessGuard},
+        RegisterScriptState, ScriptingSystemSet,
+    };
 
     use super::*;
     struct OnTestCallback;
@@ -352,9 +872,18 @@ mod test {
                 runtime.push((entity, script.clone()));
                 Ok(ScriptValue::Unit)
             },
+            execution_budget: Default::default(),
+            defined_callbacks: |_| None,
+            index_callbacks: false,
+            reindex_after_callbacks: false,
+            parallel_callbacks: false,
         });
         app.add_systems(Update, event_handler::<L, TestPlugin>);
-        app.insert_resource::<Scripts<TestPlugin>>(Scripts { scripts });
+        app.insert_resource::<Scripts<TestPlugin>>(Scripts {
+            scripts,
+            suspended: Default::default(),
+            callback_index: Default::default(),
+        });
         app.insert_resource(RuntimeContainer::<TestPlugin> { runtime });
         app.init_resource::<StaticScripts>();
         app.insert_resource(ContextLoadingSettings::<TestPlugin> {
@@ -464,6 +993,193 @@ mod test {
         assert_response_events(app.world_mut(), vec![].into_iter());
     }
 
+    #[test]
+    fn test_handler_only_called_for_scripts_defining_callback() {
+        let scripts = HashMap::from_iter(["defines", "doesnt_define", "not_indexed"].map(|id| {
+            let id = Cow::Borrowed(id);
+            (
+                id.clone(),
+                Script {
+                    id,
+                    asset: None,
+                    context: Arc::new(Mutex::new(TestContext::default())),
+                },
+            )
+        }));
+        let runtime = TestRuntime {
+            invocations: vec![].into(),
+        };
+        let mut app = setup_app::<OnTestCallback>(runtime, scripts);
+        {
+            let mut scripts = app.world_mut().resource_mut::<Scripts<TestPlugin>>();
+            scripts.index_callbacks(&"defines".into(), |_| {
+                Some(vec![OnTestCallback::into_callback_label()])
+            });
+            scripts.index_callbacks(&"doesnt_define".into(), |_| Some(vec!["on_other".into()]));
+            scripts.index_callbacks(&"not_indexed".into(), |_| None);
+        }
+        let entity = app
+            .world_mut()
+            .spawn(ScriptComponent::new([
+                "defines",
+                "doesnt_define",
+                "not_indexed",
+            ]))
+            .id();
+
+        app.world_mut().send_event(ScriptCallbackEvent::new_for_all(
+            OnTestCallback::into_callback_label(),
+            vec![],
+        ));
+        app.update();
+
+        let test_runtime = app.world().resource::<RuntimeContainer<TestPlugin>>();
+        let mut invocations = test_runtime.runtime.invocations.lock().clone();
+        invocations.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            invocations,
+            vec![
+                (entity, Cow::Borrowed("defines")),
+                (entity, Cow::Borrowed("not_indexed"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handler_reindexes_only_contexts_which_ran_callbacks() {
+        let scripts = HashMap::from_iter(["defines", "doesnt_define"].map(|id| {
+            let id = Cow::Borrowed(id);
+            (
+                id.clone(),
+                Script {
+                    id,
+                    asset: None,
+                    context: Arc::new(Mutex::new(TestContext::default())),
+                },
+            )
+        }));
+        let runtime = TestRuntime {
+            invocations: vec![].into(),
+        };
+        let mut app = setup_app::<OnTestCallback>(runtime, scripts);
+        {
+            let mut settings = app
+                .world_mut()
+                .resource_mut::<CallbackSettings<TestPlugin>>();
+            settings.index_callbacks = true;
+            settings.reindex_after_callbacks = true;
+            // running a callback defines a new one
+            settings.defined_callbacks = |context| {
+                let mut callbacks = vec![OnTestCallback::into_callback_label()];
+                if !context.invocations.is_empty() {
+                    callbacks.push("on_defined_later".into());
+                }
+                Some(callbacks)
+            };
+        }
+        {
+            let mut scripts = app.world_mut().resource_mut::<Scripts<TestPlugin>>();
+            scripts.index_callbacks(&"defines".into(), |_| {
+                Some(vec![OnTestCallback::into_callback_label()])
+            });
+            scripts.index_callbacks(&"doesnt_define".into(), |_| Some(vec!["on_other".into()]));
+        }
+        let entity = app
+            .world_mut()
+            .spawn(ScriptComponent::new(["defines", "doesnt_define"]))
+            .id();
+
+        app.world_mut().send_event(ScriptCallbackEvent::new_for_all(
+            OnTestCallback::into_callback_label(),
+            vec![ScriptValue::Unit],
+        ));
+        app.update();
+
+        let test_runtime = app.world().resource::<RuntimeContainer<TestPlugin>>();
+        assert_eq!(
+            *test_runtime.runtime.invocations.lock(),
+            vec![(entity, Cow::Borrowed("defines"))]
+        );
+
+        let index = app
+            .world()
+            .resource::<Scripts<TestPlugin>>()
+            .callback_index();
+        assert!(index.may_handle(&"defines".into(), &"on_defined_later".into()));
+        // the script which did not run is neither called nor indexed again
+        assert!(!index.may_handle(
+            &"doesnt_define".into(),
+            &OnTestCallback::into_callback_label()
+        ));
+        assert!(index.may_handle(&"doesnt_define".into(), &"on_other".into()));
+    }
+
+    #[test]
+    fn test_parallel_callbacks_run_for_every_context() {
+        let script_ids = ["script_1", "script_2", "script_3"];
+        let scripts = HashMap::from_iter(script_ids.map(|id| {
+            let id = Cow::Borrowed(id);
+            (
+                id.clone(),
+                Script {
+                    id,
+                    asset: None,
+                    context: Arc::new(Mutex::new(TestContext::default())),
+                },
+            )
+        }));
+        let runtime = TestRuntime {
+            invocations: vec![].into(),
+        };
+        let mut app = setup_app::<OnTestCallback>(runtime, scripts);
+        app.insert_resource(
+            CallbackSettings::<TestPlugin>::new(|args, entity, script, _, ctxt, _, runtime| {
+                ctxt.invocations.extend(args);
+                runtime.invocations.lock().push((entity, script.clone()));
+                // exclusive access is granted to one callback at a time
+                let world = ThreadWorldContainer.try_get_world()?;
+                world.with_global_access(|world| {
+                    world.spawn_empty();
+                })?;
+                Ok(ScriptValue::Bool(true))
+            })
+            .with_parallel_callbacks(),
+        );
+        let entity = app.world_mut().spawn(ScriptComponent::new(script_ids)).id();
+        let entities = app.world().entities().len();
+
+        app.world_mut().send_event(
+            ScriptCallbackEvent::new_for_all(OnTestCallback::into_callback_label(), vec![])
+                .with_response(),
+        );
+        app.update();
+        assert_eq!(app.world().entities().len(), entities + 3);
+
+        let test_runtime = app.world().resource::<RuntimeContainer<TestPlugin>>();
+        let mut invocations = test_runtime.runtime.invocations.lock().clone();
+        invocations.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            invocations,
+            script_ids.map(|id| (entity, Cow::Borrowed(id))).to_vec()
+        );
+
+        let mut responses = app
+            .world_mut()
+            .resource_mut::<Events<ScriptCallbackResponseEvent>>()
+            .drain()
+            .collect::<Vec<_>>();
+        responses.sort_by(|a, b| a.script.cmp(&b.script));
+        assert_eq!(
+            responses
+                .into_iter()
+                .map(|r| (r.script, r.response))
+                .collect::<Vec<_>>(),
+            script_ids
+                .map(|id| (Cow::Borrowed(id), Ok(ScriptValue::Bool(true))))
+                .to_vec()
+        );
+    }
+
     #[test]
     fn test_handler_called_on_right_recipients() {
         let test_script_id = Cow::Borrowed("test_script");
@@ -537,6 +1253,139 @@ mod test {
         assert_response_events(app.world_mut(), vec![].into_iter());
     }
 
+    #[test]
+    fn test_handler_called_on_entity_sets_queries_and_exclusions() {
+        #[derive(bevy::ecs::component::Component, bevy::reflect::Reflect)]
+        struct Marker;
+
+        let script_ids = ["script_a", "script_b", "script_c"];
+        let scripts = HashMap::from_iter(script_ids.map(|id| {
+            let id = Cow::Borrowed(id);
+            (
+                id.clone(),
+                Script {
+                    id,
+                    asset: None,
+                    context: Arc::new(Mutex::new(TestContext::default())),
+                },
+            )
+        }));
+        let runtime = TestRuntime {
+            invocations: vec![].into(),
+        };
+        let mut app = setup_app::<OnTestCallback>(runtime, scripts);
+        let [entity_a, entity_b, _] =
+            script_ids.map(|id| app.world_mut().spawn(ScriptComponent::new([id])).id());
+        app.world_mut().entity_mut(entity_b).insert(Marker);
+
+        let marker = crate::bindings::query::ScriptComponentRegistration::new(
+            crate::bindings::query::ScriptTypeRegistration::new(Arc::new(
+                bevy::reflect::TypeRegistration::of::<Marker>(),
+            )),
+            app.world_mut().register_component::<Marker>(),
+        );
+        let mut query = ScriptQueryBuilder::default();
+        query.with_component(marker);
+
+        for (arg, recipients) in [
+            ("entities", Recipients::entities([entity_a, entity_b])),
+            ("scripts", Recipients::scripts(["script_c"])),
+            ("query", Recipients::Query(query)),
+            (
+                "except",
+                Recipients::All.except(Recipients::Entity(entity_a)),
+            ),
+        ] {
+            app.world_mut().send_event(ScriptCallbackEvent::new(
+                OnTestCallback::into_callback_label(),
+                vec![ScriptValue::String(arg.into())],
+                recipients,
+            ));
+        }
+        app.update();
+
+        let test_scripts = app.world().resource::<Scripts<TestPlugin>>();
+        let invocations_of = |id: &'static str| {
+            test_scripts.scripts[&Cow::Borrowed(id)]
+                .context
+                .lock()
+                .invocations
+                .clone()
+        };
+        assert_eq!(
+            invocations_of("script_a"),
+            vec![ScriptValue::String("entities".into())]
+        );
+        assert_eq!(
+            invocations_of("script_b"),
+            vec![
+                ScriptValue::String("entities".into()),
+                ScriptValue::String("query".into()),
+                ScriptValue::String("except".into())
+            ]
+        );
+        assert_eq!(
+            invocations_of("script_c"),
+            vec![
+                ScriptValue::String("scripts".into()),
+                ScriptValue::String("except".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_script_caller_converts_arguments_and_results() {
+        let test_script_id = Cow::Borrowed("test_script");
+        let scripts = HashMap::from_iter(vec![(
+            test_script_id.clone(),
+            Script {
+                id: test_script_id.clone(),
+                asset: None,
+                context: Arc::new(Mutex::new(TestContext::default())),
+            },
+        )]);
+        let runtime = TestRuntime {
+            invocations: vec![].into(),
+        };
+        let mut app = setup_app::<OnTestCallback>(runtime, scripts);
+        app.insert_resource(CallbackSettings::<TestPlugin>::new(
+            |args, _, _, label, _, _, _| {
+                assert_eq!(label.as_ref(), "add");
+                Ok(ScriptValue::Integer(
+                    args.into_iter()
+                        .map(|arg| match arg {
+                            ScriptValue::Integer(i) => i,
+                            _ => 0,
+                        })
+                        .sum(),
+                ))
+            },
+        ));
+
+        let (sum, mismatch, missing) = app
+            .world_mut()
+            .run_system_once(|caller: crate::extractors::ScriptCaller<TestPlugin>| {
+                let script_id = Cow::Borrowed("test_script");
+                let entity = Entity::from_raw(0);
+                (
+                    caller.call::<i64>(&script_id, entity, "add", (1, 2usize)),
+                    caller.call::<bool>(&script_id, entity, "add", ()),
+                    caller.call::<i64>(&"missing".into(), entity, "add", ()),
+                )
+            })
+            .unwrap();
+
+        assert_eq!(sum, Ok(3));
+        assert!(matches!(
+            mismatch.unwrap_err().downcast_interop_inner(),
+            Some(InteropErrorInner::ValueMismatch { .. })
+        ));
+        assert!(matches!(
+            missing.unwrap_err().downcast_interop_inner(),
+            Some(InteropErrorInner::MissingScript { .. })
+        ));
+    }
+
     #[test]
     fn test_handler_called_for_static_scripts() {
         let test_script_id = Cow::Borrowed("test_script");
@@ -591,6 +1440,106 @@ mod test {
         assert_response_events(app.world_mut(), vec![].into_iter());
     }
 
+    #[cfg(feature = "bevy_state")]
+    #[derive(States, Reflect, Default, Debug, Clone, PartialEq, Eq, Hash)]
+    enum TestState {
+        #[default]
+        MainMenu,
+        InGame,
+    }
+
+    #[cfg(feature = "bevy_state")]
+    #[test]
+    fn test_state_transition_handler_calls_enter_and_exit_callbacks() {
+        let test_script_id = Cow::Borrowed("test_script");
+        let scripts = HashMap::from_iter(vec![(
+            test_script_id.clone(),
+            Script {
+                id: test_script_id.clone(),
+                asset: None,
+                context: Arc::new(Mutex::new(TestContext::default())),
+            },
+        )]);
+        let runtime = TestRuntime {
+            invocations: vec![].into(),
+        };
+        let mut app = setup_app::<OnTestCallback>(runtime, scripts);
+        app.world_mut()
+            .resource_mut::<CallbackSettings<TestPlugin>>()
+            .callback_handler = |_, _, _, callback, ctxt, _, _| {
+            ctxt.invocations
+                .push(ScriptValue::String(callback.to_string().into()));
+            Ok(ScriptValue::Unit)
+        };
+        app.world_mut().insert_resource(StaticScripts {
+            scripts: vec![test_script_id.clone()].into_iter().collect(),
+        });
+        // the app is already finished, so the plugin cannot be added
+        StatesPlugin.build(&mut app);
+        app.init_resource::<AppReflectAllocator>()
+            .init_state::<TestState>()
+            .register_script_state::<TestState>()
+            .add_systems(
+                StateTransition,
+                state_transition_handler::<TestPlugin>
+                    .in_set(ScriptingSystemSet::StateTransitionCallbacks),
+            )
+            .configure_sets(
+                StateTransition,
+                ScriptingSystemSet::StateTransitionCallbacks
+                    .after(StateTransitionSteps::EnterSchedules),
+            );
+
+        let invocations = |app: &App| {
+            app.world()
+                .resource::<Scripts<TestPlugin>>()
+                .scripts
+                .get(&test_script_id)
+                .unwrap()
+                .context
+                .lock()
+                .invocations
+                .clone()
+        };
+
+        // the initial state is entered on startup
+        app.update();
+        assert_eq!(
+            invocations(&app),
+            vec![ScriptValue::String("on_enter_test_state_main_menu".into())]
+        );
+
+        let guard = WorldAccessGuard::new_exclusive(app.world_mut());
+        let state = guard.get_state_by_name("TestState").unwrap().unwrap();
+        guard
+            .set_next_state(&state, ScriptValue::String("InGame".into()))
+            .unwrap();
+        assert!(guard
+            .set_next_state(&state, ScriptValue::String("Paused".into()))
+            .is_err());
+
+        app.update();
+        assert_eq!(
+            invocations(&app),
+            vec![
+                ScriptValue::String("on_enter_test_state_main_menu".into()),
+                ScriptValue::String("on_exit_test_state_main_menu".into()),
+                ScriptValue::String("on_enter_test_state_in_game".into()),
+            ]
+        );
+        assert_eq!(
+            app.world().resource::<State<TestState>>().get(),
+            &TestState::InGame
+        );
+
+        let guard = WorldAccessGuard::new_exclusive(app.world_mut());
+        let current = guard.get_state(&state).unwrap().unwrap();
+        assert_eq!(
+            current.variant_name(guard.clone()).unwrap().as_deref(),
+            Some("InGame")
+        );
+    }
+
     #[test]
     fn event_handler_reinserts_resources() {
         let mut app = App::new();
@@ -618,4 +1567,65 @@ mod test {
             .get_resource::<Events<ScriptCallbackEvent>>()
             .is_some());
     }
+    #[test]
+    fn test_execution_budget_aborts_over_budget_callbacks() {
+        let script_id = ScriptId::from("test_script");
+        let callback = OnTestCallback::into_callback_label();
+        let budget = ExecutionBudget::unlimited().with_max_instructions(10);
+
+        let result: Result<(), _> =
+            ThreadExecutionBudget::with_budget(budget, &script_id, &callback, || {
+                loop {
+                    ThreadExecutionBudget::consume(1).map_err(|_| {
+                        // languages report their own error when aborting
+                        ScriptError::new_external_boxed("aborted".into())
+                    })?;
+                }
+            });
+        assert!(ThreadExecutionBudget::current().is_none());
+
+        let err = result.unwrap_err();
+        match err.downcast_interop_inner() {
+            Some(InteropErrorInner::ExecutionBudgetExceeded {
+                script_id: err_script_id,
+                callback: err_callback,
+                instructions,
+                ..
+            }) => {
+                assert_eq!(err_script_id, &script_id);
+                assert_eq!(err_callback, &callback);
+                assert_eq!(*instructions, 11);
+            }
+            e => panic!("Unexpected error: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn test_execution_budget_restores_outer_budget() {
+        let script_id = ScriptId::from("test_script");
+        let callback = OnTestCallback::into_callback_label();
+        let outer = ExecutionBudget::unlimited().with_max_instructions(10);
+        let inner = ExecutionBudget::unlimited().with_max_duration(Duration::from_secs(60));
+
+        ThreadExecutionBudget::with_budget(outer, &script_id, &callback, || {
+            ThreadExecutionBudget::with_budget(inner, &script_id, &callback, || {
+                assert_eq!(ThreadExecutionBudget::current(), Some(inner));
+                Ok(())
+            })?;
+            assert_eq!(ThreadExecutionBudget::current(), Some(outer));
+            Ok(())
+        })
+        .unwrap();
+
+        ThreadExecutionBudget::with_budget(
+            ExecutionBudget::unlimited(),
+            &script_id,
+            &callback,
+            || {
+                assert!(ThreadExecutionBudget::current().is_none());
+                ThreadExecutionBudget::consume(u64::MAX).map_err(ScriptError::from)
+            },
+        )
+        .unwrap();
+    }
 }
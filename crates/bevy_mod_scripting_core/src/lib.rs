@@ -5,29 +5,47 @@
 use crate::event::ScriptErrorEvent;
 use asset::{
     configure_asset_systems, configure_asset_systems_for_plugin, Language, ScriptAsset,
-    ScriptAssetLoader, ScriptAssetSettings,
+    ScriptAssetLoader, ScriptAssetSettings, ScriptPreprocessor,
 };
 use bevy::prelude::*;
+#[cfg(feature = "bevy_state")]
+use bevy::{
+    reflect::{GetTypeRegistration, Typed},
+    state::state::{setup_state_transitions_in_world, FreelyMutableState, StateTransitionSteps},
+};
 use bindings::{
     function::script_function::AppScriptFunctionRegistry, garbage_collector,
     schedule::AppScheduleRegistry, script_value::ScriptValue, AppReflectAllocator,
-    DynamicScriptComponentPlugin, ReflectAllocator, ReflectReference, ScriptTypeRegistration,
+    AppScriptCapabilityRegistry, AppScriptQueryCache, DynamicScriptComponentPlugin,
+    DynamicScriptEventPlugin, DynamicScriptResourcePlugin, ReflectAllocator, ReflectReference,
+    ReflectScriptEvent, ScriptCapabilities, ScriptCommands, ScriptObserverTrigger, ScriptObservers,
+    ScriptQueryFilter, ScriptTypeRegistration,
 };
 use commands::{AddStaticScript, RemoveStaticScript};
 use context::{
     Context, ContextAssignmentStrategy, ContextBuilder, ContextInitializer, ContextLoadingSettings,
     ContextPreHandlingInitializer,
 };
+use coroutine::{resume_suspended_callbacks, YieldCondition};
 use error::ScriptError;
-use event::{ScriptCallbackEvent, ScriptCallbackResponseEvent};
-use handler::{CallbackSettings, HandlerFn};
+use event::{
+    ScriptCallbackEvent, ScriptCallbackResponseEvent, ScriptLoadFailed, ScriptLoaded,
+    ScriptReloaded, ScriptUnloaded,
+};
+use handler::{CallbackSettings, DefinedCallbacksFn, ExecutionBudget, HandlerFn};
 use runtime::{initialize_runtime, Runtime, RuntimeContainer, RuntimeInitializer, RuntimeSettings};
 use script::{ScriptComponent, ScriptId, Scripts, StaticScripts};
+#[cfg(feature = "bevy_state")]
+use {
+    bindings::{send_state_transition_callbacks, AppStateRegistry},
+    handler::state_transition_handler,
+};
 
 pub mod asset;
 pub mod bindings;
 pub mod commands;
 pub mod context;
+pub mod coroutine;
 pub mod docgen;
 pub mod error;
 pub mod event;
@@ -54,6 +72,13 @@ pub enum ScriptingSystemSet {
 
     /// Systems which handle the garbage collection of allocated values
     GarbageCollection,
+
+    /// Systems which resume suspended callbacks once their yield conditions are met
+    CallbackResumption,
+
+    /// Systems which run the `on_enter_<state>_<value>` and `on_exit_<state>_<value>` callbacks of script states, in the `StateTransition` schedule
+    #[cfg(feature = "bevy_state")]
+    StateTransitionCallbacks,
 }
 
 /// Types which act like scripting plugins, by selecting a context and runtime
@@ -80,6 +105,16 @@ pub struct ScriptingPlugin<P: IntoScriptPluginParams> {
     pub runtime_settings: RuntimeSettings<P>,
     /// The handler used for executing callbacks in scripts
     pub callback_handler: HandlerFn<P>,
+    /// The budget each callback invocation is allowed to use up before being aborted
+    pub execution_budget: ExecutionBudget,
+    /// Lists the callbacks defined in a context, so events are only dispatched to scripts which can handle them
+    pub defined_callbacks: DefinedCallbacksFn<P>,
+    /// Whether the callbacks defined by each script are indexed using [`Self::defined_callbacks`]
+    pub index_callbacks: bool,
+    /// Whether the callbacks of a context are indexed again after any of its callbacks run, needed if callbacks can define new callbacks
+    pub reindex_after_callbacks: bool,
+    /// Whether callbacks of scripts with different contexts run in parallel on the [`bevy::tasks::ComputeTaskPool`]
+    pub parallel_callbacks: bool,
     /// The context builder for loading contexts
     pub context_builder: ContextBuilder<P>,
 
@@ -103,6 +138,11 @@ impl<P: IntoScriptPluginParams> Default for ScriptingPlugin<P> {
         Self {
             runtime_settings: Default::default(),
             callback_handler: CallbackSettings::<P>::default().callback_handler,
+            execution_budget: Default::default(),
+            defined_callbacks: CallbackSettings::<P>::default().defined_callbacks,
+            index_callbacks: true,
+            reindex_after_callbacks: true,
+            parallel_callbacks: false,
             context_builder: Default::default(),
             context_assignment_strategy: Default::default(),
             language: Default::default(),
@@ -122,6 +162,11 @@ impl<P: IntoScriptPluginParams> Plugin for ScriptingPlugin<P> {
             })
             .insert_resource::<CallbackSettings<P>>(CallbackSettings {
                 callback_handler: self.callback_handler,
+                execution_budget: self.execution_budget,
+                defined_callbacks: self.defined_callbacks,
+                index_callbacks: self.index_callbacks,
+                reindex_after_callbacks: self.reindex_after_callbacks,
+                parallel_callbacks: self.parallel_callbacks,
             })
             .insert_resource::<ContextLoadingSettings<P>>(ContextLoadingSettings {
                 loader: self.context_builder.clone(),
@@ -142,6 +187,26 @@ impl<P: IntoScriptPluginParams> Plugin for ScriptingPlugin<P> {
 
         register_types(app);
     }
+
+    #[cfg(feature = "bevy_state")]
+    fn finish(&self, app: &mut App) {
+        // states are registered while building, only handle their transitions if there are any
+        let has_states = app
+            .world()
+            .get_resource::<AppStateRegistry>()
+            .is_some_and(|registry| !registry.read().is_empty());
+        if has_states {
+            app.add_systems(
+                StateTransition,
+                state_transition_handler::<P>.in_set(ScriptingSystemSet::StateTransitionCallbacks),
+            )
+            .configure_sets(
+                StateTransition,
+                ScriptingSystemSet::StateTransitionCallbacks
+                    .after(StateTransitionSteps::EnterSchedules),
+            );
+        }
+    }
 }
 
 impl<P: IntoScriptPluginParams> ScriptingPlugin<P> {
@@ -196,10 +261,31 @@ pub trait ConfigureScriptPlugin {
     /// Be careful however as this also means that scripts can interfere with each other in unexpected ways! Including overwriting each other's handlers.
     fn enable_context_sharing(self) -> Self;
 
+    /// Set the strategy used to assign contexts to scripts.
+    ///
+    /// See [`ContextAssignmentStrategy`] for the available strategies.
+    fn set_context_assignment_strategy(self, strategy: ContextAssignmentStrategy) -> Self;
+
     /// Set the set of extensions to be added for the plugin's language.
     ///
     /// This is useful for adding extensions that are not supported by default by BMS.
     fn set_additional_supported_extensions(self, extensions: &'static [&'static str]) -> Self;
+
+    /// Set the budget each callback invocation is allowed to use up.
+    ///
+    /// Scripts which run over budget are aborted and the error is reported via [`crate::event::ScriptErrorEvent`].
+    fn set_execution_budget(self, budget: ExecutionBudget) -> Self;
+
+    /// Run the callbacks of scripts with different contexts in parallel on the [`bevy::tasks::ComputeTaskPool`].
+    ///
+    /// Calls needing exclusive world access, i.e. to spawn entities or run queries, wait for the other scripts to release their accesses,
+    /// while accesses conflicting with those of scripts running at the same time fail with an error.
+    fn enable_parallel_callbacks(self) -> Self;
+
+    /// Stop indexing the callbacks each script defines, and dispatch events to every targeted script instead of only those which define the callback.
+    ///
+    /// The index is enabled by default.
+    fn disable_callback_index(self) -> Self;
 }
 
 impl<P: IntoScriptPluginParams + AsMut<ScriptingPlugin<P>>> ConfigureScriptPlugin for P {
@@ -229,10 +315,30 @@ impl<P: IntoScriptPluginParams + AsMut<ScriptingPlugin<P>>> ConfigureScriptPlugi
         self
     }
 
+    fn set_context_assignment_strategy(mut self, strategy: ContextAssignmentStrategy) -> Self {
+        self.as_mut().context_assignment_strategy = strategy;
+        self
+    }
+
     fn set_additional_supported_extensions(mut self, extensions: &'static [&'static str]) -> Self {
         self.as_mut().additional_supported_extensions = extensions;
         self
     }
+
+    fn set_execution_budget(mut self, budget: ExecutionBudget) -> Self {
+        self.as_mut().execution_budget = budget;
+        self
+    }
+
+    fn enable_parallel_callbacks(mut self) -> Self {
+        self.as_mut().parallel_callbacks = true;
+        self
+    }
+
+    fn disable_callback_index(mut self) -> Self {
+        self.as_mut().index_callbacks = false;
+        self
+    }
 }
 
 /// Ensures all types with `ReflectComponent` type data are pre-registered with component ID's
@@ -259,12 +365,22 @@ impl Plugin for BMSScriptingInfrastructurePlugin {
         app.add_event::<ScriptErrorEvent>()
             .add_event::<ScriptCallbackEvent>()
             .add_event::<ScriptCallbackResponseEvent>()
+            .add_event::<ScriptLoaded>()
+            .add_event::<ScriptReloaded>()
+            .add_event::<ScriptUnloaded>()
+            .add_event::<ScriptLoadFailed>()
             .init_resource::<AppReflectAllocator>()
             .init_resource::<StaticScripts>()
             .init_asset::<ScriptAsset>()
             .init_resource::<AppScriptFunctionRegistry>()
+            .init_resource::<AppScriptCapabilityRegistry>()
+            .init_resource::<ScriptObservers>()
+            .init_resource::<AppScriptQueryCache>()
             .insert_resource(AppScheduleRegistry::new());
 
+        #[cfg(feature = "bevy_state")]
+        app.init_resource::<AppStateRegistry>();
+
         app.add_systems(
             PostUpdate,
             ((garbage_collector).in_set(ScriptingSystemSet::GarbageCollection),),
@@ -273,14 +389,18 @@ impl Plugin for BMSScriptingInfrastructurePlugin {
         configure_asset_systems(app);
 
         DynamicScriptComponentPlugin.build(app);
+        DynamicScriptResourcePlugin.build(app);
+        DynamicScriptEventPlugin.build(app);
     }
 
     fn finish(&self, app: &mut App) {
-        // read extensions from asset settings
-        let asset_settings_extensions = app
+        // read extensions and preprocessors from asset settings
+        let asset_settings = app
             .world_mut()
-            .get_resource_or_init::<ScriptAssetSettings>()
-            .supported_extensions;
+            .get_resource_or_init::<ScriptAssetSettings>();
+        let asset_settings_extensions = asset_settings.supported_extensions;
+        let asset_settings_preprocessors = asset_settings.preprocessors.clone();
+        let asset_settings_precompiled_extensions = asset_settings.precompiled_extensions;
 
         // convert extensions to static array
         bevy::log::info!(
@@ -290,7 +410,8 @@ impl Plugin for BMSScriptingInfrastructurePlugin {
 
         app.register_asset_loader(ScriptAssetLoader {
             extensions: asset_settings_extensions,
-            preprocessor: None,
+            preprocessors: asset_settings_preprocessors,
+            precompiled_extensions: asset_settings_precompiled_extensions,
         });
 
         // pre-register component id's
@@ -309,6 +430,14 @@ fn register_script_plugin_systems<P: IntoScriptPluginParams>(app: &mut App) {
             }
         }))
         .in_set(ScriptingSystemSet::RuntimeInitialization),
+    )
+    .add_systems(
+        PreUpdate,
+        resume_suspended_callbacks::<P>.in_set(ScriptingSystemSet::CallbackResumption),
+    )
+    .configure_sets(
+        PreUpdate,
+        ScriptingSystemSet::CallbackResumption.after(ScriptingSystemSet::ScriptCommandDispatch),
     );
 
     configure_asset_systems_for_plugin::<P>(app);
@@ -317,6 +446,10 @@ fn register_script_plugin_systems<P: IntoScriptPluginParams>(app: &mut App) {
 /// Register all types that need to be accessed via reflection
 fn register_types(app: &mut App) {
     app.register_type::<ScriptValue>();
+    app.register_type::<YieldCondition>();
+    app.register_type::<ScriptObserverTrigger>();
+    app.register_type::<ScriptQueryFilter>();
+    app.register_type::<ScriptCommands>();
     app.register_type::<ScriptTypeRegistration>();
     app.register_type::<ReflectReference>();
     app.register_type::<ScriptComponent>();
@@ -373,6 +506,108 @@ impl ManageStaticScripts for App {
     }
 }
 
+/// Trait for configuring the capabilities of scripts in an app
+pub trait ManageScriptCapabilities {
+    /// Restricts the given script to the given capabilities, overriding the default capabilities.
+    ///
+    /// Takes effect the next time the script is loaded or one of its callbacks is run.
+    fn set_script_capabilities(
+        &mut self,
+        script_id: impl Into<ScriptId>,
+        capabilities: ScriptCapabilities,
+    ) -> &mut Self;
+
+    /// Sets the capabilities of all scripts without capabilities of their own.
+    ///
+    /// If `None` such scripts are unrestricted, which is the default.
+    fn set_default_script_capabilities(
+        &mut self,
+        capabilities: Option<ScriptCapabilities>,
+    ) -> &mut Self;
+}
+
+impl ManageScriptCapabilities for App {
+    fn set_script_capabilities(
+        &mut self,
+        script_id: impl Into<ScriptId>,
+        capabilities: ScriptCapabilities,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<AppScriptCapabilityRegistry>()
+            .write()
+            .insert(script_id, capabilities);
+        self
+    }
+
+    fn set_default_script_capabilities(
+        &mut self,
+        capabilities: Option<ScriptCapabilities>,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<AppScriptCapabilityRegistry>()
+            .write()
+            .set_default(capabilities);
+        self
+    }
+}
+
+/// Trait for making events available to scripts
+pub trait RegisterScriptEvent {
+    /// Adds the event to the app and registers it with [`ReflectScriptEvent`] type data,
+    /// allowing scripts to send it and read it in script systems.
+    fn register_script_event<
+        E: Event + FromReflect + TypePath + bevy::reflect::GetTypeRegistration,
+    >(
+        &mut self,
+    ) -> &mut Self;
+}
+
+impl RegisterScriptEvent for App {
+    fn register_script_event<
+        E: Event + FromReflect + TypePath + bevy::reflect::GetTypeRegistration,
+    >(
+        &mut self,
+    ) -> &mut Self {
+        self.add_event::<E>()
+            .register_type::<E>()
+            .register_type_data::<E, ReflectScriptEvent>()
+    }
+}
+
+/// Trait for making bevy states available to scripts
+#[cfg(feature = "bevy_state")]
+pub trait RegisterScriptState {
+    /// Registers the state with the [`AppStateRegistry`], allowing scripts to read it and request transitions,
+    /// and sends the `on_enter_<state>_<value>` and `on_exit_<state>_<value>` callbacks to all scripts whenever it transitions.
+    ///
+    /// The state itself still needs to be initialized, i.e. via `init_state`.
+    fn register_script_state<
+        S: FreelyMutableState + FromReflect + TypePath + Typed + GetTypeRegistration,
+    >(
+        &mut self,
+    ) -> &mut Self;
+}
+
+#[cfg(feature = "bevy_state")]
+impl RegisterScriptState for App {
+    fn register_script_state<
+        S: FreelyMutableState + FromReflect + TypePath + Typed + GetTypeRegistration,
+    >(
+        &mut self,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<AppStateRegistry>()
+            .write()
+            .register::<S>();
+        // makes sure the schedule is set up the way `StatesPlugin` would, even if it has not been added yet
+        setup_state_transitions_in_world(self.world_mut());
+        self.register_type::<S>().add_systems(
+            StateTransition,
+            send_state_transition_callbacks::<S>.in_set(StateTransitionSteps::EnterSchedules),
+        )
+    }
+}
+
 /// Trait for adding a supported extension to the script asset settings.
 ///
 /// This is only valid in the plugin building phase, as the asset loader will be created in the `finalize` phase.
@@ -386,6 +621,21 @@ pub trait ConfigureScriptAssetSettings {
         extensions: &[&'static str],
         language: Language,
     ) -> &mut Self;
+
+    /// Adds a preprocessor to the end of the script asset loader's preprocessing pipeline
+    ///
+    /// This is only valid to call in the plugin building phase, as the asset loader will be created in the `finalize` phase.
+    fn add_script_preprocessor(&mut self, preprocessor: ScriptPreprocessor) -> &mut Self;
+
+    /// Adds a supported extension of precompiled scripts, such as bytecode, to the asset settings.
+    ///
+    /// Scripts with these extensions are loaded as is, without running any preprocessors.
+    /// This is only valid to call in the plugin building phase, as the asset loader will be created in the `finalize` phase.
+    fn add_precompiled_script_extensions(
+        &mut self,
+        extensions: &[&'static str],
+        language: Language,
+    ) -> &mut Self;
 }
 
 impl ConfigureScriptAssetSettings for App {
@@ -413,6 +663,32 @@ impl ConfigureScriptAssetSettings for App {
 
         self
     }
+
+    fn add_script_preprocessor(&mut self, preprocessor: ScriptPreprocessor) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ScriptAssetSettings>()
+            .preprocessors
+            .push(preprocessor);
+        self
+    }
+
+    fn add_precompiled_script_extensions(
+        &mut self,
+        extensions: &[&'static str],
+        language: Language,
+    ) -> &mut Self {
+        self.add_supported_script_extensions(extensions, language);
+
+        let mut asset_settings = self
+            .world_mut()
+            .get_resource_or_init::<ScriptAssetSettings>();
+
+        let mut new_arr = Vec::from(asset_settings.precompiled_extensions);
+        new_arr.extend(extensions);
+        asset_settings.precompiled_extensions = Vec::leak(new_arr);
+
+        self
+    }
 }
 
 #[cfg(test)]
@@ -1,9 +1,11 @@
 //! Script related types, functions and components
 
-use crate::{asset::ScriptAsset, IntoScriptPluginParams};
+use crate::{
+    asset::ScriptAsset, coroutine::SuspendedCallbacks, event::CallbackLabel, IntoScriptPluginParams,
+};
 use bevy::prelude::ReflectComponent;
 use bevy::{asset::Handle, ecs::system::Resource, reflect::Reflect, utils::HashSet};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use std::{borrow::Cow, collections::HashMap, ops::Deref, sync::Arc};
 
 /// A unique identifier for a script, by default corresponds to the path of the asset excluding the asset source.
@@ -37,6 +39,10 @@ impl ScriptComponent {
 #[derive(Resource)]
 pub struct Scripts<P: IntoScriptPluginParams> {
     pub(crate) scripts: HashMap<ScriptId, Script<P>>,
+    /// Callbacks which suspended themselves and are waiting to be resumed
+    pub(crate) suspended: Mutex<SuspendedCallbacks>,
+    /// The callbacks each script defines
+    pub(crate) callback_index: CallbackIndex,
 }
 
 #[profiling::all_functions]
@@ -48,7 +54,10 @@ impl<P: IntoScriptPluginParams> Scripts<P> {
 
     /// Removes a script from the collection, returning `true` if the script was in the collection, `false` otherwise
     pub fn remove<S: Into<ScriptId>>(&mut self, script: S) -> bool {
-        self.scripts.remove(&script.into()).is_some()
+        let script = script.into();
+        self.suspended.get_mut().remove_script(&script);
+        self.callback_index.remove(&script);
+        self.scripts.remove(&script).is_some()
     }
 
     /// Checks if a script is in the collection
@@ -76,16 +85,86 @@ impl<P: IntoScriptPluginParams> Scripts<P> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Script<P>> {
         self.scripts.values_mut()
     }
+
+    /// Returns the callbacks which suspended themselves and are waiting to be resumed
+    pub fn suspended_callbacks(&self) -> MutexGuard<'_, SuspendedCallbacks> {
+        self.suspended.lock()
+    }
+
+    /// Returns the index of the callbacks each script defines
+    pub fn callback_index(&self) -> &CallbackIndex {
+        &self.callback_index
+    }
+
+    /// Re-indexes the callbacks defined in the context of the given script.
+    ///
+    /// All the scripts sharing the context are updated, if the callbacks cannot be listed they are removed from the index instead.
+    pub fn index_callbacks(
+        &mut self,
+        script: &ScriptId,
+        defined_callbacks: impl FnOnce(&P::C) -> Option<Vec<CallbackLabel>>,
+    ) {
+        let Some(context) = self.scripts.get(script).map(|s| s.context.clone()) else {
+            return;
+        };
+        let callbacks = defined_callbacks(&context.lock());
+        let sharing = self
+            .scripts
+            .values()
+            .filter(|s| Arc::ptr_eq(&s.context, &context))
+            .map(|s| s.id.clone());
+        for id in sharing {
+            match &callbacks {
+                Some(callbacks) => self.callback_index.insert(id, callbacks.iter().cloned()),
+                None => self.callback_index.remove(&id),
+            }
+        }
+    }
 }
 
 impl<P: IntoScriptPluginParams> Default for Scripts<P> {
     fn default() -> Self {
         Self {
             scripts: Default::default(),
+            suspended: Default::default(),
+            callback_index: Default::default(),
         }
     }
 }
 
+/// An index of the callbacks each script defines.
+///
+/// Scripts which are not in the index are assumed to be able to handle any callback.
+#[derive(Default, Debug)]
+pub struct CallbackIndex {
+    scripts: HashMap<ScriptId, HashSet<CallbackLabel>>,
+}
+
+#[profiling::all_functions]
+impl CallbackIndex {
+    /// Sets the callbacks defined by the given script, replacing any previous entry
+    pub fn insert(&mut self, script: ScriptId, callbacks: impl IntoIterator<Item = CallbackLabel>) {
+        self.scripts.insert(script, callbacks.into_iter().collect());
+    }
+
+    /// Removes the given script from the index
+    pub fn remove(&mut self, script: &ScriptId) {
+        self.scripts.remove(script);
+    }
+
+    /// Returns the callbacks defined by the given script, or `None` if the script is not indexed
+    pub fn get(&self, script: &ScriptId) -> Option<&HashSet<CallbackLabel>> {
+        self.scripts.get(script)
+    }
+
+    /// Returns `false` if the script is known not to define the given callback, and `true` otherwise
+    pub fn may_handle(&self, script: &ScriptId, callback: &CallbackLabel) -> bool {
+        self.scripts
+            .get(script)
+            .is_none_or(|callbacks| callbacks.contains(callback))
+    }
+}
+
 /// A script
 pub struct Script<P: IntoScriptPluginParams> {
     /// The id of the script
@@ -1,15 +1,18 @@
 //! Contains functions defined by the [`bevy_mod_scripting_core`] crate
 
-use std::{collections::HashMap, ops::Deref};
+use std::{any::TypeId, collections::HashMap, ops::Deref};
 
 use bevy::prelude::*;
 use bevy_mod_scripting_core::{
     bindings::{
         function::{
-            from::Union, namespace::GlobalNamespace, script_function::DynamicScriptFunctionMut,
+            from::{FromScript, Union},
+            namespace::GlobalNamespace,
+            script_function::DynamicScriptFunctionMut,
         },
         script_system::ScriptSystemBuilder,
     },
+    coroutine::YieldCondition,
     docgen::info::FunctionInfo,
     *,
 };
@@ -19,13 +22,17 @@ use bindings::{
     function::{
         from::{Ref, Val},
         from_ref::FromScriptRef,
+        into::IntoScript,
         into_ref::IntoScriptRef,
         script_function::{FunctionCallContext, ScriptFunctionMut},
     },
     pretty_print::DisplayWithWorld,
     script_value::ScriptValue,
-    ReflectReference, ScriptComponentRegistration, ScriptQueryBuilder, ScriptQueryResult,
+    DynamicComponent, DynamicComponentSchema, ReflectReference, ScriptCommands,
+    ScriptComponentRegistration, ScriptEventRegistration, ScriptObserverTrigger,
+    ScriptQueryBuilder, ScriptQueryFilter, ScriptQueryResult, ScriptQueryTerm,
     ScriptResourceRegistration, ScriptTypeRegistration, ThreadWorldContainer, WorldContainer,
+    WorldGuard,
 };
 use error::InteropError;
 use reflection_extensions::{PartialReflectExt, TypeIdExtensions};
@@ -264,6 +271,48 @@ impl World {
         world.insert_component(*entity, registration.into_inner(), value)
     }
 
+    /// Spawns a new entity with the given components and returns it.
+    ///
+    /// Each component is constructed from its map of values like in `construct`, and all of them are inserted at once.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `components`: The components to construct, keyed by their registrations or type names, along with the values to construct them from.
+    /// Returns:
+    /// * `entity`: The newly spawned entity
+    fn spawn_with(
+        ctxt: FunctionCallContext,
+        components: ComponentPayloads,
+    ) -> Result<Val<Entity>, InteropError> {
+        profiling::function_scope!("spawn_with");
+        let world = ctxt.world()?;
+        let components = resolve_component_payloads(&world, components)?;
+        Ok(Val(
+            world.spawn_with(components, ctxt.convert_to_0_indexed())?
+        ))
+    }
+
+    /// Inserts the given components into the provided entity.
+    ///
+    /// Each component is constructed from its map of values like in `construct`, and all of them are inserted at once.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `entity`: The entity to insert the components into.
+    /// * `components`: The components to construct, keyed by their registrations or type names, along with the values to construct them from.
+    /// Returns:
+    /// * `result`: Nothing if the components were inserted successfully.
+    fn insert_components(
+        ctxt: FunctionCallContext,
+        entity: Val<Entity>,
+        components: ComponentPayloads,
+    ) -> Result<(), InteropError> {
+        profiling::function_scope!("insert_components");
+        let world = ctxt.world()?;
+        let components = resolve_component_payloads(&world, components)?;
+        world.insert_components(*entity, components, ctxt.convert_to_0_indexed())
+    }
+
     /// Inserts the given children entities into the provided parent entity.
     ///
     /// Arguments:
@@ -435,6 +484,12 @@ impl World {
                     &schedule,
                     builder.into_inner(),
                 )?,
+            #[cfg(feature = "rune_bindings")]
+            asset::Language::Rune => world
+                .add_system::<bevy_mod_scripting_rune::RuneScriptingPlugin>(
+                    &schedule,
+                    builder.into_inner(),
+                )?,
             _ => {
                 return Err(InteropError::unsupported_operation(
                     None,
@@ -449,6 +504,63 @@ impl World {
         Ok(Val(system))
     }
 
+    /// Adds an observer which calls the given function on the given script whenever the trigger fires.
+    ///
+    /// The function receives the entity targeted by the trigger, and for event triggers the event as a second argument.
+    /// For `on_replace` and `on_remove` triggers, the second argument is a copy of the replaced or removed component.
+    /// The observer is removed when the script is reloaded or deleted.
+    ///
+    /// Arguments:
+    /// * `trigger`: What the observer reacts to.
+    /// * `callback`: The function name in the script the observer should call.
+    /// * `script_id`: The id of the script the observer will call.
+    /// Returns:
+    /// * `observer`: The entity of the observer.
+    fn add_observer(
+        ctxt: FunctionCallContext,
+        trigger: Val<ScriptObserverTrigger>,
+        callback: String,
+        script_id: String,
+    ) -> Result<Val<Entity>, InteropError> {
+        profiling::function_scope!("add_observer");
+        let world = ctxt.world()?;
+        let trigger = trigger.into_inner();
+        let observer = match ctxt.language() {
+            #[cfg(feature = "lua_bindings")]
+            asset::Language::Lua => world
+                .add_observer::<bevy_mod_scripting_lua::LuaScriptingPlugin>(
+                    trigger,
+                    callback.into(),
+                    script_id.into(),
+                )?,
+            #[cfg(feature = "rhai_bindings")]
+            asset::Language::Rhai => world
+                .add_observer::<bevy_mod_scripting_rhai::RhaiScriptingPlugin>(
+                    trigger,
+                    callback.into(),
+                    script_id.into(),
+                )?,
+            #[cfg(feature = "rune_bindings")]
+            asset::Language::Rune => world
+                .add_observer::<bevy_mod_scripting_rune::RuneScriptingPlugin>(
+                    trigger,
+                    callback.into(),
+                    script_id.into(),
+                )?,
+            _ => {
+                return Err(InteropError::unsupported_operation(
+                    None,
+                    None,
+                    format!(
+                        "creating an observer in {} scripting language",
+                        ctxt.language()
+                    ),
+                ))
+            }
+        };
+        Ok(Val(observer))
+    }
+
     /// Quits the program.
     /// Arguments:
     /// * `ctxt`: The function call context.
@@ -463,23 +575,211 @@ impl World {
     /// Registers a new component type with the world.
     ///
     /// The component will behave like any other native component for all intents and purposes.
-    /// The type that will be instantiated to back this component will be `DynamicComponent` which contains just one field:
+    /// The type that will be instantiated to back this component will be `DynamicComponent` which always contains the fields:
     /// - `data`
+    /// - `fields`
     ///
-    /// This field can be set to any value and modified freely.
+    /// The `data` field can be set to any value and modified freely.
+    ///
+    /// If a schema is given, the `fields` field of the component will contain one typed field per schema entry. Each entry is a table with the keys:
+    /// - `name`: the name of the field
+    /// - `type`: the type of the field, either a type name or a type registration
+    /// - `default`: (optional) the default value of the field, if not given the type's `Default` implementation is used
+    ///
+    /// Typed fields are validated whenever the component is inserted.
     ///
     /// Arguments:
     /// * `ctxt`: The function call context.
     /// * `name`: The name of the component type
+    /// * `schema`: The typed fields of the component, if any.
     /// Returns:
     /// * `registration`: The registration of the new component type if successful.
     fn register_new_component(
         ctxt: FunctionCallContext,
         name: String,
+        schema: Option<Vec<HashMap<String, ScriptValue>>>,
     ) -> Result<Val<ScriptComponentRegistration>, InteropError> {
         profiling::function_scope!("register_new_component");
         let world = ctxt.world()?;
-        world.register_script_component(name).map(Val)
+        let fields = schema
+            .unwrap_or_default()
+            .into_iter()
+            .map(|mut field| {
+                let field_name = match field.remove("name") {
+                    Some(ScriptValue::String(field_name)) => field_name.into_owned(),
+                    _ => {
+                        return Err(InteropError::invalid_script_component_schema(
+                            name.clone(),
+                            "every field must have a string 'name'",
+                        ))
+                    }
+                };
+                let type_registration = match field.remove("type") {
+                    Some(ScriptValue::String(type_name)) => world
+                        .get_type_by_name(&type_name)
+                        .ok_or_else(|| {
+                            InteropError::invalid_script_component_schema(
+                                name.clone(),
+                                format!("the type '{type_name}' of field '{field_name}' is not registered"),
+                            )
+                        })?,
+                    Some(type_) => <Union<
+                        Val<ScriptTypeRegistration>,
+                        Union<Val<ScriptComponentRegistration>, Val<ScriptResourceRegistration>>,
+                    >>::from_script(type_, world.clone())
+                    .map(|registration| match registration.into_left() {
+                        Ok(l) => l.into_inner(),
+                        Err(r) => match r.into_left() {
+                            Ok(l) => (l.into_inner()).into_type_registration(),
+                            Err(r) => (r.into_inner()).into_type_registration(),
+                        },
+                    })?,
+                    None => {
+                        return Err(InteropError::invalid_script_component_schema(
+                            name.clone(),
+                            format!("the field '{field_name}' must have a 'type'"),
+                        ))
+                    }
+                };
+                let default = field.remove("default");
+                if let Some(key) = field.keys().next() {
+                    return Err(InteropError::invalid_script_component_schema(
+                        name.clone(),
+                        format!("unknown key '{key}' in the definition of field '{field_name}'"),
+                    ));
+                }
+                world.new_script_component_field(field_name, type_registration, default)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let schema = DynamicComponentSchema::new(&name, fields)?;
+        world
+            .register_script_component_with_schema(name, schema)
+            .map(Val)
+    }
+
+    /// Registers a new resource type with the world, and inserts an instance of it.
+    ///
+    /// The resource will behave like any other native resource for all intents and purposes, and can be retrieved by name via `get_type_by_name`.
+    /// The type that will be instantiated to back this resource will be `DynamicResource` which contains just one field:
+    /// - `data`
+    ///
+    /// This field can be set to any value and modified freely.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `name`: The name of the resource type
+    /// Returns:
+    /// * `registration`: The registration of the new resource type if successful.
+    fn register_new_resource(
+        ctxt: FunctionCallContext,
+        name: String,
+    ) -> Result<Val<ScriptResourceRegistration>, InteropError> {
+        profiling::function_scope!("register_new_resource");
+        let world = ctxt.world()?;
+        world.register_script_resource(name).map(Val)
+    }
+
+    /// Registers a new event type with the world.
+    ///
+    /// Script registered events can carry any value as their payload. They can be sent with `send_event`, read in script systems via `event_reader`,
+    /// and are visible to Rust systems as `DynamicEvent`s with the given name.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `name`: The name of the event type
+    /// Returns:
+    /// * `registration`: The registration of the new event type if successful.
+    fn register_new_event(
+        ctxt: FunctionCallContext,
+        name: String,
+    ) -> Result<Val<ScriptEventRegistration>, InteropError> {
+        profiling::function_scope!("register_new_event");
+        let world = ctxt.world()?;
+        world.register_script_event(name).map(Val)
+    }
+
+    /// Retrieves an event type by name. This can be either an event registered by a script, or a reflected Rust event registered for scripting.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `name`: The name of the event type, or the name it was registered with by a script.
+    /// Returns:
+    /// * `registration`: The registration of the event type, if it exists.
+    fn get_event_type(
+        ctxt: FunctionCallContext,
+        name: String,
+    ) -> Result<Option<Val<ScriptEventRegistration>>, InteropError> {
+        profiling::function_scope!("get_event_type");
+        let world = ctxt.world()?;
+        world.get_event_type(&name).map(|r| r.map(Val))
+    }
+
+    /// Sends an event of the given type.
+    ///
+    /// For events registered by scripts the payload can be any value, otherwise it must be convertible to the event type.
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `registration`: The type of event to send.
+    /// * `payload`: The event to send.
+    /// Returns:
+    /// * `result`: Nothing if the event was sent successfully.
+    fn send_event(
+        ctxt: FunctionCallContext,
+        registration: Val<ScriptEventRegistration>,
+        payload: ScriptValue,
+    ) -> Result<(), InteropError> {
+        profiling::function_scope!("send_event");
+        let world = ctxt.world()?;
+        world.send_event(&registration, payload)
+    }
+}
+
+#[cfg(feature = "bevy_state")]
+#[script_bindings(
+    remote,
+    bms_core_path = "bevy_mod_scripting_core",
+    name = "world_state_functions",
+    unregistered
+)]
+impl World {
+    /// Retrieves the current value of a state registered with scripts.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `name`: The short or full type path of the state, i.e. `GameState`.
+    /// Returns:
+    /// * `state`: A copy of the current state, or nothing if the state is not initialized.
+    fn get_state(
+        ctxt: FunctionCallContext,
+        name: String,
+    ) -> Result<Option<ReflectReference>, InteropError> {
+        profiling::function_scope!("get_state");
+        let world = ctxt.world()?;
+        let state = world
+            .get_state_by_name(&name)?
+            .ok_or_else(|| InteropError::missing_state(name))?;
+        world.get_state(&state)
+    }
+
+    /// Requests a transition of a state registered with scripts, the transition happens the next time bevy applies state transitions.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `name`: The short or full type path of the state, i.e. `GameState`.
+    /// * `next`: The next state, either a value of the state or the name of a unit variant of an enum state.
+    /// Returns:
+    /// * `result`: Nothing if the transition was requested successfully.
+    fn set_next_state(
+        ctxt: FunctionCallContext,
+        name: String,
+        next: ScriptValue,
+    ) -> Result<(), InteropError> {
+        profiling::function_scope!("set_next_state");
+        let world = ctxt.world()?;
+        let state = world
+            .get_state_by_name(&name)?
+            .ok_or_else(|| InteropError::missing_state(name))?;
+        world.set_next_state(&state, next)
     }
 }
 
@@ -542,6 +842,38 @@ impl ReflectReference {
         Ok(reference.display_value_with_world(world))
     }
 
+    /// Checks if the component or resource behind this reference was added or changed since the script last ran.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `reference`: The reference to a component or resource.
+    /// Returns:
+    /// * `changed`: Whether the value was changed.
+    fn is_changed(
+        ctxt: FunctionCallContext,
+        reference: ReflectReference,
+    ) -> Result<bool, InteropError> {
+        profiling::function_scope!("is_changed");
+        let world = ctxt.world()?;
+        reference.is_changed(world)
+    }
+
+    /// Checks if the component or resource behind this reference was added since the script last ran.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `reference`: The reference to a component or resource.
+    /// Returns:
+    /// * `added`: Whether the value was added.
+    fn is_added(
+        ctxt: FunctionCallContext,
+        reference: ReflectReference,
+    ) -> Result<bool, InteropError> {
+        profiling::function_scope!("is_added");
+        let world = ctxt.world()?;
+        reference.is_added(world)
+    }
+
     /// Gets and clones the value under the specified key if the underlying type is a map type.
     ///
     /// Arguments:
@@ -568,7 +900,7 @@ impl ReflectReference {
             key,
             world.clone(),
         )?;
-        reference.with_reflect_mut(world.clone(), |s| match s.try_map_get(key.as_ref())? {
+        reference.with_reflect(world.clone(), |s| match s.try_map_get(key.as_ref())? {
             Some(value) => {
                 let reference = {
                     let allocator = world.allocator();
@@ -606,7 +938,7 @@ impl ReflectReference {
         })?;
         let other =
             <Box<dyn PartialReflect>>::from_script_ref(target_type_id, value, world.clone())?;
-        reference.with_reflect_mut(world, |s| s.try_push_boxed(other))?
+        reference.with_reflect_mut(world, |s| s.try_push_boxed(other))
     }
 
     /// Pops the value from the reference, if the reference is an appropriate container type.
@@ -622,7 +954,7 @@ impl ReflectReference {
     ) -> Result<ScriptValue, InteropError> {
         profiling::function_scope!("pop");
         let world = ctxt.world()?;
-        let o = reference.with_reflect_mut(world.clone(), |s| s.try_pop_boxed())??;
+        let o = reference.with_reflect_mut(world.clone(), |s| s.try_pop_boxed())?;
         let reference = {
             let allocator = world.allocator();
             let mut allocator = allocator.write();
@@ -674,7 +1006,7 @@ impl ReflectReference {
         let value =
             <Box<dyn PartialReflect>>::from_script_ref(value_type_id, value, world.clone())?;
 
-        reference.with_reflect_mut(world, |s| s.try_insert_boxed(key, value))?
+        reference.with_reflect_mut(world, |s| s.try_insert_boxed(key, value))
     }
 
     /// Clears the container, if the reference is an appropriate container type.
@@ -686,7 +1018,7 @@ impl ReflectReference {
     fn clear(ctxt: FunctionCallContext, reference: ReflectReference) -> Result<(), InteropError> {
         profiling::function_scope!("clear");
         let world = ctxt.world()?;
-        reference.with_reflect_mut(world, |s| s.try_clear())?
+        reference.with_reflect_mut(world, |s| s.try_clear())
     }
 
     /// Retrieves the length of the reference, if the reference is an appropriate container type.
@@ -734,7 +1066,7 @@ impl ReflectReference {
             key.convert_to_0_indexed_key();
         }
 
-        let removed = reference.with_reflect_mut(world.clone(), |s| s.try_remove_boxed(key))??;
+        let removed = reference.with_reflect_mut(world.clone(), |s| s.try_remove_boxed(key))?;
         match removed {
             Some(removed) => {
                 let reference = {
@@ -871,6 +1203,39 @@ impl ScriptComponentRegistration {
     }
 }
 
+#[script_bindings(
+    remote,
+    bms_core_path = "bevy_mod_scripting_core",
+    name = "script_event_registration_functions",
+    core
+)]
+impl ScriptEventRegistration {
+    /// Retrieves the name of the type.
+    ///
+    /// Arguments:
+    /// * `registration`: The event registration.
+    /// Returns:
+    /// * `type_name`: The name of the type.
+    fn type_name(registration: Ref<ScriptEventRegistration>) -> &'static str {
+        profiling::function_scope!("type_name");
+        registration.type_registration().type_name()
+    }
+
+    /// Retrieves the name of the event, for events registered by scripts this is the name they were registered with,
+    /// otherwise the short name of the type.
+    /// Arguments:
+    /// * `registration`: The event registration.
+    /// Returns:
+    /// * `event_name`: The name of the event.
+    fn event_name(registration: Ref<ScriptEventRegistration>) -> String {
+        profiling::function_scope!("event_name");
+        registration
+            .script_event_name()
+            .unwrap_or_else(|| registration.type_registration().short_name())
+            .to_owned()
+    }
+}
+
 #[script_bindings(
     remote,
     bms_core_path = "bevy_mod_scripting_core",
@@ -908,7 +1273,9 @@ impl ScriptResourceRegistration {
     core
 )]
 impl ScriptQueryBuilder {
-    /// Adds a component to be retrieved by the query
+    /// Adds a component to be retrieved by the query, which is declared as read-only.
+    ///
+    /// Script systems which are not exclusive cannot write to read-only components, see `mut_component`.
     ///
     /// Arguments:
     /// * `query`: The query to add the component to
@@ -925,6 +1292,63 @@ impl ScriptQueryBuilder {
         Val(builder)
     }
 
+    /// Adds a component to be retrieved by the query, which is declared as written to.
+    ///
+    /// Script systems which are not exclusive can only write to components declared this way.
+    ///
+    /// Arguments:
+    /// * `query`: The query to add the component to
+    /// * `component`: The component to add
+    /// Returns:
+    /// * `query`: The query with the component added
+    fn mut_component(
+        query: Val<ScriptQueryBuilder>,
+        component: Val<ScriptComponentRegistration>,
+    ) -> Val<ScriptQueryBuilder> {
+        profiling::function_scope!("mut_component");
+        let mut builder = query.into_inner();
+        builder.mut_component(component.into_inner());
+        Val(builder)
+    }
+
+    /// Adds a component to be retrieved by the query if the entity has it, entities without it still match the query.
+    ///
+    /// Arguments:
+    /// * `query`: The query to add the component to
+    /// * `component`: The component to add
+    /// Returns:
+    /// * `query`: The query with the component added
+    fn optional_component(
+        query: Val<ScriptQueryBuilder>,
+        component: Val<ScriptComponentRegistration>,
+    ) -> Val<ScriptQueryBuilder> {
+        profiling::function_scope!("optional_component");
+        let mut builder = query.into_inner();
+        builder.optional_component(component.into_inner());
+        Val(builder)
+    }
+
+    /// Adds a component to be retrieved by the query if the entity has it, which is declared as written to.
+    ///
+    /// Arguments:
+    /// * `query`: The query to add the component to
+    /// * `component`: The component to add
+    /// Returns:
+    /// * `query`: The query with the component added
+    fn optional_mut_component(
+        query: Val<ScriptQueryBuilder>,
+        component: Val<ScriptComponentRegistration>,
+    ) -> Val<ScriptQueryBuilder> {
+        profiling::function_scope!("optional_mut_component");
+        let mut builder = query.into_inner();
+        builder.term(
+            ScriptQueryTerm::new(component.into_inner())
+                .mutable()
+                .optional(),
+        );
+        Val(builder)
+    }
+
     /// Adds a component to filter the query by. This component will NOT be retrieved.
     ///
     /// Arguments:
@@ -959,6 +1383,119 @@ impl ScriptQueryBuilder {
         Val(builder)
     }
 
+    /// Adds a component to filter the query by. Only entities whose component was added or changed since the script last ran are retrieved. This component will NOT be retrieved.
+    ///
+    /// Arguments:
+    /// * `query`: The query to add the component to
+    /// * `changed`: The component to filter by
+    /// Returns:
+    /// * `query`: The query with the component added
+    fn changed(
+        query: Val<ScriptQueryBuilder>,
+        changed: Val<ScriptComponentRegistration>,
+    ) -> Val<ScriptQueryBuilder> {
+        profiling::function_scope!("changed");
+        let mut builder = query.into_inner();
+        builder.changed_component(changed.into_inner());
+        Val(builder)
+    }
+
+    /// Adds a component to filter the query by. Only entities whose component was added since the script last ran are retrieved. This component will NOT be retrieved.
+    ///
+    /// Arguments:
+    /// * `query`: The query to add the component to
+    /// * `added`: The component to filter by
+    /// Returns:
+    /// * `query`: The query with the component added
+    fn added(
+        query: Val<ScriptQueryBuilder>,
+        added: Val<ScriptComponentRegistration>,
+    ) -> Val<ScriptQueryBuilder> {
+        profiling::function_scope!("added");
+        let mut builder = query.into_inner();
+        builder.added_component(added.into_inner());
+        Val(builder)
+    }
+
+    /// Adds a group of filters to the query, of which at least one must match.
+    ///
+    /// Arguments:
+    /// * `query`: The query to add the filters to
+    /// * `filters`: The filters, created via `ScriptQueryFilter`
+    /// Returns:
+    /// * `query`: The query with the filters added
+    fn any_of(
+        query: Val<ScriptQueryBuilder>,
+        filters: Vec<Val<ScriptQueryFilter>>,
+    ) -> Val<ScriptQueryBuilder> {
+        profiling::function_scope!("any_of");
+        let mut builder = query.into_inner();
+        builder.any_of(filters.into_iter().map(Val::into_inner).collect());
+        Val(builder)
+    }
+
+    /// Restricts the query to the given entities, the results are returned in the order of the list.
+    ///
+    /// Arguments:
+    /// * `query`: The query to restrict
+    /// * `entities`: The entities to query
+    /// Returns:
+    /// * `query`: The restricted query
+    fn entities(
+        query: Val<ScriptQueryBuilder>,
+        entities: Vec<Val<Entity>>,
+    ) -> Val<ScriptQueryBuilder> {
+        profiling::function_scope!("entities");
+        let mut builder = query.into_inner();
+        builder.entities(entities.into_iter().map(Val::into_inner).collect());
+        Val(builder)
+    }
+
+    /// Restricts the query to the children of the given entity.
+    ///
+    /// Arguments:
+    /// * `query`: The query to restrict
+    /// * `parent`: The parent of the entities to query
+    /// Returns:
+    /// * `query`: The restricted query
+    fn children_of(query: Val<ScriptQueryBuilder>, parent: Val<Entity>) -> Val<ScriptQueryBuilder> {
+        profiling::function_scope!("children_of");
+        let mut builder = query.into_inner();
+        builder.children_of(parent.into_inner());
+        Val(builder)
+    }
+
+    /// Builds the query and returns an iterator function, which retrieves the next entity and its component references each time it is called.
+    ///
+    /// The iterator function returns `nil` once all the entities matching the query were visited. Unlike `build`, results are only retrieved as they are needed.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `query`: The query to build.
+    /// Returns:
+    /// * `iter`: The iterator function.
+    fn iter(
+        ctxt: FunctionCallContext,
+        query: Val<ScriptQueryBuilder>,
+    ) -> Result<DynamicScriptFunctionMut, InteropError> {
+        profiling::function_scope!("iter");
+        let world = ctxt.world()?;
+        let mut iter = world.query_iter(query.into_inner())?;
+        let iter_function = move || {
+            // world is not thread safe, we can't capture it in the closure
+            let world = ThreadWorldContainer.try_get_world()?;
+            match iter.next(world.clone())? {
+                Some(result) => Val(result).into_script(world),
+                None => Ok(ScriptValue::Unit),
+            }
+        };
+
+        // the default name is the type path of the closure, which is not a valid function name in every language
+        Ok(iter_function
+            .into_dynamic_script_function_mut()
+            .with_info(FunctionInfo::new().with_name("next")))
+    }
+
     /// Builds the query and retrieves the entities and component references.
     ///
     /// Arguments:
@@ -999,13 +1536,13 @@ impl ScriptQueryResult {
 
     /// Retrieves the components from the query result.
     ///
-    /// These are ordered by the order they were added to the query.
+    /// These are ordered by the order they were added to the query, including optional components, which are returned as `nil` if the entity does not have them.
     ///
     /// Arguments:
     /// * `query`: The query result to retrieve the components from.
     /// Returns:
     /// * `components`: The components from the query result.
-    fn components(query: Ref<ScriptQueryResult>) -> Vec<ReflectReference> {
+    fn components(query: Ref<ScriptQueryResult>) -> Vec<Option<ReflectReference>> {
         profiling::function_scope!("components");
         query.components.to_vec()
     }
@@ -1151,6 +1688,25 @@ impl ScriptSystemBuilder {
         builder.into()
     }
 
+    /// Requests the system receive the events of the given type. A list of all the events sent since the system last ran will be added to the
+    /// list of arguments of the callback in the order they're provided.
+    ///
+    /// Events registered by scripts are passed as their payload, other events as references.
+    /// Arguments:
+    /// * `builder`: The system builder to add the event reader to.
+    /// * `event`: The event type to read.
+    /// Returns:
+    /// * `builder`: The system builder with the event reader added.
+    fn event_reader(
+        builder: Val<ScriptSystemBuilder>,
+        event: Val<ScriptEventRegistration>,
+    ) -> Val<ScriptSystemBuilder> {
+        profiling::function_scope!("event_reader");
+        let mut builder = builder.into_inner();
+        builder.event_reader(event.into_inner());
+        builder.into()
+    }
+
     /// Specifies the system is to run exclusively, meaning it can access anything, but will not run in parallel with other systems.
     /// Arguments:
     /// * `builder`: The system builder to make exclusive.
@@ -1202,6 +1758,213 @@ impl ScriptSystemBuilder {
     }
 }
 
+#[script_bindings(
+    remote,
+    bms_core_path = "bevy_mod_scripting_core",
+    name = "yield_condition_functions",
+    core
+)]
+impl YieldCondition {
+    /// Creates a condition which resumes a suspended callback on the next frame.
+    ///
+    /// Returns:
+    /// * `condition`: The yield condition.
+    fn next_frame() -> Val<YieldCondition> {
+        Val(YieldCondition::NextFrame)
+    }
+
+    /// Creates a condition which resumes a suspended callback after the given number of frames.
+    ///
+    /// Arguments:
+    /// * `frames`: The number of frames to wait for.
+    /// Returns:
+    /// * `condition`: The yield condition.
+    fn frames(frames: u32) -> Val<YieldCondition> {
+        Val(YieldCondition::Frames(frames))
+    }
+
+    /// Creates a condition which resumes a suspended callback after the given number of seconds.
+    ///
+    /// Arguments:
+    /// * `seconds`: The number of seconds to wait for.
+    /// Returns:
+    /// * `condition`: The yield condition.
+    fn seconds(seconds: f64) -> Result<Val<YieldCondition>, InteropError> {
+        let duration = std::time::Duration::try_from_secs_f64(seconds)
+            .map_err(|e| InteropError::external_error(Box::new(e)))?;
+        Ok(Val(YieldCondition::Duration(duration)))
+    }
+
+    /// Creates a condition which resumes a suspended callback the next time the script receives an event with the given label.
+    ///
+    /// The event payload is returned from the yield.
+    ///
+    /// Arguments:
+    /// * `label`: The label of the event to wait for.
+    /// Returns:
+    /// * `condition`: The yield condition.
+    fn event(label: String) -> Val<YieldCondition> {
+        Val(YieldCondition::Event(label.into()))
+    }
+}
+
+#[script_bindings(
+    remote,
+    bms_core_path = "bevy_mod_scripting_core",
+    name = "script_observer_trigger_functions",
+    core
+)]
+impl ScriptObserverTrigger {
+    /// Creates a trigger which fires when the component is added to an entity which did not have it.
+    ///
+    /// Arguments:
+    /// * `component`: The component to observe.
+    /// Returns:
+    /// * `trigger`: The observer trigger.
+    fn on_add(component: Val<ScriptComponentRegistration>) -> Val<ScriptObserverTrigger> {
+        Val(ScriptObserverTrigger::OnAdd(component.into_inner()))
+    }
+
+    /// Creates a trigger which fires whenever the component is inserted, even if the entity already had it.
+    ///
+    /// Arguments:
+    /// * `component`: The component to observe.
+    /// Returns:
+    /// * `trigger`: The observer trigger.
+    fn on_insert(component: Val<ScriptComponentRegistration>) -> Val<ScriptObserverTrigger> {
+        Val(ScriptObserverTrigger::OnInsert(component.into_inner()))
+    }
+
+    /// Creates a trigger which fires before the component is overwritten by an insert or removed.
+    ///
+    /// Arguments:
+    /// * `component`: The component to observe.
+    /// Returns:
+    /// * `trigger`: The observer trigger.
+    fn on_replace(component: Val<ScriptComponentRegistration>) -> Val<ScriptObserverTrigger> {
+        Val(ScriptObserverTrigger::OnReplace(component.into_inner()))
+    }
+
+    /// Creates a trigger which fires before the component is removed from an entity, or the entity is despawned.
+    ///
+    /// Arguments:
+    /// * `component`: The component to observe.
+    /// Returns:
+    /// * `trigger`: The observer trigger.
+    fn on_remove(component: Val<ScriptComponentRegistration>) -> Val<ScriptObserverTrigger> {
+        Val(ScriptObserverTrigger::OnRemove(component.into_inner()))
+    }
+
+    /// Creates a trigger which fires when an event of the given type is triggered.
+    ///
+    /// Arguments:
+    /// * `event`: The type of event to observe.
+    /// Returns:
+    /// * `trigger`: The observer trigger.
+    fn event(event: Val<ScriptEventRegistration>) -> Val<ScriptObserverTrigger> {
+        Val(ScriptObserverTrigger::Event(event.into_inner()))
+    }
+}
+
+#[script_bindings(
+    remote,
+    bms_core_path = "bevy_mod_scripting_core",
+    name = "script_query_filter_functions",
+    core
+)]
+impl ScriptQueryFilter {
+    /// Creates a filter matching entities with the component.
+    ///
+    /// Arguments:
+    /// * `component`: The component to filter by.
+    /// Returns:
+    /// * `filter`: The query filter.
+    fn with_component(component: Val<ScriptComponentRegistration>) -> Val<ScriptQueryFilter> {
+        Val(ScriptQueryFilter::With(component.into_inner()))
+    }
+
+    /// Creates a filter matching entities without the component.
+    ///
+    /// Arguments:
+    /// * `component`: The component to filter by.
+    /// Returns:
+    /// * `filter`: The query filter.
+    fn without_component(component: Val<ScriptComponentRegistration>) -> Val<ScriptQueryFilter> {
+        Val(ScriptQueryFilter::Without(component.into_inner()))
+    }
+}
+
+#[script_bindings(
+    remote,
+    bms_core_path = "bevy_mod_scripting_core",
+    name = "script_commands_functions",
+    core
+)]
+impl ScriptCommands {
+    /// Reserves a new entity and returns it, the entity is spawned once the queued commands are applied.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// Returns:
+    /// * `entity`: The entity which will be spawned
+    fn spawn(ctxt: FunctionCallContext) -> Result<Val<Entity>, InteropError> {
+        profiling::function_scope!("spawn");
+        let world = ctxt.world()?;
+        Ok(Val(world.queue_spawn()?))
+    }
+
+    /// Queues the insertion of a copy of the given component value into the provided entity.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `entity`: The entity to insert the component into.
+    /// * `registration`: The component registration of the component to insert.
+    /// * `value`: The value of the component to insert. Can be constructed using `construct`
+    /// Returns:
+    /// * `result`: Nothing if the insertion was queued successfully.
+    fn insert(
+        ctxt: FunctionCallContext,
+        entity: Val<Entity>,
+        registration: Val<ScriptComponentRegistration>,
+        value: ReflectReference,
+    ) -> Result<(), InteropError> {
+        profiling::function_scope!("insert");
+        let world = ctxt.world()?;
+        world.queue_insert_component(*entity, registration.into_inner(), value)
+    }
+
+    /// Queues the removal of the given component from the entity.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `entity`: The entity to remove the component from.
+    /// * `registration`: The component to remove.
+    /// Returns:
+    /// * `result`: Nothing if the removal was queued successfully.
+    fn remove(
+        ctxt: FunctionCallContext,
+        entity: Val<Entity>,
+        registration: Val<ScriptComponentRegistration>,
+    ) -> Result<(), InteropError> {
+        profiling::function_scope!("remove");
+        let world = ctxt.world()?;
+        world.queue_remove_component(*entity, registration.into_inner())
+    }
+
+    /// Queues the despawning of the given entity.
+    ///
+    /// Arguments:
+    /// * `ctxt`: The function call context.
+    /// * `entity`: The entity to despawn.
+    /// Returns:
+    /// * `result`: Nothing if the despawn was queued successfully.
+    fn despawn(ctxt: FunctionCallContext, entity: Val<Entity>) -> Result<(), InteropError> {
+        profiling::function_scope!("despawn");
+        let world = ctxt.world()?;
+        world.queue_despawn(*entity)
+    }
+}
+
 #[script_bindings(
     remote,
     bms_core_path = "bevy_mod_scripting_core",
@@ -1224,23 +1987,36 @@ impl GlobalNamespace {
         >,
         payload: HashMap<String, ScriptValue>,
     ) -> Result<ReflectReference, InteropError> {
-        let registration = match registration.into_left() {
-            Ok(l) => l.into_inner(),
+        let (type_registration, registration) = match registration.into_left() {
+            Ok(l) => (l.into_inner(), None),
             Err(r) => match r.into_left() {
-                Ok(l) => (l.into_inner()).into_type_registration(),
-                Err(r) => (r.into_inner()).into_type_registration(),
+                Ok(l) => {
+                    let l = l.into_inner();
+                    (l.clone().into_type_registration(), Some(l))
+                }
+                Err(r) => ((r.into_inner()).into_type_registration(), None),
             },
         };
 
         let world = ctxt.world()?;
         let one_indexed = ctxt.convert_to_0_indexed();
 
-        let val = world.construct(registration.clone(), payload, one_indexed)?;
+        let val: Box<dyn PartialReflect> = match registration {
+            // script registered components know their fields at runtime only
+            Some(component) if component.is_dynamic_script_component() => {
+                Box::new(world.construct_script_component(&component, payload)?)
+            }
+            // the typed fields of script components are not part of the static type information
+            _ if type_registration.type_id() == TypeId::of::<DynamicComponent>() => {
+                Box::new(world.construct_untyped_script_component(payload)?)
+            }
+            _ => world.construct(type_registration.clone(), payload, one_indexed)?,
+        };
         let allocator = world.allocator();
         let mut allocator = allocator.write();
         let reflect_val = val.try_into_reflect().map_err(|_| {
             InteropError::failed_from_reflect(
-                Some(registration.type_id()),
+                Some(type_registration.type_id()),
                 "Could not construct the type",
             )
         })?;
@@ -1266,6 +2042,47 @@ impl GlobalNamespace {
     }
 }
 
+/// The components passed to `spawn_with` and `insert_components` along with the values to construct them from.
+///
+/// Either a map keyed by the type names of the components, or a list of pairs of component or type registrations and values,
+/// which is what tables keyed by registrations are converted to in languages supporting them.
+type ComponentPayloads = Union<
+    HashMap<String, HashMap<String, ScriptValue>>,
+    Vec<(
+        Union<Val<ScriptComponentRegistration>, Val<ScriptTypeRegistration>>,
+        HashMap<String, ScriptValue>,
+    )>,
+>;
+
+/// Resolves the component registrations of the given [`ComponentPayloads`], failing if any of them is not a registered component
+fn resolve_component_payloads(
+    world: &WorldGuard,
+    components: ComponentPayloads,
+) -> Result<Vec<(ScriptComponentRegistration, HashMap<String, ScriptValue>)>, InteropError> {
+    match components.into_left() {
+        Ok(by_name) => by_name
+            .into_iter()
+            .map(|(name, payload)| Ok((world.get_component_type_by_name(name)?, payload)))
+            .collect(),
+        Err(by_registration) => by_registration
+            .into_iter()
+            .map(|(registration, payload)| {
+                let registration = match registration.into_left() {
+                    Ok(registration) => registration.into_inner(),
+                    Err(registration) => world
+                        .get_component_type(registration.into_inner())?
+                        .map_err(|registration| {
+                            InteropError::unregistered_component_or_resource_type(
+                                registration.type_registration().type_info().type_path(),
+                            )
+                        })?,
+                };
+                Ok((registration, payload))
+            })
+            .collect(),
+    }
+}
+
 pub fn register_core_functions(app: &mut App) {
     let world = app.world_mut();
     // we don't exclude from compilation here,
@@ -1275,12 +2092,15 @@ pub fn register_core_functions(app: &mut App) {
     #[cfg(feature = "core_functions")]
     {
         register_world_functions(world);
+        #[cfg(feature = "bevy_state")]
+        register_world_state_functions(world);
 
         register_reflect_reference_functions(world);
 
         register_script_type_registration_functions(world);
         register_script_component_registration_functions(world);
         register_script_resource_registration_functions(world);
+        register_script_event_registration_functions(world);
 
         register_script_query_builder_functions(world);
         register_script_query_result_functions(world);
@@ -1289,6 +2109,11 @@ pub fn register_core_functions(app: &mut App) {
         register_reflect_system_functions(world);
         register_script_system_builder_functions(world);
 
+        register_yield_condition_functions(world);
+        register_script_observer_trigger_functions(world);
+        register_script_query_filter_functions(world);
+        register_script_commands_functions(world);
+
         register_global_namespace_functions(world);
     }
 }
@@ -10,7 +10,7 @@ use bevy_mod_scripting_core::{
                 DynamicScriptFunction, DynamicScriptFunctionMut, FunctionCallContext,
             },
         },
-        ReflectReference,
+        DynamicComponentInfo, ReflectReference, ScriptValue,
     },
     docgen::{
         info::FunctionInfo,
@@ -262,6 +262,80 @@ impl<'t> LadFileBuilder<'t> {
         self
     }
 
+    /// Adds a component registered by a script to the list of types.
+    ///
+    /// Script registered components share a single rust type, so each component is added as its own type,
+    /// along with a `<Name>Fields` type describing the typed fields of its schema.
+    pub fn add_script_component(&mut self, info: &DynamicComponentInfo) -> &mut Self {
+        let identifier = info.name.clone();
+        let fields_identifier = format!("{identifier}Fields");
+        let component_id =
+            LadTypeId::new_string_id(format!("script_components::{identifier}").into());
+        let fields_id =
+            LadTypeId::new_string_id(format!("script_components::{fields_identifier}").into());
+
+        let fields = info
+            .schema
+            .fields()
+            .iter()
+            .map(|field| LadNamedField {
+                name: field.name().to_owned(),
+                type_: self.lad_id_from_type_id(field.type_registration().type_id()),
+            })
+            .collect();
+        self.file.types.insert(
+            fields_id.clone(),
+            LadType {
+                identifier: fields_identifier.clone(),
+                crate_: None,
+                path: fields_id.to_string(),
+                generics: vec![],
+                documentation: Some(format!(
+                    "The typed fields of the script registered component `{identifier}`."
+                )),
+                associated_functions: vec![],
+                layout: LadTypeLayout::MonoVariant(LadVariant::Struct {
+                    name: fields_identifier.into(),
+                    fields,
+                }),
+                generated: false,
+                insignificance: default_importance(),
+            },
+        );
+
+        let data_id = self.lad_id_from_type_id(TypeId::of::<ScriptValue>());
+        self.file.types.insert(
+            component_id.clone(),
+            LadType {
+                identifier: identifier.clone(),
+                crate_: None,
+                path: component_id.to_string(),
+                generics: vec![],
+                documentation: Some(
+                    "A component registered by a script, with an untyped `data` field and the typed `fields` of its schema."
+                        .to_owned(),
+                ),
+                associated_functions: vec![],
+                layout: LadTypeLayout::MonoVariant(LadVariant::Struct {
+                    name: identifier.into(),
+                    fields: vec![
+                        LadNamedField {
+                            name: "data".to_owned(),
+                            type_: data_id,
+                        },
+                        LadNamedField {
+                            name: "fields".to_owned(),
+                            type_: fields_id,
+                        },
+                    ],
+                }),
+                generated: false,
+                insignificance: default_importance(),
+            },
+        );
+        self
+    }
+
     /// Add a type definition to the LAD file.
     ///
     /// Equivalent to calling [`Self::add_type_info`] with `T::type_info()`.
@@ -787,17 +861,19 @@ impl<'t> LadFileBuilder<'t> {
 
 #[cfg(test)]
 mod test {
+    use bevy::ecs::component::ComponentId;
     use bevy_mod_scripting_core::{
         bindings::{
             function::{
                 from::Ref,
                 namespace::{GlobalNamespace, IntoNamespace},
             },
-            Union, Val,
+            DynamicComponent, DynamicComponentField, DynamicComponentSchema,
+            ScriptComponentRegistration, ScriptTypeRegistration, Union, Val,
         },
         docgen::info::GetFunctionInfo,
     };
-    use bevy_reflect::Reflect;
+    use bevy_reflect::{GetTypeRegistration, Reflect, TypePath};
 
     use super::*;
 
@@ -996,6 +1072,74 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_script_components_are_documented_with_their_schema() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<f32>();
+        type_registry.register::<ScriptValue>();
+
+        let f32_registration = ScriptTypeRegistration::new(std::sync::Arc::new(
+            type_registry.get(TypeId::of::<f32>()).unwrap().clone(),
+        ));
+        let component_registration = ScriptTypeRegistration::new(std::sync::Arc::new(
+            DynamicComponent::get_type_registration(),
+        ));
+        let schema = DynamicComponentSchema::new(
+            "Mover",
+            vec![DynamicComponentField::new_with_default(
+                "speed",
+                f32_registration,
+                Box::new(2.5f32),
+            )
+            .unwrap()],
+        )
+        .unwrap();
+        let info = DynamicComponentInfo {
+            name: "Mover".to_owned(),
+            registration: ScriptComponentRegistration::new(
+                component_registration,
+                ComponentId::new(0),
+            ),
+            schema,
+        };
+
+        let mut builder = LadFileBuilder::new_empty(&type_registry);
+        let file = builder.add_script_component(&info).build();
+
+        let component = file
+            .types
+            .get(&LadTypeId::new_string_id("script_components::Mover".into()))
+            .unwrap();
+        let fields = file
+            .types
+            .get(&LadTypeId::new_string_id(
+                "script_components::MoverFields".into(),
+            ))
+            .unwrap();
+
+        let field_names = |ty: &LadType| match &ty.layout {
+            LadTypeLayout::MonoVariant(LadVariant::Struct { fields, .. }) => fields
+                .iter()
+                .map(|f| (f.name.clone(), f.type_.to_string()))
+                .collect::<Vec<_>>(),
+            _ => panic!("expected a struct layout"),
+        };
+        pretty_assertions::assert_eq!(
+            field_names(component),
+            vec![
+                ("data".to_owned(), ScriptValue::type_path().to_owned()),
+                (
+                    "fields".to_owned(),
+                    "script_components::MoverFields".to_owned()
+                ),
+            ]
+        );
+        pretty_assertions::assert_eq!(
+            field_names(fields),
+            vec![("speed".to_owned(), "f32".to_owned())]
+        );
+    }
+
     /// Set to true to put output into test_assets.
     const BLESS_TEST_FILE: bool = false;
 
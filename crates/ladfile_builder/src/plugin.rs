@@ -13,7 +13,7 @@ use bevy::{
 use bevy_mod_scripting_core::bindings::{
     function::{namespace::Namespace, script_function::AppScriptFunctionRegistry},
     globals::AppScriptGlobalsRegistry,
-    IntoNamespace, MarkAsCore, MarkAsGenerated, MarkAsSignificant,
+    AppScriptComponentRegistry, IntoNamespace, MarkAsCore, MarkAsGenerated, MarkAsSignificant,
 };
 use ladfile::{default_importance, LadTypeKind};
 
@@ -73,16 +73,18 @@ impl ScriptingDocgenPlugin {
     }
 }
 
-/// The function used to generate a ladfile from pre-populated type, function and global registries
+/// The function used to generate a ladfile from pre-populated type, function, global and script component registries
 pub fn generate_lad_file(
     type_registry: &AppTypeRegistry,
     function_registry: &AppScriptFunctionRegistry,
     global_registry: &AppScriptGlobalsRegistry,
+    component_registry: &AppScriptComponentRegistry,
     settings: &LadFileSettings,
 ) {
     let type_registry = type_registry.read();
     let function_registry = function_registry.read();
     let global_registry = global_registry.read();
+    let component_registry = component_registry.read();
     let mut builder = LadFileBuilder::new(&type_registry);
     builder
         .set_description(settings.description)
@@ -152,6 +154,11 @@ pub fn generate_lad_file(
         builder.add_instance_manually(key.to_string(), false, LadTypeKind::Val(lad_type_id));
     }
 
+    // find components registered by scripts so far
+    for info in component_registry.iter() {
+        builder.add_script_component(info);
+    }
+
     let file = builder.build();
 
     let mut path = PathBuf::from("assets");
@@ -181,12 +188,14 @@ fn generate_lad_file_system(
     type_registry: Res<AppTypeRegistry>,
     function_registry: Res<AppScriptFunctionRegistry>,
     global_registry: Res<AppScriptGlobalsRegistry>,
+    component_registry: Res<AppScriptComponentRegistry>,
     settings: Res<LadFileSettings>,
 ) {
     generate_lad_file(
         &type_registry,
         &function_registry,
         &global_registry,
+        &component_registry,
         &settings,
     );
 }
@@ -194,6 +203,7 @@ fn generate_lad_file_system(
 impl Plugin for ScriptingDocgenPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(self.0.clone());
+        app.init_resource::<AppScriptComponentRegistry>();
         app.add_systems(Startup, generate_lad_file_system);
     }
 }
@@ -83,6 +83,21 @@ impl FromLua for LuaScriptValue {
                                 map.insert(str_, v.into());
                             }
                             return Ok(LuaScriptValue::from(ScriptValue::Map(map)));
+                        } else if k.is_userdata() {
+                            // if the key is a reference, i.e. a type registration, then it's a list of key value pairs
+                            let mut pairs = Vec::with_capacity(table.len()? as usize);
+                            pairs.push(ScriptValue::List(vec![
+                                LuaScriptValue::from_lua(k, lua)?.into(),
+                                v.into(),
+                            ]));
+                            for pair in iter {
+                                let (k, v) = pair?;
+                                pairs.push(ScriptValue::List(vec![
+                                    LuaScriptValue::from_lua(k, lua)?.into(),
+                                    v.into(),
+                                ]));
+                            }
+                            return Ok(LuaScriptValue::from(ScriptValue::List(pairs)));
                         } else {
                             // if the key is an integer, then it's a list
                             let mut vec = Vec::with_capacity(table.len()? as usize);
@@ -1,10 +1,17 @@
 //! Lua integration for the bevy_mod_scripting system.
 use bevy::{
     app::Plugin,
-    ecs::{entity::Entity, world::World},
+    ecs::{
+        entity::Entity,
+        system::Resource,
+        world::{Mut, World},
+    },
 };
 use bevy_mod_scripting_core::{
-    asset::Language,
+    asset::{
+        init_script_artifact_cache, ContentHash, Language, ScriptArtifactCache,
+        ScriptModuleResolver,
+    },
     bindings::{
         function::namespace::Namespace, globals::AppScriptGlobalsRegistry,
         script_value::ScriptValue, ThreadWorldContainer, WorldContainer,
@@ -12,17 +19,23 @@ use bevy_mod_scripting_core::{
     context::{ContextBuilder, ContextInitializer, ContextPreHandlingInitializer},
     error::ScriptError,
     event::CallbackLabel,
+    handler::{ExecutionBudget, ThreadExecutionBudget},
     reflection_extensions::PartialReflectExt,
     runtime::RuntimeSettings,
     script::ScriptId,
-    IntoScriptPluginParams, ScriptingPlugin,
+    ConfigureScriptAssetSettings, IntoScriptPluginParams, ScriptingPlugin,
 };
 use bindings::{
     reference::{LuaReflectReference, LuaStaticReflectReference},
     script_value::LuaScriptValue,
 };
 pub use mlua;
-use mlua::{Function, IntoLua, Lua, MultiValue};
+use mlua::{ChunkMode, Function, IntoLua, Lua, MultiValue, Table, Value};
+#[cfg(feature = "mlua_async")]
+use {
+    bevy_mod_scripting_core::coroutine::{ThreadScriptCoroutine, YieldCondition},
+    mlua::FromLua,
+};
 
 /// Bindings for lua.
 pub mod bindings;
@@ -46,6 +59,43 @@ impl AsMut<ScriptingPlugin<Self>> for LuaScriptingPlugin {
 pub struct LuaScriptingPlugin {
     /// The internal scripting plugin
     pub scripting_plugin: ScriptingPlugin<Self>,
+    /// Settings for loading precompiled bytecode, disabled by default
+    pub bytecode_settings: LuaBytecodeSettings,
+}
+
+impl LuaScriptingPlugin {
+    /// Enables loading precompiled bytecode from scripts with the `.luac` extension, see [`LuaBytecodeSettings`]
+    pub fn enable_bytecode_loading(mut self) -> Self {
+        self.bytecode_settings.allow_bytecode = true;
+        self
+    }
+}
+
+/// Settings for loading precompiled lua bytecode, inserted as a resource by the [`LuaScriptingPlugin`]
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct LuaBytecodeSettings {
+    /// If set, scripts with the `.luac` extension are loaded as precompiled bytecode, and are not run through any preprocessors.
+    ///
+    /// Scripts with any other extension are always loaded as source code.
+    /// Disabled by default, as lua does not verify bytecode, and loading malformed bytecode can crash the application.
+    pub allow_bytecode: bool,
+}
+
+impl LuaBytecodeSettings {
+    /// The extension of precompiled bytecode scripts
+    pub const EXTENSION: &'static str = "luac";
+
+    /// Returns the mode the given script should be loaded in, only scripts with the bytecode extension are loaded as binary chunks and only if bytecode is allowed
+    fn chunk_mode(&self, script_id: &ScriptId) -> ChunkMode {
+        let is_bytecode = std::path::Path::new(script_id.as_ref())
+            .extension()
+            .is_some_and(|ext| ext == Self::EXTENSION);
+        if self.allow_bytecode && is_bytecode {
+            ChunkMode::Binary
+        } else {
+            ChunkMode::Text
+        }
+    }
 }
 
 impl Default for LuaScriptingPlugin {
@@ -55,6 +105,12 @@ impl Default for LuaScriptingPlugin {
                 context_assignment_strategy: Default::default(),
                 runtime_settings: RuntimeSettings::default(),
                 callback_handler: lua_handler,
+                execution_budget: Default::default(),
+                defined_callbacks: lua_defined_callbacks,
+                index_callbacks: true,
+                // functions assigned to globals from within callbacks are callbacks too
+                reindex_after_callbacks: true,
+                parallel_callbacks: false,
                 context_builder: ContextBuilder::<LuaScriptingPlugin> {
                     load: lua_context_load,
                     reload: lua_context_reload,
@@ -114,6 +170,16 @@ impl Default for LuaScriptingPlugin {
 
                         Ok(())
                     },
+                    |_script_id, context: &mut Lua| {
+                        // resolve modules through the asset server, the module cache is reset on every reload
+                        // so that changed modules are picked up by the scripts importing them
+                        context
+                            .set_named_registry_value(LUA_MODULES_KEY, context.create_table()?)?;
+                        context
+                            .globals()
+                            .set("require", context.create_function(lua_require)?)?;
+                        Ok(())
+                    },
                 ],
                 context_pre_handling_initializers: vec![|script_id, entity, context| {
                     let world = ThreadWorldContainer.try_get_world()?;
@@ -133,6 +199,7 @@ impl Default for LuaScriptingPlugin {
                 additional_supported_extensions: &[],
                 language: Language::Lua,
             },
+            bytecode_settings: Default::default(),
         }
     }
 }
@@ -140,6 +207,11 @@ impl Default for LuaScriptingPlugin {
 impl Plugin for LuaScriptingPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         self.scripting_plugin.build(app);
+        init_script_artifact_cache::<LuaBytecode>(app);
+        app.insert_resource(self.bytecode_settings);
+        if self.bytecode_settings.allow_bytecode {
+            app.add_precompiled_script_extensions(&[LuaBytecodeSettings::EXTENSION], Language::Lua);
+        }
     }
 
     fn finish(&self, app: &mut bevy::app::App) {
@@ -147,6 +219,162 @@ impl Plugin for LuaScriptingPlugin {
     }
 }
 
+/// The registry key of the table caching modules loaded via `require`
+const LUA_MODULES_KEY: &str = "bms_modules";
+
+/// Converts a module name as passed to `require` into an asset path,
+/// i.e. `lib.utils` becomes `lib/utils.lua`, names with an extension are used as is.
+fn lua_module_path(name: &str) -> String {
+    if name.ends_with(".lua") || name.ends_with(".luau") {
+        name.to_owned()
+    } else {
+        format!("{}.lua", name.replace('.', "/"))
+    }
+}
+
+/// A replacement for the standard `require`, which loads modules as script assets via the [`ScriptModuleResolver`].
+///
+/// Modules already present in `package.loaded`, such as the standard library, are returned as is.
+fn lua_require(lua: &Lua, name: String) -> Result<Value, mlua::Error> {
+    if let Some(package) = lua.globals().get::<Option<Table>>("package")? {
+        if let Some(loaded) = package.get::<Option<Table>>("loaded")? {
+            let value = loaded.get::<Value>(name.as_str())?;
+            if !value.is_nil() {
+                return Ok(value);
+            }
+        }
+    }
+
+    let path = lua_module_path(&name);
+    let modules = lua.named_registry_value::<Table>(LUA_MODULES_KEY)?;
+    let value = modules.get::<Value>(path.as_str())?;
+    if !value.is_nil() {
+        return Ok(value);
+    }
+
+    let world = ThreadWorldContainer.try_get_world()?;
+    let dependent: ScriptId = lua.globals().get::<String>("script_id")?.into();
+    let module = ScriptModuleResolver::resolve(world, &dependent, &path)?;
+
+    let value = lua
+        .load(module.content.as_ref())
+        .set_name(module.id.to_string())
+        .set_mode(ChunkMode::Text)
+        .call::<Value>(name)?;
+    // modules which return nothing are cached as `true`, like the standard `require` does
+    let value = match value {
+        Value::Nil => Value::Boolean(true),
+        value => value,
+    };
+    modules.set(path, value.clone())?;
+    Ok(value)
+}
+
+/// Lua bytecode compiled from the content of a script
+#[derive(Clone, Debug)]
+pub struct LuaBytecode(pub Vec<u8>);
+
+/// Compiles lua source code into bytecode.
+///
+/// The bytecode can be saved as a `.luac` script artifact, which skips parsing when loaded, see [`LuaBytecodeSettings`] for enabling this.
+/// Bytecode is specific to the lua version it was compiled with, debug information such as line numbers is removed if `strip` is set.
+pub fn compile_lua_bytecode(content: &[u8], strip: bool) -> Result<LuaBytecode, ScriptError> {
+    #[cfg(not(feature = "luau"))]
+    let bytecode = Lua::new()
+        .load(content)
+        .set_mode(ChunkMode::Text)
+        .into_function()?
+        .dump(strip);
+    #[cfg(feature = "luau")]
+    let bytecode = mlua::Compiler::new()
+        .set_debug_level(if strip { 0 } else { 1 })
+        .compile(content)?;
+    Ok(LuaBytecode(bytecode))
+}
+
+/// Loads the content of a script as a function, going through the [`ScriptArtifactCache`] so that content which was already compiled is not parsed again.
+/// Hashes the content of a script for the bytecode cache.
+///
+/// Lua bytecode embeds the name of its chunk, so the script id is part of the hash and differently named scripts do not share bytecode.
+fn lua_bytecode_hash(script_id: &ScriptId, content: &[u8]) -> ContentHash {
+    ContentHash::from_parts([script_id.as_bytes(), content])
+}
+
+fn load_lua_chunk(
+    context: &Lua,
+    script_id: &ScriptId,
+    content: &[u8],
+) -> Result<Function, ScriptError> {
+    let world = ThreadWorldContainer.try_get_world().ok();
+    let mode = world
+        .as_ref()
+        .and_then(|world| {
+            world
+                .with_resource(|settings: &LuaBytecodeSettings| settings.chunk_mode(script_id))
+                .ok()
+        })
+        .unwrap_or(ChunkMode::Text);
+    // the chunk name shows up in errors, and lets them be mapped back through the source map of the script
+    let chunk_name = script_id.to_string();
+    if mode == ChunkMode::Binary {
+        // precompiled bytecode is not parsed, there is nothing to cache
+        return Ok(context
+            .load(content)
+            .set_name(chunk_name)
+            .set_mode(mode)
+            .into_function()?);
+    }
+
+    let hash = lua_bytecode_hash(script_id, content);
+    let cached = world.as_ref().and_then(|world| {
+        world
+            .with_resource_mut(|mut cache: Mut<ScriptArtifactCache<LuaBytecode>>| {
+                cache
+                    .track(script_id.clone(), hash)
+                    .then(|| cache.get(&hash).cloned())
+                    .flatten()
+            })
+            .ok()
+            .flatten()
+    });
+    if let Some(bytecode) = cached {
+        return Ok(context
+            .load(bytecode.0)
+            .set_name(chunk_name)
+            .set_mode(ChunkMode::Binary)
+            .into_function()?);
+    }
+
+    #[cfg(not(feature = "luau"))]
+    let (function, bytecode) = {
+        let function = context
+            .load(content)
+            .set_name(chunk_name)
+            .set_mode(ChunkMode::Text)
+            .into_function()?;
+        let bytecode = function.dump(false);
+        (function, bytecode)
+    };
+    #[cfg(feature = "luau")]
+    let (function, bytecode) = {
+        let bytecode = mlua::Compiler::new().compile(content)?;
+        let function = context
+            .load(&bytecode)
+            .set_name(chunk_name)
+            .set_mode(ChunkMode::Binary)
+            .into_function()?;
+        (function, bytecode)
+    };
+
+    if let Some(world) = world {
+        // the cache is optional
+        let _ = world.with_resource_mut(|mut cache: Mut<ScriptArtifactCache<LuaBytecode>>| {
+            cache.insert(script_id.clone(), hash, LuaBytecode(bytecode))
+        });
+    }
+    Ok(function)
+}
+
 fn load_lua_content_into_context(
     context: &mut Lua,
     script_id: &ScriptId,
@@ -162,9 +390,8 @@ fn load_lua_content_into_context(
         .iter()
         .try_for_each(|init| init(script_id, Entity::from_raw(0), context))?;
 
-    context
-        .load(content)
-        .exec()
+    load_lua_chunk(context, script_id, content)?
+        .call::<()>(())
         .map_err(ScriptError::from_mlua_error)?;
 
     Ok(())
@@ -214,6 +441,22 @@ pub fn lua_context_reload(
     Ok(())
 }
 
+/// Lists the global functions of a lua context, which are the callbacks it can handle.
+///
+/// Functions assigned to globals after the script is loaded, i.e. from within callbacks other than `on_script_loaded`, are not listed.
+pub fn lua_defined_callbacks(context: &Lua) -> Option<Vec<CallbackLabel>> {
+    Some(
+        context
+            .globals()
+            .pairs::<String, Value>()
+            .filter_map(|pair| match pair {
+                Ok((name, Value::Function(_))) => CallbackLabel::new(&name),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 #[profiling::function]
 /// The lua handler for events
@@ -230,6 +473,12 @@ pub fn lua_handler(
         .iter()
         .try_for_each(|init| init(script_id, entity, context))?;
 
+    #[cfg(feature = "mlua_async")]
+    if let Some(thread) = ThreadScriptCoroutine::take_resumed::<mlua::Thread>()? {
+        let input = into_lua_args(args, context)?;
+        return resume_lua_coroutine(context, thread, input);
+    }
+
     let handler: Function = match context.globals().raw_get(callback_label.as_ref()) {
         Ok(handler) => handler,
         // not subscribed to this event type
@@ -243,18 +492,176 @@ pub fn lua_handler(
         }
     };
 
-    let input = MultiValue::from_vec(
+    let input = into_lua_args(args, context)?;
+
+    #[cfg(feature = "mlua_async")]
+    let out = resume_lua_coroutine(context, context.create_thread(handler)?, input)?;
+    #[cfg(not(feature = "mlua_async"))]
+    let out =
+        with_execution_budget_hook(context, None, || handler.call::<LuaScriptValue>(input))?.into();
+    Ok(out)
+}
+
+fn into_lua_args(args: Vec<ScriptValue>, context: &Lua) -> Result<MultiValue, mlua::Error> {
+    Ok(MultiValue::from_vec(
         args.into_iter()
             .map(|arg| LuaScriptValue::from(arg).into_lua(context))
             .collect::<Result<_, _>>()?,
-    );
+    ))
+}
+
+/// Resumes the coroutine running a callback.
+///
+/// If the coroutine yields, the callback is suspended until the yielded [`YieldCondition`] is met.
+#[cfg(feature = "mlua_async")]
+fn resume_lua_coroutine(
+    context: &Lua,
+    thread: mlua::Thread,
+    input: MultiValue,
+) -> Result<ScriptValue, ScriptError> {
+    let mut out = with_execution_budget_hook(context, Some(&thread), || {
+        thread.resume::<MultiValue>(input)
+    })?;
+    let value = match out.pop_front() {
+        Some(value) => LuaScriptValue::from_lua(value, context)?.into(),
+        None => ScriptValue::Unit,
+    };
+
+    if thread.status() == mlua::ThreadStatus::Resumable {
+        let world = ThreadWorldContainer.try_get_world()?;
+        let condition = YieldCondition::from_script_value(value, world)?;
+        ThreadScriptCoroutine::suspend(condition, thread);
+        return Ok(ScriptValue::Unit);
+    }
+    Ok(value)
+}
+
+/// Marks a context as running a callback with the execution budget hook installed
+struct ExecutionBudgetHookInstalled;
+
+/// Runs the closure with a hook installed which aborts the running callback once it goes over the [`ThreadExecutionBudget`].
+///
+/// The hook is removed from the thread it was installed on once the closure returns, even if the callback yielded.
+/// Callbacks nested in one which installed the hook leave it in place.
+/// Lua only supports a single hook, so the budget cannot be enforced if a hook was installed by anything else and the callback is refused instead.
+fn with_execution_budget_hook<O>(
+    context: &Lua,
+    thread: Option<&mlua::Thread>,
+    f: impl FnOnce() -> Result<O, mlua::Error>,
+) -> Result<O, mlua::Error> {
+    let Some(budget) = ThreadExecutionBudget::current() else {
+        return f();
+    };
+    if context
+        .app_data_ref::<ExecutionBudgetHookInstalled>()
+        .is_some()
+    {
+        return f();
+    }
+    if has_foreign_hook(context)? {
+        return Err(mlua::Error::RuntimeError(
+            "Cannot enforce the execution budget of the callback, a hook is already installed on the Lua context"
+                .to_owned(),
+        ));
+    }
+
+    set_execution_budget_hook(context, thread, budget);
+    context.set_app_data(ExecutionBudgetHookInstalled);
+    let out = f();
+    context.remove_app_data::<ExecutionBudgetHookInstalled>();
+    remove_execution_budget_hook(context, thread);
+    out
+}
+
+/// The maximum number of instructions between budget checks
+const EXECUTION_BUDGET_HOOK_INTERVAL: u64 = 1000;
+
+/// Installs a hook aborting the running callback once it goes over the [`ThreadExecutionBudget`].
+///
+/// Hooks are per coroutine, so if the callback is running in one the hook is installed on its thread.
+#[cfg(not(feature = "luau"))]
+fn set_execution_budget_hook(
+    context: &Lua,
+    thread: Option<&mlua::Thread>,
+    budget: ExecutionBudget,
+) {
+    let interval = budget
+        .max_instructions
+        .map_or(EXECUTION_BUDGET_HOOK_INTERVAL, |max| {
+            max.clamp(1, EXECUTION_BUDGET_HOOK_INTERVAL)
+        });
+    let triggers = mlua::HookTriggers::new().every_nth_instruction(interval as u32);
+    let hook = move |_: &Lua, _: mlua::Debug<'_>| {
+        ThreadExecutionBudget::consume(interval)
+            .map(|_| mlua::VmState::Continue)
+            .map_err(mlua::Error::external)
+    };
+    match thread {
+        Some(thread) => thread.set_hook(triggers, hook),
+        None => context.set_hook(triggers, hook),
+    }
+}
+
+/// Removes the hook installed by [`set_execution_budget_hook`] from the thread it was installed on.
+#[cfg(not(feature = "luau"))]
+fn remove_execution_budget_hook(context: &Lua, thread: Option<&mlua::Thread>) {
+    // setting a hook without any triggers removes it from the thread
+    if let Some(thread) = thread {
+        thread.set_hook(mlua::HookTriggers::new(), |_, _| {
+            Ok(mlua::VmState::Continue)
+        });
+    }
+    // removes the hook from the main thread, along with the hook callback
+    context.remove_hook();
+}
+
+/// Checks if the context has a hook installed which would be replaced by the execution budget hook
+#[cfg(not(feature = "luau"))]
+fn has_foreign_hook(context: &Lua) -> Result<bool, mlua::Error> {
+    let mut installed = false;
+    // safety: only reads the hook of the main thread, the stack is left untouched
+    unsafe {
+        context.exec_raw::<()>((), |state| {
+            installed = mlua::ffi::lua_gethook(state).is_some();
+        })?
+    };
+    Ok(installed)
+}
+
+/// Installs an interrupt aborting the running callback once it goes over the [`ThreadExecutionBudget`].
+///
+/// Luau does not support instruction hooks, interrupts run at function calls and loop iterations instead,
+/// each of which is counted as a single instruction.
+#[cfg(feature = "luau")]
+fn set_execution_budget_hook(context: &Lua, _: Option<&mlua::Thread>, _: ExecutionBudget) {
+    context.set_interrupt(|_| {
+        ThreadExecutionBudget::consume(1)
+            .map(|_| mlua::VmState::Continue)
+            .map_err(mlua::Error::external)
+    });
+}
 
-    let out = handler.call::<LuaScriptValue>(input)?;
-    Ok(out.into())
+#[cfg(feature = "luau")]
+fn remove_execution_budget_hook(context: &Lua, _: Option<&mlua::Thread>) {
+    context.remove_interrupt();
+}
+
+/// Checks if the context has an interrupt installed which would be replaced by the execution budget interrupt
+#[cfg(feature = "luau")]
+fn has_foreign_hook(context: &Lua) -> Result<bool, mlua::Error> {
+    let mut installed = false;
+    // safety: only reads the callbacks of the state, the stack is left untouched
+    unsafe {
+        context.exec_raw::<()>((), |state| {
+            installed = (*mlua::ffi::lua_callbacks(state)).interrupt.is_some();
+        })?
+    };
+    Ok(installed)
 }
 
 #[cfg(test)]
 mod test {
+    use bevy_mod_scripting_core::bindings::WorldGuard;
     use mlua::Value;
 
     use super::*;
@@ -297,4 +704,138 @@ mod test {
         assert!(globals.get::<Value>("hello_world_from_first_load").is_ok());
         assert!(globals.get::<Value>("hello_world_from_second_load").is_ok());
     }
+
+    #[test]
+    fn test_precompiled_bytecode_loads_like_source() {
+        let mut world = World::default();
+        world.insert_resource(LuaBytecodeSettings {
+            allow_bytecode: true,
+        });
+        let bytecode = compile_lua_bytecode(b"precompiled_value = 42", false).unwrap();
+
+        WorldGuard::with_static_guard(&mut world, |guard| {
+            ThreadWorldContainer.set_world(guard.clone()).unwrap();
+            let lua =
+                lua_context_load(&ScriptId::from("asd.luac"), &bytecode.0, &[], &[], &()).unwrap();
+            assert_eq!(lua.globals().get::<i64>("precompiled_value").unwrap(), 42);
+
+            // sources are never loaded as bytecode
+            assert!(
+                lua_context_load(&ScriptId::from("asd.lua"), &bytecode.0, &[], &[], &()).is_err()
+            );
+        });
+    }
+
+    #[test]
+    fn test_bytecode_is_not_loaded_unless_allowed() {
+        let mut world = World::default();
+        world.init_resource::<LuaBytecodeSettings>();
+        let bytecode = compile_lua_bytecode(b"precompiled_value = 42", false).unwrap();
+
+        WorldGuard::with_static_guard(&mut world, |guard| {
+            ThreadWorldContainer.set_world(guard.clone()).unwrap();
+            assert!(
+                lua_context_load(&ScriptId::from("asd.luac"), &bytecode.0, &[], &[], &()).is_err()
+            );
+        });
+    }
+
+    #[test]
+    fn test_unchanged_script_is_loaded_from_artifact_cache() {
+        let mut world = World::default();
+        world.init_resource::<ScriptArtifactCache<LuaBytecode>>();
+        let script_id = ScriptId::from("asd.lua");
+        let content = b"cached_value = 1";
+
+        WorldGuard::with_static_guard(&mut world, |guard| {
+            ThreadWorldContainer.set_world(guard.clone()).unwrap();
+            lua_context_load(&script_id, content, &[], &[], &()).unwrap();
+
+            // replace the cached artifact, so a cache hit can be told apart from compiling the content again
+            guard
+                .with_resource_mut(|mut cache: Mut<ScriptArtifactCache<LuaBytecode>>| {
+                    assert_eq!(cache.len(), 1);
+                    let bytecode = compile_lua_bytecode(b"cached_value = 2", false).unwrap();
+                    cache.insert(
+                        script_id.clone(),
+                        lua_bytecode_hash(&script_id, content),
+                        bytecode,
+                    );
+                })
+                .unwrap();
+
+            let lua = lua_context_load(&script_id, content, &[], &[], &()).unwrap();
+            assert_eq!(lua.globals().get::<i64>("cached_value").unwrap(), 2);
+
+            let lua = lua_context_load(&script_id, b"cached_value = 3", &[], &[], &()).unwrap();
+            assert_eq!(lua.globals().get::<i64>("cached_value").unwrap(), 3);
+        });
+    }
+
+    #[cfg(not(feature = "luau"))]
+    #[test]
+    fn test_execution_budget_does_not_replace_existing_hook() {
+        let lua = Lua::new();
+        lua.set_hook(mlua::HookTriggers::EVERY_LINE, |_, _| {
+            Ok(mlua::VmState::Continue)
+        });
+
+        let out = ThreadExecutionBudget::with_budget(
+            ExecutionBudget::unlimited().with_max_instructions(1000),
+            &ScriptId::from("asd.lua"),
+            &CallbackLabel::new_lossy("on_test"),
+            || {
+                Ok(with_execution_budget_hook(&lua, None, || {
+                    lua.load("return 1").eval::<i64>()
+                }))
+            },
+        )
+        .unwrap();
+
+        assert!(out.is_err());
+        assert!(has_foreign_hook(&lua).unwrap());
+    }
+
+    #[cfg(not(feature = "luau"))]
+    #[test]
+    fn test_execution_budget_hook_is_removed_from_yielded_thread() {
+        let lua = Lua::new();
+        let thread = lua
+            .create_thread(lua.load("coroutine.yield()").into_function().unwrap())
+            .unwrap();
+
+        ThreadExecutionBudget::with_budget(
+            ExecutionBudget::unlimited().with_max_instructions(1000),
+            &ScriptId::from("asd.lua"),
+            &CallbackLabel::new_lossy("on_test"),
+            || {
+                with_execution_budget_hook(&lua, Some(&thread), || thread.resume::<()>(()))?;
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(thread.status(), mlua::ThreadStatus::Resumable);
+        // the pointer of a thread is its state
+        let state = thread.to_pointer() as *mut mlua::ffi::lua_State;
+        assert!(unsafe { mlua::ffi::lua_gethook(state) }.is_none());
+    }
+
+    #[test]
+    fn test_defined_callbacks_lists_global_functions() {
+        let script_id = ScriptId::from("asd.lua");
+        let lua = lua_context_load(
+            &script_id,
+            b"function on_test() end\nlocal function helper() end\nnot_a_function = 2",
+            &[],
+            &[],
+            &(),
+        )
+        .unwrap();
+
+        let callbacks = lua_defined_callbacks(&lua).unwrap();
+        assert!(callbacks.contains(&CallbackLabel::new_lossy("on_test")));
+        assert!(!callbacks.contains(&CallbackLabel::new_lossy("helper")));
+        assert!(!callbacks.contains(&CallbackLabel::new_lossy("not_a_function")));
+    }
 }
@@ -4,17 +4,25 @@ use std::ops::Deref;
 
 use bevy::{
     app::Plugin,
-    ecs::{entity::Entity, world::World},
+    ecs::{
+        entity::Entity,
+        world::{Mut, World},
+    },
 };
 use bevy_mod_scripting_core::{
-    asset::Language,
+    asset::{
+        init_script_artifact_cache, ContentHash, Language, ScriptArtifactCache,
+        ScriptModuleResolver,
+    },
     bindings::{
         function::namespace::Namespace, globals::AppScriptGlobalsRegistry,
         script_value::ScriptValue, ThreadWorldContainer, WorldContainer,
     },
     context::{ContextBuilder, ContextInitializer, ContextPreHandlingInitializer},
-    error::ScriptError,
+    coroutine::{ThreadScriptCoroutine, YieldCondition},
+    error::{InteropError, ScriptError},
     event::CallbackLabel,
+    handler::ThreadExecutionBudget,
     reflection_extensions::PartialReflectExt,
     runtime::RuntimeSettings,
     script::ScriptId,
@@ -25,7 +33,10 @@ use bindings::{
     script_value::{FromDynamic, IntoDynamic},
 };
 use parking_lot::RwLock;
-use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST};
+use rhai::{
+    CallFnOptions, Dynamic, Engine, EvalAltResult, FnPtr, Module, ModuleResolver, Position, Scope,
+    Shared, AST,
+};
 
 pub use rhai;
 /// Bindings for rhai.
@@ -77,10 +88,38 @@ impl Default for RhaiScriptingPlugin {
                         engine.build_type::<RhaiReflectReference>();
                         engine.build_type::<RhaiStaticReflectReference>();
                         engine.register_iterator_result::<RhaiReflectReference, _>();
+                        engine.set_module_resolver(RhaiModuleResolver);
+                        // abort callbacks which run over their execution budget
+                        engine.on_progress(|_| {
+                            ThreadExecutionBudget::consume(1)
+                                .err()
+                                .map(|_| Dynamic::UNIT)
+                        });
+                        // rhai has no coroutines, instead callbacks suspend by passing the rest of their work as a closure
+                        engine.register_fn(
+                            "suspend",
+                            |condition: Dynamic,
+                             continuation: FnPtr|
+                             -> Result<(), Box<EvalAltResult>> {
+                                let world = ThreadWorldContainer.try_get_world()?;
+                                let condition = YieldCondition::from_script_value(
+                                    ScriptValue::from_dynamic(condition)?,
+                                    world,
+                                )?;
+                                ThreadScriptCoroutine::suspend(condition, continuation);
+                                Ok(())
+                            },
+                        );
                         Ok(())
                     }],
                 },
                 callback_handler: rhai_callback_handler,
+                execution_budget: Default::default(),
+                defined_callbacks: rhai_defined_callbacks,
+                index_callbacks: true,
+                // callbacks cannot define new functions, these only change on reload
+                reindex_after_callbacks: false,
+                parallel_callbacks: false,
                 context_builder: ContextBuilder {
                     load: rhai_context_load,
                     reload: rhai_context_reload,
@@ -167,9 +206,51 @@ impl Default for RhaiScriptingPlugin {
     }
 }
 
+/// Resolves `import` statements through the asset server via the [`ScriptModuleResolver`].
+///
+/// Paths without an extension are given the `.rhai` extension, i.e. `import "lib/utils" as utils;` loads `lib/utils.rhai`.
+pub struct RhaiModuleResolver;
+
+impl ModuleResolver for RhaiModuleResolver {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        source: Option<&str>,
+        path: &str,
+        _pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        let module_path = if path.ends_with(".rhai") {
+            path.to_owned()
+        } else {
+            format!("{path}.rhai")
+        };
+        let dependent: ScriptId = source
+            .ok_or_else(|| {
+                InteropError::script_module_load_error(
+                    module_path.clone(),
+                    "modules can only be imported by scripts",
+                )
+            })?
+            .to_owned()
+            .into();
+
+        let world = ThreadWorldContainer.try_get_world()?;
+        let module = ScriptModuleResolver::resolve(world, &dependent, &module_path)?;
+
+        let content = std::str::from_utf8(&module.content).map_err(|e| {
+            InteropError::script_module_load_error(module_path.clone(), e.to_string())
+        })?;
+        let mut ast = engine.compile(content)?;
+        // modules imported by this module are dependencies of the importing script too
+        ast.set_source(dependent.to_string());
+        Ok(Module::eval_ast_as_new(Scope::new(), &ast, engine)?.into())
+    }
+}
+
 impl Plugin for RhaiScriptingPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         self.scripting_plugin.build(app);
+        init_script_artifact_cache::<AST>(app);
     }
 
     fn finish(&self, app: &mut bevy::app::App) {
@@ -177,6 +258,39 @@ impl Plugin for RhaiScriptingPlugin {
     }
 }
 
+/// Compiles the content of a script, going through the [`ScriptArtifactCache`] so that content which was already compiled is not parsed again.
+fn compile_rhai_content(
+    runtime: &Engine,
+    script: &ScriptId,
+    content: &[u8],
+) -> Result<AST, ScriptError> {
+    let world = ThreadWorldContainer.try_get_world().ok();
+    let hash = ContentHash::new(content);
+    let cached = world.as_ref().and_then(|world| {
+        world
+            .with_resource_mut(|mut cache: Mut<ScriptArtifactCache<AST>>| {
+                cache
+                    .track(script.clone(), hash)
+                    .then(|| cache.get(&hash).cloned())
+                    .flatten()
+            })
+            .ok()
+            .flatten()
+    });
+    if let Some(ast) = cached {
+        return Ok(ast);
+    }
+
+    let ast = runtime.compile(std::str::from_utf8(content)?)?;
+    if let Some(world) = world {
+        // the cache is optional
+        let _ = world.with_resource_mut(|mut cache: Mut<ScriptArtifactCache<AST>>| {
+            cache.insert(script.clone(), hash, ast.clone())
+        });
+    }
+    Ok(ast)
+}
+
 // NEW helper function to load content into an existing context without clearing previous definitions.
 fn load_rhai_content_into_context(
     context: &mut RhaiScriptContext,
@@ -188,7 +302,7 @@ fn load_rhai_content_into_context(
 ) -> Result<(), ScriptError> {
     let runtime = runtime.read();
 
-    context.ast = runtime.compile(std::str::from_utf8(content)?)?;
+    context.ast = compile_rhai_content(&runtime, script, content)?;
     context.ast.set_source(script.to_string());
 
     initializers
@@ -246,6 +360,17 @@ pub fn rhai_context_reload(
     )
 }
 
+/// Lists the functions defined in the AST of a rhai context, which are the callbacks it can handle.
+pub fn rhai_defined_callbacks(context: &RhaiScriptContext) -> Option<Vec<CallbackLabel>> {
+    Some(
+        context
+            .ast
+            .iter_functions()
+            .filter_map(|f| CallbackLabel::new(f.name))
+            .collect(),
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 /// The rhai callback handler.
 pub fn rhai_callback_handler(
@@ -276,6 +401,20 @@ pub fn rhai_callback_handler(
     );
     let runtime = runtime.read();
 
+    if let Some(continuation) = ThreadScriptCoroutine::take_resumed::<FnPtr>()? {
+        // closures are functions in the AST with their captured variables curried in
+        let mut continuation_args = continuation.curry().to_vec();
+        continuation_args.extend(args);
+        let out = runtime.call_fn_with_options::<Dynamic>(
+            options,
+            &mut context.scope,
+            &context.ast,
+            continuation.fn_name(),
+            continuation_args,
+        )?;
+        return Ok(ScriptValue::from_dynamic(out)?);
+    }
+
     match runtime.call_fn_with_options::<Dynamic>(
         options,
         &mut context.scope,
@@ -301,6 +440,8 @@ pub fn rhai_callback_handler(
 
 #[cfg(test)]
 mod test {
+    use bevy_mod_scripting_core::bindings::WorldGuard;
+
     use super::*;
 
     #[test]
@@ -339,4 +480,54 @@ mod test {
         let hello2 = context.scope.get_value::<i64>("hello2").unwrap();
         assert_eq!(hello2, 3);
     }
+
+    #[test]
+    fn test_unchanged_script_is_loaded_from_artifact_cache() {
+        let mut world = World::default();
+        world.init_resource::<ScriptArtifactCache<AST>>();
+        let runtime = RhaiRuntime::new(Engine::new());
+        let script_id = ScriptId::from("asd.rhai");
+        let content = b"let cached_value = 1;";
+
+        WorldGuard::with_static_guard(&mut world, |guard| {
+            ThreadWorldContainer.set_world(guard.clone()).unwrap();
+            rhai_context_load(&script_id, content, &[], &[], &runtime).unwrap();
+
+            // replace the cached artifact, so a cache hit can be told apart from compiling the content again
+            guard
+                .with_resource_mut(|mut cache: Mut<ScriptArtifactCache<AST>>| {
+                    assert_eq!(cache.len(), 1);
+                    let ast = runtime.read().compile("let cached_value = 2;").unwrap();
+                    cache.insert(script_id.clone(), ContentHash::new(content), ast);
+                })
+                .unwrap();
+
+            let context = rhai_context_load(&script_id, content, &[], &[], &runtime).unwrap();
+            assert_eq!(context.scope.get_value::<i64>("cached_value"), Some(2));
+
+            let context =
+                rhai_context_load(&script_id, b"let cached_value = 3;", &[], &[], &runtime)
+                    .unwrap();
+            assert_eq!(context.scope.get_value::<i64>("cached_value"), Some(3));
+        });
+    }
+
+    #[test]
+    fn test_defined_callbacks_lists_script_functions() {
+        let runtime = RhaiRuntime::new(Engine::new());
+        let script_id = ScriptId::from("asd.rhai");
+        let context = rhai_context_load(
+            &script_id,
+            b"fn on_test() {} let not_a_function = 2;",
+            &[],
+            &[],
+            &runtime,
+        )
+        .unwrap();
+
+        assert_eq!(
+            rhai_defined_callbacks(&context),
+            Some(vec![CallbackLabel::new_lossy("on_test")])
+        );
+    }
 }
@@ -0,0 +1,4 @@
+/// ReflectReference implementations and rune wrappers
+pub mod reference;
+/// ScriptValue implementations and rune wrappers
+pub mod script_value;
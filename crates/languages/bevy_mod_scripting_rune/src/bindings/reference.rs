@@ -0,0 +1,469 @@
+use super::script_value::{FromRuneValue, IntoRuneValue, RUNE_CALLER_CONTEXT};
+use bevy::{
+    reflect::{TypeInfo, VariantInfo},
+    utils::HashSet,
+};
+use bevy_mod_scripting_core::{
+    bindings::{
+        function::{
+            namespace::Namespace,
+            script_function::{DynamicScriptFunction, DynamicScriptFunctionMut},
+        },
+        script_value::ScriptValue,
+        ReflectReference, ThreadWorldContainer, WorldContainer, WorldGuard,
+    },
+    error::InteropError,
+    reflection_extensions::TypeIdExtensions,
+};
+use parking_lot::Mutex;
+use rune::{
+    alloc::fmt::TryWrite,
+    runtime::{Formatter, InstAddress, Memory, Output, Protocol, Value, VmError, VmResult},
+    Any, ContextError, Module,
+};
+use std::{any::TypeId, cmp::Ordering, sync::LazyLock};
+
+/// The number of tuple struct fields accessible via `reference.0` style syntax.
+///
+/// Fields past this index can still be reached by indexing with a string, i.e. `reference["_16"]`.
+pub const TUPLE_FIELD_ACCESSORS: usize = 16;
+
+/// Rune wrapper for [`bevy_mod_scripting_core::bindings::ReflectReference`].
+/// Acts as a rune reflection interface. Any value which is registered in the type registry can be interacted with using this type.
+///
+/// Rune resolves instance functions and fields by name hash, so the functions and fields available on this type are
+/// generated from the script function registry and type registry when the module is built, see [`reflect_reference_module`].
+#[derive(Any, Debug, Clone, PartialEq)]
+#[rune(name = ReflectReference)]
+pub struct RuneReflectReference(pub ReflectReference);
+
+impl AsRef<ReflectReference> for RuneReflectReference {
+    fn as_ref(&self) -> &ReflectReference {
+        &self.0
+    }
+}
+
+impl From<RuneReflectReference> for ReflectReference {
+    fn from(value: RuneReflectReference) -> Self {
+        value.0
+    }
+}
+
+impl From<ReflectReference> for RuneReflectReference {
+    fn from(value: ReflectReference) -> Self {
+        Self(value)
+    }
+}
+
+/// Rune wrapper for a [`DynamicScriptFunction`], invoked via `function.call(args..)`.
+#[derive(Any, Clone)]
+#[rune(name = ScriptFunction)]
+pub struct RuneScriptFunction(pub DynamicScriptFunction);
+
+/// Rune wrapper for a [`DynamicScriptFunctionMut`], invoked via `function.call(args..)`.
+#[derive(Any, Clone)]
+#[rune(name = ScriptFunctionMut)]
+pub struct RuneScriptFunctionMut(pub DynamicScriptFunctionMut);
+
+/// An iterator over a [`ReflectReference`], produced when iterating over a [`RuneReflectReference`] in a `for` loop.
+#[derive(Any)]
+#[rune(name = ReflectReferenceIter)]
+pub struct RuneReflectRefIter {
+    next_func: DynamicScriptFunctionMut,
+}
+
+impl RuneReflectRefIter {
+    fn next(&mut self) -> VmResult<Option<Value>> {
+        let next = match self.next_func.call(vec![], RUNE_CALLER_CONTEXT) {
+            Ok(next) => next,
+            Err(e) => return VmResult::Err(e.into()),
+        };
+        match next {
+            ScriptValue::Unit => VmResult::Ok(None),
+            v => VmResult::Ok(Some(rune::vm_try!(v.into_rune_value()))),
+        }
+    }
+}
+
+/// Keywords reserved by the rune parser, functions and fields with these names are exposed with a trailing underscore, i.e. `default_`.
+pub const RESERVED_KEYWORDS: &[&str] = &[
+    "abstract", "alignof", "as", "async", "await", "become", "break", "const", "continue", "crate",
+    "default", "do", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "is", "let", "loop", "macro", "match", "mod", "move", "mut", "not", "offsetof", "override",
+    "priv", "proc", "pub", "pure", "ref", "return", "select", "Self", "self", "sizeof", "static",
+    "struct", "super", "true", "typeof", "unsafe", "use", "virtual", "while", "yield",
+];
+
+/// Returns the name under which the given function or field is exposed to rune scripts.
+pub fn script_name(name: &str) -> String {
+    if RESERVED_KEYWORDS.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Rune only accepts `'static` names for instance and field functions, names are therefore leaked once and re-used
+/// for every module built afterwards.
+fn intern(name: &str) -> &'static str {
+    static INTERNED: LazyLock<Mutex<HashSet<&'static str>>> = LazyLock::new(Default::default);
+    let mut interned = INTERNED.lock();
+    match interned.get(name) {
+        Some(name) => name,
+        None => {
+            let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+            interned.insert(name);
+            name
+        }
+    }
+}
+
+/// Converts a result into the [`VmResult`] rune native functions need to return.
+fn vm_result<T>(result: Result<T, VmError>) -> VmResult<T> {
+    match result {
+        Ok(value) => VmResult::Ok(value),
+        Err(e) => VmResult::Err(e),
+    }
+}
+
+/// Wraps a function operating on the full argument list into a raw rune function handler.
+fn raw_handler(
+    f: impl Fn(Vec<Value>) -> Result<Value, VmError> + Send + Sync + 'static,
+) -> impl Fn(&mut dyn Memory, InstAddress, usize, Output) -> VmResult<()> + Send + Sync + 'static {
+    move |stack, addr, args, out| {
+        let args = rune::vm_try!(stack.slice_at(addr, args)).to_vec();
+        let value = rune::vm_try!(f(args));
+        rune::vm_try!(out.store(stack, value));
+        VmResult::Ok(())
+    }
+}
+
+fn convert_args(args: Vec<Value>) -> Result<Vec<ScriptValue>, VmError> {
+    args.into_iter().map(ScriptValue::from_rune_value).collect()
+}
+
+/// Calls the given script function with rune arguments
+fn call_script_function(
+    function: &DynamicScriptFunction,
+    args: Vec<Value>,
+) -> Result<Value, VmError> {
+    let out = function.call(convert_args(args)?, RUNE_CALLER_CONTEXT)?;
+    out.into_rune_value()
+}
+
+/// Looks up the function with the given name on the receiver's type and calls it with all the arguments, including the receiver.
+fn call_instance_function(name: &'static str, args: Vec<Value>) -> Result<Value, VmError> {
+    let world = ThreadWorldContainer.try_get_world()?;
+    let receiver = match args.first() {
+        Some(receiver) => receiver.borrow_ref::<RuneReflectReference>()?.0.clone(),
+        None => {
+            return Err(
+                InteropError::invariant("instance function called without a receiver").into(),
+            )
+        }
+    };
+    let type_id = receiver.tail_type_id(world.clone())?.or_fake_id();
+    let function = world
+        .lookup_function([type_id, TypeId::of::<ReflectReference>()], name)
+        .map_err(|name| InteropError::missing_function(type_id, name.to_string()))?;
+    call_script_function(&function, args)
+}
+
+/// Calls the overloads of a binary operator on the type of the reference
+fn binary_operator(
+    name: &'static str,
+) -> impl Fn(&RuneReflectReference, Value) -> VmResult<Value> + Send + Sync + 'static {
+    move |self_, other| {
+        profiling::function_scope!("binary_operator");
+        let out = (|| {
+            let world = ThreadWorldContainer.try_get_world()?;
+            let other = ScriptValue::from_rune_value(other)?;
+            let target_type_id = self_.0.tail_type_id(world.clone())?.or_fake_id();
+            let args = vec![ScriptValue::Reference(self_.0.clone()), other];
+            let out = world.try_call_overloads(target_type_id, name, args, RUNE_CALLER_CONTEXT)?;
+            out.into_rune_value()
+        })();
+        vm_result(out)
+    }
+}
+
+fn reference_equals(self_: &RuneReflectReference, other: Value) -> Result<bool, VmError> {
+    let out = binary_operator("eq")(self_, other).into_result()?;
+    Ok(out.as_bool()?)
+}
+
+fn index_get(self_: &RuneReflectReference, key: ScriptValue) -> Result<Value, VmError> {
+    let world = ThreadWorldContainer.try_get_world()?;
+    let registry = world.script_function_registry();
+    let registry = registry.read();
+    let out = registry
+        .magic_functions
+        .get(RUNE_CALLER_CONTEXT, self_.0.clone(), key)?;
+    out.into_rune_value()
+}
+
+fn index_set(self_: &RuneReflectReference, key: ScriptValue, value: Value) -> Result<(), VmError> {
+    let world = ThreadWorldContainer.try_get_world()?;
+    let value = ScriptValue::from_rune_value(value)?;
+    let registry = world.script_function_registry();
+    let registry = registry.read();
+    registry
+        .magic_functions
+        .set(RUNE_CALLER_CONTEXT, self_.0.clone(), key, value)?;
+    Ok(())
+}
+
+fn display_with(
+    self_: &RuneReflectReference,
+    function: &'static str,
+    f: &mut Formatter,
+) -> VmResult<()> {
+    let out = (|| {
+        let world = ThreadWorldContainer.try_get_world()?;
+        let func = world
+            .lookup_function([TypeId::of::<ReflectReference>()], function)
+            .map_err(|f| InteropError::missing_function(TypeId::of::<ReflectReference>(), f))?;
+        let out = func.call(
+            vec![ScriptValue::Reference(self_.0.clone())],
+            RUNE_CALLER_CONTEXT,
+        )?;
+        Ok::<_, VmError>(out.as_string().unwrap_or_default().to_string())
+    })();
+    let out = rune::vm_try!(out);
+    rune::vm_write!(f, "{out}")
+}
+
+/// Builds the rune module containing [`RuneReflectReference`] and the other proxy types.
+///
+/// Every function name in the script function registry is registered as an instance function dispatching on the type of the receiver,
+/// and every field name of the reflected structs in the type registry as a field getter and setter.
+pub fn reflect_reference_module(world: WorldGuard) -> Result<Module, ContextError> {
+    profiling::function_scope!("reflect_reference_module");
+    let mut module = Module::new();
+    module.ty::<RuneReflectReference>()?;
+    module.ty::<RuneScriptFunction>()?;
+    module.ty::<RuneScriptFunctionMut>()?;
+    module.ty::<RuneReflectRefIter>()?;
+
+    module
+        .raw_function(
+            "call",
+            raw_handler(|mut args| {
+                if args.is_empty() {
+                    return Err(InteropError::invariant("call without a receiver").into());
+                }
+                let function = args.remove(0).borrow_ref::<RuneScriptFunction>()?.0.clone();
+                call_script_function(&function, args)
+            }),
+        )
+        .build_associated::<RuneScriptFunction>()?;
+    module
+        .raw_function(
+            "call",
+            raw_handler(|mut args| {
+                if args.is_empty() {
+                    return Err(InteropError::invariant("call without a receiver").into());
+                }
+                let function = args
+                    .remove(0)
+                    .borrow_ref::<RuneScriptFunctionMut>()?
+                    .0
+                    .clone();
+                let out = function.call(convert_args(args)?, RUNE_CALLER_CONTEXT)?;
+                out.into_rune_value()
+            }),
+        )
+        .build_associated::<RuneScriptFunctionMut>()?;
+
+    module.associated_function(&Protocol::NEXT, RuneReflectRefIter::next)?;
+    module.associated_function(
+        &Protocol::INTO_ITER,
+        |self_: &RuneReflectReference| -> VmResult<RuneReflectRefIter> {
+            let out = (|| {
+                let world = ThreadWorldContainer.try_get_world()?;
+                let iter_func = world
+                    .lookup_function([TypeId::of::<ReflectReference>()], "iter")
+                    .map_err(|f| {
+                        InteropError::missing_function(TypeId::of::<ReflectReference>(), f)
+                    })?;
+                match iter_func.call(
+                    vec![ScriptValue::Reference(self_.0.clone())],
+                    RUNE_CALLER_CONTEXT,
+                )? {
+                    ScriptValue::FunctionMut(next_func) => Ok(RuneReflectRefIter { next_func }),
+                    _ => Err(VmError::from(InteropError::invariant(
+                        "iter function did not return a function",
+                    ))),
+                }
+            })();
+            vm_result(out)
+        },
+    )?;
+
+    module.associated_function(&Protocol::ADD, binary_operator("add"))?;
+    module.associated_function(&Protocol::SUB, binary_operator("sub"))?;
+    module.associated_function(&Protocol::MUL, binary_operator("mul"))?;
+    module.associated_function(&Protocol::DIV, binary_operator("div"))?;
+    module.associated_function(&Protocol::REM, binary_operator("rem"))?;
+    module.associated_function(
+        &Protocol::PARTIAL_EQ,
+        |self_: &RuneReflectReference, other: Value| -> VmResult<bool> {
+            vm_result(reference_equals(self_, other))
+        },
+    )?;
+    module.associated_function(
+        &Protocol::PARTIAL_CMP,
+        |self_: &RuneReflectReference, other: Value| -> VmResult<Option<Ordering>> {
+            let out = (|| {
+                if binary_operator("lt")(self_, other.clone())
+                    .into_result()?
+                    .as_bool()?
+                {
+                    Ok(Some(Ordering::Less))
+                } else if reference_equals(self_, other)? {
+                    Ok(Some(Ordering::Equal))
+                } else {
+                    Ok(Some(Ordering::Greater))
+                }
+            })();
+            vm_result(out)
+        },
+    )?;
+
+    module.associated_function(
+        &Protocol::INDEX_GET,
+        |self_: &RuneReflectReference, key: Value| -> VmResult<Value> {
+            let out = ScriptValue::from_rune_value(key).and_then(|key| index_get(self_, key));
+            vm_result(out)
+        },
+    )?;
+    module.associated_function(
+        &Protocol::INDEX_SET,
+        |self_: &RuneReflectReference, key: Value, value: Value| -> VmResult<()> {
+            let out =
+                ScriptValue::from_rune_value(key).and_then(|key| index_set(self_, key, value));
+            vm_result(out)
+        },
+    )?;
+
+    module.associated_function(
+        &Protocol::DISPLAY_FMT,
+        |self_: &RuneReflectReference, f: &mut Formatter| display_with(self_, "display_value", f),
+    )?;
+    module.associated_function(
+        &Protocol::DEBUG_FMT,
+        |self_: &RuneReflectReference, f: &mut Formatter| display_with(self_, "display_ref", f),
+    )?;
+
+    // instance functions
+    let mut names = HashSet::<&'static str>::default();
+    {
+        let function_registry = world.script_function_registry();
+        let function_registry = function_registry.read();
+        for (key, _) in function_registry.iter_all() {
+            if key.namespace != Namespace::Global {
+                names.insert(intern(&key.name));
+            }
+        }
+    }
+    for name in names {
+        module
+            .raw_function(
+                intern(&script_name(name)),
+                raw_handler(move |args| call_instance_function(name, args)),
+            )
+            .build_associated::<RuneReflectReference>()?;
+    }
+
+    // field accessors
+    let mut fields = HashSet::<&'static str>::default();
+    {
+        let type_registry = world.type_registry();
+        let type_registry = type_registry.read();
+        for registration in type_registry.iter() {
+            match registration.type_info() {
+                TypeInfo::Struct(info) => {
+                    fields.extend(info.field_names().iter().map(|f| intern(f)))
+                }
+                TypeInfo::Enum(info) => {
+                    for variant in info.iter() {
+                        if let VariantInfo::Struct(variant) = variant {
+                            fields.extend(variant.field_names().iter().map(|f| intern(f)));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    for field in fields {
+        let script_field = intern(&script_name(field));
+        module.field_function(
+            &Protocol::GET,
+            script_field,
+            move |self_: &RuneReflectReference| -> VmResult<Value> {
+                vm_result(index_get(self_, ScriptValue::String(field.into())))
+            },
+        )?;
+        module.field_function(
+            &Protocol::SET,
+            script_field,
+            move |self_: &RuneReflectReference, value: Value| -> VmResult<()> {
+                vm_result(index_set(self_, ScriptValue::String(field.into()), value))
+            },
+        )?;
+    }
+    for index in 0..TUPLE_FIELD_ACCESSORS {
+        module.index_function(
+            &Protocol::GET,
+            index,
+            move |self_: &RuneReflectReference| -> VmResult<Value> {
+                vm_result(index_get(
+                    self_,
+                    ScriptValue::String(format!("_{index}").into()),
+                ))
+            },
+        )?;
+        module.index_function(
+            &Protocol::SET,
+            index,
+            move |self_: &RuneReflectReference, value: Value| -> VmResult<()> {
+                vm_result(index_set(
+                    self_,
+                    ScriptValue::String(format!("_{index}").into()),
+                    value,
+                ))
+            },
+        )?;
+    }
+
+    Ok(module)
+}
+
+/// Builds a rune module exposing every function in the given namespace as a free function.
+///
+/// For example the functions in the namespace of `World` are exposed under the `world` module, and called via `world::spawn()`.
+/// If no item is given, the functions are installed at the root of the script.
+pub fn namespace_module(
+    item: Option<&str>,
+    namespace: Namespace,
+    world: WorldGuard,
+) -> Result<Module, ContextError> {
+    let mut module = match item {
+        Some(item) => Module::with_item([item])?,
+        None => Module::new(),
+    };
+    let function_registry = world.script_function_registry();
+    let function_registry = function_registry.read();
+    for (key, function) in function_registry.iter_namespace(namespace) {
+        let function = function.clone();
+        let name = script_name(&key.name);
+        module
+            .raw_function(
+                name.as_str(),
+                raw_handler(move |args| call_script_function(&function, args)),
+            )
+            .build()?;
+    }
+    Ok(module)
+}
@@ -0,0 +1,132 @@
+use super::reference::{RuneReflectReference, RuneScriptFunction, RuneScriptFunctionMut};
+use bevy_mod_scripting_core::{
+    asset::Language,
+    bindings::{function::script_function::FunctionCallContext, script_value::ScriptValue},
+    error::InteropError,
+};
+use rune::{
+    alloc,
+    runtime::{Function, Object, OwnedTuple, Value, Vec as RuneVec, VmError},
+    TypeHash,
+};
+use std::collections::{HashMap, VecDeque};
+
+/// The default function call context for rune
+pub const RUNE_CALLER_CONTEXT: FunctionCallContext = FunctionCallContext::new(Language::Rune);
+
+/// A trait for converting types into a rune [`Value`]
+pub trait IntoRuneValue {
+    /// Convert the type into a rune [`Value`]
+    fn into_rune_value(self) -> Result<Value, VmError>;
+}
+
+/// A trait for converting a rune [`Value`] into a type
+pub trait FromRuneValue: Sized {
+    /// Convert a rune [`Value`] into a type
+    fn from_rune_value(value: Value) -> Result<Self, VmError>;
+}
+
+#[profiling::all_functions]
+impl IntoRuneValue for ScriptValue {
+    fn into_rune_value(self) -> Result<Value, VmError> {
+        Ok(match self {
+            ScriptValue::Unit => rune::to_value(())?,
+            ScriptValue::Bool(b) => rune::to_value(b)?,
+            ScriptValue::Integer(i) => rune::to_value(i)?,
+            ScriptValue::Float(f) => rune::to_value(f)?,
+            ScriptValue::String(s) => rune::to_value(alloc::String::try_from(s.as_ref())?)?,
+            ScriptValue::List(list) => {
+                let mut vec = alloc::Vec::try_with_capacity(list.len())?;
+                for value in list {
+                    vec.try_push(value.into_rune_value()?)?;
+                }
+                Value::vec(vec)?
+            }
+            ScriptValue::Map(map) => {
+                let mut object = Object::new();
+                for (key, value) in map {
+                    object.insert(alloc::String::try_from(key)?, value.into_rune_value()?)?;
+                }
+                rune::to_value(object)?
+            }
+            ScriptValue::Reference(reflect_reference) => {
+                Value::new(RuneReflectReference(reflect_reference))?
+            }
+            ScriptValue::Function(function) => Value::new(RuneScriptFunction(function))?,
+            ScriptValue::FunctionMut(function) => Value::new(RuneScriptFunctionMut(function))?,
+            ScriptValue::Error(interop_error) => return Err(interop_error.into()),
+        })
+    }
+}
+
+#[profiling::all_functions]
+impl FromRuneValue for ScriptValue {
+    fn from_rune_value(value: Value) -> Result<Self, VmError> {
+        let type_hash = value.type_hash();
+        Ok(match type_hash {
+            _ if value.into_unit().is_ok() => ScriptValue::Unit,
+            h if h == bool::HASH => ScriptValue::Bool(value.as_bool()?),
+            h if h == i64::HASH => ScriptValue::Integer(value.as_signed()?),
+            h if h == u64::HASH => ScriptValue::Integer(value.as_integer::<i64>()?),
+            h if h == f64::HASH => ScriptValue::Float(value.as_float()?),
+            h if h == char::HASH => ScriptValue::String(value.as_char()?.to_string().into()),
+            h if h == alloc::String::HASH => {
+                ScriptValue::String(value.borrow_string_ref()?.to_string().into())
+            }
+            h if h == RuneVec::HASH => ScriptValue::List(
+                value
+                    .borrow_ref::<RuneVec>()?
+                    .iter()
+                    .map(|v| ScriptValue::from_rune_value(v.clone()))
+                    .collect::<Result<_, _>>()?,
+            ),
+            h if h == OwnedTuple::HASH => ScriptValue::List(
+                value
+                    .borrow_ref::<OwnedTuple>()?
+                    .iter()
+                    .map(|v| ScriptValue::from_rune_value(v.clone()))
+                    .collect::<Result<_, _>>()?,
+            ),
+            h if h == Object::HASH => ScriptValue::Map(
+                value
+                    .borrow_ref::<Object>()?
+                    .iter()
+                    .map(|(k, v)| Ok((k.to_string(), ScriptValue::from_rune_value(v.clone())?)))
+                    .collect::<Result<HashMap<_, _>, VmError>>()?,
+            ),
+            h if h == RuneReflectReference::HASH => {
+                ScriptValue::Reference(value.borrow_ref::<RuneReflectReference>()?.0.clone())
+            }
+            h if h == RuneScriptFunction::HASH => {
+                ScriptValue::Function(value.borrow_ref::<RuneScriptFunction>()?.0.clone())
+            }
+            h if h == RuneScriptFunctionMut::HASH => {
+                ScriptValue::FunctionMut(value.borrow_ref::<RuneScriptFunctionMut>()?.0.clone())
+            }
+            h if h == Function::HASH => {
+                let function = value.downcast::<Function>()?.into_sync()?;
+                ScriptValue::Function(
+                    (move |_context: FunctionCallContext, args: VecDeque<ScriptValue>| {
+                        let out = args
+                            .into_iter()
+                            .map(ScriptValue::into_rune_value)
+                            .collect::<Result<Vec<_>, _>>()
+                            .and_then(|args| function.call::<Value>(args).into_result())
+                            .and_then(ScriptValue::from_rune_value);
+                        match out {
+                            Ok(v) => v,
+                            Err(e) => ScriptValue::Error(InteropError::external_error(Box::new(e))),
+                        }
+                    })
+                    .into(),
+                )
+            }
+            _ => {
+                return Err(InteropError::impossible_conversion(
+                    std::any::TypeId::of::<ScriptValue>(),
+                )
+                .into())
+            }
+        })
+    }
+}
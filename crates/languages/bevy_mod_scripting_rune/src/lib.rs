@@ -1,20 +1,78 @@
-use std::sync::Arc;
+//! Rune scripting language support for Bevy.
 
-use bevy_mod_scripting_core::{asset::Language, IntoScriptPluginParams, ScriptingPlugin};
+use std::{any::TypeId, collections::HashMap, sync::Arc};
+
+use bevy::{
+    app::Plugin,
+    ecs::{entity::Entity, world::World},
+};
+use bevy_mod_scripting_core::{
+    asset::Language,
+    bindings::{
+        function::namespace::Namespace, globals::AppScriptGlobalsRegistry,
+        script_value::ScriptValue, ReflectReference, ThreadWorldContainer, WorldContainer,
+    },
+    context::{ContextBuilder, ContextInitializer, ContextPreHandlingInitializer},
+    error::ScriptError,
+    event::CallbackLabel,
+    handler::ThreadExecutionBudget,
+    reflection_extensions::PartialReflectExt,
+    runtime::RuntimeSettings,
+    script::ScriptId,
+    IntoScriptPluginParams, ScriptingPlugin,
+};
+use bindings::{
+    reference::{namespace_module, reflect_reference_module},
+    script_value::{FromRuneValue, IntoRuneValue},
+};
+use parking_lot::RwLock;
 use rune::{
-    runtime::{Args, RuntimeContext},
-    Unit, Vm,
+    runtime::{RuntimeContext, Value, VmResult},
+    termcolor::Buffer,
+    vm_try, Context, Diagnostics, Module, Source, Sources, Unit, Vm,
 };
 
-pub trait RuneEventArg: Args + Clone + Send + Sync + 'static {}
-impl<T: Args + Clone + Send + Sync + 'static> RuneEventArg for T {}
+pub use rune;
+/// Bindings for rune.
+pub mod bindings;
+
+/// The rune runtime type.
+///
+/// Rune virtual machines are cheap to create and are instantiated per callback, so no state is kept here.
+pub type RuneRuntime = ();
 
+/// The rune context type.
 pub struct RuneScriptContext {
+    /// The compile time context, context initializers install their modules into it before the script is compiled
+    pub context: Context,
+    /// The compiled script
     pub unit: Arc<Unit>,
+    /// The runtime context built from [`Self::context`] after compilation
     pub runtime_context: Arc<RuntimeContext>,
+    /// Values exposed to the script through zero argument functions, i.e. `entity()` or `script_id()`.
+    ///
+    /// Rune has no global variables, so these are read whenever the function is called,
+    /// and can be updated from pre-handling initializers.
+    /// The set of getters is fixed when the script is compiled.
+    pub globals: Arc<RwLock<HashMap<String, ScriptValue>>>,
 }
 
-pub type RuneRuntime = Vm;
+impl RuneScriptContext {
+    /// Creates a new empty context with the rune default modules installed
+    pub fn new() -> Result<Self, ScriptError> {
+        Ok(Self {
+            context: Context::with_default_modules()?,
+            unit: Default::default(),
+            runtime_context: Default::default(),
+            globals: Default::default(),
+        })
+    }
+
+    /// Sets the value of a global, creating it if it does not exist.
+    pub fn set_global(&mut self, name: impl Into<String>, value: ScriptValue) {
+        self.globals.write().insert(name.into(), value);
+    }
+}
 
 impl IntoScriptPluginParams for RuneScriptingPlugin {
     type C = RuneScriptContext;
@@ -22,283 +80,391 @@ impl IntoScriptPluginParams for RuneScriptingPlugin {
 
     const LANGUAGE: Language = Language::Rune;
 
-    fn build_runtime() -> Self::R {
-        todo!()
-    }
+    fn build_runtime() -> Self::R {}
 }
 
+/// The rune scripting plugin. Used to add rune scripting to a bevy app within the context of the BMS framework.
 pub struct RuneScriptingPlugin {
+    /// The internal scripting plugin
     pub scripting_plugin: ScriptingPlugin<Self>,
 }
 
+impl AsMut<ScriptingPlugin<Self>> for RuneScriptingPlugin {
+    fn as_mut(&mut self) -> &mut ScriptingPlugin<Self> {
+        &mut self.scripting_plugin
+    }
+}
+
 impl Default for RuneScriptingPlugin {
     fn default() -> Self {
-        todo!()
-        // Self {
-        //     scripting_plugin: ScriptingPlugin {
-        //         runtime_builder: todo!(),
-        //         runtime_settings: todo!(),
-        //         callback_handler: todo!(),
-        //         context_builder: todo!(),
-        //         context_assigner: todo!(),
-        //     },
-        // }
+        RuneScriptingPlugin {
+            scripting_plugin: ScriptingPlugin {
+                context_assignment_strategy: Default::default(),
+                runtime_settings: RuntimeSettings::default(),
+                callback_handler: rune_callback_handler,
+                execution_budget: Default::default(),
+                defined_callbacks: rune_defined_callbacks,
+                index_callbacks: true,
+                // callbacks cannot define new functions, these only change on reload
+                reindex_after_callbacks: false,
+                parallel_callbacks: false,
+                context_builder: ContextBuilder {
+                    load: rune_context_load,
+                    reload: rune_context_reload,
+                },
+                context_initializers: vec![
+                    |_, context: &mut RuneScriptContext| {
+                        let world = ThreadWorldContainer.try_get_world()?;
+                        context
+                            .context
+                            .install(reflect_reference_module(world.clone())?)?;
+                        context.context.install(namespace_module(
+                            Some("world"),
+                            Namespace::OnType(TypeId::of::<World>()),
+                            world,
+                        )?)?;
+                        Ok(())
+                    },
+                    |_, context: &mut RuneScriptContext| {
+                        // initialize global functions
+                        let world = ThreadWorldContainer.try_get_world()?;
+                        let globals_registry =
+                            world.with_resource(|r: &AppScriptGlobalsRegistry| r.clone())?;
+                        let globals_registry = globals_registry.read();
+
+                        for (key, global) in globals_registry.iter() {
+                            match &global.maker {
+                                Some(maker) => {
+                                    let global = (maker)(world.clone())?;
+                                    context.set_global(key.to_string(), global);
+                                }
+                                // reference functions are already associated functions of the proxy type
+                                None if global.type_id == TypeId::of::<ReflectReference>() => {}
+                                None => {
+                                    // static references are exposed as modules, i.e. `Vec3::new(1.0, 2.0, 3.0)`
+                                    let namespace = Namespace::OnType(global.type_id);
+                                    let has_functions = world
+                                        .script_function_registry()
+                                        .read()
+                                        .iter_namespace(namespace)
+                                        .next()
+                                        .is_some();
+                                    if has_functions {
+                                        context.context.install(namespace_module(
+                                            Some(key),
+                                            namespace,
+                                            world.clone(),
+                                        )?)?;
+                                    }
+                                }
+                            }
+                        }
+
+                        // then go through functions in the global namespace and add them to the root of the script
+                        context.context.install(namespace_module(
+                            None,
+                            Namespace::Global,
+                            world,
+                        )?)?;
+                        Ok(())
+                    },
+                ],
+                context_pre_handling_initializers: vec![|script, entity, context| {
+                    let world = ThreadWorldContainer.try_get_world()?;
+                    context.set_global(
+                        "entity",
+                        ScriptValue::Reference(<Entity>::allocate(Box::new(entity), world)),
+                    );
+                    context.set_global("script_id", ScriptValue::String(script.to_owned().into()));
+                    Ok(())
+                }],
+                // already supported by BMS core
+                additional_supported_extensions: &[],
+                language: Language::Rune,
+            },
+        }
+    }
+}
+
+impl Plugin for RuneScriptingPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        self.scripting_plugin.build(app);
+    }
+
+    fn finish(&self, app: &mut bevy::app::App) {
+        self.scripting_plugin.finish(app);
+    }
+}
+
+/// Builds a module with a zero argument getter function for each global currently set on the context
+fn globals_module(context: &RuneScriptContext) -> Result<Module, ScriptError> {
+    let mut module = Module::new();
+    let names = context.globals.read().keys().cloned().collect::<Vec<_>>();
+    for name in names {
+        let globals = context.globals.clone();
+        let key = name.clone();
+        module
+            .function(name.as_str(), move || -> VmResult<Value> {
+                let value = globals
+                    .read()
+                    .get(&key)
+                    .cloned()
+                    .unwrap_or(ScriptValue::Unit);
+                VmResult::Ok(vm_try!(value.into_rune_value()))
+            })
+            .build()?;
     }
+    Ok(module)
 }
 
-// use std::{marker::PhantomData, sync::Arc};
-
-// use bevy::prelude::*;
-// use bevy_mod_scripting_core::{
-//     prelude::*,
-//     systems::{self, CachedScriptState},
-// };
-// use prelude::{RuneDocFragment, RuneFile, RuneLoader};
-// use rune::{
-//     runtime::{Args, RuntimeContext, VmError, VmResult},
-//     Context, Diagnostics, Source, Sources, Unit, Vm,
-// };
-
-// mod assets;
-// mod docs;
-
-// pub mod prelude {
-//     pub use crate::{
-//         assets::{RuneFile, RuneLoader},
-//         docs::RuneDocFragment,
-//         RuneArgs, RuneEvent, RuneScriptContext, RuneScriptHost,
-//     };
-//     pub use rune::{self, runtime::Args, Context};
-// }
-
-// /// Super trait adding additional bounds to Rune's `Args` trait.
-// /// It's gets automatically implemented for any type that implments `Args`,
-// /// so you should never have to manually implement it.
-// pub trait RuneArgs: Args + Clone + Send + Sync + 'static {}
-
-// impl<T: Args + Clone + Send + Sync + 'static> RuneArgs for T {}
-
-// /// A Rune script hook.
-// #[derive(Debug, Clone, Event)]
-// pub struct RuneEvent<A: RuneArgs> {
-//     /// The name of the Rune function to call.
-//     pub hook_name: String,
-//     /// The arguments to supply the function being invoked. If you
-//     /// don't need any arguments, `()` is a good default value.
-//     pub args: A,
-//     /// The target set of scripts that should handle this event.
-//     pub recipients: Recipients,
-// }
-
-// impl<A: RuneArgs> ScriptEvent for RuneEvent<A> {
-//     fn recipients(&self) -> &Recipients {
-//         &self.recipients
-//     }
-// }
-
-// /// A cached Rune Vm used to execute units.
-// struct RuneVm(Vm);
-
-// impl Default for RuneVm {
-//     fn default() -> Self {
-//         Self(Vm::new(
-//             Arc::new(RuntimeContext::default()),
-//             Arc::new(Unit::default()),
-//         ))
-//     }
-// }
-
-// /// Script context for a rune script.
-// pub struct RuneScriptContext {
-//     pub unit: Arc<Unit>,
-//     pub runtime_context: Arc<RuntimeContext>,
-// }
-
-// #[derive(Resource)]
-// /// Rune script host. Enables Rune scripting.
-// pub struct RuneScriptHost<A: RuneArgs> {
-//     _ph: PhantomData<A>,
-// }
-
-// impl<A: RuneArgs> Default for RuneScriptHost<A> {
-//     fn default() -> Self {
-//         Self {
-//             _ph: Default::default(),
-//         }
-//     }
-// }
-
-// impl<A: RuneArgs> RuneScriptHost<A> {
-//     /// Helper function to handle errors from a Rune virtual machine.
-//     ///
-//     #[cold]
-//     fn handle_rune_error(world: &mut World, error: VmError, script_data: &ScriptData<'_>) {
-//         let mut state: CachedScriptState<Self> = world.remove_resource().unwrap();
-
-//         let (_, mut error_wrt, _) = state.event_state.get_mut(world);
-
-//         let error = ScriptError::RuntimeError {
-//             script: script_data.name.to_owned(),
-//             msg: error.to_string(),
-//         };
-
-//         error!("{}", error);
-
-//         error_wrt.send(ScriptErrorEvent { error });
-//         world.insert_resource(state);
-//     }
-// }
-
-// impl<A: RuneArgs> ScriptHost for RuneScriptHost<A> {
-//     type ScriptContext = RuneScriptContext;
-
-//     type ScriptEvent = RuneEvent<A>;
-
-//     type ScriptAsset = RuneFile;
-
-//     type APITarget = Context;
-
-//     type DocTarget = RuneDocFragment;
-
-//     fn register_with_app_in_set(
-//         app: &mut App,
-//         schedule: impl bevy::ecs::schedule::ScheduleLabel,
-//         set: impl SystemSet,
-//     ) {
-//         app.add_priority_event::<Self::ScriptEvent>()
-//             .init_asset::<RuneFile>()
-//             .init_asset_loader::<RuneLoader>()
-//             .init_resource::<CachedScriptState<Self>>()
-//             .init_resource::<ScriptContexts<Self::ScriptContext>>()
-//             .init_resource::<APIProviders<Self>>()
-//             .register_type::<ScriptCollection<Self::ScriptAsset>>()
-//             .register_type::<Script<Self::ScriptAsset>>()
-//             .register_type::<Handle<RuneFile>>()
-//             // Add a cached Vm as a non-send resource.
-//             .insert_non_send_resource(RuneVm::default())
-//             // handle script insertions removal first
-//             // then update their contexts later on script asset changes
-//             .add_systems(
-//                 schedule,
-//                 (
-//                     systems::script_add_synchronizer::<Self>,
-//                     systems::script_remove_synchronizer::<Self>,
-//                     systems::script_hot_reload_handler::<Self>,
-//                 )
-//                     .chain()
-//                     .in_set(set),
-//             );
-//     }
-
-//     fn load_script(
-//         &mut self,
-//         script: &[u8],
-//         script_data: &ScriptData,
-//         providers: &mut APIProviders<Self>,
-//     ) -> Result<Self::ScriptContext, ScriptError> {
-//         let mut context = rune_modules::default_context().map_err(ScriptError::new_other)?;
-
-//         // Rune requires that we tell it what modules and types we'll be using before
-//         // it compiles a file.
-//         providers.attach_all(&mut context).unwrap();
-
-//         let mut diagnostics = Diagnostics::new();
-
-//         let mut sources = Sources::new();
-//         sources
-//             .insert(
-//                 Source::new(
-//                     script_data.name,
-//                     std::str::from_utf8(script).expect("Slice is not UTF-8"),
-//                 )
-//                 .map_err(|msg| ScriptError::FailedToLoad {
-//                     script: script_data.name.into(),
-//                     msg: msg.to_string(),
-//                 })?,
-//             )
-//             .map_err(|msg| ScriptError::FailedToLoad {
-//                 script: script_data.name.into(),
-//                 msg: msg.to_string(),
-//             })?;
-
-//         let result = rune::prepare(&mut sources)
-//             .with_context(&context)
-//             .with_diagnostics(&mut diagnostics)
-//             .build();
-
-//         if !diagnostics.is_empty() {
-//             let mut writer = rune::termcolor::Buffer::no_color();
-
-//             diagnostics
-//                 .emit(&mut writer, &sources)
-//                 .expect("Failed to write diagnostics to buffer");
-
-//             return Err(ScriptError::SyntaxError {
-//                 script: script_data.name.into(),
-//                 msg: std::str::from_utf8(writer.as_slice())
-//                     .expect("Slice was not UTF-8")
-//                     .to_owned(),
-//             });
-//         }
-
-//         let unit = result.expect("Failed to build Rune unit.");
-
-//         let runtime_ctx = context
-//             .runtime()
-//             .expect("Failed to create Rune runtime context.");
-
-//         Ok(RuneScriptContext {
-//             unit: Arc::new(unit),
-//             runtime_context: Arc::new(runtime_ctx),
-//         })
-//     }
-
-//     fn setup_script(
-//         &mut self,
-//         script_data: &ScriptData,
-//         ctx: &mut Self::ScriptContext,
-//         providers: &mut APIProviders<Self>,
-//     ) -> Result<(), ScriptError> {
-//         providers.setup_all(script_data, ctx)
-//     }
-
-//     fn handle_events<'a>(
-//         &mut self,
-//         world: &mut World,
-//         events: &[Self::ScriptEvent],
-//         ctxs: impl Iterator<Item = (ScriptData<'a>, &'a mut Self::ScriptContext)>,
-//         _providers: &mut APIProviders<Self>,
-//     ) {
-//         // Grab the cached Vm.
-//         let RuneVm(mut vm) = world.remove_non_send_resource::<RuneVm>().unwrap(/* invariant */);
-
-//         {
-//             ctxs.for_each(|(script_data, ctx)| {
-//                 for event in events {
-//                     if !event.recipients().is_recipient(&script_data) {
-//                         continue;
-//                     }
-
-//                     // Swap out the old context and old unit with the new ones.
-//                     *vm.context_mut() = Arc::clone(&ctx.runtime_context);
-//                     *vm.unit_mut() = Arc::clone(&ctx.unit);
-
-//                     let mut exec = match vm.execute([event.hook_name.as_str()], event.args.clone())
-//                     {
-//                         Ok(exec) => exec,
-//                         Err(error) => {
-//                             Self::handle_rune_error(world, error, &script_data);
-//                             continue;
-//                         }
-//                     };
-
-//                     if let VmResult::Err(error) = exec.complete() {
-//                         Self::handle_rune_error(world, error, &script_data);
-//                     }
-//                 }
-//             });
-//         }
-
-//         world.insert_non_send_resource(RuneVm(vm));
-//     }
-// }
+fn load_rune_content_into_context(
+    context: &mut RuneScriptContext,
+    script: &ScriptId,
+    content: &[u8],
+    initializers: &[ContextInitializer<RuneScriptingPlugin>],
+    pre_handling_initializers: &[ContextPreHandlingInitializer<RuneScriptingPlugin>],
+) -> Result<(), ScriptError> {
+    initializers
+        .iter()
+        .try_for_each(|init| init(script, context))?;
+    pre_handling_initializers
+        .iter()
+        .try_for_each(|init| init(script, Entity::from_raw(0), context))?;
+    let globals = globals_module(context)?;
+    context.context.install(globals)?;
+
+    let mut sources = Sources::new();
+    sources
+        .insert(
+            Source::new(script.as_ref(), std::str::from_utf8(content)?)
+                .map_err(ScriptError::new_external)?,
+        )
+        .map_err(ScriptError::new_external)?;
+
+    let mut diagnostics = Diagnostics::new();
+    let result = rune::prepare(&mut sources)
+        .with_context(&context.context)
+        .with_diagnostics(&mut diagnostics)
+        .build();
+
+    let unit = match result {
+        Ok(unit) => unit,
+        Err(e) => {
+            let mut writer = Buffer::no_color();
+            diagnostics
+                .emit(&mut writer, &sources)
+                .map_err(ScriptError::new_external)?;
+            return Err(
+                ScriptError::from(e).with_context(String::from_utf8_lossy(writer.as_slice()))
+            );
+        }
+    };
+
+    context.unit = Arc::new(unit);
+    context.runtime_context = Arc::new(
+        context
+            .context
+            .runtime()
+            .map_err(ScriptError::new_external)?,
+    );
+    Ok(())
+}
+
+/// Load a rune context from a script.
+pub fn rune_context_load(
+    script: &ScriptId,
+    content: &[u8],
+    initializers: &[ContextInitializer<RuneScriptingPlugin>],
+    pre_handling_initializers: &[ContextPreHandlingInitializer<RuneScriptingPlugin>],
+    _: &RuneRuntime,
+) -> Result<RuneScriptContext, ScriptError> {
+    let mut context = RuneScriptContext::new()?;
+    load_rune_content_into_context(
+        &mut context,
+        script,
+        content,
+        initializers,
+        pre_handling_initializers,
+    )?;
+    Ok(context)
+}
+
+/// Reload a rune context from a script.
+///
+/// Rune modules cannot be uninstalled, so the context is rebuilt from scratch.
+pub fn rune_context_reload(
+    script: &ScriptId,
+    content: &[u8],
+    old_ctxt: &mut RuneScriptContext,
+    initializers: &[ContextInitializer<RuneScriptingPlugin>],
+    pre_handling_initializers: &[ContextPreHandlingInitializer<RuneScriptingPlugin>],
+    runtime: &RuneRuntime,
+) -> Result<(), ScriptError> {
+    *old_ctxt = rune_context_load(
+        script,
+        content,
+        initializers,
+        pre_handling_initializers,
+        runtime,
+    )?;
+    Ok(())
+}
+
+/// Lists the top level functions of a rune context, which are the callbacks it can handle.
+///
+/// Function names are only available from the debug information of the unit, without it all callbacks are dispatched to the script.
+pub fn rune_defined_callbacks(context: &RuneScriptContext) -> Option<Vec<CallbackLabel>> {
+    let debug_info = context.unit.debug_info()?;
+    Some(
+        debug_info
+            .functions
+            .values()
+            .filter_map(|signature| CallbackLabel::new(signature.path.as_local()?))
+            .collect(),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+/// The rune callback handler.
+pub fn rune_callback_handler(
+    args: Vec<ScriptValue>,
+    entity: Entity,
+    script_id: &ScriptId,
+    callback: &CallbackLabel,
+    context: &mut RuneScriptContext,
+    pre_handling_initializers: &[ContextPreHandlingInitializer<RuneScriptingPlugin>],
+    _: &RuneRuntime,
+) -> Result<ScriptValue, ScriptError> {
+    pre_handling_initializers
+        .iter()
+        .try_for_each(|init| init(script_id, entity, context))?;
+
+    let mut vm = Vm::new(context.runtime_context.clone(), context.unit.clone());
+    if vm.lookup_function([callback.as_ref()]).is_err() {
+        bevy::log::trace!(
+            "Script {} is not subscribed to callback {}",
+            script_id,
+            callback
+        );
+        return Ok(ScriptValue::Unit);
+    }
+
+    let args = args
+        .into_iter()
+        .map(IntoRuneValue::into_rune_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    bevy::log::trace!(
+        "Calling callback {} in script {} with args: {:?}",
+        callback,
+        script_id,
+        args
+    );
+
+    let out = if ThreadExecutionBudget::current().is_some() {
+        // step through the callback one instruction at a time so we can abort it once over budget
+        let mut execution = vm.execute([callback.as_ref()], args)?;
+        loop {
+            ThreadExecutionBudget::consume(1)?;
+            if let Some(out) = execution.step().into_result()? {
+                break out;
+            }
+        }
+    } else {
+        vm.call([callback.as_ref()], args)?
+    };
+    Ok(ScriptValue::from_rune_value(out)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_callback_is_called_with_arguments() {
+        let script_id = ScriptId::from("test.rn");
+        let mut context =
+            rune_context_load(&script_id, b"pub fn on_test(a, b) { a + b }", &[], &[], &())
+                .unwrap();
+
+        let out = rune_callback_handler(
+            vec![ScriptValue::Integer(1), ScriptValue::Integer(2)],
+            Entity::from_raw(0),
+            &script_id,
+            &CallbackLabel::new_lossy("on_test"),
+            &mut context,
+            &[],
+            &(),
+        )
+        .unwrap();
+
+        assert_eq!(out, ScriptValue::Integer(3));
+    }
+
+    #[test]
+    fn test_defined_callbacks_lists_top_level_functions() {
+        let script_id = ScriptId::from("test.rn");
+        let context = rune_context_load(
+            &script_id,
+            b"pub fn on_test() {} mod nested { pub fn on_nested() {} }",
+            &[],
+            &[],
+            &(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            rune_defined_callbacks(&context),
+            Some(vec![CallbackLabel::new_lossy("on_test")])
+        );
+    }
+
+    #[test]
+    fn test_missing_callback_returns_unit() {
+        let script_id = ScriptId::from("test.rn");
+        let mut context =
+            rune_context_load(&script_id, b"pub fn on_test() {}", &[], &[], &()).unwrap();
+
+        let out = rune_callback_handler(
+            vec![],
+            Entity::from_raw(0),
+            &script_id,
+            &CallbackLabel::new_lossy("on_other"),
+            &mut context,
+            &[],
+            &(),
+        )
+        .unwrap();
+
+        assert_eq!(out, ScriptValue::Unit);
+    }
+
+    #[test]
+    fn test_reload_replaces_script() {
+        let script_id = ScriptId::from("test.rn");
+        let mut context =
+            rune_context_load(&script_id, b"pub fn on_test() { 1 }", &[], &[], &()).unwrap();
+        rune_context_reload(
+            &script_id,
+            b"pub fn on_test() { 2 }",
+            &mut context,
+            &[],
+            &[],
+            &(),
+        )
+        .unwrap();
+
+        let out = rune_callback_handler(
+            vec![],
+            Entity::from_raw(0),
+            &script_id,
+            &CallbackLabel::new_lossy("on_test"),
+            &mut context,
+            &[],
+            &(),
+        )
+        .unwrap();
+
+        assert_eq!(out, ScriptValue::Integer(2));
+    }
+}
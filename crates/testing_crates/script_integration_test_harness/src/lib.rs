@@ -187,6 +187,56 @@ pub fn make_test_rhai_plugin() -> bevy_mod_scripting_rhai::RhaiScriptingPlugin {
     })
 }
 
+#[cfg(feature = "rune")]
+pub fn make_test_rune_plugin() -> bevy_mod_scripting_rune::RuneScriptingPlugin {
+    use bevy_mod_scripting_core::{
+        bindings::{ThreadWorldContainer, WorldContainer},
+        ConfigureScriptPlugin,
+    };
+    use bevy_mod_scripting_rune::{
+        rune::{
+            runtime::{Function, VmResult},
+            vm_try, Module,
+        },
+        RuneScriptContext, RuneScriptingPlugin,
+    };
+
+    RuneScriptingPlugin::default().add_context_initializer(|_, ctxt: &mut RuneScriptContext| {
+        let mut module = Module::new();
+        module
+            .function(
+                "assert_throws",
+                |f: Function, reg: String| -> VmResult<()> {
+                    let world = vm_try!(ThreadWorldContainer.try_get_world());
+                    let err = match f.call::<()>(()) {
+                        VmResult::Ok(_) => {
+                            return VmResult::panic(
+                                "Expected function to throw error, but it did not.",
+                            )
+                        }
+                        VmResult::Err(e) => {
+                            ScriptError::from_rune_error(e).display_with_world(world)
+                        }
+                    };
+
+                    let regex = regex::Regex::new(&reg).unwrap();
+                    if regex.is_match(&err) {
+                        VmResult::Ok(())
+                    } else {
+                        VmResult::panic(format!(
+                            "Expected error message to match the regex: \n{}\n\nBut got:\n{}",
+                            regex.as_str(),
+                            err
+                        ))
+                    }
+                },
+            )
+            .build()?;
+        ctxt.context.install(module)?;
+        Ok(())
+    })
+}
+
 #[cfg(feature = "lua")]
 pub fn execute_lua_integration_test(script_id: &str) -> Result<(), String> {
     let plugin = make_test_lua_plugin();
@@ -199,6 +249,12 @@ pub fn execute_rhai_integration_test(script_id: &str) -> Result<(), String> {
     execute_integration_test(plugin, |_, _| {}, script_id)
 }
 
+#[cfg(feature = "rune")]
+pub fn execute_rune_integration_test(script_id: &str) -> Result<(), String> {
+    let plugin = make_test_rune_plugin();
+    execute_integration_test(plugin, |_, _| {}, script_id)
+}
+
 pub fn execute_integration_test<
     P: IntoScriptPluginParams + Plugin + AsMut<ScriptingPlugin<P>>,
     F: FnOnce(&mut World, &mut TypeRegistry),
@@ -6,8 +6,12 @@ use std::{
 use bevy::{
     app::App,
     ecs::component::ComponentId,
-    prelude::{Entity, World},
+    prelude::{Entity, Event, World},
     reflect::{Reflect, TypeRegistration},
+    state::{
+        app::{AppExtStates, StatesPlugin},
+        state::States,
+    },
 };
 use bevy_mod_scripting_core::{
     asset::Language,
@@ -21,6 +25,7 @@ use bevy_mod_scripting_core::{
         ScriptResourceRegistration, ScriptTypeRegistration, ScriptValue,
     },
     error::InteropError,
+    RegisterScriptEvent, RegisterScriptState,
 };
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha12Rng;
@@ -34,7 +39,28 @@ pub static RNG: std::sync::LazyLock<Mutex<ChaCha12Rng>> = std::sync::LazyLock::n
 
 pub use rand;
 
+/// An event which scripts can send and read in tests
+#[derive(Event, Reflect, Clone)]
+pub struct TestEvent {
+    pub value: String,
+}
+
+/// A state which scripts can read and transition in tests
+#[derive(States, Reflect, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TestState {
+    #[default]
+    Idle,
+    Running,
+}
+
 pub fn register_test_functions(world: &mut App) {
+    world.register_script_event::<TestEvent>();
+    if !world.is_plugin_added::<StatesPlugin>() {
+        world.add_plugins(StatesPlugin);
+    }
+    world
+        .init_state::<TestState>()
+        .register_script_state::<TestState>();
     let world = world.world_mut();
     NamespaceBuilder::<World>::new_unregistered(world)
         .register("_get_mock_type", |s: FunctionCallContext| {
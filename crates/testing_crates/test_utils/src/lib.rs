@@ -11,6 +11,7 @@ pub mod test_plugin;
 pub enum TestKind {
     Lua,
     Rhai,
+    Rune,
 }
 
 impl std::fmt::Display for TestKind {
@@ -18,6 +19,7 @@ impl std::fmt::Display for TestKind {
         match self {
             TestKind::Lua => write!(f, "Lua"),
             TestKind::Rhai => write!(f, "Rhai"),
+            TestKind::Rune => write!(f, "Rune"),
         }
     }
 }
@@ -26,6 +28,24 @@ impl std::fmt::Display for TestKind {
 pub struct Test {
     pub path: PathBuf,
     pub kind: TestKind,
+    /// If set, the test does not exist for this language and is reported as skipped for the given reason
+    pub skip_reason: Option<String>,
+}
+
+/// The extension of marker files which exclude a shared script test from a language.
+///
+/// A marker sits next to the test file it replaces, i.e. `vec3.rn.skip` for `vec3.rn`, and contains the reason the test cannot be ported.
+/// Such tests are discovered as skipped, so that gaps in the coverage of a language show up in the test output.
+pub const SKIP_MARKER_EXTENSION: &str = "skip";
+
+fn test_kind(path: &Path) -> Option<TestKind> {
+    path.extension()
+        .and_then(|e| match e.to_string_lossy().as_ref() {
+            "lua" => Some(TestKind::Lua),
+            "rhai" => Some(TestKind::Rhai),
+            "rn" => Some(TestKind::Rune),
+            _ => None,
+        })
 }
 
 fn visit_dirs(dir: &Path, cb: &mut dyn FnMut(&DirEntry)) -> io::Result<()> {
@@ -49,20 +69,19 @@ pub fn discover_all_tests(manifest_dir: PathBuf, filter: impl Fn(&Test) -> bool)
     let assets_root = manifest_dir.join("assets");
     let mut test_files = Vec::new();
     visit_dirs(&assets_root, &mut |entry| {
-        let path = entry.path();
-        if let Some(kind) = path
-            .extension()
-            .and_then(|e| match e.to_string_lossy().as_ref() {
-                "lua" => Some(TestKind::Lua),
-                "rhai" => Some(TestKind::Rhai),
-                _ => None,
-            })
-        {
+        let mut path = entry.path();
+        let mut skip_reason = None;
+        if path.extension().is_some_and(|e| e == SKIP_MARKER_EXTENSION) {
+            skip_reason = Some(fs::read_to_string(&path).unwrap().trim().to_owned());
+            path.set_extension("");
+        }
+        if let Some(kind) = test_kind(&path) {
             // only take the path from the assets  bit
             let relative = path.strip_prefix(&assets_root).unwrap();
             let test = Test {
                 path: relative.to_path_buf(),
                 kind,
+                skip_reason,
             };
 
             if !filter(&test) {
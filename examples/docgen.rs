@@ -4,6 +4,7 @@ use bevy_mod_scripting::ScriptFunctionsPlugin;
 use bevy_mod_scripting_core::bindings::function::script_function::AppScriptFunctionRegistry;
 use bevy_mod_scripting_core::bindings::globals::core::CoreScriptGlobalsPlugin;
 use bevy_mod_scripting_core::bindings::globals::AppScriptGlobalsRegistry;
+use bevy_mod_scripting_core::bindings::AppScriptComponentRegistry;
 use ladfile_builder::plugin::{generate_lad_file, LadFileSettings, ScriptingDocgenPlugin};
 
 fn main() -> std::io::Result<()> {
@@ -47,6 +48,11 @@ fn main() -> std::io::Result<()> {
         .get_resource::<AppScriptGlobalsRegistry>()
         .unwrap()
         .clone();
+    let component_registry = app
+        .world()
+        .get_resource::<AppScriptComponentRegistry>()
+        .unwrap()
+        .clone();
 
     let settings = LadFileSettings {
         description: "Core BMS framework bindings",
@@ -57,6 +63,7 @@ fn main() -> std::io::Result<()> {
         &type_registry,
         &function_registry,
         &global_registry,
+        &component_registry,
         &settings,
     );
 
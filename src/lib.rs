@@ -14,10 +14,10 @@ pub mod rhai {
     pub use bevy_mod_scripting_rhai::*;
 }
 
-// #[cfg(feature = "rune")]
-// pub mod rune {
-//     pub use bevy_mod_scripting_rune::*;
-// }
+#[cfg(feature = "rune")]
+pub mod rune {
+    pub use bevy_mod_scripting_rune::*;
+}
 
 use bevy::app::plugin_group;
 use bevy_mod_scripting_core::{
@@ -35,5 +35,7 @@ plugin_group! {
         bevy_mod_scripting_lua:::LuaScriptingPlugin,
         #[custom(cfg(feature = "rhai"))]
         bevy_mod_scripting_rhai:::RhaiScriptingPlugin,
+        #[custom(cfg(feature = "rune"))]
+        bevy_mod_scripting_rune:::RuneScriptingPlugin,
     }
 }
@@ -0,0 +1,479 @@
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::panic, missing_docs)]
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{AssetPath, AssetServer},
+    ecs::{event::Events, schedule::IntoSystemConfigs, world::Command},
+};
+use bevy_mod_scripting_core::{
+    asset::{
+        PreprocessedScript, ScriptAsset, ScriptLoaderSettings, ScriptPreprocessor, ScriptSourceMap,
+    },
+    bindings::{pretty_print::DisplayWithWorld, script_value::ScriptValue, WorldGuard},
+    callback_labels,
+    commands::{AddStaticScript, CreateOrUpdateScript},
+    error::ScriptError,
+    event::{ScriptCallbackEvent, ScriptCallbackResponseEvent, ScriptErrorEvent},
+    handler::{event_handler, CallbackSettings, ExecutionBudget},
+    ConfigureScriptAssetSettings, ConfigureScriptPlugin, IntoScriptPluginParams,
+};
+use bevy_mod_scripting_lua::LuaScriptingPlugin;
+use script_integration_test_harness::{
+    install_test_plugin, make_test_lua_plugin, make_test_rhai_plugin, make_test_rune_plugin,
+};
+use test_utils::test_data::setup_integration_test;
+
+callback_labels!(
+    OnSetup => "on_setup",
+    OnTest => "on_test",
+);
+
+/// Sets up an app with the given plugin, and loads the given scripts as static scripts
+fn setup_app<P: IntoScriptPluginParams + Plugin>(plugin: P, scripts: &[(&str, &str)]) -> App {
+    let mut app = setup_integration_test(|_, _| {});
+    install_test_plugin(&mut app, plugin, false);
+    app.add_systems(
+        Update,
+        (event_handler::<OnSetup, P>, event_handler::<OnTest, P>).chain(),
+    );
+    app.cleanup();
+    app.finish();
+
+    for (id, content) in scripts {
+        CreateOrUpdateScript::<P>::new(id.to_string().into(), content.as_bytes().into(), None)
+            .apply(app.world_mut());
+        AddStaticScript::new(id.to_string()).apply(app.world_mut());
+    }
+    assert_no_errors(&mut app);
+    app
+}
+
+/// Drains the errors raised by scripts so far, formatted for display
+fn drain_errors(app: &mut App) -> Vec<String> {
+    let errors = app
+        .world_mut()
+        .resource_mut::<Events<ScriptErrorEvent>>()
+        .drain()
+        .collect::<Vec<_>>();
+    errors
+        .into_iter()
+        .map(|error| {
+            error
+                .error
+                .display_with_world(WorldGuard::new_exclusive(app.world_mut()))
+        })
+        .collect()
+}
+
+fn assert_no_errors(app: &mut App) {
+    if let Some(error) = drain_errors(app).into_iter().next() {
+        panic!("{error}");
+    }
+}
+
+/// The budget used by tests checking scripts stuck in an infinite loop are aborted
+fn infinite_loop_budget() -> ExecutionBudget {
+    ExecutionBudget::unlimited().with_max_instructions(100_000)
+}
+
+/// Runs the `on_test` callback of the given script, which never returns, and checks it is aborted and reported
+fn assert_infinite_loop_is_aborted<P: IntoScriptPluginParams + Plugin>(
+    plugin: P,
+    script: (&str, &str),
+) {
+    let mut app = setup_app(plugin, &[script]);
+
+    app.world_mut()
+        .send_event(ScriptCallbackEvent::new_for_all(OnTest, vec![]));
+    app.update();
+
+    let errors = drain_errors(&mut app);
+    assert_eq!(errors.len(), 1, "unexpected errors: {errors:?}");
+    assert!(
+        errors[0].contains("was aborted for running over its execution budget"),
+        "unexpected error: {}",
+        errors[0]
+    );
+}
+
+/// Sends the event to all scripts, runs an update, and returns the responses sorted by script id
+fn send_event(app: &mut App, event: ScriptCallbackEvent) -> Vec<(String, ScriptValue)> {
+    app.world_mut().send_event(event.with_response());
+    app.update();
+    assert_no_errors(app);
+    let mut responses = app
+        .world_mut()
+        .resource_mut::<Events<ScriptCallbackResponseEvent>>()
+        .drain()
+        .map(|r| (r.script.to_string(), r.response.unwrap()))
+        .collect::<Vec<_>>();
+    responses.sort_by(|a, b| a.0.cmp(&b.0));
+    responses
+}
+
+#[test]
+fn lua_callback_defined_after_load_receives_events() {
+    let mut app = setup_app(
+        make_test_lua_plugin(),
+        &[(
+            "script.lua",
+            r#"
+            function on_setup()
+                function on_test()
+                    return "defined later"
+                end
+            end
+            "#,
+        )],
+    );
+
+    send_event(&mut app, ScriptCallbackEvent::new_for_all(OnSetup, vec![]));
+
+    assert_eq!(
+        send_event(&mut app, ScriptCallbackEvent::new_for_all(OnTest, vec![])),
+        vec![(
+            "script.lua".to_owned(),
+            ScriptValue::String("defined later".into())
+        )]
+    );
+}
+
+#[test]
+fn lua_scripts_without_callback_are_skipped() {
+    let mut app = setup_app(
+        make_test_lua_plugin(),
+        &[
+            ("a.lua", "function on_test() return 1 end"),
+            ("b.lua", "function on_setup() end"),
+        ],
+    );
+
+    // only scripts defining the callback are visited, so only they respond
+    assert_eq!(
+        send_event(&mut app, ScriptCallbackEvent::new_for_all(OnTest, vec![])),
+        vec![("a.lua".to_owned(), ScriptValue::Integer(1))]
+    );
+}
+
+/// Runs the `on_test` callback of two scripts with their own contexts, in parallel if the plugin is configured to,
+/// and checks each of them responds with the sum of the numbers up to its own limit, 100 for `a` and 200 for `b`
+fn assert_parallel_contexts_respond<P: IntoScriptPluginParams + Plugin>(
+    plugin: P,
+    scripts: [(&str, &str); 2],
+) {
+    let mut app = setup_app(plugin, &scripts);
+
+    assert_eq!(
+        send_event(&mut app, ScriptCallbackEvent::new_for_all(OnTest, vec![])),
+        vec![
+            (scripts[0].0.to_owned(), ScriptValue::Integer(5050)),
+            (scripts[1].0.to_owned(), ScriptValue::Integer(20100)),
+        ]
+    );
+}
+
+#[test]
+fn lua_parallel_contexts_respond() {
+    assert_parallel_contexts_respond(
+        make_test_lua_plugin().enable_parallel_callbacks(),
+        [
+            (
+                "a.lua",
+                r#"
+                limit = 100
+                function on_test()
+                    local total = 0
+                    for i = 1, limit do
+                        total = total + i
+                    end
+                    return total
+                end
+                "#,
+            ),
+            (
+                "b.lua",
+                r#"
+                limit = 200
+                function on_test()
+                    local total = 0
+                    for i = 1, limit do
+                        total = total + i
+                    end
+                    return total
+                end
+                "#,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn rhai_parallel_contexts_respond() {
+    assert_parallel_contexts_respond(
+        make_test_rhai_plugin().enable_parallel_callbacks(),
+        [
+            (
+                "a.rhai",
+                r#"
+                fn on_test() {
+                    let total = 0;
+                    for i in 1..=100 {
+                        total += i;
+                    }
+                    total
+                }
+                "#,
+            ),
+            (
+                "b.rhai",
+                r#"
+                fn on_test() {
+                    let total = 0;
+                    for i in 1..=200 {
+                        total += i;
+                    }
+                    total
+                }
+                "#,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn lua_parallel_callbacks_can_query_and_require() {
+    let script = r#"
+        function on_test()
+            local greeting = require("modules.greeting")
+            local component = world.get_type_by_name("TestComponent")
+            local found = 0
+            for _, result in pairs(world.query():component(component):build()) do
+                found = found + 1
+            end
+            return greeting.greet(script_id) .. " " .. found
+        end
+        "#;
+    let mut app = setup_app(
+        make_test_lua_plugin().enable_parallel_callbacks(),
+        &[("a.lua", script), ("b.lua", script)],
+    );
+
+    // modules are only resolved once they are loaded
+    let asset_server = app.world().resource::<AssetServer>().clone();
+    let modules = ["modules/greeting.lua", "modules/punctuation.lua"]
+        .map(|path| asset_server.load::<ScriptAsset>(path));
+    for _ in 0..1000 {
+        if modules.iter().all(|m| asset_server.is_loaded(m)) {
+            break;
+        }
+        app.update();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    assert_eq!(
+        send_event(&mut app, ScriptCallbackEvent::new_for_all(OnTest, vec![])),
+        vec![
+            (
+                "a.lua".to_owned(),
+                ScriptValue::String("Hello, a.lua! 1".into())
+            ),
+            (
+                "b.lua".to_owned(),
+                ScriptValue::String("Hello, b.lua! 1".into())
+            ),
+        ]
+    );
+}
+
+/// Strips lines starting with `--`, mapping the remaining lines back to where they came from
+fn strip_comment_lines(
+    content: &[u8],
+    _: &AssetPath<'_>,
+    _: &ScriptLoaderSettings,
+) -> Result<PreprocessedScript, ScriptError> {
+    let content = String::from_utf8(content.to_vec()).map_err(ScriptError::new_external)?;
+    let (lines, original_lines): (Vec<_>, Vec<_>) = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_start().starts_with("--"))
+        .map(|(i, line)| (line, i + 1))
+        .unzip();
+    Ok(PreprocessedScript::new(lines.join("\n").into_bytes())
+        .with_source_map(ScriptSourceMap::new(original_lines)))
+}
+
+#[test]
+fn lua_errors_in_required_modules_keep_the_module_lines() {
+    let mut app = setup_integration_test(|_, _| {});
+    app.add_script_preprocessor(ScriptPreprocessor::new(
+        "strip_comment_lines",
+        strip_comment_lines,
+    ));
+    install_test_plugin(&mut app, make_test_lua_plugin(), false);
+    app.add_systems(Update, event_handler::<OnTest, LuaScriptingPlugin>);
+    app.cleanup();
+    app.finish();
+
+    let asset_server = app.world().resource::<AssetServer>().clone();
+    let assets = [
+        "scripts/requires_raising_module.lua",
+        "modules/raises_error.lua",
+    ]
+    .map(|path| asset_server.load::<ScriptAsset>(path));
+    for _ in 0..1000 {
+        if assets.iter().all(|a| asset_server.is_loaded(a)) {
+            break;
+        }
+        app.update();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    AddStaticScript::new("scripts/requires_raising_module.lua").apply(app.world_mut());
+    assert_no_errors(&mut app);
+
+    app.world_mut()
+        .send_event(ScriptCallbackEvent::new_for_all(OnTest, vec![]));
+    app.update();
+
+    let errors = drain_errors(&mut app);
+    assert_eq!(errors.len(), 1, "unexpected errors: {errors:?}");
+    // the module is not preprocessed into different lines, so its line is reported as is,
+    // while the call in the script is mapped back past the stripped comment lines
+    assert!(
+        errors[0].contains("[string \"modules/raises_error.lua\"]:3: raised in module"),
+        "unexpected error: {}",
+        errors[0]
+    );
+    assert!(
+        errors[0].contains("[string \"scripts/requires_raising_module.lua\"]:6:"),
+        "unexpected error: {}",
+        errors[0]
+    );
+}
+
+/// Runs the `on_test` callback of the given script, which spawns an entity on each of the three frames it runs over,
+/// and checks it is resumed across frames while running under a budget, and after the budget is lifted
+fn assert_callback_resumes_across_frames<P: IntoScriptPluginParams + Plugin>(
+    plugin: P,
+    script: (&str, &str),
+) {
+    let mut app = setup_app(plugin, &[script]);
+    let entities = app.world().entities().len();
+
+    app.world_mut()
+        .send_event(ScriptCallbackEvent::new_for_all(OnTest, vec![]));
+    app.update();
+    assert_no_errors(&mut app);
+    assert_eq!(app.world().entities().len(), entities + 1);
+
+    app.update();
+    assert_no_errors(&mut app);
+    assert_eq!(app.world().entities().len(), entities + 2);
+
+    app.world_mut()
+        .resource_mut::<CallbackSettings<P>>()
+        .execution_budget = ExecutionBudget::unlimited();
+    app.update();
+    assert_no_errors(&mut app);
+    assert_eq!(app.world().entities().len(), entities + 3);
+
+    app.update();
+    assert_no_errors(&mut app);
+    assert_eq!(app.world().entities().len(), entities + 3);
+}
+
+#[cfg(feature = "mlua_async")]
+#[test]
+fn lua_callback_with_budget_resumes_across_frames() {
+    assert_callback_resumes_across_frames(
+        make_test_lua_plugin()
+            .set_execution_budget(ExecutionBudget::unlimited().with_max_instructions(1_000_000)),
+        (
+            "script.lua",
+            r#"
+            function on_test()
+                for frame = 1, 3 do
+                    if frame > 1 then
+                        coroutine.yield(YieldCondition.next_frame())
+                    end
+                    local total = 0
+                    for i = 1, 10000 do
+                        total = total + i
+                    end
+                    world.spawn()
+                end
+            end
+            "#,
+        ),
+    );
+}
+
+#[test]
+fn rhai_callback_with_budget_resumes_across_frames() {
+    assert_callback_resumes_across_frames(
+        make_test_rhai_plugin()
+            .set_execution_budget(ExecutionBudget::unlimited().with_max_instructions(1_000_000)),
+        (
+            "script.rhai",
+            r#"
+            fn on_test() {
+                world.spawn_.call();
+                suspend(YieldCondition.next_frame.call(), Fn("second_frame"));
+            }
+
+            fn second_frame() {
+                world.spawn_.call();
+                suspend(YieldCondition.next_frame.call(), Fn("third_frame"));
+            }
+
+            fn third_frame() {
+                world.spawn_.call();
+            }
+            "#,
+        ),
+    );
+}
+
+#[test]
+fn lua_infinite_loop_is_aborted_by_execution_budget() {
+    assert_infinite_loop_is_aborted(
+        make_test_lua_plugin().set_execution_budget(infinite_loop_budget()),
+        (
+            "script.lua",
+            r#"
+            function on_test()
+                while true do end
+            end
+            "#,
+        ),
+    );
+}
+
+#[test]
+fn rhai_infinite_loop_is_aborted_by_execution_budget() {
+    assert_infinite_loop_is_aborted(
+        make_test_rhai_plugin().set_execution_budget(infinite_loop_budget()),
+        (
+            "script.rhai",
+            r#"
+            fn on_test() {
+                loop {}
+            }
+            "#,
+        ),
+    );
+}
+
+#[test]
+fn rune_infinite_loop_is_aborted_by_execution_budget() {
+    assert_infinite_loop_is_aborted(
+        make_test_rune_plugin().set_execution_budget(infinite_loop_budget()),
+        (
+            "script.rn",
+            r#"
+            pub fn on_test() {
+                loop {}
+            }
+            "#,
+        ),
+    );
+}
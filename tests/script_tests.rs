@@ -4,7 +4,7 @@ use std::path::PathBuf;
 
 use libtest_mimic::{Arguments, Failed, Trial};
 use script_integration_test_harness::{
-    execute_lua_integration_test, execute_rhai_integration_test,
+    execute_lua_integration_test, execute_rhai_integration_test, execute_rune_integration_test,
 };
 
 use test_utils::{discover_all_tests, Test, TestKind};
@@ -21,13 +21,14 @@ impl TestExecutor for Test {
         match self.kind {
             TestKind::Lua => execute_lua_integration_test(&self.path.to_string_lossy())?,
             TestKind::Rhai => execute_rhai_integration_test(&self.path.to_string_lossy())?,
+            TestKind::Rune => execute_rune_integration_test(&self.path.to_string_lossy())?,
         }
 
         Ok(())
     }
 
     fn name(&self) -> String {
-        format!(
+        let name = format!(
             "script_test - {} - {}",
             self.kind,
             self.path
@@ -35,7 +36,11 @@ impl TestExecutor for Test {
                 .split(&format!("tests{}data", std::path::MAIN_SEPARATOR))
                 .last()
                 .unwrap()
-        )
+        );
+        match &self.skip_reason {
+            Some(reason) => format!("{name} (skipped: {reason})"),
+            None => name,
+        }
     }
 }
 
@@ -48,7 +53,10 @@ fn main() {
 
     let tests = discover_all_tests(manifest_dir, |p| p.path.starts_with("tests"))
         .into_iter()
-        .map(|t| Trial::test(t.name(), move || t.execute()))
+        .map(|t| {
+            let skipped = t.skip_reason.is_some();
+            Trial::test(t.name(), move || t.execute()).with_ignored_flag(skipped)
+        })
         .collect::<Vec<_>>();
 
     libtest_mimic::run(&args, tests).exit();